@@ -0,0 +1,126 @@
+//! # SQL Layer Derive
+//!
+//! The `#[derive(SqlRecord)]` macro, re-exported by `sql-layer` behind its `derive`
+//! feature. It maps a plain Rust struct to the rows of a table by implementing
+//! `sql_layer::SqlRecord`:
+//!
+//! - The table is named after the struct, unless `#[sql_record(table = "name")]` is set on
+//!   the struct.
+//! - Every field is a column, in declaration order, named after the field. Its type is
+//!   given by the `sql_layer::FromColumn` implementation of the field type, `Option`
+//!   fields holding nullable columns and the others `NOT NULL` columns.
+//! - The fields marked `#[sql_record(primary_key)]` make the primary key, in declaration
+//!   order. At least one field must be marked.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implements `sql_layer::SqlRecord` for a struct with named fields, see the crate
+/// documentation.
+#[proc_macro_derive(SqlRecord, attributes(sql_record))]
+pub fn derive_sql_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let mut table_name = name.unraw().to_string();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("sql_record"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("table") {
+                return Err(meta.error("expected `table = \"...\"`"));
+            }
+            table_name = meta.value()?.parse::<LitStr>()?.value();
+            Ok(())
+        })?;
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "SqlRecord can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "SqlRecord can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut idents = vec![];
+    let mut names = vec![];
+    let mut types = vec![];
+    let mut primary_key = vec![];
+    for field in &fields.named {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let field_name = ident.unraw().to_string();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("sql_record"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("primary_key") {
+                    return Err(meta.error("expected `primary_key`"));
+                }
+                primary_key.push(field_name.clone());
+                Ok(())
+            })?;
+        }
+        idents.push(ident);
+        names.push(field_name);
+        types.push(&field.ty);
+    }
+    if primary_key.is_empty() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "SqlRecord requires a field marked `#[sql_record(primary_key)]`",
+        ));
+    }
+    let positions = 0..idents.len();
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sql_layer::SqlRecord for #name #type_generics #where_clause {
+            fn table() -> ::sql_layer::Table {
+                let mut table = ::sql_layer::Table::new(
+                    #table_name.to_string(),
+                    vec![#(#primary_key.to_string()),*],
+                );
+                #(
+                    table.add_field(::sql_layer::Field {
+                        nullable: <#types as ::sql_layer::FromColumn>::NULLABLE,
+                        ..::sql_layer::Field::new(
+                            #names.to_string(),
+                            <#types as ::sql_layer::FromColumn>::FIELD_TYPE,
+                        )
+                    });
+                )*
+                table
+            }
+
+            fn into_record(self) -> ::sql_layer::Record {
+                ::sql_layer::Record::new(vec![#(::sql_layer::Column::from(self.#idents)),*])
+            }
+
+            fn from_record(record: ::sql_layer::Record) -> ::sql_layer::Result<Self> {
+                Ok(Self {
+                    #(#idents: record.column_as(#positions)?,)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,268 @@
+//! # Aggregate Module
+//!
+//! Aggregate functions computed over the records of a table, as `Database::aggregate`
+//! does. Records are folded one at a time into an [`Accumulator`], so an aggregation never
+//! holds more than one record in memory.
+//!
+//! Like in SQL, `Count` counts every record while the other functions skip null columns,
+//! and yield null when no record has a value.
+
+use crate::errors::SqlLayerError;
+use crate::record::{Column, Record};
+use crate::table::{FieldType, Table};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    /// The number of records.
+    Count,
+    /// The sum of a numeric column, an integer for integer columns.
+    Sum(String),
+    /// The average of a numeric column, always a float.
+    Avg(String),
+    Min(String),
+    Max(String),
+}
+
+impl Aggregate {
+    /// The aggregated column, `None` for `Count`.
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Aggregate::Count => None,
+            Aggregate::Sum(field)
+            | Aggregate::Avg(field)
+            | Aggregate::Min(field)
+            | Aggregate::Max(field) => Some(field),
+        }
+    }
+}
+
+/// Running state of an aggregate function.
+pub(crate) struct Accumulator<'a> {
+    aggregate: &'a Aggregate,
+    /// The position of the aggregated column among the table fields.
+    position: Option<usize>,
+    field_type: Option<FieldType>,
+    /// The number of records, or of non-null columns for the other functions.
+    count: u64,
+    int_sum: i128,
+    float_sum: f64,
+    /// The smallest or greatest column seen.
+    extremum: Option<Column>,
+}
+
+impl<'a> Accumulator<'a> {
+    /// Resolves the aggregated column against the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The aggregated column is not a field of the table.
+    /// - A sum or an average is computed over a non-numeric column.
+    pub fn new(table: &Table, aggregate: &'a Aggregate) -> crate::errors::Result<Self> {
+        let (position, field_type) = match aggregate.field() {
+            Some(field) => {
+                let position = table
+                    .get_field_pos(field)
+                    .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
+                let field_type = table.fields[position].r#type;
+                let numeric = matches!(
+                    field_type,
+                    FieldType::Int
+                        | FieldType::Int32
+                        | FieldType::Int16
+                        | FieldType::UInt64
+                        | FieldType::Float
+                );
+                if matches!(aggregate, Aggregate::Sum(_) | Aggregate::Avg(_)) && !numeric {
+                    return Err(SqlLayerError::MismatchedColumnType(
+                        "Int or Float".to_string(),
+                        format!("{field_type:?}"),
+                    ));
+                }
+                (Some(position), Some(field_type))
+            }
+            None => (None, None),
+        };
+        Ok(Self {
+            aggregate,
+            position,
+            field_type,
+            count: 0,
+            int_sum: 0,
+            float_sum: 0.0,
+            extremum: None,
+        })
+    }
+
+    /// Folds a record into the aggregate.
+    pub fn add(&mut self, record: &Record) {
+        let column = match self.position {
+            Some(position) => match record.columns.get(position) {
+                Some(Column::Null) | None => return,
+                Some(column) => column,
+            },
+            None => {
+                self.count += 1;
+                return;
+            }
+        };
+        self.count += 1;
+        match (self.aggregate, column) {
+            (Aggregate::Sum(_) | Aggregate::Avg(_), Column::Int(value)) => {
+                self.int_sum += *value as i128
+            }
+            (Aggregate::Sum(_) | Aggregate::Avg(_), Column::UInt64(value)) => {
+                self.int_sum += *value as i128
+            }
+            (Aggregate::Sum(_) | Aggregate::Avg(_), Column::Float(value)) => {
+                self.float_sum += value
+            }
+            (Aggregate::Min(_), column) => {
+                if self
+                    .extremum
+                    .as_ref()
+                    .is_none_or(|min| column.total_cmp(min).is_lt())
+                {
+                    self.extremum = Some(column.clone());
+                }
+            }
+            (Aggregate::Max(_), column) => {
+                if self
+                    .extremum
+                    .as_ref()
+                    .is_none_or(|max| column.total_cmp(max).is_gt())
+                {
+                    self.extremum = Some(column.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Sets the result of a `Min` or `Max` found without folding records, such as the
+    /// endpoint of an index.
+    pub fn set_extremum(&mut self, column: Column) {
+        self.count = 1;
+        self.extremum = Some(column);
+    }
+
+    /// Computes the value of the aggregate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Overflow` if the sum of an integer column doesn't fit a
+    /// 64-bit integer.
+    pub fn finish(self) -> crate::errors::Result<Column> {
+        if self.count == 0 && *self.aggregate != Aggregate::Count {
+            return Ok(Column::Null);
+        }
+        let column = match self.aggregate {
+            Aggregate::Count => Column::Int(self.count as i64),
+            Aggregate::Sum(_) if self.field_type == Some(FieldType::Float) => {
+                Column::Float(self.float_sum)
+            }
+            Aggregate::Sum(field) if self.field_type == Some(FieldType::UInt64) => Column::UInt64(
+                u64::try_from(self.int_sum)
+                    .map_err(|_| SqlLayerError::Overflow(format!("SUM({field})")))?,
+            ),
+            Aggregate::Sum(field) => Column::Int(
+                i64::try_from(self.int_sum)
+                    .map_err(|_| SqlLayerError::Overflow(format!("SUM({field})")))?,
+            ),
+            Aggregate::Avg(_) => {
+                Column::Float((self.float_sum + self.int_sum as f64) / self.count as f64)
+            }
+            Aggregate::Min(_) | Aggregate::Max(_) => self.extremum.unwrap_or(Column::Null),
+        };
+        Ok(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::{Accumulator, Aggregate};
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, Record};
+    use crate::table::{Field, FieldType, Table};
+
+    fn table() -> Table {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table
+    }
+
+    fn aggregate(aggregate: &Aggregate, records: &[Record]) -> crate::errors::Result<Column> {
+        let table = table();
+        let mut accumulator = Accumulator::new(&table, aggregate)?;
+        for record in records {
+            accumulator.add(record);
+        }
+        accumulator.finish()
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let records = [
+            ("John", Some(20), 1.8),
+            ("Jane", None, 1.6),
+            ("Bob", Some(40), 1.7),
+        ]
+        .map(|(name, age, height)| Record {
+            columns: vec![
+                Column::String(name.to_string()),
+                age.map(Column::Int).unwrap_or(Column::Null),
+                Column::Float(height),
+            ],
+        });
+
+        let result = |function| aggregate(&function, &records).unwrap();
+        assert_eq!(result(Aggregate::Count), Column::Int(3));
+        assert_eq!(result(Aggregate::Sum("age".to_string())), Column::Int(60));
+        assert_eq!(
+            result(Aggregate::Avg("age".to_string())),
+            Column::Float(30.0)
+        );
+        assert_eq!(result(Aggregate::Min("age".to_string())), Column::Int(20));
+        assert_eq!(
+            result(Aggregate::Max("name".to_string())),
+            Column::String("John".to_string())
+        );
+        assert!(matches!(
+            result(Aggregate::Sum("height".to_string())),
+            Column::Float(sum) if (sum - 5.1).abs() < 1e-9
+        ));
+
+        // nulls are skipped, no value yields null
+        assert_eq!(
+            aggregate(&Aggregate::Avg("age".to_string()), &records[1..2]).unwrap(),
+            Column::Null
+        );
+        assert_eq!(aggregate(&Aggregate::Count, &[]).unwrap(), Column::Int(0));
+    }
+
+    #[test]
+    fn test_invalid_aggregates() {
+        assert!(matches!(
+            aggregate(&Aggregate::Sum("name".to_string()), &[]),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            aggregate(&Aggregate::Min("unknown".to_string()), &[]),
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+
+        let records = [i64::MAX, 1].map(|age| Record {
+            columns: vec![
+                Column::String("John".to_string()),
+                Column::Int(age),
+                Column::Float(1.8),
+            ],
+        });
+        assert!(matches!(
+            aggregate(&Aggregate::Sum("age".to_string()), &records),
+            Err(SqlLayerError::Overflow(_))
+        ));
+    }
+}
@@ -0,0 +1,269 @@
+//! # Codec Module
+//!
+//! The serialization of stored rows, behind the [`RowCodec`] trait. Every table selects the
+//! [`RowEncoding`] of its rows when it is created, see [`Table::set_row_encoding`], and
+//! keeps it:
+//!
+//! - `Avro`, the default: rows are encoded with the Avro schema generated from the
+//!   definition of the table, see `RowSchema`, and decoded whatever version of the crate
+//!   wrote them, see [`SchemaMode`].
+//! - `Bincode`, behind the `bincode` feature: faster to encode and decode, at the cost of
+//!   larger rows.
+//! - `Protobuf`, behind the `protobuf` feature: compact and fast, but unable to detect the
+//!   fields written by newer versions of the crate, which are always ignored.
+//!
+//! [`Table::set_row_encoding`]: crate::Table::set_row_encoding
+
+use crate::errors::SqlLayerError;
+use crate::row::Row;
+use crate::schema::SchemaMode;
+use serde::{Deserialize, Serialize};
+
+/// Encodes and decodes the stored rows of a table.
+pub trait RowCodec: Send + Sync {
+    /// Encodes a stored row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row can't be encoded.
+    fn encode(&self, row: &Row) -> crate::errors::Result<Vec<u8>>;
+
+    /// Decodes a stored row, written by this or a newer version of the crate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The bytes aren't a row encoded by this codec.
+    /// - The row holds unknown trailing bytes in `SchemaMode::Strict`, for the codecs able
+    ///   to detect them.
+    fn decode(&self, bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Row>;
+}
+
+/// The codec of the rows of a table, see the module documentation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RowEncoding {
+    #[default]
+    Avro,
+    Bincode,
+    Protobuf,
+}
+
+impl RowEncoding {
+    /// Returns the codec of the encoding. The Avro codec is the generic one, of the rows
+    /// written before the table had a row schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Unsupported` if the crate was built without the feature of
+    /// the encoding.
+    pub fn codec(self) -> crate::errors::Result<&'static dyn RowCodec> {
+        match self {
+            RowEncoding::Avro => Ok(&AvroCodec),
+            #[cfg(feature = "bincode")]
+            RowEncoding::Bincode => Ok(&bincode_codec::BincodeCodec),
+            #[cfg(feature = "protobuf")]
+            RowEncoding::Protobuf => Ok(&protobuf_codec::ProtobufCodec),
+            #[allow(unreachable_patterns)]
+            encoding => {
+                let feature = format!("{encoding:?}").to_lowercase();
+                Err(SqlLayerError::Unsupported(format!(
+                    "{encoding:?} rows, the crate is built without the {feature} feature"
+                )))
+            }
+        }
+    }
+}
+
+/// Rows encoded with the generic Avro schema of [`Row`].
+pub(crate) struct AvroCodec;
+
+impl RowCodec for AvroCodec {
+    fn encode(&self, row: &Row) -> crate::errors::Result<Vec<u8>> {
+        row.to_bytes()
+    }
+
+    fn decode(&self, bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Row> {
+        Row::from_bytes_with_mode(bytes, mode)
+    }
+}
+
+#[cfg(feature = "bincode")]
+mod bincode_codec {
+    use crate::codec::RowCodec;
+    use crate::errors::SqlLayerError;
+    use crate::row::Row;
+    use crate::schema::SchemaMode;
+
+    pub(crate) struct BincodeCodec;
+
+    impl RowCodec for BincodeCodec {
+        fn encode(&self, row: &Row) -> crate::errors::Result<Vec<u8>> {
+            bincode::serde::encode_to_vec(row, bincode::config::standard())
+                .map_err(|error| SqlLayerError::Codec(error.to_string()))
+        }
+
+        fn decode(&self, bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Row> {
+            let (row, read) =
+                bincode::serde::decode_from_slice::<Row, _>(bytes, bincode::config::standard())
+                    .map_err(|error| SqlLayerError::Codec(error.to_string()))?;
+            if mode == SchemaMode::Strict && read < bytes.len() {
+                return Err(SqlLayerError::UnknownFields(bytes.len() - read));
+            }
+            Ok(row)
+        }
+    }
+}
+
+#[cfg(feature = "protobuf")]
+mod protobuf_codec {
+    use crate::codec::RowCodec;
+    use crate::errors::SqlLayerError;
+    use crate::record::{array_bytes, Column};
+    use crate::row::Row;
+    use crate::schema::SchemaMode;
+    use prost::Message;
+
+    pub(crate) struct ProtobufCodec;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct ProtoRow {
+        #[prost(message, repeated, tag = "1")]
+        columns: Vec<ProtoColumn>,
+    }
+
+    /// A column, null when it has no value.
+    #[derive(Clone, PartialEq, Message)]
+    struct ProtoColumn {
+        #[prost(oneof = "Value", tags = "1, 2, 3, 4, 5")]
+        value: Option<Value>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    enum Value {
+        #[prost(string, tag = "1")]
+        String(String),
+        #[prost(int64, tag = "2")]
+        Int(i64),
+        #[prost(double, tag = "3")]
+        Float(f64),
+        #[prost(bool, tag = "4")]
+        Bool(bool),
+        #[prost(bytes = "vec", tag = "5")]
+        Bytes(Vec<u8>),
+    }
+
+    impl RowCodec for ProtobufCodec {
+        fn encode(&self, row: &Row) -> crate::errors::Result<Vec<u8>> {
+            let columns = row
+                .columns
+                .iter()
+                .map(|column| {
+                    let value = match column.as_ref().map(Column::from) {
+                        Some(Column::String(value)) => Some(Value::String(value)),
+                        Some(Column::Int(value)) => Some(Value::Int(value)),
+                        Some(Column::Float(value)) => Some(Value::Float(value)),
+                        Some(Column::Bool(value)) => Some(Value::Bool(value)),
+                        Some(Column::Bytes(value)) => Some(Value::Bytes(value)),
+                        // typed back after their fields, see `Record::from_stored_row`
+                        Some(Column::Date(value)) => Some(Value::Int(value.into())),
+                        Some(Column::Time(value) | Column::Timestamp(value)) => {
+                            Some(Value::Int(value))
+                        }
+                        Some(Column::UInt64(value)) => Some(Value::Int(value as i64)),
+                        Some(Column::Uuid(value)) => Some(Value::Bytes(value.to_vec())),
+                        Some(Column::Decimal(value)) => Some(Value::Bytes(value.to_stored())),
+                        Some(Column::Array(values)) => Some(Value::Bytes(array_bytes(&values))),
+                        Some(Column::Null) | None => None,
+                    };
+                    ProtoColumn { value }
+                })
+                .collect();
+            Ok(ProtoRow { columns }.encode_to_vec())
+        }
+
+        /// Fields written by newer versions are skipped as unknown protobuf fields, so they
+        /// are ignored in both modes.
+        fn decode(&self, bytes: &[u8], _mode: SchemaMode) -> crate::errors::Result<Row> {
+            let proto =
+                ProtoRow::decode(bytes).map_err(|error| SqlLayerError::Codec(error.to_string()))?;
+            let mut row = Row::new();
+            for column in proto.columns {
+                let column = match column.value {
+                    Some(Value::String(value)) => Column::String(value),
+                    Some(Value::Int(value)) => Column::Int(value),
+                    Some(Value::Float(value)) => Column::Float(value),
+                    Some(Value::Bool(value)) => Column::Bool(value),
+                    Some(Value::Bytes(value)) => Column::Bytes(value),
+                    None => {
+                        row.add_null_column();
+                        continue;
+                    }
+                };
+                row.add_column((&column).into());
+            }
+            Ok(row)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::RowEncoding;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, Record};
+    use crate::row::Row;
+    use crate::schema::SchemaMode;
+
+    fn row() -> Row {
+        Row::from(&Record::new(vec![
+            Column::String("John".to_string()),
+            Column::Int(20),
+            Column::Float(1.8),
+            Column::Bool(true),
+            Column::Bytes(vec![1, 2]),
+            Column::Null,
+        ]))
+    }
+
+    #[test]
+    fn test_row_encodings() {
+        for encoding in [
+            RowEncoding::Avro,
+            RowEncoding::Bincode,
+            RowEncoding::Protobuf,
+        ] {
+            let codec = match encoding.codec() {
+                Ok(codec) => codec,
+                Err(SqlLayerError::Unsupported(_)) => continue,
+                Err(error) => panic!("Unable to get codec: {error}"),
+            };
+            let bytes = codec.encode(&row()).expect("Unable to encode row");
+            let decoded = codec
+                .decode(&bytes, SchemaMode::Strict)
+                .expect("Unable to decode row");
+            assert_eq!(decoded, row(), "{encoding:?}");
+        }
+    }
+
+    #[cfg(not(feature = "bincode"))]
+    #[test]
+    fn test_unsupported_encoding() {
+        assert!(matches!(
+            RowEncoding::Bincode.codec(),
+            Err(SqlLayerError::Unsupported(_))
+        ));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_unknown_trailing_fields() {
+        let codec = RowEncoding::Bincode.codec().unwrap();
+        let mut bytes = codec.encode(&row()).expect("Unable to encode row");
+        bytes.push(1);
+        assert_eq!(codec.decode(&bytes, SchemaMode::Compat).unwrap(), row());
+        assert!(matches!(
+            codec.decode(&bytes, SchemaMode::Strict),
+            Err(SqlLayerError::UnknownFields(1))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! # Collation Module
+//!
+//! The collation of a string field tells which of its strings are equal and how they sort,
+//! see [`Field::collation`](crate::Field::collation). A string is collated by mapping it to
+//! the string it is indexed and compared as:
+//!
+//! - `Binary`: the string itself, strings compare by code point.
+//! - `CaseInsensitive`: the string lowercased, so that `John@Doe.com` matches
+//!   `john@doe.com`.
+//! - `Unicode`: the string case folded then normalized to NFC, so that strings differing
+//!   in case, or only in how their accented characters are composed, match. Folding is
+//!   full, e.g. `ß` matches `ss`.
+//!
+//! The records keep their strings as written. Only the keys of the secondary indexes are
+//! built from the collated strings, and the predicates compare the collated strings, so
+//! that e.g. a case-insensitive email lookup reads a single index entry. The primary key
+//! and the sort keys stay binary.
+
+use crate::record::Column;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+
+/// Collation of a string field, see the module documentation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Collation {
+    #[default]
+    Binary,
+    CaseInsensitive,
+    Unicode,
+}
+
+impl Collation {
+    /// Returns the string a string is indexed and compared as.
+    pub(crate) fn apply(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(value),
+            Collation::CaseInsensitive => Cow::Owned(value.to_lowercase()),
+            Collation::Unicode => {
+                Cow::Owned(caseless::default_case_fold_str(value).nfc().collect())
+            }
+        }
+    }
+
+    /// Returns the column a column is indexed and compared as: its string, or the strings
+    /// of its array, collated, any other column as it is.
+    pub(crate) fn column(self, column: &Column) -> Cow<'_, Column> {
+        match column {
+            _ if self == Collation::Binary => Cow::Borrowed(column),
+            Column::String(value) => Cow::Owned(Column::String(self.apply(value).into_owned())),
+            Column::Array(values)
+                if values
+                    .iter()
+                    .any(|value| matches!(value, Column::String(_))) =>
+            {
+                let values = values
+                    .iter()
+                    .map(|value| self.column(value).into_owned())
+                    .collect();
+                Cow::Owned(Column::Array(values))
+            }
+            column => Cow::Borrowed(column),
+        }
+    }
+
+    /// Parses the name of a collation, as in `COLLATE case_insensitive`, regardless of
+    /// its case.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "binary" => Some(Collation::Binary),
+            "case_insensitive" => Some(Collation::CaseInsensitive),
+            "unicode" => Some(Collation::Unicode),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collation::Collation;
+    use crate::record::Column;
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(Collation::Binary.apply("John@Doe.com"), "John@Doe.com");
+        assert_eq!(
+            Collation::CaseInsensitive.apply("John@Doe.com"),
+            "john@doe.com"
+        );
+        assert_eq!(Collation::CaseInsensitive.apply("Straße"), "straße");
+
+        // composed and decomposed characters, and full case folding
+        assert_eq!(
+            Collation::Unicode.apply("Cafe\u{301}"),
+            Collation::Unicode.apply("CAF\u{c9}")
+        );
+        assert_eq!(Collation::Unicode.apply("Straße"), "strasse");
+    }
+
+    #[test]
+    fn test_column() {
+        let string = |value: &str| Column::String(value.to_string());
+        assert_eq!(
+            *Collation::CaseInsensitive.column(&string("JOHN")),
+            string("john")
+        );
+        assert_eq!(
+            *Collation::CaseInsensitive.column(&Column::Array(vec![string("A"), Column::Null])),
+            Column::Array(vec![string("a"), Column::Null])
+        );
+        assert_eq!(
+            *Collation::CaseInsensitive.column(&Column::Int(1)),
+            Column::Int(1)
+        );
+        assert_eq!(
+            Collation::from_name("Case_Insensitive"),
+            Some(Collation::CaseInsensitive)
+        );
+        assert_eq!(Collation::from_name("nocase"), None);
+    }
+}
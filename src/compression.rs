@@ -0,0 +1,174 @@
+//! # Compression Module
+//!
+//! Optional compression of the encoded rows of a table, see
+//! [`Table::set_compression`](crate::Table::set_compression). Rows of at least the
+//! threshold of the table are compressed with its algorithm, behind the feature of the
+//! same name:
+//!
+//! - `Zstd`, behind the `zstd` feature: the better ratio, for tables of large columns.
+//! - `Lz4`, behind the `lz4` feature: the faster one.
+//!
+//! A compressed row starts with a zero format byte, then the algorithm, then the
+//! compressed bytes. No row encoding starts with a zero byte, it would be a row without
+//! columns, so the rows stored uncompressed, including the ones written before the table
+//! was compressed, decode as they are. A row is only stored compressed when it gets
+//! smaller, and the algorithm of every row is recorded with it, so the compression of a
+//! table can change at any time.
+
+use crate::errors::SqlLayerError;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Format byte of a compressed row.
+const COMPRESSED: u8 = 0;
+
+/// Size of the encoded rows from which rows are compressed by default, in bytes.
+pub(crate) const DEFAULT_COMPRESSION_THRESHOLD: i64 = 1024;
+
+/// Compression algorithm of the rows of a table, see the module documentation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RowCompression {
+    #[default]
+    None,
+    Zstd,
+    Lz4,
+}
+
+impl RowCompression {
+    /// Checks that the crate is built with the feature of the algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Unsupported` if the feature of the algorithm is missing.
+    pub(crate) fn check_supported(self) -> crate::errors::Result<()> {
+        match self {
+            RowCompression::None => Ok(()),
+            #[cfg(feature = "zstd")]
+            RowCompression::Zstd => Ok(()),
+            #[cfg(feature = "lz4")]
+            RowCompression::Lz4 => Ok(()),
+            #[allow(unreachable_patterns)]
+            compression => Err(unsupported(compression)),
+        }
+    }
+
+    /// Returns the byte recording the algorithm in the compressed rows.
+    fn id(self) -> u8 {
+        self as u8
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        [RowCompression::Zstd, RowCompression::Lz4]
+            .into_iter()
+            .find(|compression| compression.id() == id)
+    }
+
+    #[cfg_attr(not(any(feature = "zstd", feature = "lz4")), allow(unused_variables))]
+    fn compress_bytes(self, bytes: &[u8]) -> crate::errors::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "zstd")]
+            RowCompression::Zstd => zstd::bulk::compress(bytes, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|error| SqlLayerError::Codec(error.to_string())),
+            #[cfg(feature = "lz4")]
+            RowCompression::Lz4 => Ok(lz4_flex::compress_prepend_size(bytes)),
+            compression => Err(unsupported(compression)),
+        }
+    }
+
+    #[cfg_attr(not(any(feature = "zstd", feature = "lz4")), allow(unused_variables))]
+    fn decompress_bytes(self, bytes: &[u8]) -> crate::errors::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "zstd")]
+            RowCompression::Zstd => zstd::stream::decode_all(bytes)
+                .map_err(|error| SqlLayerError::Codec(error.to_string())),
+            #[cfg(feature = "lz4")]
+            RowCompression::Lz4 => lz4_flex::decompress_size_prepended(bytes)
+                .map_err(|error| SqlLayerError::Codec(error.to_string())),
+            compression => Err(unsupported(compression)),
+        }
+    }
+}
+
+/// Compresses an encoded row with the given algorithm if it is at least `threshold` bytes
+/// long and gets smaller, see the module documentation.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::Unsupported` if the crate is built without the feature of the
+/// algorithm, or `SqlLayerError::Codec` if the compression fails.
+pub(crate) fn compress(
+    bytes: Vec<u8>,
+    compression: RowCompression,
+    threshold: i64,
+) -> crate::errors::Result<Vec<u8>> {
+    if compression == RowCompression::None || (bytes.len() as i64) < threshold {
+        return Ok(bytes);
+    }
+    let compressed = compression.compress_bytes(&bytes)?;
+    if compressed.len() + 2 >= bytes.len() {
+        return Ok(bytes);
+    }
+    let mut row = Vec::with_capacity(compressed.len() + 2);
+    row.extend_from_slice(&[COMPRESSED, compression.id()]);
+    row.extend(compressed);
+    Ok(row)
+}
+
+/// Decompresses a stored row, returning the rows stored uncompressed as they are.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::Unsupported` if the crate is built without the feature of the
+/// algorithm of the row, or `SqlLayerError::Codec` if the row can't be decompressed.
+pub(crate) fn decompress(bytes: &[u8]) -> crate::errors::Result<Cow<'_, [u8]>> {
+    let [COMPRESSED, id, ..] = bytes else {
+        return Ok(Cow::Borrowed(bytes));
+    };
+    let compression = RowCompression::from_id(*id)
+        .ok_or_else(|| SqlLayerError::Codec(format!("unknown compression algorithm {id}")))?;
+    Ok(Cow::Owned(compression.decompress_bytes(&bytes[2..])?))
+}
+
+fn unsupported(compression: RowCompression) -> SqlLayerError {
+    let feature = format!("{compression:?}").to_lowercase();
+    SqlLayerError::Unsupported(format!(
+        "{compression:?} compression, the crate is built without the {feature} feature"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::{compress, decompress, RowCompression};
+    use crate::errors::SqlLayerError;
+
+    #[test]
+    fn test_compression() {
+        let row = b"John".repeat(100);
+        for compression in [RowCompression::Zstd, RowCompression::Lz4] {
+            let compressed = match compress(row.clone(), compression, 100) {
+                Ok(compressed) => compressed,
+                Err(SqlLayerError::Unsupported(_)) => {
+                    assert!(compression.check_supported().is_err());
+                    continue;
+                }
+                Err(error) => panic!("Unable to compress row: {error}"),
+            };
+            assert!(compressed.len() < row.len(), "{compression:?}");
+            assert_eq!(&compressed[..2], &[0, compression as u8]);
+            assert_eq!(
+                decompress(&compressed).expect("Unable to decompress row"),
+                &row[..]
+            );
+
+            // rows below the threshold are stored as they are
+            assert_eq!(compress(row.clone(), compression, 1000).unwrap(), row);
+        }
+
+        assert_eq!(compress(row.clone(), RowCompression::None, 0).unwrap(), row);
+        assert_eq!(decompress(&row).unwrap(), &row[..]);
+        assert!(matches!(
+            decompress(&[0, 9, 1]),
+            Err(SqlLayerError::Codec(_))
+        ));
+    }
+}
@@ -0,0 +1,453 @@
+//! # Conversions Module
+//!
+//! Conversions between columns and common Rust types, so that values can be handed to and
+//! read from records without spelling out the column variants. Borrowed strings and bytes
+//! are always available, the other types are behind the feature named after their crate:
+//!
+//! - `chrono`: a `NaiveDate`, a `NaiveTime` and a `DateTime<Utc>` are respectively a
+//!   `Date`, a `Time` and a `Timestamp` column. A `DateTime<Utc>` is also read from the
+//!   `Int` columns holding microseconds since the Unix epoch, as instants were stored
+//!   before timestamps were columns.
+//! - `uuid`: a `Uuid` is a `Uuid` column, also read from the `Bytes` columns holding 16
+//!   bytes, as UUIDs were stored before they were columns. [`Column::uuid_v4`] and
+//!   [`Column::uuid_v7`] generate new UUIDs.
+//! - `rust_decimal`: a `Decimal` is a `Decimal` column, also read from the `String`
+//!   columns holding its exact representation, as decimals were stored before they were
+//!   columns.
+//! - `serde_json`: a JSON scalar is the column of the same type, while arrays and objects
+//!   are `String` columns holding their JSON text. Any column converts back into a JSON
+//!   value.
+//!
+//! Reading a column of another type fails with `SqlLayerError::MismatchedColumnType`, as
+//! [`Record::column_as`](crate::Record::column_as) does. The types implementing
+//! [`FromColumn`] can also be used as the fields of a `SqlRecord`.
+
+use crate::errors::SqlLayerError;
+use crate::record::{Column, FromColumn};
+use std::borrow::Cow;
+
+/// Reads a column as a `T`.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::MismatchedColumnType` if the column can't be read as a `T`.
+pub(crate) fn convert<T: FromColumn>(column: &Column) -> crate::errors::Result<T> {
+    T::from_column(column).ok_or_else(|| mismatch::<T>(column))
+}
+
+fn mismatch<T>(column: &Column) -> SqlLayerError {
+    SqlLayerError::MismatchedColumnType(
+        std::any::type_name::<T>().to_string(),
+        format!("{column:?}"),
+    )
+}
+
+/// Implements `TryFrom` columns for types implementing [`FromColumn`].
+macro_rules! try_from_column {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl TryFrom<&Column> for $type {
+                type Error = SqlLayerError;
+
+                fn try_from(column: &Column) -> crate::errors::Result<Self> {
+                    convert(column)
+                }
+            }
+
+            impl TryFrom<Column> for $type {
+                type Error = SqlLayerError;
+
+                fn try_from(column: Column) -> crate::errors::Result<Self> {
+                    convert(&column)
+                }
+            }
+        )*
+    };
+}
+
+try_from_column!(String, i64, i32, i16, u64, f64, bool, Vec<u8>);
+
+impl From<Cow<'_, str>> for Column {
+    fn from(value: Cow<'_, str>) -> Self {
+        Column::String(value.into_owned())
+    }
+}
+
+impl From<&[u8]> for Column {
+    fn from(value: &[u8]) -> Self {
+        Column::Bytes(value.to_vec())
+    }
+}
+
+impl<'a> TryFrom<&'a Column> for &'a str {
+    type Error = SqlLayerError;
+
+    fn try_from(column: &'a Column) -> crate::errors::Result<Self> {
+        match column {
+            Column::String(value) => Ok(value),
+            column => Err(mismatch::<&str>(column)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Column> for &'a [u8] {
+    type Error = SqlLayerError;
+
+    fn try_from(column: &'a Column) -> crate::errors::Result<Self> {
+        match column {
+            Column::Bytes(value) => Ok(value),
+            column => Err(mismatch::<&[u8]>(column)),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use crate::conversions::convert;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, FromColumn};
+    use crate::table::FieldType;
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+
+    /// Days from the first day of the common era to the Unix epoch.
+    const UNIX_EPOCH_FROM_CE: i32 = 719_163;
+
+    impl FromColumn for DateTime<Utc> {
+        const FIELD_TYPE: FieldType = FieldType::Timestamp;
+
+        fn from_column(column: &Column) -> Option<Self> {
+            match column {
+                Column::Timestamp(micros) | Column::Int(micros) => {
+                    DateTime::from_timestamp_micros(*micros)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    impl From<DateTime<Utc>> for Column {
+        fn from(value: DateTime<Utc>) -> Self {
+            Column::Timestamp(value.timestamp_micros())
+        }
+    }
+
+    impl FromColumn for NaiveDate {
+        const FIELD_TYPE: FieldType = FieldType::Date;
+
+        fn from_column(column: &Column) -> Option<Self> {
+            match column {
+                Column::Date(days) => {
+                    NaiveDate::from_num_days_from_ce_opt(days.checked_add(UNIX_EPOCH_FROM_CE)?)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    impl From<NaiveDate> for Column {
+        fn from(value: NaiveDate) -> Self {
+            Column::Date(value.num_days_from_ce() - UNIX_EPOCH_FROM_CE)
+        }
+    }
+
+    impl FromColumn for NaiveTime {
+        const FIELD_TYPE: FieldType = FieldType::Time;
+
+        fn from_column(column: &Column) -> Option<Self> {
+            match column {
+                Column::Time(micros) => NaiveTime::from_num_seconds_from_midnight_opt(
+                    u32::try_from(micros.div_euclid(1_000_000)).ok()?,
+                    (micros.rem_euclid(1_000_000) * 1_000) as u32,
+                ),
+                _ => None,
+            }
+        }
+    }
+
+    /// Leap seconds are folded into the last microsecond of their second.
+    impl From<NaiveTime> for Column {
+        fn from(value: NaiveTime) -> Self {
+            let micros = (value - NaiveTime::MIN)
+                .num_microseconds()
+                .unwrap_or_default();
+            Column::Time(micros)
+        }
+    }
+
+    try_from_column!(DateTime<Utc>, NaiveDate, NaiveTime);
+}
+
+#[cfg(feature = "uuid")]
+mod uuid_conversions {
+    use crate::conversions::convert;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, FromColumn};
+    use crate::table::FieldType;
+    use uuid::Uuid;
+
+    impl Column {
+        /// Generates a random UUID, of version 4.
+        pub fn uuid_v4() -> Column {
+            Uuid::new_v4().into()
+        }
+
+        /// Generates a UUID of version 7, starting with the current Unix time in
+        /// milliseconds. Such UUIDs sort by creation time, so the rows keyed by them are
+        /// appended to the primary key and indexes rather than spread over them.
+        pub fn uuid_v7() -> Column {
+            Uuid::now_v7().into()
+        }
+    }
+
+    impl FromColumn for Uuid {
+        const FIELD_TYPE: FieldType = FieldType::Uuid;
+
+        fn from_column(column: &Column) -> Option<Self> {
+            match column {
+                Column::Uuid(bytes) => Some(Uuid::from_bytes(*bytes)),
+                Column::Bytes(bytes) => Uuid::from_slice(bytes).ok(),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Uuid> for Column {
+        fn from(value: Uuid) -> Self {
+            Column::Uuid(value.into_bytes())
+        }
+    }
+
+    try_from_column!(Uuid);
+}
+
+#[cfg(feature = "rust_decimal")]
+mod decimal_conversions {
+    use crate::conversions::convert;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, FromColumn};
+    use crate::table::FieldType;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    impl FromColumn for Decimal {
+        const FIELD_TYPE: FieldType = FieldType::Decimal;
+
+        fn from_column(column: &Column) -> Option<Self> {
+            match column {
+                Column::Decimal(value) => {
+                    Decimal::try_from_i128_with_scale(value.mantissa(), value.scale().into()).ok()
+                }
+                Column::String(value) => Decimal::from_str(value).ok(),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Decimal> for Column {
+        fn from(value: Decimal) -> Self {
+            // the scale of a `rust_decimal::Decimal` is at most 28
+            Column::Decimal(
+                crate::Decimal::new(value.mantissa(), value.scale() as u8)
+                    .expect("Scale above the maximum"),
+            )
+        }
+    }
+
+    try_from_column!(Decimal);
+}
+
+#[cfg(feature = "serde_json")]
+mod json_conversions {
+    use crate::record::Column;
+    use serde_json::{Number, Value};
+
+    impl From<Value> for Column {
+        fn from(value: Value) -> Self {
+            match value {
+                Value::Null => Column::Null,
+                Value::Bool(value) => Column::Bool(value),
+                Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+                    (Some(value), _) => Column::Int(value),
+                    (None, Some(value)) => Column::UInt64(value),
+                    _ => number.as_f64().map_or(Column::Null, Column::Float),
+                },
+                Value::String(value) => Column::String(value),
+                value => Column::String(value.to_string()),
+            }
+        }
+    }
+
+    /// Bytes are an array of numbers, temporal columns the integers they hold, UUIDs their
+    /// hyphenated text, decimals their exact text, arrays an array of their elements, and
+    /// non-finite floats are null.
+    impl From<&Column> for Value {
+        fn from(column: &Column) -> Self {
+            match column {
+                Column::String(value) => Value::String(value.clone()),
+                Column::Int(value) => Value::Number((*value).into()),
+                Column::UInt64(value) => Value::Number((*value).into()),
+                Column::Float(value) => Number::from_f64(*value).map_or(Value::Null, Value::Number),
+                Column::Bool(value) => Value::Bool(*value),
+                Column::Bytes(value) => Value::Array(
+                    value
+                        .iter()
+                        .map(|byte| Value::Number((*byte).into()))
+                        .collect(),
+                ),
+                Column::Date(value) => Value::Number((*value).into()),
+                Column::Time(value) | Column::Timestamp(value) => Value::Number((*value).into()),
+                Column::Uuid(value) => Value::String(hyphenated(value)),
+                Column::Decimal(value) => Value::String(value.to_string()),
+                Column::Array(values) => Value::Array(values.iter().map(Value::from).collect()),
+                Column::Null => Value::Null,
+            }
+        }
+    }
+
+    impl From<Column> for Value {
+        fn from(column: Column) -> Self {
+            Value::from(&column)
+        }
+    }
+
+    /// Writes a UUID in its hyphenated form, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    fn hyphenated(uuid: &[u8; 16]) -> String {
+        let hex = uuid
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        [
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..],
+        ]
+        .join("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::record::Column;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            Column::from(Cow::Borrowed("John")),
+            Column::String("John".to_string())
+        );
+        assert_eq!(Column::from(&[1u8, 2][..]), Column::Bytes(vec![1, 2]));
+
+        let column = Column::String("John".to_string());
+        assert_eq!(<&str>::try_from(&column).unwrap(), "John");
+        assert_eq!(String::try_from(column.clone()).unwrap(), "John");
+        assert!(matches!(
+            i64::try_from(&column),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            <&[u8]>::try_from(&column),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            bool::try_from(Column::Null),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversions() {
+        use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+        let instant = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let column = Column::from(instant);
+        assert_eq!(column, Column::Timestamp(1_700_000_000_123_456));
+        assert_eq!(DateTime::<Utc>::try_from(column).unwrap(), instant);
+        assert_eq!(
+            DateTime::<Utc>::try_from(Column::Int(1_700_000_000_123_456)).unwrap(),
+            instant
+        );
+
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(Column::from(date), Column::Date(19_782));
+        assert_eq!(NaiveDate::try_from(Column::Date(19_782)).unwrap(), date);
+        let date = NaiveDate::from_ymd_opt(1969, 12, 31).unwrap();
+        assert_eq!(Column::from(date), Column::Date(-1));
+
+        let time = NaiveTime::from_hms_micro_opt(12, 30, 15, 500_000).unwrap();
+        assert_eq!(Column::from(time), Column::Time(45_015_500_000));
+        assert_eq!(
+            NaiveTime::try_from(Column::Time(45_015_500_000)).unwrap(),
+            time
+        );
+        assert!(matches!(
+            NaiveTime::try_from(Column::Time(-1)),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_conversions() {
+        use uuid::Uuid;
+
+        let uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let column = Column::from(uuid);
+        assert_eq!(column, Column::Uuid(*uuid.as_bytes()));
+        assert_eq!(Uuid::try_from(&column).unwrap(), uuid);
+        assert_eq!(
+            Uuid::try_from(Column::Bytes(uuid.as_bytes().to_vec())).unwrap(),
+            uuid
+        );
+        assert!(matches!(
+            Uuid::try_from(Column::Bytes(vec![1, 2, 3])),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+
+        let v4 = Uuid::try_from(Column::uuid_v4()).unwrap();
+        assert_eq!(v4.get_version_num(), 4);
+        let first = Column::uuid_v7();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = Column::uuid_v7();
+        assert_eq!(Uuid::try_from(&first).unwrap().get_version_num(), 7);
+        assert!(first.total_cmp(&second).is_lt());
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_decimal_conversions() {
+        use rust_decimal::Decimal;
+
+        let decimal = Decimal::new(12345, 2);
+        let column = Column::from(decimal);
+        assert_eq!(column, Column::Decimal("123.45".parse().unwrap()));
+        assert_eq!(Decimal::try_from(column).unwrap(), decimal);
+        let column = Column::String("123.45".to_string());
+        assert_eq!(Decimal::try_from(column).unwrap(), decimal);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_conversions() {
+        use serde_json::{json, Value};
+
+        assert_eq!(Column::from(json!(20)), Column::Int(20));
+        assert_eq!(Column::from(json!(1.5)), Column::Float(1.5));
+        assert_eq!(Column::from(json!(null)), Column::Null);
+        assert_eq!(
+            Column::from(json!({"name": "John"})),
+            Column::String(r#"{"name":"John"}"#.to_string())
+        );
+        assert_eq!(Value::from(Column::Bytes(vec![1, 2])), json!([1, 2]));
+        assert_eq!(Value::from(Column::Float(f64::NAN)), Value::Null);
+        let uuid = 0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8_u128.to_be_bytes();
+        assert_eq!(
+            Value::from(Column::Uuid(uuid)),
+            json!("67e55044-10b1-426f-9247-bb680e5fe0c8")
+        );
+    }
+}
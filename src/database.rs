@@ -1,15 +1,74 @@
+use crate::aggregate::{Accumulator, Aggregate};
+use crate::codec::{AvroCodec, RowCodec, RowEncoding};
+use crate::collation::Collation;
+use crate::compression;
+use crate::deserialize::from_record;
 use crate::errors::SqlLayerError;
+use crate::index::{IndexState, IndexStatus};
+use crate::limits::{limit_records, ResultGuard, ResultLimits};
+use crate::migrations::{Backfill, MigrationStep, Migrations};
+use crate::plan::{common_index, Direction, IndexRange, Plan, SortKey, WritePath};
+use crate::predicate::Predicate;
+use crate::query::{combine, join_positions, joins, Join, JoinStrategy};
 use crate::record::Column;
-use crate::record::{Columns, Record};
+use crate::record::{Columns, KeyColumns, Record, RowMetadata};
 use crate::row::Row;
-use crate::storage::Storage;
+use crate::row_schema::RowSchema;
+use crate::schema::SchemaMode;
+use crate::sort::{compare_records, intersect_ordered, merge_ordered, TopK};
+use crate::sql::{Command, QueryResult};
+use crate::storage::{CommitProfile, Storage, MAX_TRANSACTION_TAG_LENGTH};
 use crate::table;
-use crate::table::{FieldType, Table};
-use crate::table_metadata::TableMetadata;
-use foundationdb::{FdbBindingError, RetryableTransaction};
+use crate::table::{Field, FieldType, ForeignKey, OnDelete, Table, TableDescription};
+use crate::table_metadata::{TableMetadata, TableStats};
+use crate::validation::{ValidationReport, ViolationKind};
+use foundationdb::future::FdbKeyValue;
+use foundationdb::options::{MutationType, TransactionOption};
+use foundationdb::{
+    FdbBindingError, MaybeCommitted, RangeOption, RetryableTransaction, Transaction,
+};
 use foundationdb_tuple::{pack, unpack, Subspace, TupleDepth, TuplePack, VersionstampOffset};
+use futures::future::{try_join_all, BoxFuture};
+use futures::stream::LocalBoxStream;
+use futures::{pin_mut, Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
 use std::io::Write;
 use std::iter::zip;
+use std::ops::{Bound, Range, RangeBounds};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{info_span, Instrument, Span};
+
+/// Maximum number of rows processed by a single transaction of a backfill.
+const BACKFILL_BATCH_SIZE: usize = 100;
+
+/// Maximum number of rows read by a single transaction of a checksum.
+const CHECKSUM_BATCH_SIZE: usize = 1000;
+
+/// Maximum number of entries resolved into their rows by a single transaction of a scan.
+const ROW_BATCH_SIZE: usize = 20;
+
+/// Maximum number of records deleted by a single transaction of `Database::delete_many`.
+const DELETE_BATCH_SIZE: usize = 100;
+
+/// Number of row ids reserved at once by `Database::allocate_row_id`.
+const ROW_ID_BLOCK_SIZE: i64 = 100;
+
+/// Maximum size of a FoundationDB value, in bytes. Larger rows are split into chunks of
+/// this size, see `Database::set_row`.
+const MAX_VALUE_SIZE: usize = 100_000;
+
+/// Prefix of the value stored under the key of a row split into chunks, followed by the
+/// length of the row as a little-endian `u64`. Compressed rows start with a zero byte as
+/// well, but never with this algorithm.
+const CHUNKED_ROW: [u8; 2] = [0x00, 0xff];
+
+/// Maximum number of chunks of a blob written by a single transaction of
+/// `Database::write_blob`.
+const BLOB_BATCH_SIZE: usize = 20;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum DataPrefix {
@@ -18,6 +77,21 @@ enum DataPrefix {
     Row = 3,
     PrimaryKey = 4,
     Index = 5,
+    RowMetadata = 6,
+    /// Id of the next table created, see `Database::allocate_table_id`.
+    NextTableId = 7,
+    /// Version of the last migration applied, see `Database::migrate`.
+    SchemaVersion = 8,
+    /// Description of every migration applied, by version.
+    Migration = 9,
+    /// Versionstamp of the last change to the definition of every table, by name, see
+    /// `Database::get_table_internal`.
+    TableVersion = 10,
+    /// Chunks of the rows too large for a single value, by row id and position, see
+    /// `Database::set_row`.
+    RowChunk = 11,
+    /// Blobs of the blob fields, by row id and blob id, see `Database::store_blobs`.
+    Blob = 12,
 }
 
 impl TuplePack for DataPrefix {
@@ -30,9 +104,143 @@ impl TuplePack for DataPrefix {
     }
 }
 
+/// Counters of [`TableStats`], each stored under its own key next to the table metadata so
+/// that concurrent writers update them with atomic operations without conflicting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TableStat {
+    RowCount = 1,
+    TotalBytes = 2,
+    LargestRow = 3,
+    /// First row id not reserved yet, see `Database::allocate_row_id`.
+    NextRowId = 4,
+    /// First value of every serial field not reserved yet, by stored position, see
+    /// `Database::allocate_serial`.
+    NextSerial = 5,
+}
+
+impl TuplePack for TableStat {
+    fn pack<W: Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> std::io::Result<VersionstampOffset> {
+        (*self as u64).pack(w, tuple_depth)
+    }
+}
+
+/// System key holding the lock of a locked database, whose transactions can't commit.
+const DATABASE_LOCKED_KEY: &[u8] = b"\xff/dbLocked";
+
+/// FoundationDB error code of a read of a key the transaction wrote with a versionstamp,
+/// which is only known once the transaction commits.
+const ACCESSED_UNREADABLE: i32 = 1036;
+
+/// Whether the database accepts writes, see `Database::access_mode`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum AccessMode {
+    #[default]
+    ReadWrite,
+    /// The cluster is locked: writes fail fast with `SqlLayerError::ReadOnly` while reads
+    /// keep being served.
+    ReadOnly,
+}
+
+/// Conflict ranges registered by the writes maintaining secondary indexes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum IndexConflictMode {
+    /// Every index entry written registers a write conflict range on its key.
+    #[default]
+    Strict,
+    /// The entries of non-unique indexes are written without registering a write conflict
+    /// range, those of unique indexes keep theirs.
+    ///
+    /// Transactions reading hot index ranges, such as index scans, are no longer aborted by
+    /// every insert landing in the range, at the cost of not noticing those inserts: a scan
+    /// may commit having missed an entry written concurrently. Concurrent writers of the
+    /// same record still conflict on its primary key and row keys, and concurrent writers of
+    /// the same unique value on its index entries, so uniqueness is still enforced.
+    Relaxed,
+}
+
+/// Content fingerprint of a table, as computed by `Database::table_checksum`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TableChecksum {
+    /// Read version the rows were read at.
+    version: i64,
+    row_count: u64,
+    /// Wrapping sum of the hashes of the rows, independent of their order.
+    checksum: u64,
+}
+
+/// Opaque position of a paginated scan, resuming right after the last entry read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Continuation(Vec<u8>);
+
+impl Continuation {
+    /// Serializes the token, to be handed over to a client.
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    /// Deserializes a token handed back by a client, it is checked against the scanned
+    /// range when used.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+/// A primary key or index entry, along with the record of the row it references.
+struct EntryRow {
+    key: Vec<u8>,
+    row_id: i64,
+    /// The record, or `None` if the row no longer exists.
+    record: Option<Record>,
+}
+
+/// Where the blob of a record is streamed from, see `Database::read_blob`.
+enum BlobSource {
+    /// The chunks of a stored blob, along with its length.
+    Stored(Subspace, usize),
+    /// The default of a blob field added after the row was written.
+    Default(Vec<u8>),
+}
+
+/// A row written by an insert, see `Database::insert`.
+#[derive(Debug, PartialEq)]
+struct InsertedRow {
+    /// The id the row is stored under.
+    row_id: i64,
+    /// The record as stored, holding the values given to its serial fields.
+    record: Record,
+}
+
+/// A page of records, as returned by the paginated scans.
+#[derive(Debug, PartialEq)]
+struct Page {
+    records: Vec<Record>,
+    /// The token to read the next page, or `None` if the scan is over.
+    continuation: Option<Continuation>,
+}
+
 struct Database {
     root_subspace: Subspace,
     storage: Storage,
+    index_conflict_mode: IndexConflictMode,
+    schema_mode: SchemaMode,
+    table_tags: bool,
+    read_only: AtomicBool,
+    /// Called with the new access mode whenever it changes.
+    access_mode_listener: Option<Box<dyn Fn(AccessMode) + Send + Sync>>,
+    /// Row ids reserved by this instance and not taken yet, per table.
+    row_ids: Mutex<HashMap<String, Range<i64>>>,
+    /// Values of serial fields reserved by this instance and not taken yet, per table and
+    /// stored position of the field.
+    serials: Mutex<HashMap<(String, usize), Range<i64>>>,
+    result_limits: ResultLimits,
+    /// Table definitions read by this instance, per name, along with their version.
+    tables: Mutex<HashMap<String, (Option<Vec<u8>>, Table)>>,
+    /// Row schemas known to be stored, per table name and fingerprint.
+    row_schemas: Mutex<HashMap<(String, u64), Arc<RowSchema>>>,
 }
 
 impl Database {
@@ -40,9 +248,226 @@ impl Database {
         Self {
             root_subspace,
             storage,
+            index_conflict_mode: IndexConflictMode::default(),
+            schema_mode: SchemaMode::default(),
+            table_tags: false,
+            read_only: AtomicBool::new(false),
+            access_mode_listener: None,
+            row_ids: Mutex::new(HashMap::new()),
+            serials: Mutex::new(HashMap::new()),
+            result_limits: ResultLimits::default(),
+            tables: Mutex::new(HashMap::new()),
+            row_schemas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn set_index_conflict_mode(&mut self, mode: IndexConflictMode) {
+        self.index_conflict_mode = mode;
+    }
+
+    /// Sets how rows and tables written by newer versions of the crate are decoded.
+    fn set_schema_mode(&mut self, mode: SchemaMode) {
+        self.schema_mode = mode;
+    }
+
+    /// Sets whether transactions on a table are tagged with its name, on top of the tags of
+    /// the storage, see [`Storage::set_transaction_tags`].
+    ///
+    /// This lets operators throttle the workload of a single table from the cluster. Only
+    /// the first 16 bytes of a table name fit in a tag, tables sharing them share their tag.
+    fn set_table_tags(&mut self, enabled: bool) {
+        self.table_tags = enabled;
+    }
+
+    /// Sets the limits of the queries collecting their records, see the
+    /// [`crate::limits`] module. Queries are unbounded by default.
+    fn set_result_limits(&mut self, limits: ResultLimits) {
+        self.result_limits = limits;
+    }
+
+    /// Returns the tag of the transactions on the given table, if table tags are enabled.
+    fn table_tag<'a>(&self, table_name: &'a str) -> Option<&'a str> {
+        if !self.table_tags {
+            return None;
+        }
+        let mut end = table_name.len().min(MAX_TRANSACTION_TAG_LENGTH);
+        while !table_name.is_char_boundary(end) {
+            end -= 1;
+        }
+        Some(&table_name[..end])
+    }
+
+    /// Returns whether the database accepts writes.
+    ///
+    /// The database switches to [`AccessMode::ReadOnly`] when a write finds the cluster
+    /// locked, and back to [`AccessMode::ReadWrite`] when
+    /// [`Database::refresh_access_mode`] finds it unlocked.
+    fn access_mode(&self) -> AccessMode {
+        if self.read_only.load(Ordering::Relaxed) {
+            AccessMode::ReadOnly
+        } else {
+            AccessMode::ReadWrite
+        }
+    }
+
+    /// Sets a callback called with the new access mode whenever it changes.
+    fn set_access_mode_listener(&mut self, listener: impl Fn(AccessMode) + Send + Sync + 'static) {
+        self.access_mode_listener = Some(Box::new(listener));
+    }
+
+    /// Switches the access mode, notifying the listener if it changed.
+    ///
+    /// Transactions read lock aware while the database is read-only, so reads keep being
+    /// served by a locked cluster.
+    fn set_access_mode(&self, mode: AccessMode) {
+        let read_only = mode == AccessMode::ReadOnly;
+        let previous = self.read_only.swap(read_only, Ordering::Relaxed);
+        if previous == read_only {
+            return;
+        }
+        self.storage.set_read_lock_aware(read_only);
+        if let Some(listener) = &self.access_mode_listener {
+            listener(mode);
+        }
+    }
+
+    /// Checks whether the cluster is locked and updates the access mode accordingly.
+    ///
+    /// A read-only database never retries writes on its own, this is how it learns that the
+    /// cluster has been unlocked. It is meant to be polled while the database is read-only.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock of the cluster can't be read.
+    async fn refresh_access_mode(&self) -> crate::errors::Result<AccessMode> {
+        let locked = self
+            .storage
+            .run(None, |trx, _| async move { Ok(is_locked(&trx).await?) })
+            .await?;
+        let mode = if locked {
+            AccessMode::ReadOnly
+        } else {
+            AccessMode::ReadWrite
+        };
+        self.set_access_mode(mode);
+        Ok(mode)
+    }
+
+    /// Runs a user-facing transaction writing to the given table, see
+    /// [`Database::write_with_profile`].
+    async fn write<F, Fut, T>(&self, table_name: &str, closure: F) -> crate::errors::Result<T>
+    where
+        F: Fn(RetryableTransaction, MaybeCommitted) -> Fut,
+        Fut: Future<Output = Result<T, FdbBindingError>>,
+    {
+        self.write_with_profile(
+            CommitProfile::Interactive,
+            self.table_tag(table_name),
+            closure,
+        )
+        .await
+    }
+
+    /// Runs a writing transaction with the options of a commit profile, see
+    /// [`Storage::run_with_profile`].
+    ///
+    /// Transactions on a locked cluster are retried until it is unlocked, so every attempt
+    /// checks the lock: a locked cluster switches the database to [`AccessMode::ReadOnly`]
+    /// and the write fails instead. The lock is read concurrently with the closure, so it
+    /// doesn't delay its reads. Once read-only, writes fail without reaching the cluster.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::ReadOnly` if the database is read-only, or the error of the
+    /// transaction.
+    async fn write_with_profile<F, Fut, T>(
+        &self,
+        profile: CommitProfile,
+        tag: Option<&str>,
+        closure: F,
+    ) -> crate::errors::Result<T>
+    where
+        F: Fn(RetryableTransaction, MaybeCommitted) -> Fut,
+        Fut: Future<Output = Result<T, FdbBindingError>>,
+    {
+        if self.access_mode() == AccessMode::ReadOnly {
+            return Err(SqlLayerError::ReadOnly);
+        }
+        let closure = &closure;
+        let result = self
+            .storage
+            .run_with_profile(profile, tag, |trx, maybe_committed| async move {
+                // the lock read is polled first, setting its options before the closure reads
+                let (locked, value) =
+                    futures::join!(is_locked(&trx), closure(trx.clone(), maybe_committed));
+                if locked? {
+                    return Err(SqlLayerError::ReadOnly.into());
+                }
+                value
+            })
+            .await;
+        if matches!(result, Err(SqlLayerError::ReadOnly)) {
+            self.set_access_mode(AccessMode::ReadOnly);
+        }
+        result
+    }
+
+    /// Writes an entry of `index` pointing to `row_id`, honoring the index conflict mode.
+    ///
+    /// The entries of a unique index always register their write conflict range: it is
+    /// what aborts one of two concurrent writers of the same value, whose uniqueness checks
+    /// read the range of the value, see [`Database::check_unique_entry`].
+    fn set_index_entry(
+        &self,
+        trx: &RetryableTransaction,
+        index: &table::Index,
+        key: &[u8],
+        row_id: i64,
+    ) -> crate::errors::Result<()> {
+        if self.index_conflict_mode == IndexConflictMode::Relaxed && !index.is_unique() {
+            trx.set_option(TransactionOption::NextWriteNoWriteConflictRange)?;
+        }
+        trx.set(key, pack(&row_id).as_ref());
+        Ok(())
+    }
+
+    /// Returns the subspace holding the data of kind `prefix` for the given table.
+    ///
+    /// The data is keyed by the id of the table, see [`Table::id`], or by its name for the
+    /// tables created before ids were allocated.
+    fn table_subspace(&self, prefix: DataPrefix, table: &Table) -> Subspace {
+        let subspace = self.root_subspace.subspace(&prefix);
+        match table.id {
+            Some(id) => subspace.subspace(&id),
+            None => subspace.subspace(&table.name),
         }
     }
 
+    /// Returns the key of the primary key entry of `pk`, its columns encoded in the
+    /// directions of the primary key, see [`Table::set_primary_key_directions`].
+    fn primary_key_entry(&self, table: &Table, pk: &Columns<'_>) -> Vec<u8> {
+        self.table_subspace(DataPrefix::PrimaryKey, table)
+            .pack(&KeyColumns::new(pk.0, &table.primary_key_directions))
+    }
+
+    /// Returns the key range of the entries of an index within the bounds of a plan.
+    fn index_key_range(&self, table: &Table, range: &IndexRange) -> (Vec<u8>, Vec<u8>) {
+        let directions = table
+            .get_index(&range.index)
+            .map_or(&[][..], |index| index.directions());
+        key_range(
+            &self.index_subspace(table, &range.index),
+            directions,
+            &(range.start.clone(), range.end.clone()),
+        )
+    }
+
+    /// Returns the subspace holding the entries of the given index.
+    fn index_subspace(&self, table: &Table, index_name: &str) -> Subspace {
+        self.table_subspace(DataPrefix::Index, table)
+            .subspace(&index_name)
+    }
+
     /// Creates a new table in the database.
     ///
     /// This method serializes the provided table into a byte array
@@ -50,22 +475,167 @@ impl Database {
     /// with tables. The table's name is used to generate a unique key
     /// within the `root_subspace` for storage.
     ///
+    /// The table is allocated a new id, see [`Table::id`], which keys its rows, primary
+    /// key and index entries. An existing table is never replaced, as its data would be
+    /// left unreachable.
+    ///
     /// # Arguments
     ///
     /// * `table` - A reference to the `Table` to be created and stored in the database.
+    /// * `if_not_exists` - Whether an existing table of the same name is kept as is rather
+    ///   than reported, as `CREATE TABLE IF NOT EXISTS` does.
     ///
     /// # Errors
     ///
     /// Returns an error if:
+    /// - A table of the same name already exists, unless `if_not_exists` is set.
+    /// - The table, one of its fields or indexes has an invalid name.
+    /// - The primary key is empty, or names a missing, repeated or array field, see
+    ///   [`Table::check_primary_key`].
+    /// - Two indexes share a name, or an index names a field the table doesn't have, see
+    ///   [`Table::check_indexes`].
+    /// - The row encoding or the compression of the table needs a feature the crate is
+    ///   built without.
+    /// - A check constraint references a column that is not a field of the table, or
+    ///   compares a column to a value of another type.
+    /// - A foreign key references a table that doesn't exist, or doesn't match its primary
+    ///   key.
+    /// - An array field or a multi-entry index isn't valid, see
+    ///   [`Table::check_array_fields`].
+    /// - An enum field has no variants or duplicate ones, see [`Table::check_enum_fields`].
+    /// - A collated field doesn't hold strings, see [`Table::check_collations`].
+    /// - A serial field isn't an integer field or has a default, see
+    ///   [`Table::check_serial_fields`].
     /// - Serialization of the table fails.
     /// - An error occurs during the storage operation (e.g., database write failure).
-    async fn create_table(&self, table: &Table) -> crate::errors::Result<()> {
-        let bytes = table.to_bytes()?;
+    async fn create_table(&self, table: &Table, if_not_exists: bool) -> crate::errors::Result<()> {
+        table.check_identifiers()?;
+        table.check_primary_key()?;
+        table.check_indexes()?;
+        table.check_blob_fields()?;
+        table.check_array_fields()?;
+        table.check_enum_fields()?;
+        table.check_collations()?;
+        table.check_serial_fields()?;
+        table.row_encoding.codec()?;
+        table.compression.check_supported()?;
+        for check in &table.checks {
+            check.predicate()?.validate(table)?;
+        }
         let key = self
             .root_subspace
             .subspace(&DataPrefix::Table)
             .pack(&table.name);
-        self.storage.set(&key, &bytes).await?;
+        let key = key.as_slice();
+        self.write(&table.name, |trx, _| async move {
+            if trx.get(key, false).await?.is_some() {
+                if if_not_exists {
+                    return Ok(());
+                }
+                return Err(SqlLayerError::TableAlreadyExists(table.name.clone()).into());
+            }
+            self.validate_foreign_keys(&trx, table).await?;
+            let mut table = Table {
+                id: Some(self.allocate_table_id(&trx).await?),
+                ..table.clone()
+            };
+            self.register_referencing_table(&trx, &mut table).await?;
+            trx.set(key, &table.to_bytes()?);
+            self.touch_table_version(&trx, &table.name);
+            self.store_row_schema(&trx, &table)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Allocates the id of a new table, ids being allocated in increasing order from 1.
+    async fn allocate_table_id(&self, trx: &RetryableTransaction) -> crate::errors::Result<i64> {
+        let key = self.root_subspace.pack(&DataPrefix::NextTableId);
+        let id = match trx.get(&key, false).await? {
+            Some(bytes) => unpack::<i64>(&bytes).map_err(FdbBindingError::PackError)?,
+            None => 1,
+        };
+        trx.set(&key, pack(&(id + 1)).as_ref());
+        Ok(id)
+    }
+
+    /// Checks that every foreign key of a table matches the primary key of its parent
+    /// table, field by field and type by type. A table may reference itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The parent table doesn't exist.
+    /// - The foreign key fields don't have the types of the parent primary key fields,
+    ///   reported as `SqlLayerError::InvalidForeignKey`.
+    async fn validate_foreign_keys(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+    ) -> crate::errors::Result<()> {
+        let types = |table: &Table, fields: &[String]| {
+            fields
+                .iter()
+                .map(|field| {
+                    table
+                        .get_field_pos(field)
+                        .map(|pos| table.fields[pos].r#type)
+                })
+                .collect::<Vec<_>>()
+        };
+        for foreign_key in &table.foreign_keys {
+            let stored;
+            let parent = if foreign_key.parent == table.name {
+                table
+            } else {
+                stored = self
+                    .get_table_internal(trx, &foreign_key.parent)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(foreign_key.parent.clone()))?;
+                &stored
+            };
+            if types(table, &foreign_key.fields) != types(parent, &parent.primary_key) {
+                return Err(SqlLayerError::InvalidForeignKey(format!(
+                    "foreign key {} doesn't match the primary key of {}",
+                    foreign_key.name, parent.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a new table in the [`Table::referencing_tables`] of every table its foreign
+    /// keys reference, its own included when it references itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a referenced table can't be read or updated.
+    async fn register_referencing_table(
+        &self,
+        trx: &RetryableTransaction,
+        table: &mut Table,
+    ) -> crate::errors::Result<()> {
+        let mut parents = table
+            .foreign_keys
+            .iter()
+            .map(|foreign_key| foreign_key.parent.clone())
+            .collect::<Vec<_>>();
+        parents.sort();
+        parents.dedup();
+        for parent in parents {
+            if parent == table.name {
+                table.referencing_tables.push(table.name.clone());
+                continue;
+            }
+            let mut stored = self
+                .get_table_internal(trx, &parent)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(parent.clone()))?;
+            if !stored.referencing_tables.contains(&table.name) {
+                stored.referencing_tables.push(table.name.clone());
+                self.update_table_internal(trx, &stored).await?;
+            }
+        }
         Ok(())
     }
 
@@ -75,6 +645,10 @@ impl Database {
     /// adds the specified index to the table's metadata, and updates the table's information
     /// in the database.
     ///
+    /// The index is stored in the `Backfilling` state while rows already stored in the table
+    /// are indexed by [`Database::backfill_index`], then switched to `Ready`, so lookups
+    /// through the new index are complete once this method returns.
+    ///
     /// # Arguments
     ///
     /// * `table_name` - The name of the table to which the index should be added.
@@ -83,465 +657,9585 @@ impl Database {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The index name is not a valid identifier.
     /// - The table with the specified name does not exist.
+    /// - The table already has an index of the same name, or lacks one of the indexed fields.
     /// - The table update operation fails due to a database error.
+    /// - The backfill of the existing rows fails.
     async fn add_index(&self, table_name: &str, index: &table::Index) -> crate::errors::Result<()> {
-        self.storage
-            .database
-            .run(|trx, _| async move {
+        crate::sql::check_identifier(index.name())?;
+        let mut index = index.clone();
+        index.set_state(IndexState::Backfilling);
+        let index = &index;
+        let table = self
+            .write(table_name, |trx, _| async move {
                 let mut table = self
                     .get_table_internal(&trx, table_name)
                     .await?
                     .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
                 table.add_index(index);
-                self.update_table_internal(&trx, table).await?;
-                Ok(())
+                table.check_indexes()?;
+                table.check_blob_fields()?;
+                table.check_array_fields()?;
+                self.update_table_internal(&trx, &table).await?;
+                Ok(table)
             })
             .await?;
-        Ok(())
+        self.backfill_index(&table, index).await?;
+        self.set_index_state(table_name, index.name(), IndexState::Ready)
+            .await
     }
 
+    /// Adds a field at the end of a table, without rewriting the rows already stored.
     ///
-    /// Retrieves a table from the database by its name.
-    ///
-    /// This method fetches a serialized table using the provided name from the database.
-    /// If the table is found, it deserializes the bytes into a `Table` instance and
-    /// returns it. If the table does not exist, `None` is returned.
+    /// Rows written before the field was added hold no column for it: they are read with
+    /// the default of the field in its place, as are the records inserted without it. The
+    /// given default replaces any default set on the field, a null default leaving the
+    /// field without one.
     ///
     /// # Arguments
     ///
-    /// * `trx` - A reference to a `RetryableTransaction` used for the operation.
-    /// * `table_name` - The name of the table to be retrieved.
+    /// * `table_name` - The name of the table to which the field should be added.
+    /// * `field` - The field to add.
+    /// * `default` - The value of the field in the rows already stored.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// * `Ok(Some(Table))` if the table is found and successfully deserialized.
-    /// * `Ok(None)` if the table does not exist in the database.
-    /// * `Err` if an error occurs during the deserialization or retrieval process.
-    async fn get_table_internal(
+    /// Returns an error if:
+    /// - The field name is not a valid identifier.
+    /// - The table does not exist, or already has a field of the same name.
+    /// - The default doesn't match the type of the field, or is null while the field is
+    ///   `NOT NULL`, reported as `SqlLayerError::MismatchedColumnType`.
+    /// - The field is a blob, array or enum field that isn't valid, or is collated without
+    ///   holding strings, or is a serial field, the existing rows having no value for it.
+    /// - The table update operation fails due to a database error.
+    async fn add_column(
         &self,
-        trx: &RetryableTransaction,
         table_name: &str,
-    ) -> crate::errors::Result<Option<Table>> {
-        let key = self
-            .root_subspace
-            .subspace(&DataPrefix::Table)
-            .pack(&table_name);
-        let bytes = trx.get(&key, false).await?;
-        match bytes {
-            Some(bytes) => Ok(Some(Table::from_bytes(&bytes)?)),
-            None => Ok(None),
-        }
+        field: Field,
+        default: Column,
+    ) -> crate::errors::Result<()> {
+        let field = &with_default(field, default)?;
+        self.write(table_name, |trx, _| async move {
+            Ok(self.add_column_internal(&trx, table_name, field).await?)
+        })
+        .await
     }
 
-    async fn update_table_internal(
+    /// Adds a field at the end of a table within a transaction, see
+    /// [`Database::add_column`].
+    async fn add_column_internal(
         &self,
         trx: &RetryableTransaction,
-        table: Table,
+        table_name: &str,
+        field: &Field,
     ) -> crate::errors::Result<()> {
-        let key = self
-            .root_subspace
-            .subspace(&DataPrefix::Table)
-            .pack(&table.name);
-        let bytes = table.to_bytes()?;
-        trx.set(&key, &bytes);
-        Ok(())
+        let mut table = self
+            .get_table_internal(trx, table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        if table.get_field_pos(&field.name).is_some() {
+            return Err(SqlLayerError::FieldAlreadyExists(field.name.clone()));
+        }
+        table.add_field(field.clone());
+        table.check_blob_fields()?;
+        table.check_array_fields()?;
+        table.check_enum_fields()?;
+        table.check_collations()?;
+        table.check_serial_fields()?;
+        self.update_table_internal(trx, &table).await
     }
 
-    async fn get_table(&self, table_name: &str) -> crate::errors::Result<Option<Table>> {
-        let table = self
-            .storage
-            .database
-            .run(|trx, _| async move { Ok(self.get_table_internal(&trx, table_name).await?) })
-            .await?;
-        Ok(table)
+    /// Drops a field from a table.
+    ///
+    /// The stored rows are left untouched: the column of the field stays in place and is
+    /// ignored when the rows are read, while the rows written from then on hold a null in
+    /// its place. The field can't be part of the primary key, nor be used by an index, a
+    /// check or a foreign key of the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table from which the field should be dropped.
+    /// * `field_name` - The name of the field to drop.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist, or has no field of this name.
+    /// - The field is part of the primary key, or is used by an index, a check or a foreign
+    ///   key.
+    /// - The table update operation fails due to a database error.
+    async fn drop_column(&self, table_name: &str, field_name: &str) -> crate::errors::Result<()> {
+        self.write(table_name, |trx, _| async move {
+            let mut table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            table.drop_field(field_name)?;
+            self.update_table_internal(&trx, &table).await?;
+            Ok(())
+        })
+        .await
     }
 
-    /// Retrieves the metadata of a table from the database by its name.
+    /// Renames a field of a table.
     ///
-    /// This method fetches the serialized metadata of a table using the provided
-    /// table name. If the metadata is not found, a new `TableMetadata` instance is
-    /// created, serialized, and stored in the database before being returned.
+    /// The primary key, the indexes and the foreign keys of the table follow the new name,
+    /// and so do the foreign keys of other tables, which reference primary keys by
+    /// position. The checks are expressions over field names: a field used by a check
+    /// can't be renamed.
     ///
     /// # Arguments
     ///
-    /// * `trx` - A reference to a `RetryableTransaction` used to perform the operation.
-    /// * `table_name` - The name of the table whose metadata needs to be retrieved.
+    /// * `table_name` - The name of the table holding the field.
+    /// * `from` - The current name of the field.
+    /// * `to` - The new name of the field.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// * `Ok(TableMetadata)` containing the metadata if retrieval or creation succeeds.
-    /// * `Err` if an error occurs during retrieval, creation, or serialization.
-    async fn get_table_meta(
+    /// Returns an error if:
+    /// - The new name is not a valid identifier.
+    /// - The table does not exist, has no field named `from` or already has a field named
+    ///   `to`.
+    /// - The field is used by a check.
+    /// - The table update operation fails due to a database error.
+    async fn rename_column(
         &self,
-        trx: &RetryableTransaction,
         table_name: &str,
-    ) -> crate::errors::Result<TableMetadata> {
-        let key = self
-            .root_subspace
-            .subspace(&DataPrefix::TableMeta)
-            .pack(&table_name);
-        let bytes = trx.get(&key, false).await?;
-        match bytes {
-            Some(bytes) => Ok(TableMetadata::from_bytes(&bytes)?),
-            None => {
-                let table_meta = TableMetadata::new(table_name.to_string());
-                let bytes = table_meta.to_bytes()?;
-                trx.set(&key, &bytes);
-                Ok(table_meta)
-            }
-        }
+        from: &str,
+        to: &str,
+    ) -> crate::errors::Result<()> {
+        self.write(table_name, |trx, _| async move {
+            let mut table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            table.rename_field(from, to)?;
+            self.update_table_internal(&trx, &table).await?;
+            Ok(())
+        })
+        .await
     }
 
-    /// Inserts a record into a specified table in the database.
+    async fn set_index_state(
+        &self,
+        table_name: &str,
+        index_name: &str,
+        state: IndexState,
+    ) -> crate::errors::Result<()> {
+        self.write(table_name, |trx, _| async move {
+            let mut table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            table
+                .get_index_mut(index_name)
+                .ok_or(SqlLayerError::IndexNotFound(index_name.to_string()))?
+                .set_state(state);
+            self.update_table_internal(&trx, &table).await?;
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the indexes of a table along with their health.
     ///
-    /// This method validates the provided record against the table's schema, ensuring that
-    /// all required fields are present and match the expected data types. It then constructs
-    /// a primary key based on the table's schema and stores the record in the database.
+    /// For every index, the report holds its definition, its build state, the size of its
+    /// entries as estimated by FoundationDB, when the cluster supports it, and its exact
+    /// number of entries. Counting entries scans the whole index, so this is meant for
+    /// operational tooling rather than hot paths.
     ///
     /// # Arguments
     ///
-    /// * `table_name` - The name of the table where the record is to be inserted.
-    /// * `record` - A reference to the `Record` that contains the data to be inserted.
+    /// * `table_name` - The name of the table whose indexes are listed.
     ///
     /// # Errors
     ///
     /// Returns an error if:
     /// - The table does not exist.
-    /// - The record is missing required fields or has fields that do not match the schema.
-    /// - An error occurs during the storage operation, such as a database write failure.
-    async fn insert(&self, table_name: &str, record: &Record) -> crate::errors::Result<()> {
-        self.storage
-            .database
-            .run(|trx, _| async move {
-                if let Some(table) = self.get_table_internal(&trx, table_name).await? {
-                    // check column fit table fields
-                    for (field, column) in zip(table.fields.iter(), record.columns.iter()) {
-                        check_field_against_column(&field.r#type, column)?;
-                    }
-
-                    // build the primary key tuple
-                    let mut pk = vec![];
-                    for field in table.primary_key.iter() {
-                        let i = table
-                            .get_field_pos(field)
-                            .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
-                        let columns = record
-                            .columns
-                            .get(i)
-                            .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
-                        pk.push(columns);
-                    }
-                    let mut meta = self.get_table_meta(&trx, table_name).await?;
-                    let row_id = meta.get_current_row_id() as i64;
-
-                    // store the primary key
-                    let pk = Columns::new(&pk);
-                    // dbg!("Inserting record with primary key {:?}", &pk);
-                    let subspace_pk = self
-                        .root_subspace
-                        .subspace(&DataPrefix::PrimaryKey)
-                        .subspace(&table_name)
-                        .pack(&pk);
-
-                    trx.set(&subspace_pk, pack(&row_id).as_ref());
-
-                    // store index
-                    for index in &table.indexes {
-                        let mut columns = vec![];
-                        for field in index.fields() {
-                            let i = table
-                                .get_field_pos(field)
-                                .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
-                            let column = record
-                                .columns
-                                .get(i)
-                                .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
-                            columns.push(column);
-                        }
-                        let columns = Columns::new(&columns);
-                        let subspace_index = self
-                            .root_subspace
-                            .subspace(&DataPrefix::Index)
-                            .subspace(&table_name)
-                            .pack(&columns);
-                        println!(
-                            "Inserting record with index {} {:?}",
-                            index.name(),
-                            &columns
-                        );
-                        trx.set(&subspace_index, pack(&row_id).as_ref());
-                    }
+    /// - There is an issue with the database read operations.
+    async fn list_indexes(&self, table_name: &str) -> crate::errors::Result<Vec<IndexStatus>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let mut statuses = vec![];
+        for index in &table.indexes {
+            statuses.push(self.index_status(&table, index).await?);
+        }
+        Ok(statuses)
+    }
 
-                    // store the record
-                    let row: Row = record.into();
-                    let row_bytes = row.to_bytes()?;
-                    let key = self
-                        .root_subspace
-                        .subspace(&DataPrefix::Row)
-                        .subspace(&table_name)
-                        .pack(&row_id);
-                    trx.set(&key, &row_bytes);
-
-                    // increment row_id
-                    meta.increment_max_row_id();
-                    let meta_bytes = meta.to_bytes()?;
-                    trx.set(
-                        &self
-                            .root_subspace
-                            .subspace(&DataPrefix::TableMeta)
-                            .pack(&table_name),
-                        &meta_bytes,
-                    );
-                }
+    /// Inspects a single index of a table, as [`Database::list_indexes`] does for all of
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table holding the index.
+    /// * `index_name` - The name of the index to inspect.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table or the index does not exist.
+    /// - There is an issue with the database read operations.
+    async fn inspect_index(
+        &self,
+        table_name: &str,
+        index_name: &str,
+    ) -> crate::errors::Result<IndexStatus> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let index = table
+            .get_index(index_name)
+            .ok_or(SqlLayerError::IndexNotFound(index_name.to_string()))?;
+        self.index_status(&table, index).await
+    }
 
-                Ok(())
+    /// Reports the definition, build state and size of an index.
+    async fn index_status(
+        &self,
+        table: &Table,
+        index: &table::Index,
+    ) -> crate::errors::Result<IndexStatus> {
+        let (start, end) = self.index_subspace(table, index.name()).range();
+        let approximate_size = if self.storage.capabilities().estimated_range_size {
+            Some(self.storage.estimated_size(&start, &end).await?)
+        } else {
+            None
+        };
+        Ok(IndexStatus {
+            name: index.name().to_string(),
+            fields: index.fields().clone(),
+            unique: index.is_unique(),
+            state: index.state(),
+            approximate_size,
+            entry_count: self.storage.count(&start, &end).await?,
+        })
+    }
+
+    /// Computes an order-independent checksum of the rows of a table.
+    ///
+    /// Every row is hashed from its record, regardless of its row id, and the hashes are
+    /// summed. Two tables holding the same records thus share the same checksum even if
+    /// they were inserted in another order, which allows verifying a copy or a replica
+    /// against its source. The stored bytes of a row depend on how it was written, its
+    /// compression, row schema or blob ids, so every record is decoded, along with its
+    /// blobs, and encoded with the generic schema of [`Row`] before being hashed.
+    ///
+    /// Rows are read in batches of [`CHECKSUM_BATCH_SIZE`], each batch in its own snapshot
+    /// transaction pinned to the same read version, so the checksum reflects a consistent
+    /// snapshot. FoundationDB only keeps about five seconds of history: the whole table
+    /// must be read within that window after the version.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to checksum.
+    /// * `at_version` - The read version of the snapshot, or `None` to use the current
+    ///   version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The version is too old or in the future.
+    /// - There is an issue with the database read operations.
+    async fn table_checksum(
+        &self,
+        table_name: &str,
+        at_version: Option<i64>,
+    ) -> crate::errors::Result<TableChecksum> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let version = match at_version {
+            Some(version) => version,
+            None => {
+                let trx = self.storage.database.create_trx()?;
+                self.storage.prepare_transaction(
+                    &trx,
+                    self.table_tag(table_name),
+                    CommitProfile::Background,
+                )?;
+                trx.get_read_version().await?
+            }
+        };
+
+        let mut checksum = TableChecksum {
+            version,
+            row_count: 0,
+            checksum: 0,
+        };
+        let row_subspace = self.table_subspace(DataPrefix::Row, &table);
+        let (mut start, end) = row_subspace.range();
+        loop {
+            // a plain transaction: a retry at the same version could never succeed
+            let trx = self.storage.database.create_trx()?;
+            self.storage.prepare_transaction(
+                &trx,
+                self.table_tag(table_name),
+                CommitProfile::Background,
+            )?;
+            trx.set_read_version(version);
+            let mut range = RangeOption::from((start.as_slice(), end.as_slice()));
+            range.limit = Some(CHECKSUM_BATCH_SIZE);
+            let kvs = trx.get_range(&range, 1, true).await?;
+            let Some(last) = kvs.last() else {
+                break;
+            };
+            for kv in kvs.iter() {
+                let row_id = row_subspace
+                    .unpack::<i64>(kv.key())
+                    .map_err(FdbBindingError::PackError)?;
+                let record = self
+                    .read_record(&trx, &table, row_id, kv.value(), None)
+                    .await?;
+                let hash = row_hash(&AvroCodec.encode(&record.to_stored_row(&table))?);
+                checksum.row_count += 1;
+                checksum.checksum = checksum.checksum.wrapping_add(hash);
+            }
+            // resume right after the last read row
+            start = last.key().to_vec();
+            start.push(0x00);
+        }
+        Ok(checksum)
+    }
+
+    /// Materializes the entries of `index` for the rows already stored in the table.
+    ///
+    /// Rows are read and indexed in batches of [`BACKFILL_BATCH_SIZE`], each batch in its
+    /// own transaction so that large tables don't hit FoundationDB transaction limits.
+    /// Rows written concurrently are indexed by the write path as soon as the index is part
+    /// of the table definition, rewriting their entry here is harmless.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a stored row can't be decoded or a batch transaction fails, or if
+    /// the index is unique and existing rows share the same indexed values. The index is then
+    /// left in the `Backfilling` state.
+    async fn backfill_index(
+        &self,
+        table: &Table,
+        index: &table::Index,
+    ) -> crate::errors::Result<()> {
+        let row_subspace = self.table_subspace(DataPrefix::Row, table);
+        let (mut start, end) = row_subspace.range();
+        let end = end.as_slice();
+        loop {
+            let batch_start = start.as_slice();
+            let row_subspace = &row_subspace;
+            let last_key = self
+                .write_with_profile(
+                    CommitProfile::Background,
+                    self.table_tag(&table.name),
+                    |trx, _| async move {
+                        let mut range = RangeOption::from((batch_start, end));
+                        range.limit = Some(BACKFILL_BATCH_SIZE);
+                        let kvs = trx.get_range(&range, 1, false).await?;
+                        let mut last_key = None;
+                        for kv in kvs.iter() {
+                            self.index_stored_row(&trx, table, index, row_subspace, kv)
+                                .await?;
+                            last_key = Some(kv.key().to_vec());
+                        }
+                        Ok(last_key)
+                    },
+                )
+                .await?;
+            let Some(mut last_key) = last_key else {
+                break;
+            };
+            // resume right after the last indexed row
+            last_key.push(0x00);
+            start = last_key;
+        }
+        Ok(())
+    }
+
+    /// Writes the entry of a stored row into an index being built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row can't be decoded, or if the index is unique and another
+    /// row already has the same indexed values.
+    async fn index_stored_row(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        index: &table::Index,
+        row_subspace: &Subspace,
+        kv: &FdbKeyValue,
+    ) -> crate::errors::Result<()> {
+        let row_id = row_subspace
+            .unpack::<i64>(kv.key())
+            .map_err(FdbBindingError::PackError)?;
+        let row = self.resolve_row(trx, table, row_id, kv.value()).await?;
+        let record = self.decode_record(table, &row)?;
+        if index.is_unique() {
+            self.check_unique(trx, table, index, &record, row_id)
+                .await?;
+        }
+        for subspace_index in self.index_entry_keys(table, index, &record, row_id)? {
+            self.set_index_entry(trx, index, &subspace_index, row_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the schema version of the database: the version of the last migration
+    /// applied, see [`Database::migrate`], or 0 if none was.
+    async fn schema_version(&self) -> crate::errors::Result<i64> {
+        self.storage
+            .run(None, |trx, _| async move {
+                Ok(self.schema_version_internal(&trx).await?)
+            })
+            .await
+    }
+
+    async fn schema_version_internal(
+        &self,
+        trx: &RetryableTransaction,
+    ) -> crate::errors::Result<i64> {
+        let key = self.root_subspace.pack(&DataPrefix::SchemaVersion);
+        match trx.get(&key, false).await? {
+            Some(bytes) => Ok(unpack::<i64>(&bytes).map_err(FdbBindingError::PackError)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Lists the migrations applied to the database, as their versions and descriptions,
+    /// in the order of their versions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an issue with the database read operation.
+    async fn applied_migrations(&self) -> crate::errors::Result<Vec<(i64, String)>> {
+        let subspace = &self.root_subspace.subspace(&DataPrefix::Migration);
+        self.storage
+            .run(None, |trx, _| async move {
+                let (start, end) = subspace.range();
+                let entries = trx
+                    .get_ranges_keyvalues(
+                        RangeOption::from((start.as_slice(), end.as_slice())),
+                        false,
+                    )
+                    .map_ok(|entry| (entry.key().to_vec(), entry.value().to_vec()))
+                    .try_collect::<Vec<_>>()
+                    .await?;
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((subspace.unpack::<i64>(key)?, unpack::<String>(value)?))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(FdbBindingError::PackError)
             })
+            .await
+    }
+
+    /// Applies the registered migrations the database hasn't been migrated to yet, in the
+    /// order of their versions, see the [`crate::migrations`] module.
+    ///
+    /// Every migration is applied in a single transaction, along with the record of its
+    /// version: a migration failing leaves the database at the previous version, and
+    /// stops the migrations. The transactions of instances applying the same migration
+    /// at the same time conflict on the schema version, the instances losing the race find
+    /// the migration applied once they retry, and skip it.
+    ///
+    /// The indexes added by a migration are built, and its backfills applied, within its
+    /// transaction: the rows of the tables they apply to must be read and written within
+    /// the limits of a transaction. Larger tables are indexed with
+    /// [`Database::add_index`], which builds indexes in batches.
+    ///
+    /// # Arguments
+    ///
+    /// * `migrations` - The migrations of the application.
+    ///
+    /// # Returns
+    ///
+    /// Returns the schema version of the database once migrated.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first migration failing, see [`Database::add_column`],
+    /// [`Database::add_index`] and [`Database::update`] for the errors of its steps.
+    async fn migrate(&self, migrations: &Migrations) -> crate::errors::Result<i64> {
+        let key = &self.root_subspace.pack(&DataPrefix::SchemaVersion);
+        let mut version = self.schema_version().await?;
+        for migration in migrations.pending(version) {
+            version = self
+                .write_with_profile(CommitProfile::Interactive, None, |trx, _| async move {
+                    let version = self.schema_version_internal(&trx).await?;
+                    if version >= migration.version {
+                        return Ok(version);
+                    }
+                    for step in &migration.steps {
+                        self.apply_migration_step(&trx, step).await?;
+                    }
+                    trx.set(key, &pack(&migration.version));
+                    let record = self
+                        .root_subspace
+                        .subspace(&DataPrefix::Migration)
+                        .pack(&migration.version);
+                    trx.set(&record, &pack(&migration.description));
+                    Ok(migration.version)
+                })
+                .await?;
+        }
+        Ok(version)
+    }
+
+    /// Applies a step of a migration within its transaction.
+    async fn apply_migration_step(
+        &self,
+        trx: &RetryableTransaction,
+        step: &MigrationStep,
+    ) -> crate::errors::Result<()> {
+        match step {
+            MigrationStep::AddColumn {
+                table,
+                field,
+                default,
+            } => {
+                let field = with_default(field.clone(), default.clone())?;
+                self.add_column_internal(trx, table, &field).await
+            }
+            MigrationStep::AddIndex { table, index } => {
+                self.build_index_internal(trx, table, index).await
+            }
+            MigrationStep::Backfill { table, update } => {
+                self.backfill_records_internal(trx, table, update).await
+            }
+        }
+    }
+
+    /// Adds an index to a table and builds it from every stored row, within a single
+    /// transaction, the index being ready once the transaction commits.
+    async fn build_index_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table_name: &str,
+        index: &table::Index,
+    ) -> crate::errors::Result<()> {
+        crate::sql::check_identifier(index.name())?;
+        let mut table = self
+            .get_table_internal(trx, table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let mut index = index.clone();
+        index.set_state(IndexState::Ready);
+        table.add_index(&index);
+        self.update_table_internal(trx, &table).await?;
+
+        let row_subspace = self.table_subspace(DataPrefix::Row, &table);
+        let (start, end) = row_subspace.range();
+        let kvs = trx
+            .get_ranges_keyvalues(RangeOption::from((start.as_slice(), end.as_slice())), false)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for kv in &kvs {
+            self.index_stored_row(trx, &table, &index, &row_subspace, kv)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites every record of a table with a function within a single transaction, the
+    /// records left unchanged being skipped.
+    async fn backfill_records_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table_name: &str,
+        update: &Backfill,
+    ) -> crate::errors::Result<()> {
+        let table = self
+            .get_table_internal(trx, table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let row_subspace = self.table_subspace(DataPrefix::Row, &table);
+        let (start, end) = row_subspace.range();
+        let rows = trx
+            .get_ranges_keyvalues(RangeOption::from((start.as_slice(), end.as_slice())), false)
+            .try_collect::<Vec<_>>()
             .await?;
+        for kv in &rows {
+            let row_id = row_subspace
+                .unpack::<i64>(kv.key())
+                .map_err(FdbBindingError::PackError)?;
+            let record = self
+                .read_record(trx, &table, row_id, kv.value(), None)
+                .await?;
+            let updated = update(record.clone())?;
+            if updated == record {
+                continue;
+            }
+            let pk = extract_columns(&table, &table.primary_key, &record)?;
+            self.update_internal(trx, &table, &Columns::new(&pk), &updated)
+                .await?;
+        }
         Ok(())
     }
 
     ///
-    /// Fetches a record from the database based on the given primary key.
+    /// Retrieves a table from the database by its name.
     ///
-    /// # Parameters
+    /// This method fetches a serialized table using the provided name from the database.
+    /// If the table is found, it deserializes the bytes into a `Table` instance and
+    /// returns it. If the table does not exist, `None` is returned.
     ///
-    /// - `table_name`: The name of the table from which to fetch the record.
-    /// - `pk`: A reference to the primary key of the record to retrieve.
+    /// # Arguments
+    ///
+    /// * `trx` - A reference to a `RetryableTransaction` used for the operation.
+    /// * `table_name` - The name of the table to be retrieved.
     ///
     /// # Returns
     ///
-    /// Returns `Ok(Some(record))` containing the record if it exists, `Ok(None)` if the record
-    /// does not exist, or an error if the operation fails.
+    /// * `Ok(Some(Table))` if the table is found and successfully deserialized.
+    /// * `Ok(None)` if the table does not exist in the database.
+    /// * `Err` if an error occurs during the deserialization or retrieval process.
     ///
-    /// # Errors
+    /// Definitions are cached along with their version: every change to a definition sets
+    /// its version to the versionstamp of the transaction, so reading the small version
+    /// key is enough to validate the cached definition. The definition itself is only read
+    /// and decoded once it changed, or when the transaction changed it itself, as its new
+    /// version can't be read before the transaction commits.
     ///
-    /// Returns an error if:
-    /// - The table does not exist.
-    /// - The provided primary key does not match the schema.
-    /// - There is an issue with the database read operation.
-    // todo: use [get_mapped_ranges] instead
-    async fn get_record_by_pk(
+    /// The row schemas of the table are loaded along with its definition, see
+    /// [`Database::decode_record`].
+    async fn get_table_internal(
         &self,
+        trx: &RetryableTransaction,
         table_name: &str,
-        pk: &Columns<'_>,
-    ) -> crate::errors::Result<Option<Record>> {
-        let kv = self
-            .storage
-            .database
-            .run(|trx, _| async move {
-                // build primary key subspace out of the primary key columns
-                let subspace_pk = self
-                    .root_subspace
-                    .subspace(&DataPrefix::PrimaryKey)
-                    .subspace(&table_name)
-                    .pack(&pk);
+    ) -> crate::errors::Result<Option<Table>> {
+        let version = match trx.get(&self.table_version_key(table_name), false).await {
+            Ok(version) => Some(version.map(|version| version.to_vec())),
+            Err(error) if error.code() == ACCESSED_UNREADABLE => None,
+            Err(error) => return Err(error.into()),
+        };
+        if let Some(version) = &version {
+            if let Some((_, table)) = self
+                .table_cache()
+                .get(table_name)
+                .filter(|(cached, _)| cached == version)
+            {
+                return Ok(Some(table.clone()));
+            }
+        }
+
+        let key = self
+            .root_subspace
+            .subspace(&DataPrefix::Table)
+            .pack(&table_name);
+        let Some(bytes) = trx.get(&key, false).await? else {
+            return Ok(None);
+        };
+        let table = Table::from_bytes_with_mode(&bytes, self.schema_mode)?;
+        if let Some(version) = version {
+            self.load_row_schemas(trx, table_name).await?;
+            self.table_cache()
+                .insert(table_name.to_string(), (version, table.clone()));
+        }
+        Ok(Some(table))
+    }
 
-                // get the row_id
-                let kv = trx.get(&subspace_pk, false).await?;
+    /// Returns the key of a row schema of a table, stored along with its metadata.
+    fn row_schema_key(&self, table_name: &str, fingerprint: u64) -> Vec<u8> {
+        self.root_subspace
+            .subspace(&DataPrefix::TableMeta)
+            .subspace(&(table_name, "row_schema"))
+            .pack(&fingerprint)
+    }
 
-                let Some(kv) = kv else {
-                    return Ok(None);
-                };
+    /// Stores the row schema of the current definition of a table.
+    ///
+    /// The rows of the table are only encoded with it once it is known to be stored, that
+    /// is once a later transaction loaded it, see [`Database::encode_row`].
+    fn store_row_schema(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+    ) -> crate::errors::Result<()> {
+        if table.row_encoding != RowEncoding::Avro {
+            return Ok(());
+        }
+        let schema = RowSchema::new(table)?;
+        trx.set(
+            &self.row_schema_key(&table.name, schema.fingerprint()),
+            schema.json().as_bytes(),
+        );
+        Ok(())
+    }
+
+    /// Loads every row schema stored for a table into the cache of this instance.
+    async fn load_row_schemas(
+        &self,
+        trx: &RetryableTransaction,
+        table_name: &str,
+    ) -> crate::errors::Result<()> {
+        let (start, end) = self
+            .root_subspace
+            .subspace(&DataPrefix::TableMeta)
+            .subspace(&(table_name, "row_schema"))
+            .range();
+        let schemas = trx
+            .get_ranges_keyvalues(RangeOption::from((start.as_slice(), end.as_slice())), false)
+            .map_ok(|entry| entry.value().to_vec())
+            .try_collect::<Vec<_>>()
+            .await?;
+        for json in schemas {
+            let json = String::from_utf8(json)
+                .map_err(|error| SqlLayerError::Deserialization(error.to_string()))?;
+            let schema = RowSchema::parse(&json)?;
+            self.row_schema_cache().insert(
+                (table_name.to_string(), schema.fingerprint()),
+                Arc::new(schema),
+            );
+        }
+        Ok(())
+    }
+
+    fn row_schema_cache(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<(String, u64), Arc<RowSchema>>> {
+        // schemas are never changed once stored, a panicked holder can't leave them stale
+        self.row_schemas
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the key of the version of the definition of a table.
+    fn table_version_key(&self, table_name: &str) -> Vec<u8> {
+        self.root_subspace
+            .subspace(&DataPrefix::TableVersion)
+            .pack(&table_name)
+    }
+
+    /// Sets the version of the definition of a table to the versionstamp of the transaction.
+    fn touch_table_version(&self, trx: &RetryableTransaction, table_name: &str) {
+        // the versionstamp placeholder, followed by its offset in the value
+        let mut value = vec![0xff; 10];
+        value.extend_from_slice(&0u32.to_le_bytes());
+        trx.atomic_op(
+            &self.table_version_key(table_name),
+            &value,
+            MutationType::SetVersionstampedValue,
+        );
+    }
+
+    fn table_cache(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Option<Vec<u8>>, Table)>> {
+        // the cache is validated on every read, a panicked holder can't leave it stale
+        self.tables
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn update_table_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+    ) -> crate::errors::Result<()> {
+        let key = self
+            .root_subspace
+            .subspace(&DataPrefix::Table)
+            .pack(&table.name);
+        // every change to the definition bumps its version
+        let table = Table {
+            schema_version: table.schema_version + 1,
+            ..table.clone()
+        };
+        let bytes = table.to_bytes()?;
+        trx.set(&key, &bytes);
+        self.touch_table_version(trx, &table.name);
+        self.store_row_schema(trx, &table)?;
+        Ok(())
+    }
+
+    /// Decodes a row of a table into a record.
+    ///
+    /// Rows written before fields were added to the table are completed with the default
+    /// of the added fields, see [`Database::add_column`], and the columns of the dropped
+    /// fields are skipped, see [`Database::drop_column`].
+    ///
+    /// Rows are decompressed, see [`RowCompression`](crate::RowCompression), then decoded
+    /// with the codec of the table, see [`RowEncoding`]. Avro rows are decoded with the row
+    /// schema they were written with, see [`RowSchema`], or with the generic schema of
+    /// [`Row`] for the rows written before the table had one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::UnknownRowSchema` if the row was written with a schema not
+    /// loaded along with the table, that is by a definition of the table newer than the one
+    /// given.
+    fn decode_record(&self, table: &Table, bytes: &[u8]) -> crate::errors::Result<Record> {
+        let bytes = &compression::decompress(bytes)?;
+        let row = match RowSchema::fingerprint_of(bytes) {
+            _ if table.row_encoding != RowEncoding::Avro => table
+                .row_encoding
+                .codec()?
+                .decode(bytes, self.schema_mode)?,
+            Some(fingerprint) => {
+                let schema = self
+                    .row_schema_cache()
+                    .get(&(table.name.clone(), fingerprint))
+                    .cloned()
+                    .ok_or_else(|| {
+                        SqlLayerError::UnknownRowSchema(table.name.clone(), fingerprint)
+                    })?;
+                schema.decode(bytes, self.schema_mode)?
+            }
+            None => AvroCodec.decode(bytes, self.schema_mode)?,
+        };
+        Ok(Record::from_stored_row(row, table))
+    }
+
+    /// Encodes a stored row of a table, with the codec of the table, then compresses it
+    /// according to the table, see [`RowCompression`](crate::RowCompression).
+    ///
+    /// Avro rows are encoded with the row schema of the current definition of the table
+    /// once it is known to be stored, so that every instance can decode them. Until then, that is
+    /// in the transaction changing the definition or for the tables created before row
+    /// schemas, they are encoded with the generic schema of [`Row`].
+    fn encode_row(&self, table: &Table, row: &Row) -> crate::errors::Result<Vec<u8>> {
+        let bytes = if table.row_encoding != RowEncoding::Avro {
+            table.row_encoding.codec()?.encode(row)?
+        } else {
+            let schema = RowSchema::new(table)?;
+            let stored = self
+                .row_schema_cache()
+                .contains_key(&(table.name.clone(), schema.fingerprint()));
+            if stored {
+                schema.encode(row)?
+            } else {
+                AvroCodec.encode(row)?
+            }
+        };
+        compression::compress(bytes, table.compression, table.compression_threshold)
+    }
+
+    /// Returns the subspace of the chunks of a row, see [`Database::set_row`].
+    fn row_chunks(&self, table: &Table, row_id: i64) -> Subspace {
+        self.table_subspace(DataPrefix::RowChunk, table)
+            .subspace(&row_id)
+    }
+
+    /// Writes an encoded row, replacing the previous version of the row if any.
+    ///
+    /// Rows larger than a FoundationDB value are split into chunks of [`MAX_VALUE_SIZE`],
+    /// stored under the row id and their position, the key of the row then holding the
+    /// [`CHUNKED_ROW`] header. Rows are reassembled on read by [`Database::resolve_row`].
+    fn set_row(&self, trx: &Transaction, table: &Table, row_id: i64, bytes: &[u8]) {
+        let chunks = self.row_chunks(table, row_id);
+        let (start, end) = chunks.range();
+        trx.clear_range(&start, &end);
+        let key = self.table_subspace(DataPrefix::Row, table).pack(&row_id);
+        if bytes.len() <= MAX_VALUE_SIZE {
+            trx.set(&key, bytes);
+            return;
+        }
+        let mut header = CHUNKED_ROW.to_vec();
+        header.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        trx.set(&key, &header);
+        for (position, chunk) in bytes.chunks(MAX_VALUE_SIZE).enumerate() {
+            trx.set(&chunks.pack(&(position as i64)), chunk);
+        }
+    }
+
+    /// Clears a row, along with its chunks, see [`Database::set_row`], and its blobs, see
+    /// [`Database::store_blobs`].
+    fn clear_row(&self, trx: &Transaction, table: &Table, row_id: i64) {
+        trx.clear(&self.table_subspace(DataPrefix::Row, table).pack(&row_id));
+        for subspace in [
+            self.row_chunks(table, row_id),
+            self.row_blobs(table, row_id),
+        ] {
+            let (start, end) = subspace.range();
+            trx.clear_range(&start, &end);
+        }
+    }
+
+    /// Reassembles a row from the value stored under its key, read in the same
+    /// transaction. Rows stored in a single value are returned as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Deserialization` if the chunks don't add up to the length
+    /// of the row, or an error if the chunks can't be read.
+    async fn resolve_row<'a>(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+        bytes: &'a [u8],
+    ) -> crate::errors::Result<Cow<'a, [u8]>> {
+        let Some(length) = chunked_length(bytes) else {
+            return Ok(Cow::Borrowed(bytes));
+        };
+        let row = read_chunks(trx, &self.row_chunks(table, row_id), length)
+            .await?
+            .ok_or_else(|| {
+                SqlLayerError::Deserialization(format!(
+                    "chunks of row {row_id} of table {} don't add up to {length} bytes",
+                    table.name
+                ))
+            })?;
+        Ok(Cow::Owned(row))
+    }
+
+    /// Reads an encoded row by its id, reassembling its chunks if any.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the row doesn't exist.
+    async fn get_row(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+    ) -> crate::errors::Result<Option<Vec<u8>>> {
+        let key = self.table_subspace(DataPrefix::Row, table).pack(&row_id);
+        let Some(bytes) = trx.get(&key, false).await? else {
+            return Ok(None);
+        };
+        let row = self.resolve_row(trx, table, row_id, &bytes).await?;
+        Ok(Some(row.into_owned()))
+    }
+
+    /// Decodes a row read in a transaction into a record, see [`Database::decode_record`],
+    /// reassembling its chunks and reading its blobs.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The positions of the columns read, or `None` for every column: the
+    ///   blobs of the other columns are not read, their columns holding the id of the blob.
+    async fn read_record(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+        bytes: &[u8],
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<Record> {
+        let row = self.resolve_row(trx, table, row_id, bytes).await?;
+        let mut record = self.decode_record(table, &row)?;
+        self.load_blobs(trx, table, row_id, &mut record, positions)
+            .await?;
+        Ok(record)
+    }
+
+    /// Reads a record by its row id, along with its blobs, see [`Database::read_record`].
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the row doesn't exist.
+    async fn get_record(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+    ) -> crate::errors::Result<Option<Record>> {
+        let key = self.table_subspace(DataPrefix::Row, table).pack(&row_id);
+        let Some(bytes) = trx.get(&key, false).await? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            self.read_record(trx, table, row_id, &bytes, None).await?,
+        ))
+    }
+
+    /// Decodes a row read outside of a transaction, e.g. by a scan spanning several ones.
+    ///
+    /// A row split into chunks, or referencing blobs among the columns read, is read again
+    /// in a new transaction along with its chunks and blobs, so that they all come from the
+    /// same version of the row, see [`Database::read_record`].
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the row was deleted before it was read again.
+    async fn read_scanned_record(
+        &self,
+        table: &Table,
+        row_id: i64,
+        bytes: Vec<u8>,
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<Option<Record>> {
+        if chunked_length(&bytes).is_none() {
+            let record = self.decode_record(table, &bytes)?;
+            if blob_ids(table, &record, positions).next().is_none() {
+                return Ok(Some(record));
+            }
+        }
+        let key = &self.table_subspace(DataPrefix::Row, table).pack(&row_id);
+        self.storage
+            .run(self.table_tag(&table.name), |trx, _| async move {
+                let Some(bytes) = trx.get(key, false).await? else {
+                    return Ok(None);
+                };
+                Ok(Some(
+                    self.read_record(&trx, table, row_id, &bytes, positions)
+                        .await?,
+                ))
+            })
+            .await
+    }
+
+    /// Returns the subspace of the blobs of a row, see [`Database::store_blobs`].
+    fn row_blobs(&self, table: &Table, row_id: i64) -> Subspace {
+        self.table_subspace(DataPrefix::Blob, table)
+            .subspace(&row_id)
+    }
+
+    /// Writes the blobs of the blob fields of a record, see [`Field::new_blob`], clearing
+    /// the blobs of the previous version of the row, including the ones of dropped fields.
+    ///
+    /// Every blob is stored under the row id and a blob id taken from the row ids of the
+    /// table: its length, then its chunks of [`MAX_VALUE_SIZE`] by position. Blobs are
+    /// never modified, a new value is a new blob, so that a blob streamed across several
+    /// transactions is read entirely or detected as replaced, see [`Database::read_blob`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the record to store in the row, holding the id of its blob in the column of
+    /// every blob field that isn't null.
+    async fn store_blobs<'a>(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+        record: &'a Record,
+    ) -> crate::errors::Result<Cow<'a, Record>> {
+        let (start, end) = self.row_blobs(table, row_id).range();
+        trx.clear_range(&start, &end);
+        if !table.fields.iter().any(|field| field.blob) {
+            return Ok(Cow::Borrowed(record));
+        }
+        let mut columns = Vec::with_capacity(record.columns.len());
+        for (field, column) in zip(&table.fields, &record.columns) {
+            match column {
+                Column::Bytes(bytes) if field.blob => {
+                    let blob_id = self.allocate_row_id(&table.name).await?;
+                    self.set_blob(trx, table, row_id, blob_id, bytes);
+                    columns.push(Column::Int(blob_id));
+                }
+                column => columns.push(column.clone()),
+            }
+        }
+        Ok(Cow::Owned(Record::new(columns)))
+    }
+
+    /// Writes a blob of a row, see [`Database::store_blobs`].
+    fn set_blob(&self, trx: &Transaction, table: &Table, row_id: i64, blob_id: i64, bytes: &[u8]) {
+        let blobs = self.row_blobs(table, row_id);
+        trx.set(&blobs.pack(&blob_id), &(bytes.len() as u64).to_le_bytes());
+        let chunks = blobs.subspace(&blob_id);
+        for (position, chunk) in bytes.chunks(MAX_VALUE_SIZE).enumerate() {
+            trx.set(&chunks.pack(&(position as i64)), chunk);
+        }
+    }
+
+    /// Clears a blob of a row, see [`Database::store_blobs`].
+    fn clear_blob(&self, trx: &Transaction, table: &Table, row_id: i64, blob_id: i64) {
+        let blobs = self.row_blobs(table, row_id);
+        trx.clear(&blobs.pack(&blob_id));
+        let (start, end) = blobs.subspace(&blob_id).range();
+        trx.clear_range(&start, &end);
+    }
+
+    /// Reads the length of a blob of a row, or `None` if the blob doesn't exist.
+    async fn blob_length(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+        blob_id: i64,
+    ) -> crate::errors::Result<Option<usize>> {
+        let key = self.row_blobs(table, row_id).pack(&blob_id);
+        let Some(length) = trx.get(&key, false).await? else {
+            return Ok(None);
+        };
+        let length = <[u8; 8]>::try_from(length.as_ref()).map_err(|_| {
+            SqlLayerError::Deserialization(format!("invalid length of blob {blob_id}"))
+        })?;
+        Ok(Some(u64::from_le_bytes(length) as usize))
+    }
+
+    /// Replaces the ids of the blobs of a decoded record with the blobs, for the columns
+    /// at the given positions, or every column.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Deserialization` if a blob is missing or doesn't add up to
+    /// its length, or an error if a blob can't be read.
+    async fn load_blobs(
+        &self,
+        trx: &Transaction,
+        table: &Table,
+        row_id: i64,
+        record: &mut Record,
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<()> {
+        let blobs = blob_ids(table, record, positions).collect::<Vec<_>>();
+        for (position, blob_id) in blobs {
+            let chunks = self.row_blobs(table, row_id).subspace(&blob_id);
+            let blob = match self.blob_length(trx, table, row_id, blob_id).await? {
+                Some(length) => read_chunks(trx, &chunks, length).await?,
+                None => None,
+            };
+            let blob = blob.ok_or_else(|| {
+                SqlLayerError::Deserialization(format!(
+                    "blob {blob_id} of row {row_id} of table {} is incomplete",
+                    table.name
+                ))
+            })?;
+            record.columns[position] = Column::Bytes(blob);
+        }
+        Ok(())
+    }
+
+    async fn get_table(&self, table_name: &str) -> crate::errors::Result<Option<Table>> {
+        let table = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                Ok(self.get_table_internal(&trx, table_name).await?)
+            })
+            .await?;
+        Ok(table)
+    }
+
+    /// Reads the definitions of every table of the database, in the order of their names.
+    async fn tables_internal(
+        &self,
+        trx: &RetryableTransaction,
+    ) -> crate::errors::Result<Vec<Table>> {
+        let (start, end) = self.root_subspace.subspace(&DataPrefix::Table).range();
+        let definitions = trx
+            .get_ranges_keyvalues(RangeOption::from((start.as_slice(), end.as_slice())), false)
+            .map_ok(|entry| entry.value().to_vec())
+            .try_collect::<Vec<_>>()
+            .await?;
+        definitions
+            .iter()
+            .map(|bytes| Table::from_bytes_with_mode(bytes, self.schema_mode))
+            .collect()
+    }
+
+    /// Lists the names of the tables of the database.
+    ///
+    /// # Returns
+    ///
+    /// Returns the names in the order of their keys, which for names in plain ASCII is the
+    /// alphabetical order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an issue with the database read operation or a table
+    /// definition can't be decoded.
+    async fn list_tables(&self) -> crate::errors::Result<Vec<String>> {
+        let tables = self
+            .storage
+            .run(None, |trx, _| async move {
+                Ok(self.tables_internal(&trx).await?)
+            })
+            .await?;
+        Ok(tables.into_iter().map(|table| table.name).collect())
+    }
+
+    /// Describes the schema of a table: its fields, primary key, indexes and constraints.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to describe.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read operation.
+    async fn describe(&self, table_name: &str) -> crate::errors::Result<TableDescription> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        Ok(table.into())
+    }
+
+    /// Retrieves the metadata of a table from the database by its name.
+    ///
+    /// This method fetches the serialized metadata of a table using the provided
+    /// table name. If the metadata is not found, a new `TableMetadata` instance is
+    /// created, serialized, and stored in the database before being returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `trx` - A reference to a `RetryableTransaction` used to perform the operation.
+    /// * `table_name` - The name of the table whose metadata needs to be retrieved.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TableMetadata)` containing the metadata if retrieval or creation succeeds.
+    /// * `Err` if an error occurs during retrieval, creation, or serialization.
+    async fn get_table_meta(
+        &self,
+        trx: &RetryableTransaction,
+        table_name: &str,
+    ) -> crate::errors::Result<TableMetadata> {
+        let key = self
+            .root_subspace
+            .subspace(&DataPrefix::TableMeta)
+            .pack(&table_name);
+        let bytes = trx.get(&key, false).await?;
+        match bytes {
+            Some(bytes) => Ok(TableMetadata::from_bytes_with_mode(
+                &bytes,
+                self.schema_mode,
+            )?),
+            None => {
+                let table_meta = TableMetadata::new(table_name.to_string());
+                let bytes = table_meta.to_bytes()?;
+                trx.set(&key, &bytes);
+                Ok(table_meta)
+            }
+        }
+    }
+
+    /// Returns the key of a payload counter of the given table.
+    fn stat_key(&self, table_name: &str, stat: TableStat) -> Vec<u8> {
+        self.root_subspace
+            .subspace(&DataPrefix::TableMeta)
+            .subspace(&table_name)
+            .pack(&stat)
+    }
+
+    /// Accounts for a row write in the payload statistics of the table.
+    ///
+    /// `previous` and `new` are the encoded sizes of the row before and after the write,
+    /// `None` when the row didn't exist before or is deleted.
+    fn record_row_write(
+        &self,
+        trx: &RetryableTransaction,
+        table_name: &str,
+        previous: Option<usize>,
+        new: Option<usize>,
+    ) {
+        let row_count = new.is_some() as i64 - previous.is_some() as i64;
+        let total_bytes = new.unwrap_or(0) as i64 - previous.unwrap_or(0) as i64;
+        // negative deltas wrap around, which is how little-endian additions subtract
+        for (stat, delta) in [
+            (TableStat::RowCount, row_count),
+            (TableStat::TotalBytes, total_bytes),
+        ] {
+            if delta != 0 {
+                trx.atomic_op(
+                    &self.stat_key(table_name, stat),
+                    &delta.to_le_bytes(),
+                    MutationType::Add,
+                );
+            }
+        }
+        if let Some(new) = new {
+            trx.atomic_op(
+                &self.stat_key(table_name, TableStat::LargestRow),
+                &(new as u64).to_le_bytes(),
+                MutationType::Max,
+            );
+        }
+    }
+
+    /// Returns the payload statistics of a table.
+    ///
+    /// The statistics are read from counters maintained by every write, see [`TableStats`],
+    /// so this doesn't scan the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read operations.
+    async fn stats(&self, table_name: &str) -> crate::errors::Result<TableStats> {
+        let stats = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                self.get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                let mut counters = [0u64; 3];
+                for (counter, stat) in counters.iter_mut().zip([
+                    TableStat::RowCount,
+                    TableStat::TotalBytes,
+                    TableStat::LargestRow,
+                ]) {
+                    let bytes = trx.get(&self.stat_key(table_name, stat), true).await?;
+                    *counter = bytes
+                        .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+                        .map(u64::from_le_bytes)
+                        .unwrap_or(0);
+                }
+                Ok(counters)
+            })
+            .await?;
+        let [row_count, total_bytes, largest_row] = stats;
+        Ok(TableStats {
+            row_count,
+            total_bytes,
+            largest_row,
+        })
+    }
+
+    /// Takes a new row id for a row of the table.
+    ///
+    /// Row ids are reserved by blocks of [`ROW_ID_BLOCK_SIZE`], in a transaction of their
+    /// own moving the row id counter of the table, and then handed out from memory. The
+    /// transactions inserting rows thus share no key and don't conflict with each other,
+    /// only reservations do, once per block. Tables created before the counter existed
+    /// start it from the row id of their metadata.
+    ///
+    /// Row ids are unique but not dense: the ids taken by a transaction that doesn't commit
+    /// and those left in memory when the instance is dropped are never used. When several
+    /// instances insert into a table, row id order follows insertion order within each
+    /// block only.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table the row is inserted into.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a block can't be reserved.
+    async fn allocate_row_id(&self, table_name: &str) -> crate::errors::Result<i64> {
+        if let Some(row_id) = self
+            .reserved_row_ids()
+            .get_mut(table_name)
+            .and_then(Iterator::next)
+        {
+            return Ok(row_id);
+        }
+        let mut block = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let key = self.stat_key(table_name, TableStat::NextRowId);
+                let start = match trx.get(&key, false).await? {
+                    Some(bytes) => <[u8; 8]>::try_from(bytes.as_ref())
+                        .map(i64::from_le_bytes)
+                        .unwrap_or(0),
+                    None => self
+                        .get_table_meta(&trx, table_name)
+                        .await?
+                        .get_current_row_id() as i64,
+                };
+                let end = start + ROW_ID_BLOCK_SIZE;
+                trx.set(&key, &end.to_le_bytes());
+                Ok(start..end)
+            })
+            .await?;
+        let row_id = block.next().unwrap_or(block.end);
+        // a block reserved concurrently by another insert of this instance is dropped
+        self.reserved_row_ids()
+            .insert(table_name.to_string(), block);
+        Ok(row_id)
+    }
+
+    fn reserved_row_ids(&self) -> std::sync::MutexGuard<'_, HashMap<String, Range<i64>>> {
+        // the blocks are consistent even if a holder of the lock panicked
+        self.row_ids
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Takes the next value of a serial field, see [`Field::new_serial`].
+    ///
+    /// Values are reserved by blocks of [`ROW_ID_BLOCK_SIZE`] from a counter of the field
+    /// starting at 1, and then handed out from memory, as row ids are, see
+    /// [`Database::allocate_row_id`]: they are unique, the transactions inserting rows don't
+    /// conflict on the counter, but values taken by a transaction that doesn't commit are
+    /// never used.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table the row is inserted into.
+    /// * `position` - The stored position of the field, which a rename doesn't change.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a block can't be reserved.
+    async fn allocate_serial(
+        &self,
+        table_name: &str,
+        position: usize,
+    ) -> crate::errors::Result<i64> {
+        let slot = (table_name.to_string(), position);
+        if let Some(value) = self
+            .reserved_serials()
+            .get_mut(&slot)
+            .and_then(Iterator::next)
+        {
+            return Ok(value);
+        }
+        let mut block = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let key = self
+                    .root_subspace
+                    .subspace(&DataPrefix::TableMeta)
+                    .subspace(&(table_name, TableStat::NextSerial))
+                    .pack(&(position as u64));
+                let start = trx
+                    .get(&key, false)
+                    .await?
+                    .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+                    .map_or(1, i64::from_le_bytes);
+                let end = start + ROW_ID_BLOCK_SIZE;
+                trx.set(&key, &end.to_le_bytes());
+                Ok(start..end)
+            })
+            .await?;
+        let value = block.next().unwrap_or(block.end);
+        // a block reserved concurrently by another insert of this instance is dropped
+        self.reserved_serials().insert(slot, block);
+        Ok(value)
+    }
+
+    fn reserved_serials(&self) -> std::sync::MutexGuard<'_, HashMap<(String, usize), Range<i64>>> {
+        self.serials
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Fills the null columns of the serial fields of a record with the next values of
+    /// their counters, see [`Database::allocate_serial`].
+    async fn assign_serials<'a>(
+        &self,
+        table: &Table,
+        record: &'a Record,
+    ) -> crate::errors::Result<Cow<'a, Record>> {
+        let mut record = Cow::Borrowed(record);
+        for (position, field) in table.fields.iter().enumerate() {
+            if !field.serial || record.columns.get(position) != Some(&Column::Null) {
+                continue;
+            }
+            let value = self
+                .allocate_serial(&table.name, table.stored_position(position))
+                .await?;
+            record.to_mut().columns[position] = Column::Int(value);
+        }
+        Ok(record)
+    }
+
+    /// Runs several operations within a single transaction, committed atomically.
+    ///
+    /// The closure receives a [`DatabaseTransaction`] exposing the record operations of the
+    /// database. Its operations see the writes of the previous ones, and either all of them
+    /// are committed or none is: the closure failing rolls the transaction back. As any
+    /// FoundationDB transaction, it is retried on conflicts, so the closure may run several
+    /// times and must not have side effects outside the transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `closure` - The operations to run, given the transaction handle.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value returned by the closure once the transaction is committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The database is read-only.
+    /// - The closure fails, its error is returned.
+    /// - The transaction can't be committed.
+    async fn transact<'a, F, Fut, T>(&'a self, closure: F) -> crate::errors::Result<T>
+    where
+        F: Fn(DatabaseTransaction<'a>) -> Fut,
+        Fut: Future<Output = crate::errors::Result<T>>,
+    {
+        self.write_with_profile(CommitProfile::Interactive, None, |trx, _| {
+            let operations = closure(DatabaseTransaction {
+                database: self,
+                trx,
+            });
+            async move { Ok(operations.await?) }
+        })
+        .await
+    }
+
+    /// Inserts a record into a specified table in the database.
+    ///
+    /// This method validates the provided record against the table's schema, ensuring that
+    /// all required fields are present and match the expected data types. It then constructs
+    /// a primary key based on the table's schema and stores the record in the database.
+    ///
+    /// The null columns of the serial fields are filled with the next values of their
+    /// counters first, see [`Field::new_serial`].
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table where the record is to be inserted.
+    /// * `record` - A reference to the `Record` that contains the data to be inserted.
+    ///
+    /// # Returns
+    ///
+    /// Returns the id of the new row along with the record as stored, holding the values
+    /// given to its serial fields, so that the row can be referenced without reading it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The record is missing required fields or has fields that do not match the schema.
+    /// - The record fails a check constraint of the table.
+    /// - The record references a missing row through a foreign key.
+    /// - A record with the same primary key already exists.
+    /// - An error occurs during the storage operation, such as a database write failure.
+    async fn insert(
+        &self,
+        table_name: &str,
+        record: &Record,
+    ) -> crate::errors::Result<InsertedRow> {
+        self.write(table_name, |trx, _| async move {
+            let table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            Ok(self.insert_internal(&trx, &table, record).await?)
+        })
+        .await
+    }
+
+    /// Inserts several records into a table within a single transaction.
+    ///
+    /// Meant for imports: the transaction runs with the [`CommitProfile::Bulk`] profile.
+    /// Either every record is inserted or none is, and the records must fit in the
+    /// FoundationDB transaction size limit, so large imports are split by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table where the records are to be inserted.
+    /// * `records` - The records to insert.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - A record is missing required fields or has fields that do not match the schema.
+    /// - A record has the same primary key as an existing record or another record of the
+    ///   batch.
+    /// - An error occurs during the storage operation, such as a database write failure.
+    async fn insert_many(&self, table_name: &str, records: &[Record]) -> crate::errors::Result<()> {
+        self.write_with_profile(
+            CommitProfile::Bulk,
+            self.table_tag(table_name),
+            |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                for record in records {
+                    self.insert_internal(&trx, &table, record).await?;
+                }
+                Ok(())
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+    ) -> crate::errors::Result<InsertedRow> {
+        let record = self.assign_serials(table, record).await?;
+        // check column fit table fields
+        let coerced = coerce_record(table, &record);
+        let record = &*coerced;
+        validate_record(table, record).into_result()?;
+        check_constraints(table, record)?;
+        self.check_foreign_keys(trx, table, record).await?;
+
+        // build the primary key tuple
+        let pk = extract_columns(table, &table.primary_key, record)?;
+
+        // store the primary key
+        let pk = Columns::new(&pk);
+        let subspace_pk = self.primary_key_entry(table, &pk);
+        if trx.get(&subspace_pk, false).await?.is_some() {
+            return Err(SqlLayerError::DuplicatePrimaryKey(format!("{:?}", pk.0)));
+        }
+        let row_id = self.allocate_row_id(&table.name).await?;
+
+        trx.set(&subspace_pk, pack(&row_id).as_ref());
+
+        // store index
+        self.check_unique_indexes(trx, table, record, row_id)
+            .await?;
+        for index in &table.indexes {
+            for subspace_index in self.index_entry_keys(table, index, record, row_id)? {
+                self.set_index_entry(trx, index, &subspace_index, row_id)?;
+            }
+        }
+
+        // store the record
+        let stored = self.store_blobs(trx, table, row_id, record).await?;
+        let row_bytes = self.encode_row(table, &stored.to_stored_row(table))?;
+        self.set_row(trx, table, row_id, &row_bytes);
+        self.record_row_write(trx, &table.name, None, Some(row_bytes.len()));
+        Ok(InsertedRow {
+            row_id,
+            record: coerced.into_owned(),
+        })
+    }
+
+    /// Inserts a record, or replaces the existing one if its primary key is already taken.
+    ///
+    /// When a record with the same primary key exists, it is rewritten in place under
+    /// its current row id and its index entries are fixed up, exactly as [`Database::update`]
+    /// does. Otherwise the record is inserted as a new row. Both paths run in a single
+    /// transaction.
+    ///
+    /// Tables without secondary indexes take the [`WritePath::Blind`] path instead: the
+    /// replaced row is overwritten without being read.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table where the record is to be stored.
+    /// * `record` - A reference to the `Record` to insert or replace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The record is missing required fields or has fields that do not match the schema.
+    /// - The record fails a check constraint of the table.
+    /// - The record references a missing row through a foreign key.
+    /// - An error occurs during the storage operation, such as a database write failure.
+    async fn upsert(&self, table_name: &str, record: &Record) -> crate::errors::Result<()> {
+        self.write(table_name, |trx, _| async move {
+            let table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            self.upsert_internal(&trx, &table, record).await?;
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+    ) -> crate::errors::Result<()> {
+        let record = self.assign_serials(table, record).await?;
+        let record = &*record;
+        if WritePath::new(table) == WritePath::Blind {
+            return self.upsert_blind(trx, table, record).await;
+        }
+        let pk = extract_columns(table, &table.primary_key, record)?;
+        let replaced = self
+            .update_internal(trx, table, &Columns::new(&pk), record)
+            .await?;
+        if replaced.is_none() {
+            self.insert_internal(trx, table, record).await?;
+        }
+        Ok(())
+    }
+
+    /// Upserts a record into a table without secondary indexes.
+    ///
+    /// The primary key entry is still read, as rows are keyed by row id: it tells the row
+    /// to overwrite, or that a row id must be allocated. The replaced row itself is not
+    /// read, the statistics account for it as a row of the size of its replacement, see
+    /// [`TableStats`].
+    async fn upsert_blind(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+    ) -> crate::errors::Result<()> {
+        let coerced = coerce_record(table, record);
+        let record = &*coerced;
+        validate_record(table, record).into_result()?;
+        check_constraints(table, record)?;
+        self.check_foreign_keys(trx, table, record).await?;
+
+        let pk = extract_columns(table, &table.primary_key, record)?;
+        let subspace_pk = self.primary_key_entry(table, &Columns::new(&pk));
+        let (row_id, replaced) = match trx.get(&subspace_pk, false).await? {
+            Some(row_id) => (
+                unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?,
+                true,
+            ),
+            None => {
+                let row_id = self.allocate_row_id(&table.name).await?;
+                trx.set(&subspace_pk, pack(&row_id).as_ref());
+                (row_id, false)
+            }
+        };
+
+        let stored = self.store_blobs(trx, table, row_id, record).await?;
+        let row_bytes = self.encode_row(table, &stored.to_stored_row(table))?;
+        self.set_row(trx, table, row_id, &row_bytes);
+        // the replaced row is estimated at the size of the new one, see `TableStats`
+        let previous_size = replaced.then_some(row_bytes.len());
+        self.record_row_write(trx, &table.name, previous_size, Some(row_bytes.len()));
+        Ok(())
+    }
+
+    ///
+    /// Fetches a record from the database based on the given primary key.
+    ///
+    /// The primary key entry and the row it references are read in a single request, see
+    /// [`Database::entries_with_rows`].
+    ///
+    /// # Parameters
+    ///
+    /// - `table_name`: The name of the table from which to fetch the record.
+    /// - `pk`: A reference to the primary key of the record to retrieve.
+    /// - `projection`: The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some(record))` containing the record if it exists, `Ok(None)` if the record
+    /// does not exist, or an error if the operation fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The provided primary key does not match the schema.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operation.
+    async fn get_record_by_pk(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<Option<Record>> {
+        self.storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                // read the table, for the subspaces keyed by its id, the fields added since
+                // the row was written and the projected columns
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                // resolve the projected columns against the table fields, the blobs of the
+                // other columns aren't read
+                let positions = projection
+                    .map(|projection| resolve_projection(&table, projection))
+                    .transpose()?;
+
+                // build primary key subspace out of the primary key columns, then read the
+                // primary key entry along with its row
+                let subspace_pk = self.primary_key_entry(&table, &pk);
+                let mut end = subspace_pk.clone();
+                end.push(0);
+                let entries = self
+                    .entries_with_rows(&trx, &table, &subspace_pk, &end, None, positions.as_deref())
+                    .await?;
+                let record = entries.into_iter().next().and_then(|entry| entry.record);
+
+                Ok(record.map(|record| match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                }))
+            })
+            .await
+    }
+
+    /// Fetches a record by the id of its row, as returned by [`Database::insert`].
+    ///
+    /// The row is read directly, without going through the primary key entry. Row ids are
+    /// never reused, an updated record keeping the id of its row.
+    ///
+    /// # Parameters
+    ///
+    /// - `table_name`: The name of the table from which to fetch the record.
+    /// - `row_id`: The id of the row of the record.
+    /// - `projection`: The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some(record))` containing the record if the row exists, `Ok(None)` if it
+    /// doesn't, e.g. once the record is deleted, or an error if the operation fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operation.
+    async fn get_record_by_row_id(
+        &self,
+        table_name: &str,
+        row_id: i64,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<Option<Record>> {
+        self.storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                let positions = projection
+                    .map(|projection| resolve_projection(&table, projection))
+                    .transpose()?;
+                let key = self.table_subspace(DataPrefix::Row, &table).pack(&row_id);
+                let Some(bytes) = trx.get(&key, false).await? else {
+                    return Ok(None);
+                };
+                let record = self
+                    .read_record(&trx, &table, row_id, &bytes, positions.as_deref())
+                    .await?;
+                Ok(Some(match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                }))
+            })
+            .await
+    }
+
+    /// Scans every record of a table.
+    ///
+    /// Rows are streamed in row id order, that is insertion order, with
+    /// [`Storage::full_scan`] and deserialized into records as they are read, so the table
+    /// is never loaded in memory at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the records of the table, or an error if the table does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized, reported through the stream.
+    /// - The records cross a result limit, reported through the stream.
+    async fn scan_table(
+        &self,
+        table_name: &str,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let records = self.table_records(table_name, projection).await?;
+        Ok(limit_records(self.result_limits, records))
+    }
+
+    /// Scans every record of a table as [`Database::scan_table`] does, regardless of the
+    /// result limits, for the plans reading the whole table.
+    async fn table_records(
+        &self,
+        table_name: &str,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let row_subspace = self.table_subspace(DataPrefix::Row, &table);
+        let (start, end) = row_subspace.range();
+
+        Ok(async_stream::try_stream! {
+            let rows = self.storage.full_scan(&start, &end).await;
+            pin_mut!(rows);
+            while let Some(row) = rows.next().await {
+                let (key, bytes) = row?;
+                let row_id = row_subspace
+                    .unpack::<i64>(&key)
+                    .map_err(FdbBindingError::PackError)?;
+                // a row read again for its chunks or blobs is skipped if deleted meanwhile
+                let record = self
+                    .read_scanned_record(&table, row_id, bytes, positions.as_deref())
+                    .await?;
+                let Some(record) = record else {
+                    continue;
+                };
+                let record = match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                };
+                yield record;
+            }
+        })
+    }
+
+    /// Streams the blob of a record, see [`Field::new_blob`], chunk by chunk.
+    ///
+    /// The id of the blob is read along with the record, then its chunks are read in
+    /// batches, each in its own transaction, so that blobs of any size are read without
+    /// being held in memory. Blobs are never modified, see [`Database::store_blobs`], so a
+    /// blob replaced or deleted while streamed can't be mixed up with another one: it
+    /// comes up short, which the stream reports.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table holding the record.
+    /// * `pk` - The primary key of the record.
+    /// * `field_name` - The name of the blob field.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the chunks of the blob, or `None` if no record matches the
+    /// primary key or its blob is null.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The field is not a blob field of the table.
+    /// - There is an issue with the database read operations, reported through the stream
+    ///   once it started.
+    /// - The blob is replaced or deleted while streamed, reported through the stream as
+    ///   `SqlLayerError::BlobChanged`.
+    async fn read_blob(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        field_name: &str,
+    ) -> crate::errors::Result<Option<impl Stream<Item = crate::errors::Result<Vec<u8>>>>> {
+        let source = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                let position = blob_position(&table, field_name)?;
+                let subspace_pk = self.primary_key_entry(&table, pk);
+                let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+                    return Ok(None);
+                };
+                let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+                let Some(row) = self.get_row(&trx, &table, row_id).await? else {
+                    return Ok(None);
+                };
+                let record = self.decode_record(&table, &row)?;
+                let source = match &record.columns[position] {
+                    Column::Int(blob_id) => {
+                        let length = self
+                            .blob_length(&trx, &table, row_id, *blob_id)
+                            .await?
+                            .ok_or_else(|| SqlLayerError::BlobChanged(field_name.to_string()))?;
+                        let chunks = self.row_blobs(&table, row_id).subspace(blob_id);
+                        BlobSource::Stored(chunks, length)
+                    }
+                    Column::Bytes(default) => BlobSource::Default(default.clone()),
+                    _ => return Ok(None),
+                };
+                Ok(Some(source))
+            })
+            .await?;
+        let Some(source) = source else {
+            return Ok(None);
+        };
+
+        Ok(Some(async_stream::try_stream! {
+            match source {
+                BlobSource::Default(default) => yield default,
+                BlobSource::Stored(chunks, length) => {
+                    let (start, end) = chunks.range();
+                    let chunks = self.storage.full_scan(&start, &end).await;
+                    pin_mut!(chunks);
+                    let mut read = 0;
+                    while let Some(chunk) = chunks.next().await {
+                        let (_, chunk) = chunk?;
+                        read += chunk.len();
+                        yield chunk;
+                    }
+                    if read != length {
+                        Err(SqlLayerError::BlobChanged(field_name.to_string()))?;
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Writes the blob of a record, see [`Field::new_blob`], from a stream of bytes,
+    /// replacing its previous blob.
+    ///
+    /// The bytes are written under a new blob id as they come, in batches of
+    /// [`BLOB_BATCH_SIZE`] chunks each in its own transaction, so that blobs larger than a
+    /// transaction can be written. A last transaction then stores the id of the blob in
+    /// the record and clears the previous blob, so that readers see either blob entirely.
+    ///
+    /// The chunks written are cleared if the write fails or the record is deleted
+    /// meanwhile. The chunks of a write interrupted altogether are only cleared along with
+    /// the row.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table holding the record.
+    /// * `pk` - The primary key of the record.
+    /// * `field_name` - The name of the blob field.
+    /// * `bytes` - The bytes of the blob, in any number of parts of any size.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the blob has been written, `Ok(false)` if no record matches
+    /// the primary key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The field is not a blob field of the table.
+    /// - The stream of bytes fails.
+    /// - There is an issue with the database operations.
+    async fn write_blob<S>(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        field_name: &str,
+        bytes: S,
+    ) -> crate::errors::Result<bool>
+    where
+        S: Stream<Item = crate::errors::Result<Vec<u8>>>,
+    {
+        let found = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                blob_position(&table, field_name)?;
+                let subspace_pk = self.primary_key_entry(&table, pk);
+                let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+                    return Ok(None);
+                };
+                let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+                Ok(Some((table, row_id)))
+            })
+            .await?;
+        let Some((table, row_id)) = found else {
+            return Ok(false);
+        };
+
+        let blob_id = self.allocate_row_id(&table.name).await?;
+        let stored = match self.write_blob_chunks(&table, row_id, blob_id, bytes).await {
+            Ok(length) => {
+                self.store_blob_id(table_name, pk, field_name, row_id, blob_id, length)
+                    .await
+            }
+            Err(error) => Err(error),
+        };
+        if !matches!(stored, Ok(true)) {
+            // a failure to clear the chunks would hide the error of the write, they are
+            // cleared along with the row anyway
+            let table = &table;
+            let _ = self
+                .write_with_profile(
+                    CommitProfile::Background,
+                    self.table_tag(table_name),
+                    |trx, _| async move {
+                        self.clear_blob(&trx, table, row_id, blob_id);
+                        Ok(())
+                    },
+                )
+                .await;
+        }
+        stored
+    }
+
+    /// Writes the chunks of a new blob from a stream of bytes, see
+    /// [`Database::write_blob`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the length of the blob.
+    async fn write_blob_chunks<S>(
+        &self,
+        table: &Table,
+        row_id: i64,
+        blob_id: i64,
+        bytes: S,
+    ) -> crate::errors::Result<usize>
+    where
+        S: Stream<Item = crate::errors::Result<Vec<u8>>>,
+    {
+        let chunks = &self.row_blobs(table, row_id).subspace(&blob_id);
+        let batch_size = MAX_VALUE_SIZE * BLOB_BATCH_SIZE;
+        let mut pending = vec![];
+        let mut written_chunks = 0;
+        let mut length = 0;
+        let mut done = false;
+        pin_mut!(bytes);
+        while !done {
+            match bytes.next().await {
+                Some(part) => {
+                    let part = part?;
+                    length += part.len();
+                    pending.extend(part);
+                }
+                None => done = true,
+            }
+            // full batches are written as they come, the rest once the stream is over
+            while pending.len() >= batch_size || (done && !pending.is_empty()) {
+                let batch = &pending
+                    .drain(..pending.len().min(batch_size))
+                    .collect::<Vec<_>>();
+                let first = written_chunks;
+                self.write_with_profile(
+                    CommitProfile::Background,
+                    self.table_tag(&table.name),
+                    |trx, _| async move {
+                        for (offset, chunk) in batch.chunks(MAX_VALUE_SIZE).enumerate() {
+                            trx.set(&chunks.pack(&((first + offset) as i64)), chunk);
+                        }
+                        Ok(())
+                    },
+                )
+                .await?;
+                written_chunks += batch.len().div_ceil(MAX_VALUE_SIZE);
+            }
+        }
+        Ok(length)
+    }
+
+    /// Stores the id of a blob written by [`Database::write_blob`] in its record, and
+    /// clears the previous blob.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(false)` if the record was deleted, or replaced by another row, since
+    /// the blob was written.
+    async fn store_blob_id(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        field_name: &str,
+        row_id: i64,
+        blob_id: i64,
+        length: usize,
+    ) -> crate::errors::Result<bool> {
+        self.write(table_name, |trx, _| async move {
+            let table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            let position = blob_position(&table, field_name)?;
+            let subspace_pk = self.primary_key_entry(&table, pk);
+            let current = trx.get(&subspace_pk, false).await?;
+            if current.as_deref() != Some(pack(&row_id).as_slice()) {
+                return Ok(false);
+            }
+            let Some(row) = self.get_row(&trx, &table, row_id).await? else {
+                return Ok(false);
+            };
+            let mut record = self.decode_record(&table, &row)?;
+            if let Column::Int(previous) = record.columns[position] {
+                self.clear_blob(&trx, &table, row_id, previous);
+            }
+            record.columns[position] = Column::Int(blob_id);
+            let key = self.row_blobs(&table, row_id).pack(&blob_id);
+            trx.set(&key, &(length as u64).to_le_bytes());
+
+            let row_bytes = self.encode_row(&table, &record.to_stored_row(&table))?;
+            self.set_row(&trx, &table, row_id, &row_bytes);
+            self.record_row_write(&trx, &table.name, Some(row.len()), Some(row_bytes.len()));
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Fetches a record by its primary key, deserialized into a user type.
+    ///
+    /// The columns are mapped to the fields of the type by the names of the fields of the
+    /// table, see the [`crate::deserialize`] module.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table from which to fetch the record.
+    /// * `pk` - The primary key of the record to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some(value))` if the record exists, `Ok(None)` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read operation.
+    /// - The record doesn't deserialize into the type.
+    async fn get_as<T: DeserializeOwned>(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<Option<T>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        self.get_record_by_pk(table_name, pk, None)
+            .await?
+            .map(|record| from_record(&table, record))
+            .transpose()
+    }
+
+    /// Scans every record of a table, deserialized into a user type.
+    ///
+    /// Records are streamed as [`Database::scan_table`] streams them, and mapped to the
+    /// type as [`Database::get_as`] maps them.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the deserialized records, or an error if the table does not
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read operations, or a record doesn't
+    ///   deserialize into the type, reported through the stream.
+    async fn scan_as<T: DeserializeOwned>(
+        &self,
+        table_name: &str,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<T>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let records = self.scan_table(table_name, None).await?;
+        Ok(records.map(move |record| record.and_then(|record| from_record(&table, record))))
+    }
+
+    /// Scans the records of a table matching a predicate.
+    ///
+    /// The predicate is validated against the table, then the rows are read through the
+    /// [`Plan`] chosen for it, see [`Database::explain`], and the predicate is evaluated
+    /// against every record read. The projection is applied after filtering, so the
+    /// predicate may reference columns that aren't returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `predicate` - The filter the returned records must match.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the matching records, or an error if the scan can't start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The predicate or the projection references a column that is not a field of the
+    ///   table, or compares a column to a value of another type.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized, reported through the stream.
+    /// - The records cross a result limit, reported through the stream.
+    async fn scan_where(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let records = self
+            .matching_records(table_name, predicate, projection)
+            .await?;
+        Ok(limit_records(self.result_limits, records))
+    }
+
+    /// Scans the records of a table matching a predicate as [`Database::scan_where`] does,
+    /// regardless of the result limits.
+    async fn matching_records(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        predicate.validate(&table)?;
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let plan = info_span!(
+            "sql.plan",
+            db.sql.table = table_name,
+            plan = tracing::field::Empty
+        )
+        .in_scope(|| {
+            let plan = Plan::new(&table, predicate);
+            Span::current().record("plan", tracing::field::debug(&plan));
+            plan
+        });
+
+        let records = async_stream::try_stream! {
+            let records = self.planned_records(&table, plan);
+            pin_mut!(records);
+            while let Some(record) = records.next().await {
+                let record = record?;
+                if !predicate.evaluate(&table, &record) {
+                    continue;
+                }
+                let record = match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                };
+                yield record;
+            }
+        };
+        Ok(records)
+    }
+
+    /// Scans the records of a table matching an optional predicate, ordered by sort keys.
+    ///
+    /// When the primary key or a ready index covers the sort keys, see [`Plan::ordered`],
+    /// records are streamed in order as they are read. Otherwise every matching record is
+    /// collected and sorted in memory before the first one is yielded, or with a limit only
+    /// the first `limit` records seen so far are kept in a bounded heap. Nulls sort first in
+    /// ascending order, and records with equal sort keys keep their read order.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `predicate` - The filter the returned records must match, or `None` for every
+    ///   record.
+    /// * `order_by` - The columns to sort by, with their direction.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    /// * `limit` - The maximum number of records to return, or `None` for no limit.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the matching records in order, or an error if the scan can't
+    /// start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The predicate, the sort keys or the projection reference a column that is not a
+    ///   field of the table, or the predicate compares a column to a value of another type.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized, reported through the stream.
+    /// - The records, or those sorted in memory, cross a result limit, reported through the
+    ///   stream.
+    async fn scan_sorted(
+        &self,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        order_by: &[SortKey],
+        projection: Option<&[&str]>,
+        limit: Option<usize>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        if let Some(predicate) = predicate {
+            predicate.validate(&table)?;
+        }
+        let sort_keys = order_by
+            .iter()
+            .map(|key| {
+                table
+                    .get_field_pos(&key.field)
+                    .map(|position| (position, key.direction))
+                    .ok_or(SqlLayerError::MissingColumn(key.field.clone()))
+            })
+            .collect::<crate::errors::Result<Vec<_>>>()?;
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let plan = predicate
+            .map(|predicate| Plan::new(&table, predicate))
+            .unwrap_or(Plan::TableScan);
+        let ordered = plan.clone().ordered(&table, order_by);
+        let sorted = ordered.is_none();
+        let plan = ordered.unwrap_or(plan);
+
+        let records = async_stream::try_stream! {
+            let records = self.planned_records(&table, plan).try_filter(|record| {
+                futures::future::ready(
+                    predicate.is_none_or(|predicate| predicate.evaluate(&table, record)),
+                )
+            });
+            let records = match (sorted, limit) {
+                (false, _) => records.boxed_local(),
+                (true, None) => {
+                    let mut records = self.collect_limited(records).await?;
+                    records.sort_by(|a, b| compare_records(a, b, &sort_keys));
+                    futures::stream::iter(records.into_iter().map(Ok)).boxed_local()
+                }
+                (true, Some(limit)) => {
+                    let mut top = TopK::new(limit, &sort_keys);
+                    records
+                        .try_for_each(|record| {
+                            top.push(record);
+                            futures::future::ready(Ok(()))
+                        })
+                        .await?;
+                    futures::stream::iter(top.into_sorted_vec().into_iter().map(Ok)).boxed_local()
+                }
+            };
+            let records = records.take(limit.unwrap_or(usize::MAX));
+            pin_mut!(records);
+            while let Some(record) = records.next().await {
+                let record = record?;
+                let record = match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                };
+                yield record;
+            }
+        };
+        Ok(limit_records(self.result_limits, records))
+    }
+
+    /// Scans the distinct records of a table matching an optional predicate.
+    ///
+    /// Records are compared on their projected columns, by their tuple encoding as index
+    /// keys are. When the primary key or a ready index is led by the projected columns, in
+    /// order, see [`Plan::ordered`], equal records are read next to each other and are
+    /// deduplicated on the fly, in that order. Otherwise the encodings of the records already
+    /// returned are kept in a set, and records are returned in read order.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `predicate` - The filter the returned records must match, or `None` for every
+    ///   record.
+    /// * `projection` - The names of the columns to return and compare, in order, or `None`
+    ///   for every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the distinct matching records, or an error if the scan can't
+    /// start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The predicate or the projection reference a column that is not a field of the
+    ///   table, or the predicate compares a column to a value of another type.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized, reported through the stream.
+    /// - The records cross a result limit, reported through the stream.
+    async fn scan_distinct(
+        &self,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        if let Some(predicate) = predicate {
+            predicate.validate(&table)?;
+        }
+        let fields = match projection {
+            Some(projection) => projection.to_vec(),
+            None => table
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect(),
+        };
+        let positions = resolve_projection(&table, &fields)?;
+        let order_by = fields.iter().copied().map(SortKey::asc).collect::<Vec<_>>();
+        let plan = predicate
+            .map(|predicate| Plan::new(&table, predicate))
+            .unwrap_or(Plan::TableScan);
+        let ordered = plan.clone().ordered(&table, &order_by);
+        let grouped = ordered.is_some();
+        let plan = ordered.unwrap_or(plan);
+
+        let records = async_stream::try_stream! {
+            let records = self.planned_records(&table, plan).try_filter(|record| {
+                futures::future::ready(
+                    predicate.is_none_or(|predicate| predicate.evaluate(&table, record)),
+                )
+            });
+            pin_mut!(records);
+            let mut previous = None;
+            let mut seen = HashSet::new();
+            while let Some(record) = records.next().await {
+                let record = record?.project(&positions);
+                let key = pack(&Columns::new(&record.columns.iter().collect()));
+                let distinct = if grouped {
+                    // equal records are adjacent, only the last one returned is compared
+                    let distinct = previous.as_ref() != Some(&key);
+                    previous = Some(key);
+                    distinct
+                } else {
+                    seen.insert(key)
+                };
+                if distinct {
+                    yield record;
+                }
+            }
+        };
+        Ok(limit_records(self.result_limits, records))
+    }
+
+    /// Streams the records read by a plan, which may not all match the predicate it was
+    /// chosen for.
+    fn planned_records(
+        &self,
+        table: &Table,
+        plan: Plan,
+    ) -> impl Stream<Item = crate::errors::Result<Record>> {
+        let table_name = table.name.as_str();
+        async_stream::try_stream! {
+            let union_keys = match &plan {
+                Plan::IndexUnion(ranges) => union_merge_keys(table, ranges),
+                _ => None,
+            };
+            let records = match &plan {
+                Plan::TableScan => self.table_records(table_name, None).await?.boxed_local(),
+                Plan::PrimaryKeyScan => {
+                    let pk = vec![];
+                    self.scan_by_pk_prefix(table_name, &Columns::new(&pk), None)
+                        .await?
+                        .boxed_local()
+                }
+                Plan::PrimaryKeyLookup { pk } => {
+                    let pk = pk.iter().collect::<Vec<_>>();
+                    let record = self
+                        .get_record_by_pk(table_name, &Columns::new(&pk), None)
+                        .await?;
+                    futures::stream::iter(record.map(Ok)).boxed_local()
+                }
+                Plan::PrimaryKeyLookups { pks } => {
+                    let records = self.get_records_by_pks(table, pks).await?;
+                    futures::stream::iter(records.into_iter().map(Ok)).boxed_local()
+                }
+                Plan::IndexLookups { index, values } => {
+                    let records = self
+                        .get_records_by_index_values(table, index, values)
+                        .await?;
+                    futures::stream::iter(records.into_iter().map(Ok)).boxed_local()
+                }
+                Plan::IndexRangeScan(range) => self
+                    .scan_index_range(
+                        table_name,
+                        &range.index,
+                        (range.start.clone(), range.end.clone()),
+                        None,
+                    )
+                    .await?
+                    .boxed_local(),
+                Plan::IndexIntersection(ranges) => {
+                    // the entries of an equal-value range are ordered by row id, so the
+                    // ranges are intersected as they are scanned
+                    let row_ids = ranges
+                        .iter()
+                        .map(|range| {
+                            let (start, end) = self.index_key_range(table, range);
+                            self.row_ids_in_range(start, end)
+                        })
+                        .collect::<Vec<_>>();
+                    self.rows_by_id(table, intersect_ordered(row_ids))
+                        .boxed_local()
+                }
+                Plan::IndexUnion(ranges) => match &union_keys {
+                    // the ranges of a single index are merged in the order of the index
+                    Some((sort_keys, identity)) => {
+                        let streams = ranges
+                            .iter()
+                            .map(|range| {
+                                let (start, end) = self.index_key_range(table, range);
+                                self.rows_in_range(table, start, end)
+                            })
+                            .collect::<Vec<_>>();
+                        merge_ordered(streams, sort_keys, identity).boxed_local()
+                    }
+                    None => {
+                        // row ids are gathered in a set so that rows referenced by several
+                        // ranges are read once
+                        let mut row_ids = BTreeSet::new();
+                        for range in ranges {
+                            let (start, end) = self.index_key_range(table, range);
+                            let entries = self.row_ids_in_range(start, end);
+                            pin_mut!(entries);
+                            while let Some(row_id) = entries.next().await {
+                                row_ids.insert(row_id?);
+                            }
+                        }
+                        let row_ids = row_ids.into_iter().map(Ok);
+                        self.rows_by_id(table, futures::stream::iter(row_ids))
+                            .boxed_local()
+                    }
+                },
+            };
+            pin_mut!(records);
+            while let Some(record) = records.next().await {
+                let record = record?;
+                yield record;
+            }
+        }
+    }
+
+    /// Describes how [`Database::scan_where`] reads the records matching a predicate.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to query.
+    /// * `predicate` - The filter the records must match.
+    ///
+    /// # Returns
+    ///
+    /// Returns the plan chosen for the predicate: a table scan, a primary key lookup or
+    /// a range scan of a secondary index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The predicate references a column that is not a field of the table, or compares
+    ///   a column to a value of another type.
+    /// - There is an issue with the database read operation.
+    async fn explain(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+    ) -> crate::errors::Result<Plan> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        predicate.validate(&table)?;
+        Ok(Plan::new(&table, predicate))
+    }
+
+    /// Runs SQL statements through the typed API, see the [`crate::sql`] module for the
+    /// supported subset.
+    ///
+    /// Statements run in order, each one in its own transactions: the rows of an `INSERT`
+    /// are inserted within a single transaction, so either all of them are or none is,
+    /// while the rows matched by a `DELETE` are deleted in batches, see
+    /// [`Database::delete_many`], so a failing `DELETE` may be partially applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL text, holding one or more statements separated by `;`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the result of every statement: the created table, the number of inserted
+    /// or deleted rows, or the selected records.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The text can't be parsed or falls outside of the supported subset.
+    /// - A statement fails, as the typed method it translates into would.
+    async fn execute(&self, sql: &str) -> crate::errors::Result<Vec<QueryResult>> {
+        self.execute_with_parent(sql, None).await
+    }
+
+    /// Runs SQL statements as [`Database::execute`] does, traced under a parent span.
+    ///
+    /// The statements are traced by a `sql.execute` span holding the SQL text, whose
+    /// children trace the phases: `sql.parse`, then a `sql.statement` span per statement,
+    /// holding its operation and table. Inside a statement, `sql.plan` spans record the
+    /// plans chosen and `fdb.transaction` spans the transactions run. Span fields follow
+    /// the OpenTelemetry conventions for databases, so spans exported through
+    /// `tracing-opentelemetry` show up in the traces of the embedding service.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL text, holding one or more statements separated by `;`.
+    /// * `parent` - The span to trace the statements under, or `None` for the current span.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The text can't be parsed or falls outside of the supported subset.
+    /// - A statement fails, as the typed method it translates into would.
+    async fn execute_with_parent(
+        &self,
+        sql: &str,
+        parent: Option<&Span>,
+    ) -> crate::errors::Result<Vec<QueryResult>> {
+        let span = match parent {
+            Some(parent) => info_span!(
+                parent: parent,
+                "sql.execute",
+                db.system = "foundationdb",
+                db.statement = sql
+            ),
+            None => info_span!(
+                "sql.execute",
+                db.system = "foundationdb",
+                db.statement = sql
+            ),
+        };
+        async move {
+            let commands = info_span!("sql.parse").in_scope(|| crate::sql::parse(sql))?;
+            let mut results = vec![];
+            for command in commands {
+                let span = info_span!(
+                    "sql.statement",
+                    db.operation = command.operation(),
+                    db.sql.table = command.table()
+                );
+                results.push(self.execute_command(command).instrument(span).await?);
+            }
+            Ok(results)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Runs a single statement, see [`Database::execute`].
+    async fn execute_command(&self, command: Command) -> crate::errors::Result<QueryResult> {
+        let result = match command {
+            Command::CreateTable {
+                table,
+                if_not_exists,
+            } => {
+                self.create_table(&table, if_not_exists).await?;
+                QueryResult::Created
+            }
+            Command::Insert {
+                table,
+                columns,
+                rows,
+            } => {
+                let table = self
+                    .get_table(&table)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table))?;
+                let records = rows
+                    .into_iter()
+                    .map(|values| crate::sql::insert_record(&table, columns.as_deref(), values))
+                    .collect::<crate::errors::Result<Vec<_>>>()?;
+                let (table_name, records) = (&table.name, &records);
+                self.write(table_name, |trx, _| async move {
+                    let table = self
+                        .get_table_internal(&trx, table_name)
+                        .await?
+                        .ok_or(SqlLayerError::TableNotFound(table_name.clone()))?;
+                    for record in records {
+                        self.insert_internal(&trx, &table, record).await?;
+                    }
+                    Ok(())
+                })
+                .await?;
+                QueryResult::Inserted(records.len() as u64)
+            }
+            Command::Select {
+                table,
+                projection,
+                predicate,
+                distinct,
+            } => {
+                let projection = projection
+                    .as_ref()
+                    .map(|projection| projection.iter().map(String::as_str).collect::<Vec<_>>());
+                let records = if distinct {
+                    self.scan_distinct(&table, predicate.as_ref(), projection.as_deref())
+                        .await?
+                        .try_collect()
+                        .await?
+                } else {
+                    self.select(&table, predicate.as_ref(), projection.as_deref())
+                        .await?
+                };
+                QueryResult::Rows(records)
+            }
+            Command::Delete { table, predicate } => {
+                let primary_key = self
+                    .get_table(&table)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table.clone()))?
+                    .primary_key;
+                let primary_key = primary_key.iter().map(String::as_str).collect::<Vec<_>>();
+                // the keys aren't returned, only the time spent reading them is bounded
+                let keys = self
+                    .filtered_records(&table, predicate.as_ref(), Some(&primary_key))
+                    .await?;
+                let keys = limit_records(self.result_limits.duration_only(), keys)
+                    .try_collect::<Vec<_>>()
+                    .await?;
+                let pks = keys
+                    .iter()
+                    .map(|key| key.columns.iter().collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                let pks = pks.iter().map(Columns::new).collect::<Vec<_>>();
+                let deleted = self.delete_many(&table, &pks).await?;
+                QueryResult::Deleted(deleted.into_iter().filter(|deleted| *deleted).count() as u64)
+            }
+        };
+        Ok(result)
+    }
+
+    /// Collects the records of a table matching an optional predicate, within the result
+    /// limits of the database, which bound the scans.
+    async fn select(
+        &self,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<Vec<Record>> {
+        let records = self
+            .filtered_records(table_name, predicate, projection)
+            .await?;
+        limit_records(self.result_limits, records)
+            .try_collect()
+            .await
+    }
+
+    /// Scans the records of a table matching an optional predicate, regardless of the
+    /// result limits, see [`Database::scan_table`] and [`Database::scan_where`].
+    async fn filtered_records(
+        &self,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<LocalBoxStream<'_, crate::errors::Result<Record>>> {
+        Ok(match predicate {
+            Some(predicate) => self
+                .matching_records(table_name, predicate, projection)
+                .await?
+                .boxed_local(),
+            None => self
+                .table_records(table_name, projection)
+                .await?
+                .boxed_local(),
+        })
+    }
+
+    /// Collects a stream of records to be buffered in memory, such as the records sorted by
+    /// [`Database::scan_sorted`], aborting as soon as it crosses a result limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::ResultTooLarge` if a limit is crossed, or the first error of
+    /// the stream.
+    async fn collect_limited(
+        &self,
+        records: impl Stream<Item = crate::errors::Result<Record>>,
+    ) -> crate::errors::Result<Vec<Record>> {
+        pin_mut!(records);
+        let mut guard = ResultGuard::new(self.result_limits);
+        let mut collected = vec![];
+        while let Some(record) = records.next().await {
+            let record = record?;
+            guard.check(&record)?;
+            collected.push(record);
+        }
+        Ok(collected)
+    }
+
+    /// Scans the records whose primary key starts with the given columns.
+    ///
+    /// Primary key entries are ordered by their columns, so the records sharing the leading
+    /// columns of a composite primary key are read with a single range scan, e.g. all the
+    /// orders of a customer when the primary key is `(customer_id, order_id)`. Records are
+    /// yielded in primary key order.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `partial_pk` - The leading columns of the primary key. An empty prefix matches
+    ///   every record, a full primary key at most one.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the matching records, or an error if the scan can't start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operations, reported through the stream.
+    async fn scan_by_pk_prefix(
+        &self,
+        table_name: &str,
+        partial_pk: &Columns<'_>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let (start, end) = self
+            .table_subspace(DataPrefix::PrimaryKey, &table)
+            .subspace(&KeyColumns::new(
+                partial_pk.0,
+                &table.primary_key_directions,
+            ))
+            .range();
+        Ok(async_stream::try_stream! {
+            let records = self.rows_in_range(&table, start, end);
+            pin_mut!(records);
+            while let Some(record) = records.next().await {
+                let record = record?;
+                let record = match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                };
+                yield record;
+            }
+        })
+    }
+
+    /// Computes an aggregate function over the records of a table.
+    ///
+    /// Records are streamed and folded one at a time, so the table is never loaded in
+    /// memory. Without a predicate, the minimum or maximum of a column leading a ready index
+    /// is read from the first or last non-null entry of the index instead of scanning the
+    /// table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to aggregate.
+    /// * `aggregate` - The aggregate function, such as `Aggregate::Count`.
+    /// * `predicate` - The filter the aggregated records must match, or `None` for every
+    ///   record.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value of the aggregate, null for a minimum, maximum, sum or average over
+    /// no value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The aggregated column is not a field of the table, or a sum or an average is
+    ///   computed over a non-numeric column.
+    /// - The predicate references a column that is not a field of the table, or compares
+    ///   a column to a value of another type.
+    /// - The sum of an integer column overflows.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized.
+    /// - Reading the aggregated records takes longer than the duration limit, the other
+    ///   result limits bounding returned records only.
+    async fn aggregate(
+        &self,
+        table_name: &str,
+        aggregate: &Aggregate,
+        predicate: Option<&Predicate>,
+    ) -> crate::errors::Result<Column> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        if let Some(predicate) = predicate {
+            predicate.validate(&table)?;
+        }
+        let mut accumulator = Accumulator::new(&table, aggregate)?;
+
+        let endpoint = match predicate {
+            Some(_) => None,
+            None => self.index_endpoint(&table, aggregate).await?,
+        };
+        if let Some(column) = endpoint {
+            accumulator.set_extremum(column);
+            return accumulator.finish();
+        }
+
+        // the folded records aren't returned, only the time spent reading them is bounded
+        let records = self.filtered_records(table_name, predicate, None).await?;
+        let records = limit_records(self.result_limits.duration_only(), records);
+        pin_mut!(records);
+        while let Some(record) = records.next().await {
+            accumulator.add(&record?);
+        }
+        accumulator.finish()
+    }
+
+    /// Reads the minimum or maximum of a column from the endpoints of an index it leads.
+    ///
+    /// Null entries sort first in the index and are skipped. Returns `None` when the
+    /// aggregate is not a minimum or a maximum, when the column is collated, its index
+    /// then sorting its strings differently, when no ready index is led by the column,
+    /// multi-entry indexes ordering the elements of an array rather than the arrays,
+    /// when it holds no non-null entry or when the row of the endpoint vanished meanwhile,
+    /// the records must then be scanned.
+    async fn index_endpoint(
+        &self,
+        table: &Table,
+        aggregate: &Aggregate,
+    ) -> crate::errors::Result<Option<Column>> {
+        let (field, reverse) = match aggregate {
+            Aggregate::Min(field) => (field, false),
+            Aggregate::Max(field) => (field, true),
+            _ => return Ok(None),
+        };
+        if table.collation(field) != Collation::Binary {
+            return Ok(None);
+        }
+        let Some(index) = table.indexes.iter().find(|index| {
+            index.state() == IndexState::Ready
+                && !index.is_multi_entry()
+                && index.fields().first() == Some(field)
+        }) else {
+            return Ok(None);
+        };
+        let Some(position) = table.get_field_pos(field) else {
+            return Ok(None);
+        };
+
+        // nulls are first in an ascending index, last in a descending one
+        let non_null = Bound::Excluded(vec![Column::Null]);
+        let (range, reverse) = match index.direction(0) {
+            Direction::Ascending => ((non_null, Bound::Unbounded), reverse),
+            Direction::Descending => ((Bound::Unbounded, non_null), !reverse),
+        };
+        let (start, end) = key_range(
+            &self.index_subspace(table, index.name()),
+            index.directions(),
+            &range,
+        );
+        let entry = match reverse {
+            false => self.storage.first(&start, &end).await?,
+            true => self.storage.last(&start, &end).await?,
+        };
+        let Some((_, row_id)) = entry else {
+            return Ok(None);
+        };
+        let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+        let key = self.table_subspace(DataPrefix::Row, table).pack(&row_id);
+        let Some(bytes) = self.storage.get(&key).await? else {
+            return Ok(None);
+        };
+        let record = self
+            .read_scanned_record(table, row_id, bytes, Some(&[position]))
+            .await?;
+        Ok(record.and_then(|record| record.columns.get(position).cloned()))
+    }
+
+    /// Joins the records of two tables sharing the same value in their join columns.
+    ///
+    /// The left table is read once, through the [`Plan`] chosen for the predicate, and the
+    /// right records matching every left record are looked up with the [`JoinStrategy`]
+    /// chosen for the right join column. Each joined record holds the columns of the left
+    /// record followed by the columns of the right record, in the order of the left records.
+    ///
+    /// # Arguments
+    ///
+    /// * `join` - The tables and the columns to join on.
+    /// * `predicate` - The filter the left records must match, or `None` to join every left
+    ///   record.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the joined records, or an error if the join can't start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - One of the tables does not exist.
+    /// - A join column is not a field of its table, or the join columns have different
+    ///   types.
+    /// - The predicate references a column that is not a field of the left table, or
+    ///   compares a column to a value of another type.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized, reported through the stream.
+    /// - The joined records, or the right records held in memory by a nested loop join,
+    ///   cross a result limit, reported through the stream.
+    async fn join(
+        &self,
+        join: &Join,
+        predicate: Option<&Predicate>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let left = self
+            .get_table(&join.left)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(join.left.clone()))?;
+        let right = self
+            .get_table(&join.right)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(join.right.clone()))?;
+        if let Some(predicate) = predicate {
+            predicate.validate(&left)?;
+        }
+        let (left_position, right_position) = join_positions(join, &left, &right)?;
+        let strategy = JoinStrategy::new(&right, &join.right_field);
+        let plan = predicate
+            .map(|predicate| Plan::new(&left, predicate))
+            .unwrap_or(Plan::TableScan);
+
+        let records = async_stream::try_stream! {
+            // without a lookup path, the right table is read once and held in memory
+            let right_records = match strategy {
+                JoinStrategy::NestedLoop => Some(
+                    self.scan_table(&join.right, None)
+                        .await?
+                        .try_collect::<Vec<_>>()
+                        .await?,
+                ),
+                _ => None,
+            };
+            let left_records = self.planned_records(&left, plan).try_filter(|record| {
+                futures::future::ready(
+                    predicate.is_none_or(|predicate| predicate.evaluate(&left, record)),
+                )
+            });
+            pin_mut!(left_records);
+            while let Some(left_record) = left_records.next().await {
+                let left_record = left_record?;
+                let Some(value) = left_record.columns.get(left_position) else {
+                    continue;
+                };
+                if matches!(value, Column::Null) {
+                    continue;
+                }
+                if let Some(right_records) = &right_records {
+                    for right_record in right_records {
+                        let right_column = right_record.columns.get(right_position);
+                        if right_column.is_some_and(|column| joins(value, column)) {
+                            yield combine(&left_record, right_record);
+                        }
+                    }
+                    continue;
+                }
+                let lookup = match &strategy {
+                    JoinStrategy::IndexLookup(index) => {
+                        // the index keys of a collated field hold its collated strings
+                        let key = right.collation(&join.right_field).column(value).into_owned();
+                        Plan::IndexRangeScan(IndexRange {
+                            index: index.clone(),
+                            start: Bound::Included(vec![key.clone()]),
+                            end: Bound::Included(vec![key]),
+                        })
+                    }
+                    _ => Plan::PrimaryKeyLookup {
+                        pk: vec![value.clone()],
+                    },
+                };
+                let matches = self.planned_records(&right, lookup);
+                pin_mut!(matches);
+                while let Some(right_record) = matches.next().await {
+                    let right_record = right_record?;
+                    // index entries are matched by encoding, the columns must be equal too
+                    let right_column = right_record.columns.get(right_position);
+                    if right_column.is_some_and(|column| joins(value, column)) {
+                        yield combine(&left_record, &right_record);
+                    }
+                }
+            }
+        };
+        Ok(limit_records(self.result_limits, records))
+    }
+
+    /// Reads a page of the records of a table, in row id order.
+    ///
+    /// Pages are resumed with the continuation token of the previous one rather than by
+    /// re-reading the table from its start, so a client can paginate across requests. Rows
+    /// written or deleted between two pages are seen or not depending on their row id.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `limit` - The maximum number of records of the page, at least one.
+    /// * `offset` - The number of records skipped before the page, counted from the start
+    ///   of the table or from the continuation.
+    /// * `continuation` - The token of the previous page, or `None` for the first one.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The continuation was not issued by a scan of this table.
+    /// - The limit is 0.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized.
+    async fn scan_table_page(
+        &self,
+        table_name: &str,
+        limit: usize,
+        offset: usize,
+        continuation: Option<&Continuation>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<Page> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let row_subspace = self.table_subspace(DataPrefix::Row, &table);
+
+        let (entries, continuation) = self
+            .page_entries(row_subspace.range(), continuation, offset, limit)
+            .await?;
+        let mut records = vec![];
+        for (key, bytes) in entries {
+            let row_id = row_subspace
+                .unpack::<i64>(&key)
+                .map_err(FdbBindingError::PackError)?;
+            let record = self
+                .read_scanned_record(&table, row_id, bytes, positions.as_deref())
+                .await?;
+            let Some(record) = record else {
+                continue;
+            };
+            records.push(match &positions {
+                Some(positions) => record.project(positions),
+                None => record,
+            });
+        }
+        Ok(Page {
+            records,
+            continuation,
+        })
+    }
+
+    /// Reads a page of the records whose indexed columns fall within a range, ordered by
+    /// the indexed columns.
+    ///
+    /// See [`Database::scan_table_page`] for pagination and [`Database::scan_index_range`]
+    /// for ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `index_name` - The name of the index to traverse.
+    /// * `range` - The bounds of the indexed columns, in the order of the index keys.
+    /// * `limit` - The maximum number of records of the page, at least one.
+    /// * `offset` - The number of records skipped before the page, counted from the start
+    ///   of the range or from the continuation.
+    /// * `continuation` - The token of the previous page, or `None` for the first one.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table or the index does not exist.
+    /// - The index is still being backfilled.
+    /// - The continuation was not issued by a scan of this index range.
+    /// - The limit is 0.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operations or a row can't be
+    ///   deserialized.
+    #[allow(clippy::too_many_arguments)]
+    async fn scan_index_page<R: RangeBounds<Vec<Column>>>(
+        &self,
+        table_name: &str,
+        index_name: &str,
+        range: R,
+        limit: usize,
+        offset: usize,
+        continuation: Option<&Continuation>,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<Page> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let index = table
+            .get_index(index_name)
+            .ok_or(SqlLayerError::IndexNotFound(index_name.to_string()))?;
+        if index.state() != IndexState::Ready {
+            return Err(SqlLayerError::IndexNotReady(index_name.to_string()));
+        }
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let range = key_range(
+            &self.index_subspace(&table, index_name),
+            index.directions(),
+            &range,
+        );
+
+        let (entries, continuation) = self
+            .page_entries(range, continuation, offset, limit)
+            .await?;
+        let row_ids = entries.into_iter().map(|(_, row_id)| {
+            unpack::<i64>(&row_id)
+                .map_err(FdbBindingError::PackError)
+                .map_err(SqlLayerError::from)
+        });
+        let records = self
+            .rows_by_id(&table, futures::stream::iter(row_ids))
+            .map_ok(|record| match &positions {
+                Some(positions) => record.project(positions),
+                None => record,
+            })
+            .try_collect()
+            .await?;
+        Ok(Page {
+            records,
+            continuation,
+        })
+    }
+
+    /// Reads the entries of a page of a key range.
+    ///
+    /// The scan starts right after the continuation, if any, then skips `offset` entries
+    /// and keeps up to `limit` of them. A continuation pointing to the last entry read is
+    /// returned when entries remain in the range.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidPageLimit` if `limit` is 0, as an empty page without a
+    /// continuation would end the scan. Returns an error as well if the continuation is
+    /// outside of the key range, or if there is an issue with the database read
+    /// operations.
+    async fn page_entries(
+        &self,
+        (start, end): (Vec<u8>, Vec<u8>),
+        continuation: Option<&Continuation>,
+        offset: usize,
+        limit: usize,
+    ) -> crate::errors::Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<Continuation>)> {
+        if limit == 0 {
+            return Err(SqlLayerError::InvalidPageLimit);
+        }
+        let start = match continuation {
+            Some(Continuation(key)) if *key >= start && *key < end => {
+                let mut key = key.clone();
+                key.push(0x00);
+                key
+            }
+            Some(_) => return Err(SqlLayerError::InvalidContinuation),
+            None => start,
+        };
+
+        let entries = self.storage.full_scan(&start, &end).await;
+        pin_mut!(entries);
+        // the last key read, skipped entries included, is where the next page resumes
+        let mut last_key = continuation.map(|continuation| continuation.0.clone());
+        let mut skipped = 0;
+        let mut page = vec![];
+        while let Some(entry) = entries.next().await {
+            let (key, value) = entry?;
+            if page.len() == limit {
+                return Ok((page, last_key.map(Continuation)));
+            }
+            last_key = Some(key.clone());
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            page.push((key, value));
+        }
+        Ok((page, None))
+    }
+
+    /// Scans the records whose indexed columns fall within a range.
+    ///
+    /// The bounds are compared against the leading columns of the index, so a range over
+    /// a single column can be used on a composite index. Records are yielded ordered by
+    /// the indexed columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table to scan.
+    /// * `index_name` - The name of the index to traverse.
+    /// * `range` - The bounds of the indexed columns, e.g.
+    ///   `vec![Column::Int(18)]..vec![Column::Int(30)]`, in the order of the index keys: the
+    ///   start of a descending column is its largest value.
+    /// * `projection` - The names of the columns to return, in order, or `None` to return
+    ///   every column of the table.
+    ///
+    /// # Returns
+    ///
+    /// Returns a stream of the matching records, or an error if the scan can't be set up.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table or the index does not exist.
+    /// - The index is still being backfilled.
+    /// - A projected column is not a field of the table.
+    /// - There is an issue with the database read operations, reported through the stream.
+    async fn scan_index_range<R: RangeBounds<Vec<Column>>>(
+        &self,
+        table_name: &str,
+        index_name: &str,
+        range: R,
+        projection: Option<&[&str]>,
+    ) -> crate::errors::Result<impl Stream<Item = crate::errors::Result<Record>>> {
+        let table = self
+            .get_table(table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+        let index = table
+            .get_index(index_name)
+            .ok_or(SqlLayerError::IndexNotFound(index_name.to_string()))?;
+        if index.state() != IndexState::Ready {
+            return Err(SqlLayerError::IndexNotReady(index_name.to_string()));
+        }
+        let positions = projection
+            .map(|projection| resolve_projection(&table, projection))
+            .transpose()?;
+        let (start, end) = key_range(
+            &self.index_subspace(&table, index_name),
+            index.directions(),
+            &range,
+        );
+        Ok(async_stream::try_stream! {
+            let records = self.rows_in_range(&table, start, end);
+            pin_mut!(records);
+            while let Some(record) = records.next().await {
+                let record = record?;
+                let record = match &positions {
+                    Some(positions) => record.project(positions),
+                    None => record,
+                };
+                yield record;
+            }
+        })
+    }
+
+    /// Streams the row ids held by the primary key or index entries within a key range.
+    fn row_ids_in_range(
+        &self,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    ) -> impl Stream<Item = crate::errors::Result<i64>> {
+        async_stream::try_stream! {
+            let entries = self.storage.full_scan(&start, &end).await;
+            pin_mut!(entries);
+            while let Some(entry) = entries.next().await {
+                let (_, row_id) = entry?;
+                let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+                yield row_id;
+            }
+        }
+    }
+
+    /// Reads the records of several primary keys, in the order of the keys.
+    ///
+    /// The primary key entries are read concurrently within a single transaction, each
+    /// along with the row it references, see [`Database::entries_with_rows`]. Keys without
+    /// a record are skipped.
+    async fn get_records_by_pks(
+        &self,
+        table: &Table,
+        pks: &[Vec<Column>],
+    ) -> crate::errors::Result<Vec<Record>> {
+        let table_name = table.name.as_str();
+        let pk_subspace = self.table_subspace(DataPrefix::PrimaryKey, table);
+        let ranges = &pks
+            .iter()
+            .map(|pk| {
+                let pk = pk.iter().collect::<Vec<_>>();
+                let start = pk_subspace.pack(&KeyColumns::new(&pk, &table.primary_key_directions));
+                let mut end = start.clone();
+                end.push(0);
+                (start, end)
+            })
+            .collect::<Vec<_>>();
+        let entries = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let trx = &trx;
+                Ok(try_join_all(ranges.iter().map(|(start, end)| {
+                    self.entries_with_rows(trx, table, start, end, None, None)
+                }))
+                .await?)
+            })
+            .await?;
+        Ok(entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.record)
+            .collect())
+    }
+
+    /// Reads the records referenced by the entries of an index whose leading column is one
+    /// of the values, in row id order.
+    ///
+    /// As for [`Database::get_records_by_pks`], the entries of every value are read
+    /// concurrently within a single transaction, along with the rows they reference. Rows
+    /// referenced by several values are decoded once.
+    async fn get_records_by_index_values(
+        &self,
+        table: &Table,
+        index_name: &str,
+        values: &[Column],
+    ) -> crate::errors::Result<Vec<Record>> {
+        let table_name = table.name.as_str();
+        let index_subspace = self.index_subspace(table, index_name);
+        let directions = table
+            .get_index(index_name)
+            .map_or(&[][..], |index| index.directions());
+        let ranges = &values
+            .iter()
+            .map(|value| {
+                index_subspace
+                    .subspace(&KeyColumns::new(&[value], directions))
+                    .range()
+            })
+            .collect::<Vec<_>>();
+        let entries = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let trx = &trx;
+                Ok(try_join_all(ranges.iter().map(|(start, end)| {
+                    self.entries_with_rows(trx, table, start, end, None, None)
+                }))
+                .await?)
+            })
+            .await?;
+        let records = entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some((entry.row_id, entry.record?)))
+            .collect::<BTreeMap<_, _>>();
+        Ok(records.into_values().collect())
+    }
+
+    /// Reads the primary key or index entries of a key range, each along with the row it
+    /// references.
+    ///
+    /// When the rows of the table can be addressed by a mapper, see [`Database::row_mapper`],
+    /// a mapped range read has the storage servers resolve every entry into its row, so
+    /// both are read in a single request. Otherwise the entries are read first, then
+    /// their rows concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `trx` - The transaction to read with.
+    /// * `table` - The table the entries belong to.
+    /// * `start` - The first key of the range, included.
+    /// * `end` - The last key of the range, excluded.
+    /// * `limit` - The maximum number of entries to read, or `None` to read them all.
+    /// * `positions` - The positions of the columns read, see [`Database::read_record`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries in key order, with the row id they hold and the record stored
+    /// under it, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - An entry doesn't hold a row id.
+    /// - A row can't be decoded.
+    /// - There is an issue with the database read operations.
+    async fn entries_with_rows(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        start: &[u8],
+        end: &[u8],
+        limit: Option<usize>,
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<Vec<EntryRow>> {
+        let mut range = RangeOption::from((start, end));
+        range.limit = limit;
+
+        if let Some(mapper) = self.row_mapper(table) {
+            let mut rows = vec![];
+            let batches = trx.get_mapped_ranges(range, &mapper, false);
+            pin_mut!(batches);
+            while let Some(batch) = batches.try_next().await? {
+                for entry in batch.iter() {
+                    let row_id =
+                        unpack::<i64>(entry.parent_value()).map_err(FdbBindingError::PackError)?;
+                    rows.push((
+                        entry.parent_key().to_vec(),
+                        row_id,
+                        entry.key_values().first().map(|row| row.value().to_vec()),
+                    ));
+                }
+            }
+            return self.read_entry_records(trx, table, rows, positions).await;
+        }
+        self.entries_then_rows(trx, table, range, positions).await
+    }
+
+    /// Reads the entries of a key range, then the rows they reference concurrently, see
+    /// [`Database::entries_with_rows`].
+    ///
+    /// Unlike a mapped range read, both reads see the writes of the transaction, so this
+    /// is the read to use for entries the transaction may have written.
+    async fn entries_then_rows(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        range: RangeOption<'_>,
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<Vec<EntryRow>> {
+        let row_subspace = self.table_subspace(DataPrefix::Row, table);
+        let entries = trx
+            .get_ranges_keyvalues(range, false)
+            .map_ok(|entry| (entry.key().to_vec(), entry.value().to_vec()))
+            .try_collect::<Vec<_>>()
+            .await?;
+        let row_ids = entries
+            .iter()
+            .map(|(_, row_id)| unpack::<i64>(row_id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(FdbBindingError::PackError)?;
+        let row_keys = row_ids
+            .iter()
+            .map(|row_id| row_subspace.pack(row_id))
+            .collect::<Vec<_>>();
+        let rows = try_join_all(row_keys.iter().map(|key| trx.get(key, false))).await?;
+        let rows = zip(zip(entries, row_ids), rows)
+            .map(|(((key, _), row_id), row)| (key, row_id, row.map(|row| row.to_vec())))
+            .collect();
+        self.read_entry_records(trx, table, rows, positions).await
+    }
+
+    /// Decodes the rows read along with their entries, by entry key and row id, into
+    /// records, see [`Database::read_record`].
+    async fn read_entry_records(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        rows: Vec<(Vec<u8>, i64, Option<Vec<u8>>)>,
+        positions: Option<&[usize]>,
+    ) -> crate::errors::Result<Vec<EntryRow>> {
+        let mut entries = Vec::with_capacity(rows.len());
+        for (key, row_id, row) in rows {
+            let record = match row {
+                Some(row) => Some(
+                    self.read_record(trx, table, row_id, &row, positions)
+                        .await?,
+                ),
+                None => None,
+            };
+            entries.push(EntryRow {
+                key,
+                row_id,
+                record,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Builds the mapper resolving a primary key or index entry into the row it references.
+    ///
+    /// Entries hold the packed row id, so the mapper is the key of a row with the row id
+    /// replaced by the first element of the entry value. Mappers are tuples whose string
+    /// elements escape braces, which the packed row subspace can't be relied on to do.
+    ///
+    /// # Returns
+    ///
+    /// Returns the mapper, or `None` if mapped ranges aren't supported, see
+    /// [`Capabilities`](crate::storage::Capabilities), or if the row subspace holds brace
+    /// bytes.
+    fn row_mapper(&self, table: &Table) -> Option<Vec<u8>> {
+        let row_subspace = self.table_subspace(DataPrefix::Row, table);
+        if !self.storage.capabilities().mapped_ranges
+            || row_subspace
+                .bytes()
+                .iter()
+                .any(|byte| matches!(byte, b'{' | b'}'))
+        {
+            return None;
+        }
+        Some(row_subspace.pack(&"{V[0]}"))
+    }
+
+    /// Streams the records referenced by the primary key or index entries of a key range,
+    /// in the order of the entries.
+    ///
+    /// The entries are read in batches, each along with the rows it references within its
+    /// own transaction, see [`Database::entries_with_rows`].
+    fn rows_in_range(
+        &self,
+        table: &Table,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    ) -> impl Stream<Item = crate::errors::Result<Record>> {
+        let table_name = table.name.as_str();
+        async_stream::try_stream! {
+            let mut start = start;
+            loop {
+                let batch = {
+                    let (start, end) = (&start, &end);
+                    self.storage
+                        .run(self.table_tag(table_name), |trx, _| async move {
+                            Ok(self
+                                .entries_with_rows(
+                                    &trx,
+                                    table,
+                                    start,
+                                    end,
+                                    Some(ROW_BATCH_SIZE),
+                                    None,
+                                )
+                                .await?)
+                        })
+                        .await?
+                };
+                let Some(last) = batch.last() else {
+                    break;
+                };
+                let done = batch.len() < ROW_BATCH_SIZE;
+                start = last.key.clone();
+                start.push(0);
+                for entry in batch {
+                    if let Some(record) = entry.record {
+                        yield record;
+                    }
+                }
+                if done {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Streams the records stored under the given row ids, in order.
+    ///
+    /// The rows are read in batches of [`ROW_BATCH_SIZE`] ids, the rows of a batch
+    /// concurrently within its own transaction. Row ids without a row, such as rows deleted
+    /// since their id was read, are skipped.
+    fn rows_by_id<S>(
+        &self,
+        table: &Table,
+        row_ids: S,
+    ) -> impl Stream<Item = crate::errors::Result<Record>>
+    where
+        S: Stream<Item = crate::errors::Result<i64>>,
+    {
+        let table_name = table.name.as_str();
+        let row_subspace = self.table_subspace(DataPrefix::Row, table);
+        async_stream::try_stream! {
+            let batches = row_ids.chunks(ROW_BATCH_SIZE);
+            pin_mut!(batches);
+            while let Some(batch) = batches.next().await {
+                let row_ids = &batch.into_iter().collect::<crate::errors::Result<Vec<_>>>()?;
+                let row_subspace = &row_subspace;
+                let entries = self
+                    .storage
+                    .run(self.table_tag(table_name), |trx, _| async move {
+                        let keys = row_ids
+                            .iter()
+                            .map(|row_id| row_subspace.pack(row_id))
+                            .collect::<Vec<_>>();
+                        let rows = try_join_all(keys.iter().map(|key| trx.get(key, false))).await?;
+                        let rows = zip(zip(keys, row_ids), rows)
+                            .map(|((key, row_id), row)| (key, *row_id, row.map(|row| row.to_vec())))
+                            .collect();
+                        Ok(self.read_entry_records(&trx, table, rows, None).await?)
+                    })
+                    .await?;
+                for entry in entries {
+                    if let Some(record) = entry.record {
+                        yield record;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Deletes a record from the database based on the given primary key.
+    ///
+    /// The row, its primary key entry and all the secondary index entries
+    /// pointing to it are removed within a single transaction.
+    ///
+    /// The records referencing the row through a foreign key are deleted or have their
+    /// foreign key set to null within the same transaction, as the foreign key says, see
+    /// [`OnDelete`].
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table from which to delete the record.
+    /// * `pk` - A reference to the primary key of the record to delete.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if a record has been deleted, `Ok(false)` if no record
+    /// matches the primary key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - A record referencing the row through a `RESTRICT` foreign key remains.
+    /// - There is an issue with the database read or write operations.
+    async fn delete_by_pk(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<bool> {
+        let deleted = self
+            .write(table_name, |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                Ok(self.delete_by_pk_internal(&trx, &table, pk).await?)
+            })
+            .await?;
+        Ok(deleted)
+    }
+
+    /// Deletes the records matching a list of primary keys.
+    ///
+    /// The keys are deleted in batches of [`DELETE_BATCH_SIZE`], each within its own
+    /// transaction run with the [`CommitProfile::Bulk`] profile, along with the index
+    /// entries of their records, as [`Database::delete_by_pk`] does. Should a batch fail, the
+    /// previous ones stay deleted: deleting the same keys again is harmless.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table from which to delete the records.
+    /// * `pks` - The primary keys of the records to delete.
+    ///
+    /// # Returns
+    ///
+    /// Returns, for every primary key in order, whether a record has been deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read or write operations.
+    async fn delete_many(
+        &self,
+        table_name: &str,
+        pks: &[Columns<'_>],
+    ) -> crate::errors::Result<Vec<bool>> {
+        let mut deleted = Vec::with_capacity(pks.len());
+        for batch in pks.chunks(DELETE_BATCH_SIZE) {
+            let outcomes = self
+                .write_with_profile(
+                    CommitProfile::Bulk,
+                    self.table_tag(table_name),
+                    |trx, _| async move {
+                        let table = self
+                            .get_table_internal(&trx, table_name)
+                            .await?
+                            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                        let mut outcomes = Vec::with_capacity(batch.len());
+                        for pk in batch {
+                            outcomes.push(self.delete_by_pk_internal(&trx, &table, pk).await?);
+                        }
+                        Ok(outcomes)
+                    },
+                )
+                .await?;
+            deleted.extend(outcomes);
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_by_pk_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<bool> {
+        let subspace_pk = self.primary_key_entry(table, pk);
+        let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+            return Ok(false);
+        };
+        let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+
+        if let Some(bytes) = self.get_row(trx, table, row_id).await? {
+            // the stored record is needed to locate its index entries
+            let record = self.decode_record(table, &bytes)?;
+            for subspace_index in self.index_keys(table, &record, row_id)? {
+                trx.clear(&subspace_index);
+            }
+            self.clear_row(trx, table, row_id);
+            self.record_row_write(trx, &table.name, Some(bytes.len()), None);
+        }
+        trx.clear(&self.row_metadata_key(table, row_id));
+        trx.clear(&subspace_pk);
+
+        // the row is gone by now, so that a row referencing itself doesn't cascade forever
+        let pk = pk.0.iter().map(|column| (*column).clone()).collect();
+        self.apply_on_delete(trx, table, pk).await?;
+        Ok(true)
+    }
+
+    /// Applies the [`OnDelete`] action of every foreign key referencing a deleted row.
+    ///
+    /// The records referencing the row are deleted, through [`Database::delete_by_pk`] and
+    /// its own actions, or their foreign key fields are set to null, through
+    /// [`Database::update`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::ForeignKeyViolation` naming a `RESTRICT` foreign key of a
+    /// record still referencing the row, or the error raised while deleting or updating a
+    /// referencing record.
+    fn apply_on_delete<'a>(
+        &'a self,
+        trx: &'a RetryableTransaction,
+        table: &'a Table,
+        pk: Vec<Column>,
+    ) -> BoxFuture<'a, crate::errors::Result<()>> {
+        // boxed, as deleting the referencing records recurses into the deletion
+        Box::pin(async move {
+            let pk = pk.iter().collect::<Vec<_>>();
+            let pk = Columns::new(&pk);
+            for child in self.referencing_tables(trx, table).await? {
+                for foreign_key in child.foreign_keys_to(&table.name) {
+                    self.apply_foreign_key_on_delete(trx, &child, foreign_key, &pk)
+                        .await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies the [`OnDelete`] action of a foreign key to the records referencing a
+    /// deleted row, see [`Database::apply_on_delete`].
+    async fn apply_foreign_key_on_delete(
+        &self,
+        trx: &RetryableTransaction,
+        child: &Table,
+        foreign_key: &ForeignKey,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<()> {
+        let records = self
+            .referencing_records(trx, child, foreign_key, pk)
+            .await?;
+        for mut record in records {
+            let child_pk = extract_columns(child, &child.primary_key, &record)?
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>();
+            let child_pk = child_pk.iter().collect::<Vec<_>>();
+            let child_pk = Columns::new(&child_pk);
+            match foreign_key.on_delete {
+                OnDelete::Restrict => {
+                    return Err(SqlLayerError::ForeignKeyViolation(foreign_key.name.clone()));
+                }
+                OnDelete::Cascade => {
+                    self.delete_by_pk_internal(trx, child, &child_pk).await?;
+                }
+                OnDelete::SetNull => {
+                    for field in &foreign_key.fields {
+                        if let Some(pos) = child.get_field_pos(field) {
+                            record.columns[pos] = Column::Null;
+                        }
+                    }
+                    self.update_internal(trx, child, &child_pk, &record).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the tables holding a foreign key to the given table, itself included, as
+    /// recorded in its [`Table::referencing_tables`], so that no table is read for a table
+    /// nothing references.
+    async fn referencing_tables(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+    ) -> crate::errors::Result<Vec<Table>> {
+        let mut tables = Vec::with_capacity(table.referencing_tables.len());
+        for name in &table.referencing_tables {
+            if *name == table.name {
+                tables.push(table.clone());
+            } else if let Some(child) = self.get_table_internal(trx, name).await? {
+                tables.push(child);
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Reads the records of a table whose foreign key references the row with the given
+    /// primary key.
+    ///
+    /// The records are looked up through a ready index led by the foreign key fields when
+    /// the table has one and none of them is collated, the whole table is scanned
+    /// otherwise. Both reads see the writes of the transaction.
+    async fn referencing_records(
+        &self,
+        trx: &RetryableTransaction,
+        child: &Table,
+        foreign_key: &ForeignKey,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<Vec<Record>> {
+        let binary = foreign_key
+            .fields
+            .iter()
+            .all(|field| child.collation(field) == Collation::Binary);
+        if let Some(index) = child.indexes.iter().find(|index| {
+            binary
+                && index.state() == IndexState::Ready
+                && index.fields().starts_with(&foreign_key.fields)
+        }) {
+            let (start, end) = self
+                .index_subspace(child, index.name())
+                .subspace(&KeyColumns::new(pk.0, index.directions()))
+                .range();
+            // the transaction may have written entries of the range, e.g. by inserting a
+            // referencing record or by an earlier cascading deletion
+            let range = RangeOption::from((start.as_slice(), end.as_slice()));
+            return Ok(self
+                .entries_then_rows(trx, child, range, None)
+                .await?
+                .into_iter()
+                .filter_map(|entry| entry.record)
+                .collect());
+        }
+
+        let row_subspace = self.table_subspace(DataPrefix::Row, child);
+        let (start, end) = row_subspace.range();
+        let rows = trx
+            .get_ranges_keyvalues(RangeOption::from((start.as_slice(), end.as_slice())), false)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut records = vec![];
+        for kv in &rows {
+            let row_id = row_subspace
+                .unpack::<i64>(kv.key())
+                .map_err(FdbBindingError::PackError)?;
+            let row = self.resolve_row(trx, child, row_id, kv.value()).await?;
+            let mut record = self.decode_record(child, &row)?;
+            if extract_columns(child, &foreign_key.fields, &record)? == *pk.0 {
+                self.load_blobs(trx, child, row_id, &mut record, None)
+                    .await?;
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Checks that a record references existing rows through every foreign key of its
+    /// table. A foreign key with a null field references nothing and is not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::ForeignKeyViolation` naming the first foreign key whose
+    /// referenced row doesn't exist.
+    async fn check_foreign_keys(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+    ) -> crate::errors::Result<()> {
+        for foreign_key in &table.foreign_keys {
+            let columns = extract_columns(table, &foreign_key.fields, record)?;
+            if columns.iter().any(|column| matches!(column, Column::Null)) {
+                continue;
+            }
+            let stored;
+            let parent = if foreign_key.parent == table.name {
+                table
+            } else {
+                stored = self
+                    .get_table_internal(trx, &foreign_key.parent)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(foreign_key.parent.clone()))?;
+                &stored
+            };
+            let key = self.primary_key_entry(parent, &Columns::new(&columns));
+            if trx.get(&key, false).await?.is_none() {
+                return Err(SqlLayerError::ForeignKeyViolation(foreign_key.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the record identified by the given primary key.
+    ///
+    /// The row is rewritten in place, keeping its row id, and the secondary indexes
+    /// are fixed up within the same transaction: entries built from the previous
+    /// version of the record are removed and entries for the new version are written.
+    /// Indexes whose columns are left unchanged keep their entries untouched.
+    /// The new record may change the primary key, in which case the primary key
+    /// entry is moved accordingly, unless a record references it through a foreign key.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table holding the record.
+    /// * `pk` - A reference to the primary key of the record to update.
+    /// * `record` - A reference to the new version of the record.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the record has been updated, `Ok(false)` if no record
+    /// matches the primary key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The record has fields that do not match the schema.
+    /// - The record fails a check constraint of the table.
+    /// - The record references a missing row through a foreign key.
+    /// - There is an issue with the database read or write operations.
+    async fn update(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        record: &Record,
+    ) -> crate::errors::Result<bool> {
+        let updated = self
+            .write(table_name, |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                Ok(self.update_internal(&trx, &table, pk, record).await?)
+            })
+            .await?;
+        Ok(updated.is_some())
+    }
+
+    /// Updates a record within a transaction, see [`Database::update`].
+    ///
+    /// Returns the record as stored, its columns coerced to the types of their fields, or
+    /// `None` if no record matches the primary key.
+    async fn update_internal(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        pk: &Columns<'_>,
+        record: &Record,
+    ) -> crate::errors::Result<Option<Record>> {
+        let coerced = coerce_record(table, record);
+        let record = &*coerced;
+        validate_record(table, record).into_result()?;
+        check_constraints(table, record)?;
+        self.check_foreign_keys(trx, table, record).await?;
+
+        let subspace_pk = self.primary_key_entry(table, pk);
+        let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+            return Ok(None);
+        };
+        let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+
+        let (previous, previous_size) = match self.get_row(trx, table, row_id).await? {
+            Some(bytes) => {
+                let previous = self.decode_record(table, &bytes)?;
+                (Some(previous), Some(bytes.len()))
+            }
+            None => (None, None),
+        };
+
+        // entries are keyed by the indexed columns and the row id, which is kept, so only
+        // the indexes over changed columns have entries to move
+        let touched = table
+            .indexes
+            .iter()
+            .filter(|index| {
+                previous
+                    .as_ref()
+                    .is_none_or(|previous| changes_index(table, index, previous, record))
+            })
+            .collect::<Vec<_>>();
+
+        // remove the index entries of the previous version of the record
+        if let Some(previous) = &previous {
+            for index in &touched {
+                for subspace_index in self.index_entry_keys(table, index, previous, row_id)? {
+                    trx.clear(&subspace_index);
+                }
+            }
+        }
+
+        // move the primary key entry if the update changed it
+        let new_pk = extract_columns(table, &table.primary_key, record)?;
+        let new_subspace_pk = self.primary_key_entry(table, &Columns::new(&new_pk));
+        if new_subspace_pk != subspace_pk {
+            if trx.get(&new_subspace_pk, false).await?.is_some() {
+                return Err(SqlLayerError::DuplicatePrimaryKey(format!("{new_pk:?}")));
+            }
+            // a referenced primary key can't change under the records referencing it
+            for child in self.referencing_tables(trx, table).await? {
+                for foreign_key in child.foreign_keys_to(&table.name) {
+                    if !self
+                        .referencing_records(trx, &child, foreign_key, pk)
+                        .await?
+                        .is_empty()
+                    {
+                        return Err(SqlLayerError::ForeignKeyViolation(foreign_key.name.clone()));
+                    }
+                }
+            }
+            trx.clear(&subspace_pk);
+            trx.set(&new_subspace_pk, pack(&row_id).as_ref());
+        }
+
+        for index in &touched {
+            if index.is_unique() {
+                self.check_unique(trx, table, index, record, row_id).await?;
+            }
+            for subspace_index in self.index_entry_keys(table, index, record, row_id)? {
+                self.set_index_entry(trx, index, &subspace_index, row_id)?;
+            }
+        }
+
+        let stored = self.store_blobs(trx, table, row_id, record).await?;
+        let row_bytes = self.encode_row(table, &stored.to_stored_row(table))?;
+        self.set_row(trx, table, row_id, &row_bytes);
+        self.record_row_write(trx, &table.name, previous_size, Some(row_bytes.len()));
+        Ok(Some(coerced.into_owned()))
+    }
+
+    /// Inserts a record along with metadata attached to its row.
+    ///
+    /// The record is inserted as [`Database::insert`] does, the metadata is stored next to
+    /// the row within the same transaction, see [`RowMetadata`].
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table where the record is to be inserted.
+    /// * `record` - A reference to the `Record` to insert.
+    /// * `metadata` - The metadata of the row, nothing is stored if empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The record is missing required fields or has fields that do not match the schema.
+    /// - The record fails a check constraint of the table.
+    /// - The record references a missing row through a foreign key.
+    /// - A record with the same primary key already exists.
+    /// - An error occurs during the storage operation, such as a database write failure.
+    async fn insert_with_metadata(
+        &self,
+        table_name: &str,
+        record: &Record,
+        metadata: &RowMetadata,
+    ) -> crate::errors::Result<()> {
+        self.write(table_name, |trx, _| async move {
+            let table = self
+                .get_table_internal(&trx, table_name)
+                .await?
+                .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+            let inserted = self.insert_internal(&trx, &table, record).await?;
+            self.set_row_metadata(&trx, &table, &inserted.record, metadata)
+                .await?;
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Updates a record and replaces the metadata attached to its row.
+    ///
+    /// The record is updated as [`Database::update`] does, which keeps the metadata of the
+    /// row, then the metadata is replaced within the same transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table holding the record.
+    /// * `pk` - A reference to the primary key of the record to update.
+    /// * `record` - A reference to the new version of the record.
+    /// * `metadata` - The new metadata of the row, an empty map removes it.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the record has been updated, `Ok(false)` if no record
+    /// matches the primary key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - The record has fields that do not match the schema.
+    /// - There is an issue with the database read or write operations.
+    async fn update_with_metadata(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        record: &Record,
+        metadata: &RowMetadata,
+    ) -> crate::errors::Result<bool> {
+        let updated = self
+            .write(table_name, |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                let Some(updated) = self.update_internal(&trx, &table, pk, record).await? else {
+                    return Ok(false);
+                };
+                self.set_row_metadata(&trx, &table, &updated, metadata)
+                    .await?;
+                Ok(true)
+            })
+            .await?;
+        Ok(updated)
+    }
+
+    /// Fetches a record along with the metadata attached to its row.
+    ///
+    /// The row and its metadata are read concurrently once the primary key is resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table from which to fetch the record.
+    /// * `pk` - A reference to the primary key of the record to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// Returns the record and its metadata, empty if none is attached, or `None` if no
+    /// record matches the primary key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table does not exist.
+    /// - There is an issue with the database read operations or a row or its metadata
+    ///   can't be decoded.
+    async fn get_record_with_metadata(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<Option<(Record, RowMetadata)>> {
+        let stored = self
+            .storage
+            .run(self.table_tag(table_name), |trx, _| async move {
+                let table = self
+                    .get_table_internal(&trx, table_name)
+                    .await?
+                    .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))?;
+                let subspace_pk = self.primary_key_entry(&table, pk);
+                let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+                    return Ok(None);
+                };
+                let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+                let metadata_key = self.row_metadata_key(&table, row_id);
+                let (record, metadata) =
+                    futures::try_join!(self.get_record(&trx, &table, row_id), async {
+                        Ok(trx.get(&metadata_key, false).await?)
+                    })?;
+                Ok(record.map(|record| {
+                    let metadata = metadata.map(|metadata| metadata.to_vec());
+                    (record, metadata)
+                }))
+            })
+            .await?;
+        let Some((record, metadata)) = stored else {
+            return Ok(None);
+        };
+        let metadata = match metadata {
+            Some(metadata) => unpack::<Vec<(String, String)>>(&metadata)
+                .map_err(FdbBindingError::PackError)?
+                .into_iter()
+                .collect(),
+            None => RowMetadata::new(),
+        };
+        Ok(Some((record, metadata)))
+    }
+
+    /// Returns the key of the metadata of a row.
+    fn row_metadata_key(&self, table: &Table, row_id: i64) -> Vec<u8> {
+        self.table_subspace(DataPrefix::RowMetadata, table)
+            .pack(&row_id)
+    }
+
+    /// Stores the metadata of the row of a record written by the transaction, or removes
+    /// it if empty.
+    async fn set_row_metadata(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+        metadata: &RowMetadata,
+    ) -> crate::errors::Result<()> {
+        let pk = extract_columns(table, &table.primary_key, record)?;
+        let subspace_pk = self.primary_key_entry(table, &Columns::new(&pk));
+        // the entry has just been written, it is read back from the transaction
+        let Some(row_id) = trx.get(&subspace_pk, false).await? else {
+            return Ok(());
+        };
+        let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+        let key = self.row_metadata_key(table, row_id);
+        if metadata.is_empty() {
+            trx.clear(&key);
+        } else {
+            trx.set(&key, &pack(&metadata.iter().collect::<Vec<_>>()));
+        }
+        Ok(())
+    }
+
+    /// Checks every unique index of the table, see [`Database::check_unique`].
+    async fn check_unique_indexes(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        record: &Record,
+        row_id: i64,
+    ) -> crate::errors::Result<()> {
+        for index in table.indexes.iter().filter(|index| index.is_unique()) {
+            self.check_unique(trx, table, index, record, row_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Fails if a row other than `row_id` already owns one of the entries of the unique
+    /// `index` for `record`.
+    ///
+    /// As in SQL, null values are never equal to each other: an entry with a null indexed
+    /// column never conflicts.
+    async fn check_unique(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        index: &table::Index,
+        record: &Record,
+        row_id: i64,
+    ) -> crate::errors::Result<()> {
+        for columns in index_entries(table, index, record)? {
+            let columns = columns.iter().map(|column| &**column).collect::<Vec<_>>();
+            if columns.iter().any(|column| matches!(column, Column::Null)) {
+                continue;
+            }
+            self.check_unique_entry(trx, table, index, &columns, row_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Fails if a row other than `row_id` already owns the entry of the unique `index`
+    /// holding `columns`.
+    async fn check_unique_entry(
+        &self,
+        trx: &RetryableTransaction,
+        table: &Table,
+        index: &table::Index,
+        columns: &Vec<&Column>,
+        row_id: i64,
+    ) -> crate::errors::Result<()> {
+        let subspace = self
+            .index_subspace(table, index.name())
+            .subspace(&KeyColumns::new(columns, index.directions()));
+        let (start, end) = subspace.range();
+        let mut range = RangeOption::from((start.as_slice(), end.as_slice()));
+        range.limit = Some(2);
+        let entries = trx.get_range(&range, 1, false).await?;
+        for entry in entries.iter() {
+            let owner = subspace
+                .unpack::<i64>(entry.key())
+                .map_err(FdbBindingError::PackError)?;
+            if owner != row_id {
+                return Err(SqlLayerError::UniqueConstraintViolation {
+                    index: index.name().to_string(),
+                    values: format!("{columns:?}"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the keys of the secondary index entries of `record`, for every index of the
+    /// table.
+    fn index_keys(
+        &self,
+        table: &Table,
+        record: &Record,
+        row_id: i64,
+    ) -> crate::errors::Result<Vec<Vec<u8>>> {
+        let mut keys = vec![];
+        for index in &table.indexes {
+            keys.extend(self.index_entry_keys(table, index, record, row_id)?);
+        }
+        Ok(keys)
+    }
+
+    /// Builds the keys of the entries of `index` for `record` stored under `row_id`, see
+    /// [`index_entries`].
+    ///
+    /// The row id is appended to the indexed columns so that records sharing the same
+    /// indexed values each get their own entry. The entry value holds the packed row id
+    /// as well, sparing readers from decoding the indexed columns to find it.
+    fn index_entry_keys(
+        &self,
+        table: &Table,
+        index: &table::Index,
+        record: &Record,
+        row_id: i64,
+    ) -> crate::errors::Result<Vec<Vec<u8>>> {
+        let subspace = self.index_subspace(table, index.name());
+        Ok(index_entries(table, index, record)?
+            .iter()
+            .map(|columns| {
+                let columns = columns.iter().map(|column| &**column).collect::<Vec<_>>();
+                subspace
+                    .subspace(&KeyColumns::new(&columns, index.directions()))
+                    .pack(&row_id)
+            })
+            .collect())
+    }
+}
+
+/// The record operations of a transaction, see [`Database::transact`].
+///
+/// The handle is only valid within the closure it is given to and must not be kept.
+struct DatabaseTransaction<'a> {
+    database: &'a Database,
+    trx: RetryableTransaction,
+}
+
+impl DatabaseTransaction<'_> {
+    /// Returns the definition of a table, failing if it doesn't exist.
+    async fn table(&self, table_name: &str) -> crate::errors::Result<Table> {
+        self.database
+            .get_table_internal(&self.trx, table_name)
+            .await?
+            .ok_or(SqlLayerError::TableNotFound(table_name.to_string()))
+    }
+
+    /// Inserts a record, see [`Database::insert`].
+    async fn insert(
+        &self,
+        table_name: &str,
+        record: &Record,
+    ) -> crate::errors::Result<InsertedRow> {
+        let table = self.table(table_name).await?;
+        self.database
+            .insert_internal(&self.trx, &table, record)
+            .await
+    }
+
+    /// Inserts or replaces a record, see [`Database::upsert`].
+    async fn upsert(&self, table_name: &str, record: &Record) -> crate::errors::Result<()> {
+        let table = self.table(table_name).await?;
+        self.database
+            .upsert_internal(&self.trx, &table, record)
+            .await
+    }
+
+    /// Fetches the record of a primary key, along with the writes of the transaction.
+    ///
+    /// Unlike [`Database::get_record_by_pk`], the entry and the row are read one after the
+    /// other: mapped range reads can't read the writes of their transaction.
+    async fn get(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+    ) -> crate::errors::Result<Option<Record>> {
+        let table = self.table(table_name).await?;
+        let subspace_pk = self.database.primary_key_entry(&table, pk);
+        let Some(row_id) = self.trx.get(&subspace_pk, false).await? else {
+            return Ok(None);
+        };
+        let row_id = unpack::<i64>(&row_id).map_err(FdbBindingError::PackError)?;
+        self.database.get_record(&self.trx, &table, row_id).await
+    }
+
+    /// Updates the record of a primary key, see [`Database::update`].
+    async fn update(
+        &self,
+        table_name: &str,
+        pk: &Columns<'_>,
+        record: &Record,
+    ) -> crate::errors::Result<bool> {
+        let table = self.table(table_name).await?;
+        Ok(self
+            .database
+            .update_internal(&self.trx, &table, pk, record)
+            .await?
+            .is_some())
+    }
+
+    /// Deletes the record of a primary key, see [`Database::delete_by_pk`].
+    async fn delete(&self, table_name: &str, pk: &Columns<'_>) -> crate::errors::Result<bool> {
+        let table = self.table(table_name).await?;
+        self.database
+            .delete_by_pk_internal(&self.trx, &table, pk)
+            .await
+    }
+}
+
+/// Called on every database opened by a catalog, along with its name, before its first use.
+type DatabaseSetup = Box<dyn Fn(&str, &mut Database) + Send + Sync>;
+
+/// Named databases sharing a single storage, so that a process hosts several isolated
+/// logical databases over one FoundationDB connection and its network thread.
+///
+/// Every database lives under its own root subspace, below the root of the catalog: two
+/// databases never read each other's tables, rows or indexes. Databases are opened lazily,
+/// on first use, then kept open for the lifetime of the catalog.
+///
+/// The storage is shared as a whole, which is safe as its state describes the cluster
+/// rather than a database: the transaction tags, the features of the client and whether
+/// transactions read through a lock, the lock applying to the whole cluster. The caches of
+/// a database, such as its reserved row ids, stay private to it.
+struct Catalog {
+    root_subspace: Subspace,
+    storage: Storage,
+    databases: Mutex<HashMap<String, Arc<Database>>>,
+    setup: Option<DatabaseSetup>,
+}
+
+impl Catalog {
+    fn new(root_subspace: Subspace, storage: Storage) -> Self {
+        Self {
+            root_subspace,
+            storage,
+            databases: Mutex::new(HashMap::new()),
+            setup: None,
+        }
+    }
+
+    /// Sets a callback configuring every database opened from now on, e.g. to set its
+    /// result limits or its schema mode.
+    fn set_database_setup(&mut self, setup: impl Fn(&str, &mut Database) + Send + Sync + 'static) {
+        self.setup = Some(Box::new(setup));
+    }
+
+    /// Returns the database of the given name, opening it on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidIdentifier` if the name is empty or contains a NUL
+    /// byte.
+    fn database(&self, name: &str) -> crate::errors::Result<Arc<Database>> {
+        crate::sql::check_identifier(name)?;
+        let mut databases = self.databases();
+        if let Some(database) = databases.get(name) {
+            return Ok(database.clone());
+        }
+        let mut database = Database::new(self.root_subspace.subspace(&name), self.storage.clone());
+        if let Some(setup) = &self.setup {
+            setup(name, &mut database);
+        }
+        let database = Arc::new(database);
+        databases.insert(name.to_string(), database.clone());
+        Ok(database)
+    }
+
+    /// Returns the names of the databases opened so far, in alphabetical order.
+    fn open_databases(&self) -> Vec<String> {
+        let mut names = self.databases().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Locks the open databases, recovering them from a panicked holder: the map is only
+    /// ever inserted into, so it can't be left inconsistent.
+    fn databases(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Database>>> {
+        self.databases
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether the columns indexed by `index` differ between two versions of a record.
+fn changes_index(table: &Table, index: &table::Index, previous: &Record, record: &Record) -> bool {
+    index.fields().iter().any(|field| {
+        let position = table.get_field_pos(field);
+        position.and_then(|i| previous.columns.get(i))
+            != position.and_then(|i| record.columns.get(i))
+    })
+}
+
+/// Translates bounds over the leading columns of the keys of a subspace into a key range.
+///
+/// Keys of the subspace may extend the bounded columns, an included bound therefore covers
+/// every key starting with its columns while an excluded bound skips all of them. The
+/// columns are encoded in the given directions, the bounds being in the order of the keys.
+fn key_range(
+    subspace: &Subspace,
+    directions: &[Direction],
+    range: &impl RangeBounds<Vec<Column>>,
+) -> (Vec<u8>, Vec<u8>) {
+    let prefix = |columns: &Vec<Column>| {
+        let columns = columns.iter().collect::<Vec<_>>();
+        subspace.subspace(&KeyColumns::new(&columns, directions))
+    };
+    let (first, last) = subspace.range();
+    let start = match range.start_bound() {
+        Bound::Included(columns) => prefix(columns).bytes().to_vec(),
+        Bound::Excluded(columns) => prefix(columns).range().1,
+        Bound::Unbounded => first,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(columns) => prefix(columns).range().1,
+        Bound::Excluded(columns) => prefix(columns).bytes().to_vec(),
+        Bound::Unbounded => last,
+    };
+    (start, end)
+}
+
+/// Reads whether the cluster is locked, without conflicting with the lock being taken.
+async fn is_locked(trx: &RetryableTransaction) -> foundationdb::FdbResult<bool> {
+    trx.set_option(TransactionOption::ReadSystemKeys)?;
+    trx.set_option(TransactionOption::ReadLockAware)?;
+    Ok(trx.get(DATABASE_LOCKED_KEY, true).await?.is_some())
+}
+
+/// Sets the default of a field added to a table, see [`Database::add_column`].
+///
+/// # Errors
+///
+/// Returns an error if the field name is not a valid identifier, or
+/// `SqlLayerError::MismatchedColumnType` if the default doesn't match the type of the
+/// field, or is null while the field is `NOT NULL`.
+fn with_default(mut field: Field, default: Column) -> crate::errors::Result<Field> {
+    crate::sql::check_identifier(&field.name)?;
+    field.default = None;
+    if default != Column::Null {
+        field.set_default(default)?;
+    } else if !field.nullable {
+        return Err(SqlLayerError::MismatchedColumnType(
+            format!("NOT NULL {:?}", field.r#type),
+            format!("{default:?}"),
+        ));
+    }
+    Ok(field)
+}
+
+/// The sort keys and identity columns merging the ranges of a union plan in the order of
+/// their index, see [`merge_ordered`].
+///
+/// # Returns
+///
+/// Returns the positions of the index fields and of the primary key fields, or `None` if
+/// the ranges span several indexes or the index has a collated field.
+fn union_merge_keys(
+    table: &Table,
+    ranges: &[IndexRange],
+) -> Option<(Vec<(usize, Direction)>, Vec<usize>)> {
+    let index = table.get_index(common_index(ranges)?)?;
+    // collated entries don't sort as the records do
+    if index
+        .fields()
+        .iter()
+        .any(|field| table.collation(field) != Collation::Binary)
+    {
+        return None;
+    }
+    let positions = |fields: &[String]| {
+        fields
+            .iter()
+            .map(|field| table.get_field_pos(field))
+            .collect::<Option<Vec<_>>>()
+    };
+    let sort_keys = positions(index.fields())?
+        .into_iter()
+        .enumerate()
+        .map(|(i, position)| (position, index.direction(i)))
+        .collect();
+    Some((sort_keys, positions(&table.primary_key)?))
+}
+
+/// Resolves projected column names into positions within the table fields.
+fn resolve_projection(table: &Table, projection: &[&str]) -> crate::errors::Result<Vec<usize>> {
+    projection
+        .iter()
+        .map(|name| {
+            table
+                .get_field_pos(name)
+                .ok_or(SqlLayerError::MissingColumn(name.to_string()))
+        })
+        .collect()
+}
+
+/// Collects the columns of `record` matching the given field names, in order.
+fn extract_columns<'a>(
+    table: &Table,
+    fields: &[String],
+    record: &'a Record,
+) -> crate::errors::Result<Vec<&'a Column>> {
+    fields
+        .iter()
+        .map(|field| {
+            table
+                .get_field_pos(field)
+                .and_then(|i| record.columns.get(i))
+                .ok_or(SqlLayerError::MissingColumn(field.to_string()))
+        })
+        .collect()
+}
+
+/// Returns the indexed columns of every entry of `index` for `record`: a single entry, or
+/// one per distinct element of the array of a multi-entry index, see
+/// [`Index::new_multi_entry`](table::Index::new_multi_entry).
+///
+/// The columns of collated fields are collated, see [`Collation`], so that strings equal
+/// under the collation of their field share their entries.
+fn index_entries<'a>(
+    table: &Table,
+    index: &table::Index,
+    record: &'a Record,
+) -> crate::errors::Result<Vec<Vec<Cow<'a, Column>>>> {
+    let columns = extract_columns(table, index.fields(), record)?;
+    match columns.as_slice() {
+        [Column::Array(values)] if index.is_multi_entry() => {
+            let collation = table.collation(&index.fields()[0]);
+            let mut entries = Vec::<Vec<Cow<Column>>>::new();
+            for value in values.iter().map(|value| collation.column(value)) {
+                if !entries.iter().any(|entry| entry[0] == value) {
+                    entries.push(vec![value]);
+                }
+            }
+            Ok(entries)
+        }
+        _ => Ok(vec![zip(columns, index.fields())
+            .map(|(column, field)| table.collation(field).column(column))
+            .collect()]),
+    }
+}
+
+/// Validates `record` against the table, collecting every violation found.
+///
+/// The record must hold exactly one column per table field, every column must match the
+/// type of its field, or convert into it, see [`coerce_record`], floats can't be NaN and
+/// `NOT NULL` fields can't be null. Unless the table allows it, primary key columns can't
+/// be null either, whether the field is `NOT NULL` or not. When the column count is off,
+/// the fields left without a column or the columns past the last field are reported one
+/// by one.
+fn validate_record(table: &Table, record: &Record) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    if table.fields.len() != record.columns.len() {
+        report.add(
+            None,
+            ViolationKind::ColumnCountMismatch {
+                expected: table.fields.len(),
+                found: record.columns.len(),
+            },
+        );
+        for field in table.fields.iter().skip(record.columns.len()) {
+            report.add(Some(&field.name), ViolationKind::MissingColumn);
+        }
+        for position in table.fields.len()..record.columns.len() {
+            report.add(None, ViolationKind::ExtraColumn { position });
+        }
+    }
+    let is_primary_key = |name: &String| table.primary_key.contains(name);
+    for (field, column) in zip(table.fields.iter(), record.columns.iter()) {
+        if let Err(violation) = check_field_against_column(field, column) {
+            report.add(Some(&field.name), violation);
+        }
+        // null primary key columns are reported below
+        let null_primary_key = is_primary_key(&field.name) && !table.allow_null_primary_key;
+        if *column == Column::Null && !field.nullable && !null_primary_key {
+            report.add(Some(&field.name), ViolationKind::NullColumn);
+        }
+    }
+    if !table.allow_null_primary_key {
+        for field in &table.primary_key {
+            let column = table
+                .get_field_pos(field)
+                .and_then(|i| record.columns.get(i));
+            if let Some(Column::Null) = column {
+                report.add(Some(field), ViolationKind::NullPrimaryKey);
+            }
+        }
+    }
+    report
+}
+
+/// Checks `record` against the check constraints of the table, see [`table::Check`].
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::ConstraintViolation` naming the first constraint the record
+/// fails, or `SqlLayerError::InvalidSqlText` if a constraint can't be parsed.
+fn check_constraints(table: &Table, record: &Record) -> crate::errors::Result<()> {
+    for check in &table.checks {
+        if !check.predicate()?.check(table, record) {
+            return Err(SqlLayerError::ConstraintViolation(check.name.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the length of a row split into chunks from the value stored under its key, see
+/// `Database::set_row`, or `None` if the row is stored in a single value.
+fn chunked_length(bytes: &[u8]) -> Option<usize> {
+    let length = bytes.strip_prefix(&CHUNKED_ROW)?;
+    Some(u64::from_le_bytes(length.try_into().ok()?) as usize)
+}
+
+/// Reads the chunks of a row or a blob and joins them.
+///
+/// # Returns
+///
+/// Returns `None` if the chunks don't add up to the given length.
+async fn read_chunks(
+    trx: &Transaction,
+    chunks: &Subspace,
+    length: usize,
+) -> crate::errors::Result<Option<Vec<u8>>> {
+    let (start, end) = chunks.range();
+    let range = RangeOption::from((start.as_slice(), end.as_slice()));
+    let bytes = trx
+        .get_ranges_keyvalues(range, false)
+        .try_fold(Vec::with_capacity(length), |mut bytes, chunk| {
+            bytes.extend_from_slice(chunk.value());
+            async move { Ok(bytes) }
+        })
+        .await?;
+    Ok((bytes.len() == length).then_some(bytes))
+}
+
+/// Returns the position of a blob field of a table.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::MissingColumn` if the table has no field of this name, or
+/// `SqlLayerError::InvalidBlobField` if the field isn't a blob field.
+fn blob_position(table: &Table, field_name: &str) -> crate::errors::Result<usize> {
+    let position = table
+        .get_field_pos(field_name)
+        .ok_or_else(|| SqlLayerError::MissingColumn(field_name.to_string()))?;
+    if !table.fields[position].blob {
+        return Err(SqlLayerError::InvalidBlobField(format!(
+            "{field_name} is not a blob field"
+        )));
+    }
+    Ok(position)
+}
+
+/// Iterates over the positions of the blob fields of a decoded record holding the id of
+/// their blob, along with the id, among the given positions or every position.
+fn blob_ids<'a>(
+    table: &'a Table,
+    record: &'a Record,
+    positions: Option<&'a [usize]>,
+) -> impl Iterator<Item = (usize, i64)> + 'a {
+    zip(&table.fields, &record.columns)
+        .enumerate()
+        .filter(move |(position, _)| positions.is_none_or(|positions| positions.contains(position)))
+        .filter_map(|(position, (field, column))| match column {
+            Column::Int(blob_id) if field.blob => Some((position, *blob_id)),
+            _ => None,
+        })
+}
+
+/// Hashes the encoded content of a row with 64-bit FNV-1a.
+///
+/// The hash must be stable across builds and platforms for checksums to be comparable,
+/// which rules out the standard library hashers.
+fn row_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Checks that a column is of the type of its field, the elements of an array of its item
+/// type, and that a decimal has at most the digits after the decimal point of its field.
+fn check_field_against_column(
+    field: &Field,
+    column: &Column,
+) -> std::result::Result<(), ViolationKind> {
+    let invalid_decimal = |column: &Column| match column {
+        Column::Decimal(decimal) => decimal.rescale(field.scale).is_none(),
+        _ => false,
+    };
+    // NaN has no place in the order of the floats, nor in keys
+    let nan = |column: &Column| matches!(column, Column::Float(value) if value.is_nan());
+    let expected = match coerce_column(field, column).as_deref() {
+        None => match (column, field.item_type) {
+            (Column::Array(_), Some(item_type)) if field.r#type == FieldType::Array => {
+                format!("{:?} of {item_type:?}", field.r#type)
+            }
+            _ => format!("{:?}", field.r#type),
+        },
+        Some(Column::Array(values))
+            if field.item_type.is_none()
+                || values
+                    .iter()
+                    .any(|value| invalid_decimal(value) || nan(value)) =>
+        {
+            match field.item_type {
+                Some(item_type) => format!("{:?} of {item_type:?}", field.r#type),
+                None => format!("{:?} without item type", field.r#type),
+            }
+        }
+        Some(value) if invalid_decimal(value) => {
+            format!("{:?} of scale {}", field.r#type, field.scale)
+        }
+        Some(value) if nan(value) => format!("{:?} other than NaN", field.r#type),
+        Some(value) if !field.is_variant(value) => {
+            format!("{:?} of {:?}", field.r#type, field.variants)
+        }
+        _ => return Ok(()),
+    };
+    let found = format!("{column:?}");
+    Err(ViolationKind::MismatchedColumnType { expected, found })
+}
+
+/// Converts a column into the column stored in its field, see [`Column::coerce`], the
+/// elements of an array into the item type of the field.
+///
+/// # Returns
+///
+/// Returns the column itself if it is stored as it is, the converted column, or `None` if
+/// the column or one of its elements doesn't convert.
+fn coerce_column<'a>(field: &Field, column: &'a Column) -> Option<Cow<'a, Column>> {
+    match (column, field.item_type) {
+        (Column::Array(values), Some(item_type)) if field.r#type == FieldType::Array => {
+            let elements = values
+                .iter()
+                .map(|value| value.coerce(item_type))
+                .collect::<Option<Vec<_>>>()?;
+            if elements
+                .iter()
+                .all(|element| matches!(element, Cow::Borrowed(_)))
+            {
+                return Some(Cow::Borrowed(column));
+            }
+            let elements = elements.into_iter().map(Cow::into_owned).collect();
+            Some(Cow::Owned(Column::Array(elements)))
+        }
+        _ => column.coerce(field.r#type),
+    }
+}
+
+/// Converts the columns of a record into the columns stored in their fields, see
+/// [`coerce_column`], so that a trivially convertible value, such as an integer given for
+/// a float field, isn't rejected. The columns that don't convert are left as they are, to
+/// be reported by [`validate_record`].
+fn coerce_record<'a>(table: &Table, record: &'a Record) -> Cow<'a, Record> {
+    let mut coerced = Cow::Borrowed(record);
+    for (position, (field, column)) in zip(&table.fields, &record.columns).enumerate() {
+        if let Some(Cow::Owned(column)) = coerce_column(field, column) {
+            coerced.to_mut().columns[position] = column;
+        }
+    }
+    coerced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::RowCompression;
+    use crate::index::Index;
+    use crate::limits::ResultLimit;
+    use crate::migrations::Migration;
+    use crate::storage::Capabilities;
+    use crate::table;
+    use crate::validation::Violation;
+    use table::{Field, FieldType};
+
+    fn person_table() -> Table {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        table
+    }
+
+    fn person_record(name: &str, age: i64) -> Record {
+        Record {
+            columns: vec![
+                Column::String(name.to_string()),
+                Column::Int(age),
+                Column::Float(age as f64),
+                Column::Bool(age % 2 == 0),
+                Column::Bytes(b"arbitrary data".to_vec()),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_database() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_database"), storage);
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let found_table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        assert_eq!(
+            found_table,
+            Table {
+                id: Some(1),
+                ..table.clone()
+            }
+        );
+
+        // an existing table is never replaced, nor are its rows left behind
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        let mut other = person_table();
+        other.add_index(&Index::new("idx_age", vec!["age"]));
+        assert!(matches!(
+            database.create_table(&other, false).await,
+            Err(SqlLayerError::TableAlreadyExists(_))
+        ));
+        database
+            .create_table(&other, true)
+            .await
+            .expect("Unable to create table");
+        let found_table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        assert_eq!(found_table.id, Some(1));
+        assert!(found_table.indexes.is_empty());
+
+        // a typo in the primary key fails before any row is written
+        let mut typo = person_table();
+        typo.name = "Typo".to_string();
+        typo.primary_key = vec!["nmae".to_string()];
+        assert!(matches!(
+            database.create_table(&typo, false).await,
+            Err(SqlLayerError::InvalidPrimaryKey(_))
+        ));
+        assert!(database.get_table("Typo").await.unwrap().is_none());
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![person_record("John", 20)]);
+    }
+
+    #[tokio::test]
+    async fn test_list_tables_and_describe() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_list_tables_and_describe"),
+            storage,
+        );
+        assert_eq!(
+            database.list_tables().await.expect("Unable to list tables"),
+            Vec::<String>::new()
+        );
+
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        let mut pets = Table::new("Pet".to_string(), vec!["id".to_string()]);
+        pets.add_field(Field::new("id".to_string(), FieldType::Int));
+        pets.add_field(Field::new("owner".to_string(), FieldType::String));
+        pets.add_foreign_key(ForeignKey::new(
+            "pet_owner_fkey",
+            vec!["owner".to_string()],
+            "Person",
+            OnDelete::Cascade,
+        ))
+        .expect("Invalid foreign key");
+        for table in [&table, &pets] {
+            database
+                .create_table(table, false)
+                .await
+                .expect("Unable to create table");
+        }
+        database
+            .drop_column("Person", "height")
+            .await
+            .expect("Unable to drop column");
+
+        assert_eq!(
+            database.list_tables().await.expect("Unable to list tables"),
+            vec!["Person", "Pet"]
+        );
+        let description = database
+            .describe("Person")
+            .await
+            .expect("Unable to describe table");
+        table.fields.remove(2);
+        assert_eq!(description, TableDescription::from(table));
+        let description = database
+            .describe("Pet")
+            .await
+            .expect("Unable to describe table");
+        assert_eq!(description, TableDescription::from(pets));
+        assert!(matches!(
+            database.describe("Unknown").await,
+            Err(SqlLayerError::TableNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_insert_record() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_insert_record"), storage);
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let record = Record {
+            columns: vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(20.5),
+                Column::Bool(true),
+                Column::Bytes(b"arbitrary data".to_vec()),
+            ],
+        };
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+        let found_record = database
+            .get_record_by_pk(
+                "Person",
+                &Columns(&vec![&Column::String("John".to_string())]),
+                None,
+            )
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found_record, Some(record));
+    }
+
+    #[tokio::test]
+    async fn insert_multiple_records() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"insert_multiple_records"),
+            storage,
+        );
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        // populate database
+        for i in 0..10 {
+            let record = Record {
+                columns: vec![
+                    Column::String(format!("John {}", i)),
+                    Column::Int(i),
+                    Column::Float(i as f64),
+                    Column::Bool(i % 2 == 0),
+                    Column::Bytes(b"arbitrary data".to_vec()),
+                ],
+            };
+            database
+                .insert("Person", &record)
+                .await
+                .expect("Unable to insert record");
+        }
+
+        // check record are inserted
+        for i in 0..10 {
+            let expected = Record {
+                columns: vec![
+                    Column::String(format!("John {}", i)),
+                    Column::Int(i),
+                    Column::Float(i as f64),
+                    Column::Bool(i % 2 == 0),
+                    Column::Bytes(b"arbitrary data".to_vec()),
+                ],
+            };
+            // check that the record is retrievable from pk
+            let found = database
+                .get_record_by_pk(
+                    "Person",
+                    &Columns(&vec![&Column::String(format!("John {}", i))]),
+                    None,
+                )
+                .await
+                .expect("Unable to get record");
+            assert_eq!(found, Some(expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_row_by_index() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_retrieve_row_by_index"),
+            storage,
+        );
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let index = &Index::new("idx_age", vec!["age"]);
+        database
+            .add_index("Person", index)
+            .await
+            .expect("Unable to add index");
+
+        let record = Record {
+            columns: vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(20.5),
+                Column::Bool(true),
+                Column::Bytes(b"arbitrary data".to_vec()),
+            ],
+        };
+
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_pk() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_delete_by_pk"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let record = person_record("John", 20);
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+
+        let name = Column::String("John".to_string());
+        let pk = vec![&name];
+        let deleted = database
+            .delete_by_pk("Person", &Columns(&pk))
+            .await
+            .expect("Unable to delete record");
+        assert!(deleted);
+
+        let found = database
+            .get_record_by_pk("Person", &Columns(&pk), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, None);
+
+        // the index entry must be gone as well
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let index_subspace = database.table_subspace(DataPrefix::Index, &table);
+        let (start, end) = index_subspace.range();
+        let entries = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan index");
+        assert!(entries.is_empty());
+
+        let deleted = database
+            .delete_by_pk("Person", &Columns(&pk))
+            .await
+            .expect("Unable to delete record");
+        assert!(!deleted);
+    }
+
+    #[tokio::test]
+    async fn test_null_primary_key() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_null_primary_key"), storage);
+        let table = person_table();
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let mut record = person_record("John", 20);
+        record.columns[0] = Column::Null;
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(
+            report.violations,
+            vec![Violation {
+                field: Some("name".to_string()),
+                kind: ViolationKind::NullPrimaryKey,
+            }]
+        );
+
+        // explicitly allowed nulls are encoded deterministically and can be looked up
+        let mut table = person_table();
+        table.name = "NullablePerson".to_string();
+        table.set_allow_null_primary_key(true);
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("NullablePerson", &record)
+            .await
+            .expect("Unable to insert record");
+        let found = database
+            .get_record_by_pk("NullablePerson", &Columns(&vec![&Column::Null]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(record));
+    }
+
+    #[tokio::test]
+    async fn test_not_null() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_not_null"), storage);
+        let mut table = person_table();
+        table.fields[0].nullable = false;
+        table.fields[1].nullable = false;
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        // nullable fields still accept nulls
+        let mut record = person_record("John", 20);
+        record.columns[2] = Column::Null;
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+
+        let mut record = person_record("Jane", 30);
+        record.columns[1] = Column::Null;
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(
+            report.violations,
+            vec![Violation {
+                field: Some("age".to_string()),
+                kind: ViolationKind::NullColumn,
+            }]
+        );
+
+        // a null primary key is reported once
+        record.columns[0] = Column::Null;
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(
+            report
+                .violations
+                .iter()
+                .map(|violation| &violation.kind)
+                .collect::<Vec<_>>(),
+            vec![&ViolationKind::NullColumn, &ViolationKind::NullPrimaryKey]
+        );
+
+        let name = Column::String("John".to_string());
+        let mut record = person_record("John", 21);
+        record.columns[1] = Column::Null;
+        let result = database
+            .update("Person", &Columns(&vec![&name]), &record)
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_check_constraints() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_check_constraints"), storage);
+        database
+            .execute(
+                "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT CHECK (age >= 0), \
+                 height FLOAT, CONSTRAINT tall_adults CHECK (age < 18 OR height > 1.5))",
+            )
+            .await
+            .expect("Unable to create table");
+
+        database
+            .execute("INSERT INTO Person VALUES ('John', 20, 1.8), ('Jane', NULL, NULL)")
+            .await
+            .expect("Unable to insert records");
+        assert!(matches!(
+            database.execute("INSERT INTO Person VALUES ('Bob', -1, 1.8)").await,
+            Err(SqlLayerError::ConstraintViolation(name)) if name == "Person_age_check"
+        ));
+        assert!(matches!(
+            database.execute("INSERT INTO Person VALUES ('Bob', 30, 1.2)").await,
+            Err(SqlLayerError::ConstraintViolation(name)) if name == "tall_adults"
+        ));
+
+        let name = Column::String("John".to_string());
+        let record = Record::new(vec![name.clone(), Column::Int(-20), Column::Float(1.8)]);
+        assert!(matches!(
+            database
+                .update("Person", &Columns(&vec![&name]), &record)
+                .await,
+            Err(SqlLayerError::ConstraintViolation(_))
+        ));
+
+        // constraints must reference the fields of the table
+        let mut table = person_table();
+        table.name = "Invalid".to_string();
+        table
+            .add_check(table::Check::new("weight_check", "weight > 0"))
+            .unwrap();
+        assert!(matches!(
+            database.create_table(&table, false).await,
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_foreign_keys() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_foreign_keys"), storage);
+        for sql in [
+            "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT)",
+            "CREATE TABLE Orders (id INT PRIMARY KEY, \
+             customer TEXT REFERENCES Person (name) ON DELETE CASCADE)",
+            "CREATE TABLE Reviews (id INT PRIMARY KEY, \
+             order_id INT REFERENCES Orders (id) ON DELETE SET NULL)",
+            "CREATE TABLE Badges (id INT PRIMARY KEY, owner TEXT REFERENCES Person (name))",
+        ] {
+            database.execute(sql).await.expect("Unable to create table");
+        }
+
+        // foreign keys must match the primary key of an existing table
+        assert!(matches!(
+            database
+                .execute(
+                    "CREATE TABLE Pets (id INT PRIMARY KEY, owner TEXT REFERENCES Owner (name))"
+                )
+                .await,
+            Err(SqlLayerError::TableNotFound(_))
+        ));
+        assert!(matches!(
+            database
+                .execute(
+                    "CREATE TABLE Pets (id INT PRIMARY KEY, owner INT REFERENCES Person (name))"
+                )
+                .await,
+            Err(SqlLayerError::InvalidForeignKey(_))
+        ));
+
+        for sql in [
+            "INSERT INTO Person VALUES ('John', 20), ('Jane', 30)",
+            "INSERT INTO Orders VALUES (1, 'John'), (2, 'John'), (3, NULL)",
+            "INSERT INTO Reviews VALUES (1, 1), (2, 3)",
+            "INSERT INTO Badges VALUES (1, 'Jane')",
+        ] {
+            database
+                .execute(sql)
+                .await
+                .expect("Unable to insert records");
+        }
+        assert!(matches!(
+            database.execute("INSERT INTO Orders VALUES (4, 'Bob')").await,
+            Err(SqlLayerError::ForeignKeyViolation(name)) if name == "Orders_customer_fkey"
+        ));
+        let id = Column::Int(3);
+        let record = Record::new(vec![Column::Int(3), Column::String("Bob".to_string())]);
+        assert!(matches!(
+            database
+                .update("Orders", &Columns(&vec![&id]), &record)
+                .await,
+            Err(SqlLayerError::ForeignKeyViolation(_))
+        ));
+
+        // a referenced primary key can't change
+        let record = Record::new(vec![Column::Int(30), Column::Null]);
+        assert!(matches!(
+            database.update("Orders", &Columns(&vec![&id]), &record).await,
+            Err(SqlLayerError::ForeignKeyViolation(name)) if name == "Reviews_order_id_fkey"
+        ));
+
+        // RESTRICT
+        let jane = Column::String("Jane".to_string());
+        assert!(matches!(
+            database.delete_by_pk("Person", &Columns(&vec![&jane])).await,
+            Err(SqlLayerError::ForeignKeyViolation(name)) if name == "Badges_owner_fkey"
+        ));
+        assert!(database
+            .get_record_by_pk("Person", &Columns(&vec![&jane]), None)
+            .await
+            .unwrap()
+            .is_some());
+
+        // CASCADE, then SET NULL on the records referencing the deleted orders
+        let john = Column::String("John".to_string());
+        assert!(database
+            .delete_by_pk("Person", &Columns(&vec![&john]))
+            .await
+            .unwrap());
+        let orders = database.select("Orders", None, None).await.unwrap();
+        assert_eq!(
+            orders,
+            vec![Record::new(vec![Column::Int(3), Column::Null])]
+        );
+        let reviews = database.select("Reviews", None, None).await.unwrap();
+        assert_eq!(
+            reviews,
+            vec![
+                Record::new(vec![Column::Int(1), Column::Null]),
+                Record::new(vec![Column::Int(2), Column::Int(3)]),
+            ]
+        );
+
+        // referenced tables record the tables referencing them
+        let person = database.get_table("Person").await.unwrap().unwrap();
+        assert_eq!(person.referencing_tables, vec!["Orders", "Badges"]);
+        let reviews = database.get_table("Reviews").await.unwrap().unwrap();
+        assert!(reviews.referencing_tables.is_empty());
+
+        // a record referencing the row through an index, written by the same transaction
+        database
+            .add_index("Badges", &Index::new("idx_owner", vec!["owner"]))
+            .await
+            .expect("Unable to add index");
+        let bob = &Column::String("Bob".to_string());
+        let deleted = database
+            .transact(|txn| async move {
+                txn.insert("Person", &Record::new(vec![bob.clone(), Column::Int(40)]))
+                    .await?;
+                txn.insert("Badges", &Record::new(vec![Column::Int(2), bob.clone()]))
+                    .await?;
+                txn.delete("Person", &Columns(&vec![bob])).await
+            })
+            .await;
+        assert!(matches!(
+            deleted,
+            Err(SqlLayerError::ForeignKeyViolation(name)) if name == "Badges_owner_fkey"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_record() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_update_record"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        let name = Column::String("John".to_string());
+        let updated_record = person_record("John", 21);
+        let updated = database
+            .update("Person", &Columns(&vec![&name]), &updated_record)
+            .await
+            .expect("Unable to update record");
+        assert!(updated);
+
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(updated_record.clone()));
+
+        // only the index entry of the new version remains
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database.table_subspace(DataPrefix::Index, &table).range();
+        let entries = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan index");
+        let expected_key = database
+            .index_keys(&table, &updated_record, 0)
+            .expect("Unable to build index keys");
+        let keys = entries.into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, expected_key);
+
+        // the age is kept, its index is left untouched
+        let mut photo_update = updated_record.clone();
+        photo_update.columns[4] = Column::Bytes(b"another photo".to_vec());
+        let index = table.get_index("idx_age").unwrap();
+        assert!(!changes_index(
+            &table,
+            index,
+            &updated_record,
+            &photo_update
+        ));
+        assert!(changes_index(
+            &table,
+            index,
+            &person_record("John", 20),
+            &updated_record
+        ));
+        database
+            .update("Person", &Columns(&vec![&name]), &photo_update)
+            .await
+            .expect("Unable to update record");
+        let entries = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan index");
+        let keys = entries.into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, expected_key);
+
+        let missing = Column::String("Jane".to_string());
+        let updated = database
+            .update(
+                "Person",
+                &Columns(&vec![&missing]),
+                &person_record("Jane", 30),
+            )
+            .await
+            .expect("Unable to update record");
+        assert!(!updated);
+    }
+
+    #[tokio::test]
+    async fn test_upsert() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_upsert"), storage);
+        let table = person_table();
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        database
+            .upsert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to upsert record");
+        database
+            .upsert("Person", &person_record("John", 21))
+            .await
+            .expect("Unable to upsert record");
+
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(person_record("John", 21)));
+
+        // the replaced record kept its row, no orphan has been left behind
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        assert_eq!(rows.len(), 1);
+
+        // the table has no index, the blind write estimated the replaced row at the size of
+        // its replacement, which it has
+        database
+            .upsert("Person", &person_record("Jane", 30))
+            .await
+            .expect("Unable to upsert record");
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        let schema = RowSchema::new(&person_table()).unwrap();
+        let size = |name, age| {
+            schema
+                .encode(&Row::from(&person_record(name, age)))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(stats.row_count, 2);
+        assert_eq!(
+            stats.total_bytes,
+            (size("John", 21) + size("Jane", 30)) as u64
+        );
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(
+            records,
+            vec![person_record("John", 21), person_record("Jane", 30)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_column_count_mismatch() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_insert_column_count_mismatch"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        let mut record = person_record("John", 20);
+        record.columns.pop();
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation {
+                    field: None,
+                    kind: ViolationKind::ColumnCountMismatch {
+                        expected: 5,
+                        found: 4
+                    },
+                },
+                Violation {
+                    field: Some("photo".to_string()),
+                    kind: ViolationKind::MissingColumn,
+                },
+            ]
+        );
+
+        let mut record = person_record("John", 20);
+        record.columns.push(Column::Int(42));
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation {
+                    field: None,
+                    kind: ViolationKind::ColumnCountMismatch {
+                        expected: 5,
+                        found: 6
+                    },
+                },
+                Violation {
+                    field: None,
+                    kind: ViolationKind::ExtraColumn { position: 5 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_decimal_scale() {
+        let mut table = Table::new("Payment".to_string(), vec!["amount".to_string()]);
+        table.add_field(Field::new_decimal("amount".to_string(), 2));
+        let record = |text: &str| Record::new(vec![Column::Decimal(text.parse().unwrap())]);
+        assert!(validate_record(&table, &record("12.50"))
+            .violations
+            .is_empty());
+        assert!(validate_record(&table, &record("12.5000"))
+            .violations
+            .is_empty());
+        assert_eq!(
+            validate_record(&table, &record("12.505")).violations,
+            vec![Violation {
+                field: Some("amount".to_string()),
+                kind: ViolationKind::MismatchedColumnType {
+                    expected: "Decimal of scale 2".to_string(),
+                    found: "Decimal(Decimal { mantissa: 12505, scale: 3 })".to_string(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_enum_variants() {
+        let mut table = Table::new("Order".to_string(), vec!["status".to_string()]);
+        table.add_field(Field::new_enum(
+            "status".to_string(),
+            vec!["pending".to_string(), "shipped".to_string()],
+        ));
+        let record = |status: &str| Record::new(vec![Column::String(status.to_string())]);
+        assert!(validate_record(&table, &record("shipped"))
+            .violations
+            .is_empty());
+        assert_eq!(
+            validate_record(&table, &record("lost")).violations,
+            vec![Violation {
+                field: Some("status".to_string()),
+                kind: ViolationKind::MismatchedColumnType {
+                    expected: r#"Enum of ["pending", "shipped"]"#.to_string(),
+                    found: r#"String("lost")"#.to_string(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_nan() {
+        let mut table = Table::new("Sensor".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Int));
+        table.add_field(Field::new("ratio".to_string(), FieldType::Float));
+        table.add_field(Field::new_array("samples".to_string(), FieldType::Float));
+        let record = |ratio: f64, sample: f64| {
+            Record::new(vec![
+                Column::Int(1),
+                Column::Float(ratio),
+                Column::Array(vec![Column::Float(sample)]),
+            ])
+        };
+        assert!(validate_record(&table, &record(-0.0, f64::INFINITY))
+            .violations
+            .is_empty());
+        let kinds = validate_record(&table, &record(f64::NAN, f64::NAN))
+            .violations
+            .into_iter()
+            .map(|violation| violation.kind)
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            kinds.as_slice(),
+            [
+                ViolationKind::MismatchedColumnType { expected: ratio, .. },
+                ViolationKind::MismatchedColumnType { expected: samples, .. },
+            ] if ratio == "Float other than NaN" && samples == "Array of Float"
+        ));
+    }
+
+    #[test]
+    fn test_coerce_record() {
+        let mut table = Table::new("Sensor".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::UInt64));
+        table.add_field(Field::new("level".to_string(), FieldType::Int16));
+        table.add_field(Field::new("ratio".to_string(), FieldType::Float));
+        table.add_field(Field::new_array("samples".to_string(), FieldType::Float));
+
+        let record = Record::new(vec![
+            Column::Int(1),
+            Column::Float(3.0),
+            Column::Int(2),
+            Column::Array(vec![Column::Int(1), Column::Float(0.5)]),
+        ]);
+        let coerced = coerce_record(&table, &record);
+        assert_eq!(
+            coerced.columns,
+            vec![
+                Column::UInt64(1),
+                Column::Int(3),
+                Column::Float(2.0),
+                Column::Array(vec![Column::Float(1.0), Column::Float(0.5)]),
+            ]
+        );
+        assert!(validate_record(&table, &coerced).violations.is_empty());
+
+        // values out of the range of their field aren't converted
+        let record = Record::new(vec![
+            Column::Int(-1),
+            Column::Int(40_000),
+            Column::Float(0.5),
+            Column::Null,
+        ]);
+        let coerced = coerce_record(&table, &record);
+        assert!(matches!(coerced, Cow::Borrowed(_)));
+        let expected = |field: &str, expected: &str, found: &str| Violation {
+            field: Some(field.to_string()),
+            kind: ViolationKind::MismatchedColumnType {
+                expected: expected.to_string(),
+                found: found.to_string(),
+            },
+        };
+        assert_eq!(
+            validate_record(&table, &coerced).violations,
+            vec![
+                expected("id", "UInt64", "Int(-1)"),
+                expected("level", "Int16", "Int(40000)"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_collects_violations() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_insert_collects_violations"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        let record = Record {
+            columns: vec![
+                Column::Null,
+                Column::String("twenty".to_string()),
+                Column::Float(1.8),
+                Column::Int(1),
+            ],
+        };
+        let result = database.insert("Person", &record).await;
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        let fields = report
+            .violations
+            .iter()
+            .map(|violation| violation.field.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                None,
+                Some("photo"),
+                Some("age"),
+                Some("is_married"),
+                Some("name")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_duplicate_primary_key() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_insert_duplicate_primary_key"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        let result = database.insert("Person", &person_record("John", 21)).await;
+        assert!(matches!(result, Err(SqlLayerError::DuplicatePrimaryKey(_))));
+
+        // the first record is left untouched
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(person_record("John", 20)));
+    }
+
+    #[tokio::test]
+    async fn test_add_index_backfills_existing_rows() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_add_index_backfills_existing_rows"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        // more rows than a single backfill batch
+        let count = BACKFILL_BATCH_SIZE as i64 + 10;
+        for i in 0..count {
+            database
+                .insert("Person", &person_record(&format!("John {i}"), i))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        database
+            .add_index("Person", &Index::new("idx_age", vec!["age"]))
+            .await
+            .expect("Unable to add index");
+
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database.table_subspace(DataPrefix::Index, &table).range();
+        let entries = database
+            .storage
+            .full_scan(&start, &end)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(entries.len(), count as usize);
+
+        // the index is added once, on fields of the table
+        let result = database
+            .add_index("Person", &Index::new("idx_age", vec!["height"]))
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::IndexAlreadyExists(_))));
+        let result = database
+            .add_index("Person", &Index::new("idx_aeg", vec!["aeg"]))
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::MissingColumn(_))));
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        assert_eq!(table.indexes.len(), 1);
+        assert_eq!(table.indexes[0].state(), IndexState::Ready);
+    }
+
+    #[tokio::test]
+    async fn test_list_indexes() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_list_indexes"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        for i in 0..3 {
+            database
+                .insert("Person", &person_record(&format!("John {i}"), i))
+                .await
+                .expect("Unable to insert record");
+        }
+        database
+            .add_index("Person", &Index::new("idx_age", vec!["age"]))
+            .await
+            .expect("Unable to add index");
+        database
+            .add_index("Person", &Index::new("idx_height", vec!["height"]))
+            .await
+            .expect("Unable to add index");
+        database
+            .insert("Person", &person_record("John 3", 3))
+            .await
+            .expect("Unable to insert record");
+
+        let statuses = database
+            .list_indexes("Person")
+            .await
+            .expect("Unable to list indexes");
+        assert_eq!(statuses.len(), 2);
+        for (status, name) in zip(&statuses, ["idx_age", "idx_height"]) {
+            assert_eq!(status.name, name);
+            assert_eq!(status.state, IndexState::Ready);
+            assert_eq!(status.entry_count, 4);
+            assert!(status.approximate_size.is_some_and(|size| size >= 0));
+        }
+
+        let status = database
+            .inspect_index("Person", "idx_height")
+            .await
+            .expect("Unable to inspect index");
+        assert_eq!(status.name, "idx_height");
+        assert_eq!(status.fields, vec!["height"]);
+        assert!(!status.unique);
+        assert_eq!(status.state, IndexState::Ready);
+        assert_eq!(status.entry_count, 4);
+        assert!(matches!(
+            database.inspect_index("Person", "idx_name").await,
+            Err(SqlLayerError::IndexNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scan_index_range() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_index_range"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        // insert in reverse order to check that records come out ordered by age
+        for i in (0..10).rev() {
+            database
+                .insert("Person", &person_record(&format!("John {i}"), i))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_age",
+                vec![Column::Int(3)]..vec![Column::Int(6)],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::errors::Result<Vec<_>>>()
+            .expect("Unable to read records");
+        let expected = (3..6)
+            .map(|i| person_record(&format!("John {i}"), i))
+            .collect::<Vec<_>>();
+        assert_eq!(records, expected);
+
+        let records = database
+            .scan_index_range("Person", "idx_age", vec![Column::Int(8)].., None)
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records.len(), 2);
+
+        let result = database
+            .scan_index_range("Person", "idx_missing", .., None)
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::IndexNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_relaxed_index_conflict_mode() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut database = Database::new(
+            Subspace::all().subspace(&"test_relaxed_index_conflict_mode"),
+            storage,
+        );
+        database.set_index_conflict_mode(IndexConflictMode::Relaxed);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_age",
+                vec![Column::Int(20)]..=vec![Column::Int(20)],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records.len(), 1);
+
+        // concurrent inserts of the same unique value still conflict
+        let mut table = Table::new("Account".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new_not_null("id".to_string(), FieldType::Int));
+        table.add_field(Field::new("email".to_string(), FieldType::String));
+        table.add_index(&Index::new_unique("idx_email", vec!["email"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let account = |id| {
+            Record::new(vec![
+                Column::Int(id),
+                Column::String("john@example.com".to_string()),
+            ])
+        };
+        let (first, second) = futures::join!(
+            database.insert("Account", &account(1)),
+            database.insert("Account", &account(2))
+        );
+        assert_ne!(first.is_ok(), second.is_ok());
+        assert!(matches!(
+            first.and(second),
+            Err(SqlLayerError::UniqueConstraintViolation { .. })
+        ));
+        let records = database.select("Account", None, None).await.unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_unique_index() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_non_unique_index"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        for name in ["John", "Jane", "Jack"] {
+            database
+                .insert("Person", &person_record(name, 20))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_age",
+                vec![Column::Int(20)]..=vec![Column::Int(20)],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records.len(), 3);
+
+        // deleting one of them leaves the entries of the others untouched
+        let name = Column::String("Jane".to_string());
+        database
+            .delete_by_pk("Person", &Columns(&vec![&name]))
+            .await
+            .expect("Unable to delete record");
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_age",
+                vec![Column::Int(20)]..=vec![Column::Int(20)],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_multi_entry_index() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_multi_entry_index"), storage);
+        let mut table = Table::new("Post".to_string(), vec!["title".to_string()]);
+        table.add_field(Field::new("title".to_string(), FieldType::String));
+        table.add_field(Field::new_array("tags".to_string(), FieldType::String));
+        table.add_index(&Index::new_multi_entry("idx_tags", "tags"));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let post = |title: &str, tags: &[&str]| {
+            let tags = tags.iter().map(|tag| Column::String(tag.to_string()));
+            Record::new(vec![
+                Column::String(title.to_string()),
+                Column::Array(tags.collect()),
+            ])
+        };
+        for record in [
+            post("Rust", &["rust", "code", "rust"]),
+            post("FoundationDB", &["database", "code"]),
+            post("Draft", &[]),
+        ] {
+            database
+                .insert("Post", &record)
+                .await
+                .expect("Unable to insert record");
+        }
+        let titles = |tag: &str| {
+            let database = &database;
+            let predicate = Predicate::contains("tags", Column::String(tag.to_string()));
+            async move {
+                database
+                    .scan_where("Post", &predicate, Some(&["title"][..]))
+                    .await
+                    .expect("Unable to scan table")
+                    .map(|record| record.expect("Unable to read record").columns)
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        // an entry per distinct element, none for an empty array
+        let status = database
+            .list_indexes("Post")
+            .await
+            .expect("Unable to list indexes");
+        assert_eq!(status[0].entry_count, 4);
+        assert!(matches!(
+            database
+                .explain("Post", &Predicate::contains("tags", Column::String("code".to_string())))
+                .await
+                .unwrap(),
+            Plan::IndexRangeScan(range) if range.index == "idx_tags"
+        ));
+        assert_eq!(titles("code").await.len(), 2);
+        assert_eq!(
+            titles("rust").await,
+            vec![vec![Column::String("Rust".to_string())]]
+        );
+
+        // updating the array moves the entries of its elements
+        let title = Column::String("Rust".to_string());
+        database
+            .update("Post", &Columns(&vec![&title]), &post("Rust", &["systems"]))
+            .await
+            .expect("Unable to update record");
+        assert!(titles("rust").await.is_empty());
+        assert_eq!(titles("code").await.len(), 1);
+        assert_eq!(titles("systems").await.len(), 1);
+
+        // arrays are compared as a whole, not by their elements in the index
+        database
+            .insert("Post", &post("Todo", &["a", "z"]))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(
+            database
+                .aggregate("Post", &Aggregate::Max("tags".to_string()), None)
+                .await
+                .expect("Unable to aggregate"),
+            post("Rust", &["systems"]).columns[1]
+        );
+
+        // the item type is checked
+        let result = database
+            .insert(
+                "Post",
+                &Record::new(vec![
+                    Column::String("Numbers".to_string()),
+                    Column::Array(vec![Column::Int(1)]),
+                ]),
+            )
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unique_index() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_unique_index"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new_unique("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        let result = database.insert("Person", &person_record("Jane", 20)).await;
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::UniqueConstraintViolation { index, .. }) if index == "idx_age"
+        ));
+
+        // updating a record keeps its own entry, and frees the previous value
+        let name = Column::String("John".to_string());
+        database
+            .update("Person", &Columns(&vec![&name]), &person_record("John", 20))
+            .await
+            .expect("Unable to update record");
+        database
+            .update("Person", &Columns(&vec![&name]), &person_record("John", 21))
+            .await
+            .expect("Unable to update record");
+        database
+            .insert("Person", &person_record("Jane", 20))
+            .await
+            .expect("Unable to insert record");
+    }
+
+    #[tokio::test]
+    async fn test_collated_index() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_collated_index"), storage);
+        let mut table = Table::new("Account".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Int));
+        let mut email = Field::new("email".to_string(), FieldType::String);
+        email.collation = Collation::CaseInsensitive;
+        table.add_field(email);
+        table.add_index(&Index::new_unique("idx_email", vec!["email"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let account = |id: i64, email: &str| {
+            Record::new(vec![Column::Int(id), Column::String(email.to_string())])
+        };
+
+        database
+            .insert("Account", &account(1, "John@Doe.com"))
+            .await
+            .expect("Unable to insert record");
+        let result = database
+            .insert("Account", &account(2, "JOHN@doe.com"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::UniqueConstraintViolation { index, .. }) if index == "idx_email"
+        ));
+
+        // the lookup reads the single entry, the record keeps its string
+        let predicate = Predicate::eq("email", Column::String("john@DOE.com".to_string()));
+        assert!(matches!(
+            database.explain("Account", &predicate).await.unwrap(),
+            Plan::IndexRangeScan(range) if range.index == "idx_email"
+        ));
+        let records = database
+            .scan_where("Account", &predicate, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![account(1, "John@Doe.com")]);
+
+        // only strings are collated
+        let mut table = Table::new("Invalid".to_string(), vec!["id".to_string()]);
+        let mut id = Field::new("id".to_string(), FieldType::Int);
+        id.collation = Collation::Unicode;
+        table.add_field(id);
+        let result = database.create_table(&table, false).await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidCollation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_serial_field() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let subspace = Subspace::all().subspace(&"test_serial_field");
+        let database = Database::new(subspace.clone(), storage.clone());
+        database
+            .execute("CREATE TABLE Orders (id SERIAL PRIMARY KEY, label TEXT)")
+            .await
+            .expect("Unable to create table");
+        let order =
+            |id: Column, label: &str| Record::new(vec![id, Column::String(label.to_string())]);
+
+        // omitted values are taken from the counter and returned
+        let inserted = database
+            .insert("Orders", &order(Column::Null, "first"))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(inserted.record, order(Column::Int(1), "first"));
+        database
+            .execute("INSERT INTO Orders (label) VALUES ('second')")
+            .await
+            .expect("Unable to insert record");
+        let inserted = database
+            .insert("Orders", &order(Column::Int(42), "explicit"))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(inserted.record.columns[0], Column::Int(42));
+        let id = Column::Int(2);
+        let found = database
+            .get_record_by_pk("Orders", &Columns(&vec![&id]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(order(Column::Int(2), "second")));
+
+        // the counter survives a rename, another instance reserves the next block
+        database
+            .rename_column("Orders", "id", "number")
+            .await
+            .expect("Unable to rename column");
+        let inserted = database
+            .insert("Orders", &order(Column::Null, "third"))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(inserted.record.columns[0], Column::Int(3));
+        let other = Database::new(subspace, storage);
+        let inserted = other
+            .insert("Orders", &order(Column::Null, "fourth"))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(
+            inserted.record.columns[0],
+            Column::Int(1 + ROW_ID_BLOCK_SIZE)
+        );
+
+        let mut table = Table::new("Invalid".to_string(), vec!["id".to_string()]);
+        table.add_field(Field {
+            r#type: FieldType::Float,
+            ..Field::new_serial("id".to_string())
+        });
+        let result = database.create_table(&table, false).await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidSerialField(_))));
+    }
+
+    #[tokio::test]
+    async fn test_scan_table() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_table"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        let expected = (0..50)
+            .map(|i| person_record(&format!("John {i}"), i))
+            .collect::<Vec<_>>();
+        for record in &expected {
+            database
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::errors::Result<Vec<_>>>()
+            .expect("Unable to read records");
+        assert_eq!(records, expected);
+
+        let result = database.scan_table("Unknown", None).await;
+        assert!(matches!(result, Err(SqlLayerError::TableNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_scan_where() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_where"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 20), ("Jane", 30), ("Bob", 40), ("Joe", 50)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let predicate = Predicate::like_prefix("name", "J").and(Predicate::between(
+            "age",
+            Column::Int(25),
+            Column::Int(60),
+        ));
+        let records = database
+            .scan_where("Person", &predicate, Some(&["name"][..]))
+            .await
+            .expect("Unable to scan table")
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::errors::Result<Vec<_>>>()
+            .expect("Unable to read records");
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    columns: vec![Column::String("Jane".to_string())]
+                },
+                Record {
+                    columns: vec![Column::String("Joe".to_string())]
+                },
+            ]
+        );
+
+        let predicate = Predicate::eq("age", Column::String("20".to_string()));
+        let result = database.scan_where("Person", &predicate, None).await;
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_explain() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_explain"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        table.add_index(&Index::new("idx_married", vec!["is_married"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 20), ("Jane", 30), ("Bob", 40)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let predicate = Predicate::eq("name", Column::String("Jane".to_string()));
+        assert_eq!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::PrimaryKeyLookup {
+                pk: vec![Column::String("Jane".to_string())]
+            }
+        );
+
+        let predicate = Predicate::gt("age", Column::Int(25));
+        assert_eq!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_age".to_string(),
+                start: Bound::Excluded(vec![Column::Int(25)]),
+                end: Bound::Unbounded,
+            })
+        );
+
+        let predicate = Predicate::eq("age", Column::Int(30))
+            .and(Predicate::eq("is_married", Column::Bool(true)));
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexIntersection(ranges) if ranges.len() == 2
+        ));
+        let predicate = Predicate::gt("age", Column::Int(35))
+            .or(Predicate::eq("is_married", Column::Bool(false)));
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexUnion(ranges) if ranges.len() == 2
+        ));
+
+        // every plan yields the same records as a filtered table scan
+        for predicate in [
+            Predicate::eq("name", Column::String("Jane".to_string())),
+            Predicate::gt("age", Column::Int(25)),
+            Predicate::gt("age", Column::Int(25)).and(Predicate::like_prefix("name", "B")),
+            Predicate::gt("age", Column::Int(25))
+                .and(Predicate::eq("is_married", Column::Bool(true))),
+            Predicate::eq("age", Column::Int(30))
+                .and(Predicate::eq("is_married", Column::Bool(true))),
+            Predicate::gt("age", Column::Int(35))
+                .or(Predicate::eq("is_married", Column::Bool(false))),
+            Predicate::lt("age", Column::Int(35)).or(Predicate::gt("age", Column::Int(25))),
+        ] {
+            let records = database
+                .scan_where("Person", &predicate, None)
+                .await
+                .expect("Unable to scan table")
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<crate::errors::Result<Vec<_>>>()
+                .expect("Unable to read records");
+            let expected = database
+                .scan_table("Person", None)
+                .await
+                .expect("Unable to scan table")
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<crate::errors::Result<Vec<_>>>()
+                .expect("Unable to read records")
+                .into_iter()
+                .filter(|record| predicate.evaluate(&table, record))
+                .collect::<Vec<_>>();
+            assert_eq!(records, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_union_merge() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_index_union_merge"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [
+            ("Bob", 40),
+            ("John", 20),
+            ("Alice", 50),
+            ("Jane", 10),
+            ("Eve", 30),
+        ] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        // overlapping ranges of one index are merged in its order, each record once
+        let predicate = Predicate::lt("age", Column::Int(25))
+            .or(Predicate::gt("age", Column::Int(35)))
+            .or(Predicate::lt("age", Column::Int(15)));
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexUnion(ranges) if ranges.len() == 3
+        ));
+        let records = database
+            .scan_where("Person", &predicate, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        let ages = records
+            .iter()
+            .map(|record| record.columns[1].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ages,
+            vec![
+                Column::Int(10),
+                Column::Int(20),
+                Column::Int(40),
+                Column::Int(50)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_descending_keys() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_descending_keys"), storage);
+        let mut table = person_table();
+        table.set_primary_key_directions(vec![Direction::Descending]);
+        table.add_index(
+            &Index::new("idx_age", vec!["age"]).with_directions(vec![Direction::Descending]),
+        );
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 30), ("Jane", 20), ("Bob", 40), ("Alice", 25)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+        let mut record = person_record("Joe", 0);
+        record.columns[1] = Column::Null;
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+        let column = |records: Vec<Record>, position: usize| {
+            records
+                .into_iter()
+                .map(|record| record.columns[position].clone())
+                .collect::<Vec<_>>()
+        };
+
+        // a forward scan of the primary key returns the largest names first
+        let pk = vec![];
+        let records = database
+            .scan_by_pk_prefix("Person", &Columns::new(&pk), None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        let names = ["John", "Joe", "Jane", "Bob", "Alice"].map(|name| name.to_string());
+        assert_eq!(column(records, 0), names.map(Column::String).to_vec());
+        let jane = Column::String("Jane".to_string());
+        let record = database
+            .get_record_by_pk("Person", &Columns::new(&vec![&jane]), None)
+            .await
+            .expect("Unable to get record")
+            .expect("Missing record");
+        assert_eq!(record, person_record("Jane", 20));
+
+        // the lower bound of the ages ends the range of the descending index
+        let predicate = Predicate::gt("age", Column::Int(22));
+        assert_eq!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_age".to_string(),
+                start: Bound::Unbounded,
+                end: Bound::Excluded(vec![Column::Int(22)]),
+            })
+        );
+        let records = database
+            .scan_where("Person", &predicate, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(
+            column(records, 1),
+            vec![Column::Int(40), Column::Int(30), Column::Int(25)]
+        );
+
+        // the null age sorts last, the endpoints of the index skip it
+        for (aggregate, expected) in [
+            (Aggregate::Min("age".to_string()), Column::Int(20)),
+            (Aggregate::Max("age".to_string()), Column::Int(40)),
+        ] {
+            let result = database
+                .aggregate("Person", &aggregate, None)
+                .await
+                .expect("Unable to aggregate");
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_table_checksum() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let source = Database::new(
+            Subspace::all().subspace(&"test_table_checksum_source"),
+            storage,
+        );
+        let storage = Storage::new(_guard.clone());
+        let target = Database::new(
+            Subspace::all().subspace(&"test_table_checksum_target"),
+            storage,
+        );
+        for database in [&source, &target] {
+            database
+                .create_table(&person_table(), false)
+                .await
+                .expect("Unable to create table");
+        }
+
+        // same records, inserted in another order
+        let records = (0..1500)
+            .map(|i| person_record(&format!("John {i}"), i))
+            .collect::<Vec<_>>();
+        for record in &records {
+            source
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
+        }
+        for record in records.iter().rev() {
+            target
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let checksum = source
+            .table_checksum("Person", None)
+            .await
+            .expect("Unable to checksum table");
+        assert_eq!(checksum.row_count, 1500);
+        let target_checksum = target
+            .table_checksum("Person", None)
+            .await
+            .expect("Unable to checksum table");
+        assert_eq!(target_checksum.checksum, checksum.checksum);
+
+        // the snapshot version ignores later writes
+        let pk_column = Column::String("John 0".to_string());
+        let pk = vec![&pk_column];
+        source
+            .delete_by_pk("Person", &Columns::new(&pk))
+            .await
+            .expect("Unable to delete record");
+        let snapshot = source
+            .table_checksum("Person", Some(checksum.version))
+            .await
+            .expect("Unable to checksum table");
+        assert_eq!(snapshot, checksum);
+        let current = source
+            .table_checksum("Person", None)
+            .await
+            .expect("Unable to checksum table");
+        assert_eq!(current.row_count, 1499);
+        assert_ne!(current.checksum, checksum.checksum);
+
+        // the rows are hashed from their records, however they are stored
+        if cfg!(feature = "lz4") {
+            let storage = Storage::new(_guard.clone());
+            let compressed = Database::new(
+                Subspace::all().subspace(&"test_table_checksum_compressed"),
+                storage,
+            );
+            let mut table = person_table();
+            table.set_compression(RowCompression::Lz4, 0);
+            compressed
+                .create_table(&table, false)
+                .await
+                .expect("Unable to create table");
+            for record in &records[1..] {
+                compressed
+                    .insert("Person", record)
+                    .await
+                    .expect("Unable to insert record");
+            }
+            let compressed_checksum = compressed
+                .table_checksum("Person", None)
+                .await
+                .expect("Unable to checksum table");
+            assert_eq!(compressed_checksum.row_count, 1499);
+            assert_eq!(compressed_checksum.checksum, current.checksum);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_schema_mode() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut database = Database::new(
+            Subspace::all().subspace(&"test_strict_schema_mode"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        // rewrite the row as a newer version appending a field would
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let (key, mut bytes) = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows")
+            .remove(0);
+        bytes.push(1);
+        database
+            .storage
+            .set(&key, &bytes)
+            .await
+            .expect("Unable to write row");
+
+        let pk_column = Column::String("John".to_string());
+        let pk = vec![&pk_column];
+        let record = database
+            .get_record_by_pk("Person", &Columns::new(&pk), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(record, Some(person_record("John", 20)));
+
+        database.set_schema_mode(SchemaMode::Strict);
+        let result = database
+            .get_record_by_pk("Person", &Columns::new(&pk), None)
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::UnknownFields(1))));
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_execute"), storage);
+
+        let results = database
+            .execute(
+                "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT, height FLOAT); \
+                 INSERT INTO Person VALUES ('John', 20, 1.8), ('Jane', 30, 1.6); \
+                 INSERT INTO Person (name, age) VALUES ('Bob', 40)",
+            )
+            .await
+            .expect("Unable to execute statements");
+        assert_eq!(
+            results,
+            vec![
+                QueryResult::Created,
+                QueryResult::Inserted(2),
+                QueryResult::Inserted(1)
+            ]
+        );
+
+        // a failing row rolls back the whole statement
+        let result = database
+            .execute("INSERT INTO Person VALUES ('Alice', 25, 1.7), ('John', 20, 1.8)")
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::DuplicatePrimaryKey(_))));
+        let results = database
+            .execute("SELECT name FROM Person WHERE name = 'Alice'")
+            .await
+            .expect("Unable to select records");
+        assert_eq!(results, vec![QueryResult::Rows(vec![])]);
+
+        let results = database
+            .execute("SELECT name, height FROM Person WHERE age >= 30")
+            .await
+            .expect("Unable to select records");
+        assert_eq!(
+            results,
+            vec![QueryResult::Rows(vec![
+                Record {
+                    columns: vec![Column::String("Jane".to_string()), Column::Float(1.6)]
+                },
+                Record {
+                    columns: vec![Column::String("Bob".to_string()), Column::Null]
+                },
+            ])]
+        );
+
+        let results = database
+            .execute("DELETE FROM Person WHERE name LIKE 'J%'; SELECT name FROM Person")
+            .await
+            .expect("Unable to delete records");
+        assert_eq!(
+            results,
+            vec![
+                QueryResult::Deleted(2),
+                QueryResult::Rows(vec![Record {
+                    columns: vec![Column::String("Bob".to_string())]
+                }]),
+            ]
+        );
+
+        let result = database.execute("SELECT * FROM Unknown").await;
+        assert!(matches!(result, Err(SqlLayerError::TableNotFound(_))));
+        let result = database.execute("DROP TABLE Person").await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidSqlText(_))));
+    }
+
+    #[tokio::test]
+    async fn test_scan_by_pk_prefix() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_by_pk_prefix"), storage);
+        let mut table = Table::new(
+            "Orders".to_string(),
+            vec!["customer_id".to_string(), "order_id".to_string()],
+        );
+        table.add_field(Field::new("customer_id".to_string(), FieldType::Int));
+        table.add_field(Field::new("order_id".to_string(), FieldType::Int));
+        table.add_field(Field::new("amount".to_string(), FieldType::Float));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let order = |customer_id: i64, order_id: i64| Record {
+            columns: vec![
+                Column::Int(customer_id),
+                Column::Int(order_id),
+                Column::Float(order_id as f64),
+            ],
+        };
+        for (customer_id, order_id) in [(2, 3), (1, 2), (2, 1), (3, 1), (1, 1)] {
+            database
+                .insert("Orders", &order(customer_id, order_id))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let customer = Column::Int(2);
+        let partial_pk = vec![&customer];
+        let records = database
+            .scan_by_pk_prefix("Orders", &Columns::new(&partial_pk), None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![order(2, 1), order(2, 3)]);
+
+        let partial_pk = vec![];
+        let records = database
+            .scan_by_pk_prefix(
+                "Orders",
+                &Columns::new(&partial_pk),
+                Some(&["order_id"][..]),
+            )
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            records[0],
+            Record {
+                columns: vec![Column::Int(1)]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_sorted() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_sorted"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 30), ("Jane", 20), ("Bob", 40), ("Alice", 25)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let names = |records: Vec<Record>| {
+            records
+                .into_iter()
+                .map(|record| record.columns[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let expected = |expected: &[&str]| {
+            expected
+                .iter()
+                .map(|name| Column::String(name.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // traversal of the primary key, then of the age index
+        let records = database
+            .scan_sorted(
+                "Person",
+                None,
+                &[SortKey::asc("name")],
+                Some(&["name"][..]),
+                None,
+            )
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Alice", "Bob", "Jane", "John"]));
+        let predicate = Predicate::gt("height", Column::Float(21.0));
+        let records = database
+            .scan_sorted(
+                "Person",
+                Some(&predicate),
+                &[SortKey::asc("age")],
+                None,
+                None,
+            )
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Alice", "John", "Bob"]));
+
+        // sorted in memory
+        let records = database
+            .scan_sorted("Person", None, &[SortKey::desc("age")], None, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Bob", "John", "Alice", "Jane"]));
+        let records = database
+            .scan_sorted(
+                "Person",
+                None,
+                &[SortKey::asc("is_married"), SortKey::desc("name")],
+                None,
+                None,
+            )
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Alice", "John", "Jane", "Bob"]));
+
+        // top-k, in memory then along the age index
+        let records = database
+            .scan_sorted("Person", None, &[SortKey::desc("age")], None, Some(2))
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Bob", "John"]));
+        let records = database
+            .scan_sorted("Person", None, &[SortKey::asc("age")], None, Some(3))
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Jane", "Alice", "John"]));
+
+        let result = database
+            .scan_sorted("Person", None, &[SortKey::asc("unknown")], None, None)
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::MissingColumn(_))));
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_pagination"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let expected = (0..25)
+            .map(|i| person_record(&format!("John {i}"), 100 - i))
+            .collect::<Vec<_>>();
+        for record in &expected {
+            database
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
+        }
+
+        // pages of the table follow each other, the offset applies after the continuation
+        let first = database
+            .scan_table_page("Person", 10, 2, None, None)
+            .await
+            .expect("Unable to read page");
+        assert_eq!(first.records, expected[2..12]);
+        let token = first
+            .continuation
+            .expect("Expected more records")
+            .to_bytes();
+        let second = database
+            .scan_table_page(
+                "Person",
+                10,
+                0,
+                Some(&Continuation::from_bytes(&token)),
+                None,
+            )
+            .await
+            .expect("Unable to read page");
+        assert_eq!(second.records, expected[12..22]);
+        let last = database
+            .scan_table_page("Person", 10, 0, second.continuation.as_ref(), None)
+            .await
+            .expect("Unable to read page");
+        assert_eq!(last.records, expected[22..]);
+        assert_eq!(last.continuation, None);
+
+        // pages of an index range, ordered by age
+        let range = vec![Column::Int(80)]..vec![Column::Int(90)];
+        let first = database
+            .scan_index_page(
+                "Person",
+                "idx_age",
+                range.clone(),
+                6,
+                0,
+                None,
+                Some(&["age"][..]),
+            )
+            .await
+            .expect("Unable to read page");
+        let ages = |page: &Page| {
+            page.records
+                .iter()
+                .map(|record| record.columns[0].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ages(&first), (80..86).map(Column::Int).collect::<Vec<_>>());
+        let second = database
+            .scan_index_page(
+                "Person",
+                "idx_age",
+                range.clone(),
+                6,
+                0,
+                first.continuation.as_ref(),
+                Some(&["age"][..]),
+            )
+            .await
+            .expect("Unable to read page");
+        assert_eq!(ages(&second), (86..90).map(Column::Int).collect::<Vec<_>>());
+        assert_eq!(second.continuation, None);
+
+        // a token of the table can't be used on the index
+        let result = database
+            .scan_index_page(
+                "Person",
+                "idx_age",
+                range.clone(),
+                6,
+                0,
+                Some(&Continuation::from_bytes(&token)),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidContinuation)));
+
+        // an empty page would end the scan
+        let result = database
+            .scan_index_page("Person", "idx_age", range, 0, 0, None, None)
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidPageLimit)));
+    }
+
+    #[tokio::test]
+    async fn test_aggregate() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_aggregate"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 30), ("Jane", 20), ("Bob", 40), ("Alice", 25)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+        let mut record = person_record("Joe", 0);
+        record.columns[1] = Column::Null;
+        database
+            .insert("Person", &record)
+            .await
+            .expect("Unable to insert record");
+
+        let aggregate = async |aggregate: Aggregate, predicate: Option<&Predicate>| {
+            database
+                .aggregate("Person", &aggregate, predicate)
+                .await
+                .expect("Unable to aggregate")
+        };
+        assert_eq!(aggregate(Aggregate::Count, None).await, Column::Int(5));
+        assert_eq!(
+            aggregate(Aggregate::Sum("age".to_string()), None).await,
+            Column::Int(115)
+        );
+        assert_eq!(
+            aggregate(Aggregate::Avg("height".to_string()), None).await,
+            Column::Float(23.0)
+        );
+        // read from the endpoints of the age index, skipping the null age
+        assert_eq!(
+            aggregate(Aggregate::Min("age".to_string()), None).await,
+            Column::Int(20)
+        );
+        assert_eq!(
+            aggregate(Aggregate::Max("age".to_string()), None).await,
+            Column::Int(40)
+        );
+        assert_eq!(
+            aggregate(Aggregate::Min("name".to_string()), None).await,
+            Column::String("Alice".to_string())
+        );
+
+        let predicate = Predicate::like_prefix("name", "J");
+        assert_eq!(
+            aggregate(Aggregate::Count, Some(&predicate)).await,
+            Column::Int(3)
+        );
+        assert_eq!(
+            aggregate(Aggregate::Max("age".to_string()), Some(&predicate)).await,
+            Column::Int(30)
+        );
+
+        let result = database
+            .aggregate("Person", &Aggregate::Sum("name".to_string()), None)
+            .await;
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_stats"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        let schema = RowSchema::new(&person_table()).unwrap();
+        let size = |record: &Record| schema.encode(&Row::from(record)).unwrap().len() as u64;
+
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats, TableStats::default());
+
+        let john = person_record("John", 20);
+        let jane = person_record("Jane", 30);
+        for record in [&john, &jane] {
+            database
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
+        }
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.row_count, 2);
+        assert_eq!(stats.total_bytes, size(&john) + size(&jane));
+
+        // a bigger photo grows the row, then the row is deleted
+        let mut big_john = john.clone();
+        big_john.columns[4] = Column::Bytes(vec![0; 1000]);
+        let name = Column::String("John".to_string());
+        let pk = vec![&name];
+        database
+            .update("Person", &Columns(&pk), &big_john)
+            .await
+            .expect("Unable to update record");
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.row_count, 2);
+        assert_eq!(stats.total_bytes, size(&big_john) + size(&jane));
+        assert_eq!(stats.largest_row, size(&big_john));
+
+        database
+            .delete_by_pk("Person", &Columns(&pk))
+            .await
+            .expect("Unable to delete record");
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.row_count, 1);
+        assert_eq!(stats.total_bytes, size(&jane));
+        assert_eq!(stats.largest_row, size(&big_john));
+        assert_eq!(stats.average_row_size(), Some(size(&jane) as f64));
+
+        let result = database.stats("Unknown").await;
+        assert!(matches!(result, Err(SqlLayerError::TableNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_join() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_join"), storage);
+        let mut customers = Table::new("customers".to_string(), vec!["id".to_string()]);
+        customers.add_field(Field::new("id".to_string(), FieldType::Int));
+        customers.add_field(Field::new("name".to_string(), FieldType::String));
+        let mut orders = Table::new("orders".to_string(), vec!["id".to_string()]);
+        orders.add_field(Field::new("id".to_string(), FieldType::Int));
+        orders.add_field(Field::new("customer_id".to_string(), FieldType::Int));
+        for table in [&customers, &orders] {
+            database
+                .create_table(table, false)
+                .await
+                .expect("Unable to create table");
+        }
+        for (id, name) in [(1, "John"), (2, "Jane"), (3, "Bob")] {
+            let record = Record {
+                columns: vec![Column::Int(id), Column::String(name.to_string())],
+            };
+            database
+                .insert("customers", &record)
+                .await
+                .expect("Unable to insert record");
+        }
+        for (id, customer_id) in [
+            (10, Column::Int(2)),
+            (11, Column::Int(1)),
+            (12, Column::Int(2)),
+        ]
+        .into_iter()
+        .chain([(13, Column::Null), (14, Column::Int(4))])
+        {
+            let record = Record {
+                columns: vec![Column::Int(id), customer_id],
+            };
+            database
+                .insert("orders", &record)
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let pairs = |records: Vec<Record>| {
+            records
+                .into_iter()
+                .map(|record| (record.columns[0].clone(), record.columns[2].clone()))
+                .collect::<Vec<_>>()
+        };
+        let expected = |expected: &[(i64, i64)]| {
+            expected
+                .iter()
+                .map(|&(left, right)| (Column::Int(left), Column::Int(right)))
+                .collect::<Vec<_>>()
+        };
+
+        // orders to customers, through the primary key of customers
+        let join = Join::new("orders", "customer_id", "customers", "id");
+        let records = database
+            .join(&join, None)
+            .await
+            .expect("Unable to join tables")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(pairs(records), expected(&[(10, 2), (11, 1), (12, 2)]));
+
+        // customers to orders, by nested loop then through an index
+        let join = Join::new("customers", "id", "orders", "customer_id");
+        let predicate = Predicate::gt("id", Column::Int(1));
+        for index in [None, Some(Index::new("idx_customer", vec!["customer_id"]))] {
+            if let Some(index) = index {
+                database
+                    .add_index("orders", &index)
+                    .await
+                    .expect("Unable to add index");
+            }
+            let records = database
+                .join(&join, Some(&predicate))
+                .await
+                .expect("Unable to join tables")
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("Unable to read records");
+            assert_eq!(pairs(records), expected(&[(2, 10), (2, 12)]));
+        }
+
+        let join = Join::new("customers", "name", "orders", "id");
+        let result = database.join(&join, None).await;
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_table_tags() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut database = Database::new(Subspace::all().subspace(&"test_table_tags"), storage);
+        assert_eq!(database.table_tag("Person"), None);
+        database.set_table_tags(true);
+        assert_eq!(database.table_tag("Person"), Some("Person"));
+        assert_eq!(
+            database.table_tag("a_table_name_over_16_bytes"),
+            Some("a_table_name_ove")
+        );
+        assert_eq!(database.table_tag("ééééééééé"), Some("éééééééé"));
+
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        let name = Column::String("John".to_string());
+        let pk = vec![&name];
+        let record = database
+            .get_record_by_pk("Person", &Columns(&pk), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(record, Some(person_record("John", 20)));
+    }
+
+    #[tokio::test]
+    async fn test_scan_distinct() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_scan_distinct"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age", "name"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [
+            ("John", 30),
+            ("Jane", 20),
+            ("Bob", 30),
+            ("Alice", 25),
+            ("Eve", 20),
+        ] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+        let values = |records: Vec<Record>| {
+            records
+                .into_iter()
+                .map(|record| record.columns)
+                .collect::<Vec<_>>()
+        };
+
+        // grouped along the age index
+        let records = database
+            .scan_distinct("Person", None, Some(&["age"][..]))
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(
+            values(records),
+            vec![
+                vec![Column::Int(20)],
+                vec![Column::Int(25)],
+                vec![Column::Int(30)]
+            ]
+        );
+
+        // deduplicated through a set, in the read order of the age index
+        let predicate = Predicate::gt("age", Column::Int(20));
+        let records = database
+            .scan_distinct(
+                "Person",
+                Some(&predicate),
+                Some(&["is_married", "height"][..]),
+            )
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(
+            values(records),
+            vec![
+                vec![Column::Bool(false), Column::Float(25.0)],
+                vec![Column::Bool(true), Column::Float(30.0)]
+            ]
+        );
+
+        let results = database
+            .execute("SELECT DISTINCT is_married FROM Person")
+            .await
+            .expect("Unable to execute query");
+        assert_eq!(
+            results,
+            vec![QueryResult::Rows(vec![
+                Record {
+                    columns: vec![Column::Bool(true)]
+                },
+                Record {
+                    columns: vec![Column::Bool(false)]
+                },
+            ])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_access_mode() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut database = Database::new(Subspace::all().subspace(&"test_access_mode"), storage);
+        let transitions = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let listener_transitions = transitions.clone();
+        database.set_access_mode_listener(move |mode| {
+            listener_transitions.lock().unwrap().push(mode);
+        });
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(database.access_mode(), AccessMode::ReadWrite);
+
+        // as if a write had found the cluster locked
+        database.set_access_mode(AccessMode::ReadOnly);
+        let result = database.insert("Person", &person_record("Jane", 30)).await;
+        assert!(matches!(result, Err(SqlLayerError::ReadOnly)));
+        let result = database.create_table(&person_table(), false).await;
+        assert!(matches!(result, Err(SqlLayerError::ReadOnly)));
+        let name = Column::String("John".to_string());
+        let pk = vec![&name];
+        let record = database
+            .get_record_by_pk("Person", &Columns(&pk), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(record, Some(person_record("John", 20)));
+
+        // the cluster isn't locked
+        let mode = database
+            .refresh_access_mode()
+            .await
+            .expect("Unable to refresh access mode");
+        assert_eq!(mode, AccessMode::ReadWrite);
+        database
+            .insert("Person", &person_record("Jane", 30))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![AccessMode::ReadOnly, AccessMode::ReadWrite]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_list() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_in_list"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 20), ("Jane", 30), ("Bob", 40), ("Alice", 30)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+        let names = |records: Vec<Record>| {
+            records
+                .into_iter()
+                .map(|record| record.columns[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let expected = |expected: &[&str]| {
+            expected
+                .iter()
+                .map(|name| Column::String(name.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // concurrent primary key lookups, in the order of the keys
+        let predicate = Predicate::in_list("name", expected(&["Bob", "Unknown", "John"]));
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::PrimaryKeyLookups { .. }
+        ));
+        let records = database
+            .scan_where("Person", &predicate, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Bob", "John"]));
+
+        // concurrent index lookups, in row id order
+        let predicate = Predicate::in_list(
+            "age",
+            vec![Column::Int(40), Column::Int(30), Column::Int(50)],
+        );
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexLookups { .. }
+        ));
+        let records = database
+            .scan_where("Person", &predicate, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(names(records), expected(&["Jane", "Bob", "Alice"]));
+
+        let results = database
+            .execute("SELECT name FROM Person WHERE age IN (20, 40)")
+            .await
+            .expect("Unable to execute query");
+        assert_eq!(
+            results,
+            vec![QueryResult::Rows(vec![
+                Record {
+                    columns: vec![Column::String("John".to_string())]
+                },
+                Record {
+                    columns: vec![Column::String("Bob".to_string())]
+                },
+            ])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_like_prefix() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_like_prefix"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_name", vec!["name"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [
+            ("John", 20),
+            ("Jo", 30),
+            ("Jp", 40),
+            ("Bob", 50),
+            ("Joe", 60),
+        ] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let predicate = Predicate::like_prefix("name", "Jo");
+        assert!(matches!(
+            database.explain("Person", &predicate).await.unwrap(),
+            Plan::IndexRangeScan(IndexRange { index, .. }) if index == "idx_name"
+        ));
+        let results = database
+            .execute("SELECT name FROM Person WHERE name LIKE 'Jo%'")
+            .await
+            .expect("Unable to execute query");
+        let names = ["Jo", "Joe", "John"]
+            .iter()
+            .map(|name| Record {
+                columns: vec![Column::String(name.to_string())],
+            })
+            .collect();
+        assert_eq!(results, vec![QueryResult::Rows(names)]);
+    }
+
+    #[tokio::test]
+    async fn test_entries_with_rows() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+
+        let mut unmapped = storage.clone();
+        unmapped.set_capabilities(Capabilities::for_api_version(630));
+
+        // braces in the row subspace can't be used in a mapper, nor can clients without
+        // mapped ranges, the rows are then read once the entries are
+        for (root, storage, mapped) in [
+            ("test_entries_with_rows", &storage, true),
+            ("test_{entries}", &storage, false),
+            ("test_unmapped_entries", &unmapped, false),
+        ] {
+            let database = Database::new(Subspace::all().subspace(&root), storage.clone());
+            assert_eq!(database.row_mapper(&table).is_some(), mapped);
+            database
+                .create_table(&table, false)
+                .await
+                .expect("Unable to create table");
+            for (name, age) in [("John", 20), ("Jane", 30), ("Bob", 40)] {
+                database
+                    .insert("Person", &person_record(name, age))
+                    .await
+                    .expect("Unable to insert record");
+            }
+
+            let name = Column::String("Jane".to_string());
+            let found = database
+                .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+                .await
+                .expect("Unable to get record");
+            assert_eq!(found, Some(person_record("Jane", 30)));
+
+            let records = database
+                .scan_index_range("Person", "idx_age", vec![Column::Int(25)].., None)
+                .await
+                .expect("Unable to scan index")
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("Unable to read records");
+            assert_eq!(
+                records,
+                vec![person_record("Jane", 30), person_record("Bob", 40)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_many() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_insert_many"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+
+        let records = vec![
+            person_record("John", 20),
+            person_record("Jane", 30),
+            person_record("Bob", 40),
+        ];
+        database
+            .insert_many("Person", &records)
+            .await
+            .expect("Unable to insert records");
+        let found = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(found, records);
+
+        // a duplicate within the batch rolls the whole batch back
+        let result = database
+            .insert_many(
+                "Person",
+                &[person_record("Alice", 50), person_record("Alice", 60)],
+            )
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::DuplicatePrimaryKey(_))));
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.row_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_transact() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_transact"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        // move John's age over to Jane, the operations see each other's writes
+        let john = &Column::String("John".to_string());
+        let jane = &Column::String("Jane".to_string());
+        let age = database
+            .transact(|txn| async move {
+                let record = txn.get("Person", &Columns(&vec![john])).await?;
+                let Some(Column::Int(age)) = record.map(|record| record.columns[1].clone()) else {
+                    panic!("John should have an age");
+                };
+                txn.delete("Person", &Columns(&vec![john])).await?;
+                txn.insert("Person", &person_record("Jane", age)).await?;
+                txn.update(
+                    "Person",
+                    &Columns(&vec![jane]),
+                    &person_record("Jane", age + 1),
+                )
+                .await?;
+                let jane = txn.get("Person", &Columns(&vec![jane])).await?;
+                assert_eq!(jane, Some(person_record("Jane", age + 1)));
+                Ok(age)
+            })
+            .await
+            .expect("Unable to run transaction");
+        assert_eq!(age, 20);
+        let records = database
+            .scan_where("Person", &Predicate::gt("age", Column::Int(0)), None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![person_record("Jane", 21)]);
+
+        // a failing operation rolls the previous ones back
+        let result = database
+            .transact(|txn| async move {
+                txn.upsert("Person", &person_record("Bob", 40)).await?;
+                txn.insert("Person", &person_record("Jane", 30)).await
+            })
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::DuplicatePrimaryKey(_))));
+        let bob = Column::String("Bob".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&bob]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, None);
+    }
+
+    #[tokio::test]
+    async fn test_row_metadata() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_row_metadata"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        let metadata = RowMetadata::from([
+            ("source".to_string(), "crm".to_string()),
+            ("trace_id".to_string(), "4bf92f3577b34da6".to_string()),
+        ]);
+        database
+            .insert_with_metadata("Person", &person_record("John", 20), &metadata)
+            .await
+            .expect("Unable to insert record");
+        database
+            .insert("Person", &person_record("Jane", 30))
+            .await
+            .expect("Unable to insert record");
+
+        let john = Column::String("John".to_string());
+        let jane = Column::String("Jane".to_string());
+        let found = database
+            .get_record_with_metadata("Person", &Columns(&vec![&john]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some((person_record("John", 20), metadata.clone())));
+        let found = database
+            .get_record_with_metadata("Person", &Columns(&vec![&jane]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some((person_record("Jane", 30), RowMetadata::new())));
+
+        // a plain update keeps the metadata, which follows the row when its key changes
+        let renamed = person_record("Johnny", 21);
+        database
+            .update("Person", &Columns(&vec![&john]), &renamed)
+            .await
+            .expect("Unable to update record");
+        let johnny = Column::String("Johnny".to_string());
+        let found = database
+            .get_record_with_metadata("Person", &Columns(&vec![&johnny]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some((renamed.clone(), metadata)));
+
+        let replaced = RowMetadata::from([("source".to_string(), "import".to_string())]);
+        let updated = database
+            .update_with_metadata("Person", &Columns(&vec![&johnny]), &renamed, &replaced)
+            .await
+            .expect("Unable to update record");
+        assert!(updated);
+        let found = database
+            .get_record_with_metadata("Person", &Columns(&vec![&johnny]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some((renamed, replaced.clone())));
+
+        // deleting the record removes its metadata
+        database
+            .delete_by_pk("Person", &Columns(&vec![&johnny]))
+            .await
+            .expect("Unable to delete record");
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database
+            .table_subspace(DataPrefix::RowMetadata, &table)
+            .range();
+        let entries = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan metadata");
+        assert!(entries.is_empty());
+
+        // the metadata follows the row of the coerced key
+        let mut table = Table::new("Sensor".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Float));
+        table.add_field(Field::new("label".to_string(), FieldType::String));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let sensor = |id: Column| Record::new(vec![id, Column::String("probe".to_string())]);
+        database
+            .insert("Sensor", &sensor(Column::Float(3.0)))
+            .await
+            .expect("Unable to insert record");
+        let id = Column::Float(3.0);
+        let updated = database
+            .update_with_metadata(
+                "Sensor",
+                &Columns(&vec![&id]),
+                &sensor(Column::Int(3)),
+                &replaced,
+            )
+            .await
+            .expect("Unable to update record");
+        assert!(updated);
+        let found = database
+            .get_record_with_metadata("Sensor", &Columns(&vec![&id]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some((sensor(Column::Float(3.0)), replaced)));
+    }
+
+    #[tokio::test]
+    async fn test_allocate_row_id() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let subspace = Subspace::all().subspace(&"test_allocate_row_id");
+        let database = Database::new(subspace.clone(), storage.clone());
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        // a table created before the counter existed starts it from its metadata
+        let mut meta = TableMetadata::new("Person".to_string());
+        for _ in 0..5 {
+            meta.increment_max_row_id();
+        }
+        let meta_key = subspace.subspace(&DataPrefix::TableMeta).pack(&"Person");
+        let meta_bytes = meta.to_bytes().unwrap();
+        storage
+            .run(None, |trx, _| {
+                let (meta_key, meta_bytes) = (&meta_key, &meta_bytes);
+                async move {
+                    trx.set(meta_key, meta_bytes);
+                    Ok(())
+                }
+            })
+            .await
+            .expect("Unable to write table metadata");
+
+        let inserted = database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(
+            inserted,
+            InsertedRow {
+                row_id: 5,
+                record: person_record("John", 20),
+            }
+        );
+        // concurrent inserts take distinct row ids from the reserved block
+        futures::try_join!(
+            database.insert("Person", &person_record("Jane", 30)),
+            database.upsert("Person", &person_record("Bob", 40)),
+        )
+        .expect("Unable to insert records");
+        // another instance reserves the next block
+        let other = Database::new(subspace.clone(), storage.clone());
+        let inserted = other
+            .insert("Person", &person_record("Alice", 50))
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(inserted.row_id, 5 + ROW_ID_BLOCK_SIZE);
+
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        let (start, end) = database
+            .table_subspace(DataPrefix::PrimaryKey, &table)
+            .range();
+        let row_ids = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan primary keys")
+            .iter()
+            .map(|(_, value)| unpack::<i64>(value).unwrap())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(row_ids, BTreeSet::from([5, 6, 7, 5 + ROW_ID_BLOCK_SIZE]));
+        assert_eq!(
+            database
+                .stats("Person")
+                .await
+                .expect("Unable to get stats")
+                .row_count,
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_record_by_row_id() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_get_record_by_row_id"),
+            storage,
+        );
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        let inserted = database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        let found = database
+            .get_record_by_row_id("Person", inserted.row_id, None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(person_record("John", 20)));
+        let found = database
+            .get_record_by_row_id("Person", inserted.row_id, Some(&["age", "name"]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(
+            found,
+            Some(Record::new(vec![
+                Column::Int(20),
+                Column::String("John".to_string())
+            ]))
+        );
+
+        // the row keeps its id when updated, and is gone once deleted
+        let name = Column::String("John".to_string());
+        database
+            .update("Person", &Columns(&vec![&name]), &person_record("John", 21))
+            .await
+            .expect("Unable to update record");
+        let found = database
+            .get_record_by_row_id("Person", inserted.row_id, None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(person_record("John", 21)));
+        database
+            .delete_by_pk("Person", &Columns(&vec![&name]))
+            .await
+            .expect("Unable to delete record");
+        let found = database
+            .get_record_by_row_id("Person", inserted.row_id, None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, None);
+
+        let result = database.get_record_by_row_id("Unknown", 1, None).await;
+        assert!(matches!(result, Err(SqlLayerError::TableNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_many() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_delete_many"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        database
+            .create_table(&table, false)
+            .await
+            .expect("Unable to create table");
+        let names = (0..2 * DELETE_BATCH_SIZE + 10)
+            .map(|i| format!("John {i}"))
+            .collect::<Vec<_>>();
+        let records = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| person_record(name, i as i64))
+            .collect::<Vec<_>>();
+        database
+            .insert_many("Person", &records)
+            .await
+            .expect("Unable to insert records");
+
+        // every other record is deleted, along with an unknown key and a repeated one
+        let columns = names
+            .iter()
+            .map(|name| Column::String(name.clone()))
+            .chain([Column::String("Jane".to_string())])
+            .collect::<Vec<_>>();
+        let victims = columns
+            .iter()
+            .step_by(2)
+            .chain([&columns[0]])
+            .map(|column| vec![column])
+            .collect::<Vec<_>>();
+        let pks = victims.iter().map(Columns::new).collect::<Vec<_>>();
+        let deleted = database
+            .delete_many("Person", &pks)
+            .await
+            .expect("Unable to delete records");
+        let expected = (0..columns.len())
+            .step_by(2)
+            .map(|i| i < names.len())
+            .chain([false])
+            .collect::<Vec<_>>();
+        assert_eq!(deleted, expected);
+
+        let remaining = database
+            .scan_index_range("Person", "idx_age", .., None)
+            .await
+            .expect("Unable to scan index")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        let expected = records
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, expected);
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.row_count, expected.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_parent() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(
+            Subspace::all().subspace(&"test_execute_with_parent"),
+            storage,
+        );
+
+        let parent = tracing::info_span!("request");
+        let results = database
+            .execute_with_parent(
+                "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT); \
+                 INSERT INTO Person VALUES ('John', 20); \
+                 SELECT name FROM Person WHERE name = 'John'",
+                Some(&parent),
+            )
+            .await
+            .expect("Unable to execute statements");
+        assert_eq!(
+            results,
+            vec![
+                QueryResult::Created,
+                QueryResult::Inserted(1),
+                QueryResult::Rows(vec![Record {
+                    columns: vec![Column::String("John".to_string())]
+                }])
+            ]
+        );
+
+        assert!(matches!(
+            database
+                .execute_with_parent("SELECT FROM", Some(&parent))
+                .await,
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_result_limits() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut database = Database::new(Subspace::all().subspace(&"test_result_limits"), storage);
+        database
+            .execute(
+                "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT); \
+                 INSERT INTO Person VALUES ('John', 20), ('Jane', 30), ('Bob', 40)",
+            )
+            .await
+            .expect("Unable to execute statements");
+
+        database.set_result_limits(ResultLimits {
+            max_rows: Some(2),
+            ..Default::default()
+        });
+        assert!(matches!(
+            database.execute("SELECT * FROM Person").await,
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+        assert!(matches!(
+            database.execute("SELECT DISTINCT age FROM Person").await,
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+        let results = database
+            .execute("SELECT name FROM Person WHERE age >= 30")
+            .await
+            .expect("Unable to select records");
+        assert!(matches!(&results[..], [QueryResult::Rows(records)] if records.len() == 2));
+
+        database.set_result_limits(ResultLimits {
+            max_bytes: Some(10),
+            ..Default::default()
+        });
+        assert!(matches!(
+            database.execute("SELECT * FROM Person").await,
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Bytes(10)))
+        ));
+
+        // streaming scans and in-memory sorts are bound as well
+        database.set_result_limits(ResultLimits {
+            max_rows: Some(2),
+            ..Default::default()
+        });
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records.len(), 3);
+        assert!(matches!(
+            records[2],
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+        let sorted = database
+            .scan_sorted("Person", None, &[SortKey::desc("age")], None, None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(matches!(
+            sorted,
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+
+        // aggregates and deletes return no record, only their duration is bound
+        assert_eq!(
+            database
+                .aggregate("Person", &Aggregate::Count, None)
+                .await
+                .expect("Unable to aggregate"),
+            Column::Int(3)
+        );
+
+        // paginated scans aren't bound by the limits
+        let page = database
+            .scan_table_page("Person", 10, 0, None, None)
+            .await
+            .expect("Unable to scan table");
+        assert_eq!(page.records.len(), 3);
+
+        let results = database
+            .execute("DELETE FROM Person WHERE age >= 20")
+            .await
+            .expect("Unable to delete records");
+        assert_eq!(results, vec![QueryResult::Deleted(3)]);
+
+        database.set_result_limits(ResultLimits {
+            max_duration: Some(std::time::Duration::ZERO),
+            ..Default::default()
+        });
+        database
+            .execute("INSERT INTO Person VALUES ('John', 20)")
+            .await
+            .expect("Unable to insert record");
+        assert!(matches!(
+            database.aggregate("Person", &Aggregate::Count, None).await,
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Duration(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_as() {
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Person {
+            name: String,
+            age: u8,
+            is_married: bool,
+        }
+
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_get_as"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 20), ("Jane", 31)] {
+            database
+                .insert("Person", &person_record(name, age))
+                .await
+                .expect("Unable to insert record");
+        }
+
+        let john = Column::String("John".to_string());
+        let john = vec![&john];
+        let person = database
+            .get_as::<Person>("Person", &Columns::new(&john))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(
+            person,
+            Some(Person {
+                name: "John".to_string(),
+                age: 20,
+                is_married: true,
+            })
+        );
+        let bob = Column::String("Bob".to_string());
+        let bob = vec![&bob];
+        let person = database
+            .get_as::<Person>("Person", &Columns::new(&bob))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(person, None);
+
+        let persons = database
+            .scan_as::<Person>("Person")
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to deserialize records");
+        assert_eq!(
+            persons.iter().map(|person| person.age).collect::<Vec<_>>(),
+            vec![20, 31]
+        );
+
+        assert!(matches!(
+            database
+                .get_as::<String>("Person", &Columns::new(&john))
+                .await,
+            Err(SqlLayerError::Deserialization(_))
+        ));
+        assert!(matches!(
+            database.scan_as::<Person>("Unknown").await,
+            Err(SqlLayerError::TableNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_catalog() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let mut catalog = Catalog::new(Subspace::all().subspace(&"test_catalog"), storage);
+        catalog.set_database_setup(|_, database| {
+            database.set_result_limits(ResultLimits {
+                max_rows: Some(1),
+                ..Default::default()
+            })
+        });
+
+        let sales = catalog.database("sales").expect("Unable to open database");
+        let hr = catalog.database("hr").expect("Unable to open database");
+        let reopened = catalog.database("sales").expect("Unable to open database");
+        assert!(Arc::ptr_eq(&sales, &reopened));
+        assert_eq!(catalog.open_databases(), vec!["hr", "sales"]);
+
+        // the same table name holds different rows in every database
+        for (database, name) in [(&sales, "John"), (&hr, "Jane")] {
+            database
+                .create_table(&person_table(), false)
+                .await
+                .expect("Unable to create table");
+            database
+                .insert("Person", &person_record(name, 20))
+                .await
+                .expect("Unable to insert record");
+        }
+        let results = sales
+            .execute("SELECT name FROM Person")
+            .await
+            .expect("Unable to select records");
+        assert_eq!(
+            results,
+            vec![QueryResult::Rows(vec![Record {
+                columns: vec![Column::String("John".to_string())]
+            }])]
+        );
+
+        // databases are configured by the setup callback
+        hr.insert("Person", &person_record("Bob", 30))
+            .await
+            .expect("Unable to insert record");
+        assert!(matches!(
+            hr.execute("SELECT name FROM Person").await,
+            Err(SqlLayerError::ResultTooLarge(_))
+        ));
+
+        assert!(matches!(
+            catalog.database(""),
+            Err(SqlLayerError::InvalidIdentifier(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_add_column() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_add_column"), storage);
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        let default = Column::String("unknown".to_string());
+        database
+            .add_column(
+                "Person",
+                Field::new_not_null("email".to_string(), FieldType::String),
+                default.clone(),
+            )
+            .await
+            .expect("Unable to add column");
+        database
+            .add_column(
+                "Person",
+                Field::new("nickname".to_string(), FieldType::String),
+                Column::Null,
+            )
+            .await
+            .expect("Unable to add column");
+
+        // the stored row is read with the defaults of the added fields
+        let mut john = person_record("John", 20);
+        john.columns.extend([default.clone(), Column::Null]);
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(john.clone()));
+
+        let mut jane = person_record("Jane", 30);
+        jane.columns.extend([
+            Column::String("jane@example.com".to_string()),
+            Column::String("JJ".to_string()),
+        ]);
+        database
+            .insert("Person", &jane)
+            .await
+            .expect("Unable to insert record");
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john.clone(), jane]);
+
+        // the old row is indexed with its default
+        database
+            .add_index("Person", &Index::new("idx_email", vec!["email"]))
+            .await
+            .expect("Unable to add index");
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_email",
+                vec![default.clone()]..=vec![default.clone()],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john]);
+
+        assert!(matches!(
+            database
+                .add_column(
+                    "Person",
+                    Field::new("email".to_string(), FieldType::String),
+                    Column::Null
+                )
+                .await,
+            Err(SqlLayerError::FieldAlreadyExists(_))
+        ));
+        assert!(matches!(
+            database
+                .add_column(
+                    "Person",
+                    Field::new_not_null("phone".to_string(), FieldType::String),
+                    Column::Null
+                )
+                .await,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            database
+                .add_column(
+                    "Person",
+                    Field::new("phone".to_string(), FieldType::String),
+                    Column::Int(0)
+                )
+                .await,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_table_cache() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let subspace = Subspace::all().subspace(&"test_table_cache");
+        let database = Database::new(subspace.clone(), Storage::new(_guard.clone()));
+        let other = Database::new(subspace, Storage::new(_guard.clone()));
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        assert!(database.table_cache().contains_key("Person"));
+        assert_eq!(
+            database
+                .get_table("Person")
+                .await
+                .expect("Unable to get table"),
+            Some(table.clone())
+        );
+
+        // a change made by another instance is seen despite the cache
+        other
+            .add_column(
+                "Person",
+                Field::new("email".to_string(), FieldType::String),
+                Column::Null,
+            )
+            .await
+            .expect("Unable to add column");
+        let changed = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        assert_eq!(changed.fields.len(), table.fields.len() + 1);
+        assert!(changed.fields.iter().any(|field| field.name == "email"));
+        assert_eq!(
+            database
+                .get_table("Missing")
+                .await
+                .expect("Unable to get table"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_row_schemas() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let subspace = Subspace::all().subspace(&"test_row_schemas");
+        let database = Database::new(subspace.clone(), Storage::new(_guard.clone()));
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
+
+        // rows written before a change of the definition keep the schema they were
+        // written with
+        database
+            .add_column(
+                "Person",
+                Field::new("email".to_string(), FieldType::String),
+                Column::Null,
+            )
+            .await
+            .expect("Unable to add column");
+        let mut jane = person_record("Jane", 30);
+        jane.columns
+            .push(Column::String("jane@example.com".to_string()));
+        database
+            .insert("Person", &jane)
+            .await
+            .expect("Unable to insert record");
+
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        let fingerprints = rows
+            .iter()
+            .map(|(_, row)| RowSchema::fingerprint_of(row))
+            .collect::<Vec<_>>();
+        let schema = |table: &Table| RowSchema::new(table).unwrap().fingerprint();
+        assert_eq!(
+            fingerprints,
+            vec![Some(schema(&person_table())), Some(schema(&table))]
+        );
 
-                let row_id = unpack::<i64>(&kv).map_err(FdbBindingError::PackError)?;
+        let mut john = person_record("John", 20);
+        john.columns.push(Column::Null);
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john, jane.clone()]);
 
-                // get the row
-                let key = self
-                    .root_subspace
-                    .subspace(&DataPrefix::Row)
-                    .subspace(&table_name)
-                    .pack(&row_id);
-                let row = trx.get(&key, false).await?;
+        // rows of the generic schema are still read
+        let legacy = Row::from(&jane).to_bytes().expect("Unable to encode row");
+        assert_eq!(
+            database
+                .decode_record(&table, &legacy)
+                .expect("Unable to decode row"),
+            jane
+        );
 
-                Ok(row)
-            })
-            .await?;
+        // the schemas of a table are loaded along with its definition
+        let other = Database::new(subspace, Storage::new(_guard.clone()));
+        assert!(matches!(
+            other.decode_record(&table, &rows[1].1),
+            Err(SqlLayerError::UnknownRowSchema(_, _))
+        ));
+        other
+            .get_table("Person")
+            .await
+            .expect("Unable to get table");
+        assert_eq!(
+            other
+                .decode_record(&table, &rows[1].1)
+                .expect("Unable to decode row"),
+            jane
+        );
+    }
 
-        let Some(kv) = kv else {
-            return Ok(None);
-        };
+    #[tokio::test]
+    async fn test_row_encoding() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_row_encoding"), storage);
+        let mut table = person_table();
+        table.set_row_encoding(RowEncoding::Bincode);
+        let created = database.create_table(&table, false).await;
+        if cfg!(not(feature = "bincode")) {
+            assert!(matches!(created, Err(SqlLayerError::Unsupported(_))));
+            return;
+        }
+        created.expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
 
-        let row = Row::from_bytes(&kv)?;
-        let record = Record::from(row);
-        Ok(Some(record))
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        assert_eq!(table.row_encoding, RowEncoding::Bincode);
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        let codec = RowEncoding::Bincode.codec().unwrap();
+        let john = codec
+            .encode(&Row::from(&person_record("John", 20)))
+            .unwrap();
+        assert_eq!(rows, vec![(rows[0].0.clone(), john)]);
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(person_record("John", 20)));
     }
-}
 
-fn check_field_against_column(field: &FieldType, column: &Column) -> crate::errors::Result<()> {
-    match (field, column) {
-        (FieldType::Bool, Column::Bool(_)) => {}
-        (FieldType::Int, Column::Int(_)) => {}
-        (FieldType::String, Column::String(_)) => {}
-        (FieldType::Float, Column::Float(_)) => {}
-        (FieldType::Bytes, Column::Bytes(_)) => {}
-        (expected, found) => {
-            let expected = format!("{expected:?}");
-            let found = format!("{found:?}");
-            return Err(SqlLayerError::MismatchedColumnType(expected, found));
+    #[tokio::test]
+    async fn test_row_compression() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let database = Database::new(Subspace::all().subspace(&"test_row_compression"), storage);
+        let mut table = person_table();
+        table.set_compression(RowCompression::Lz4, 100);
+        let created = database.create_table(&table, false).await;
+        if cfg!(not(feature = "lz4")) {
+            assert!(matches!(created, Err(SqlLayerError::Unsupported(_))));
+            return;
+        }
+        created.expect("Unable to create table");
+
+        // only the rows of at least the threshold are compressed
+        let mut jane = person_record("Jane", 30);
+        jane.columns[4] = Column::Bytes(b"photo".repeat(100));
+        for record in [&person_record("John", 20), &jane] {
+            database
+                .insert("Person", record)
+                .await
+                .expect("Unable to insert record");
         }
-    }
-    Ok(())
-}
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        assert_ne!(rows[0].1[0], 0);
+        assert_eq!(rows[1].1[..2], [0, RowCompression::Lz4 as u8]);
+        assert!(rows[1].1.len() < 500);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::index::Index;
-    use crate::table;
-    use table::{Field, FieldType};
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![person_record("John", 20), jane]);
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(
+            stats.total_bytes,
+            rows.iter().map(|(_, row)| row.len() as u64).sum::<u64>()
+        );
+    }
 
     #[tokio::test]
-    async fn test_database() {
+    async fn test_large_rows() {
         let _guard = fdb_testcontainer::get_db_once().await;
         let storage = Storage::new(_guard.clone());
-        let database = Database::new(Subspace::all(), storage);
-        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
-        table.add_field(Field::new("name".to_string(), FieldType::String));
-        table.add_field(Field::new("age".to_string(), FieldType::Int));
-        table.add_field(Field::new("height".to_string(), FieldType::Float));
-        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
-        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        let database = Database::new(Subspace::all().subspace(&"test_large_rows"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
         database
-            .create_table(&table)
+            .create_table(&table, false)
             .await
             .expect("Unable to create table");
-        let found_table = database
+        let table = database
             .get_table("Person")
             .await
-            .expect("Unable to get table");
-        assert_eq!(found_table, Some(table));
+            .expect("Unable to get table")
+            .expect("Table not found");
+        let chunk_count = || async {
+            let (start, end) = database
+                .table_subspace(DataPrefix::RowChunk, &table)
+                .range();
+            let chunks = database
+                .storage
+                .scan(&start, &end)
+                .await
+                .expect("Unable to scan chunks");
+            chunks.len()
+        };
+
+        // a photo larger than two values, the row is split into three chunks
+        let mut john = person_record("John", 20);
+        john.columns[4] = Column::Bytes((0..250_000u32).map(|i| (i * 7 % 251) as u8).collect());
+        database
+            .insert("Person", &john)
+            .await
+            .expect("Unable to insert record");
+        assert_eq!(chunk_count().await, 3);
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        assert_eq!(rows[0].1.len(), CHUNKED_ROW.len() + 8);
+        let row_size = database
+            .encode_row(&table, &john.to_stored_row(&table))
+            .unwrap()
+            .len();
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.total_bytes, row_size as u64);
+
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found.as_ref(), Some(&john));
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john.clone()]);
+        let records = database
+            .get_records_by_index_values(&table, "idx_age", &[Column::Int(20)])
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john.clone()]);
+        let checksum = database
+            .table_checksum("Person", None)
+            .await
+            .expect("Unable to compute checksum");
+        assert_eq!(checksum.row_count, 1);
+
+        // the chunks of the previous version are replaced, or cleared for a row stored in a
+        // single value
+        let mut smaller = john.clone();
+        smaller.columns[4] = Column::Bytes(vec![0; 150_000]);
+        database
+            .update("Person", &Columns(&vec![&name]), &smaller)
+            .await
+            .expect("Unable to update record");
+        assert_eq!(chunk_count().await, 2);
+        let updated = database
+            .update("Person", &Columns(&vec![&name]), &person_record("John", 21))
+            .await
+            .expect("Unable to update record");
+        assert!(updated);
+        assert_eq!(chunk_count().await, 0);
+
+        database
+            .update("Person", &Columns(&vec![&name]), &john)
+            .await
+            .expect("Unable to update record");
+        let deleted = database
+            .delete_by_pk("Person", &Columns(&vec![&name]))
+            .await
+            .expect("Unable to delete record");
+        assert!(deleted);
+        assert_eq!(chunk_count().await, 0);
+        let stats = database.stats("Person").await.expect("Unable to get stats");
+        assert_eq!(stats.total_bytes, 0);
     }
 
     #[tokio::test]
-    async fn test_insert_record() {
+    async fn test_blob_fields() {
         let _guard = fdb_testcontainer::get_db_once().await;
         let storage = Storage::new(_guard.clone());
-        let database = Database::new(Subspace::all(), storage);
-        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
-        table.add_field(Field::new("name".to_string(), FieldType::String));
-        table.add_field(Field::new("age".to_string(), FieldType::Int));
-        table.add_field(Field::new("height".to_string(), FieldType::Float));
-        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
-        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        let database = Database::new(Subspace::all().subspace(&"test_blob_fields"), storage);
+        let mut table = person_table();
+        table.add_field(Field::new_blob("resume".to_string()));
         database
-            .create_table(&table)
+            .create_table(&table, false)
             .await
             .expect("Unable to create table");
-        let record = Record {
-            columns: vec![
-                Column::String("John".to_string()),
-                Column::Int(20),
-                Column::Float(20.5),
-                Column::Bool(true),
-                Column::Bytes(b"arbitrary data".to_vec()),
-            ],
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Table not found");
+        let blob_keys = || async {
+            let (start, end) = database.table_subspace(DataPrefix::Blob, &table).range();
+            let keys = database
+                .storage
+                .scan(&start, &end)
+                .await
+                .expect("Unable to scan blobs");
+            keys.len()
         };
+        let database = &database;
+        let read_blob = |name: &'static str| async move {
+            let name = Column::String(name.to_string());
+            let Some(chunks) = database
+                .read_blob("Person", &Columns(&vec![&name]), "resume")
+                .await
+                .expect("Unable to read blob")
+            else {
+                return None;
+            };
+            let chunks = chunks
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("Unable to stream blob");
+            Some(chunks.concat())
+        };
+
+        // the row holds the id of the blob, stored in two chunks and a length
+        let resume = (0..150_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut john = person_record("John", 20);
+        john.columns.push(Column::Bytes(resume.clone()));
         database
-            .insert("Person", &record)
+            .insert("Person", &john)
             .await
             .expect("Unable to insert record");
-        let found_record = database
-            .get_record_by_pk(
+        assert_eq!(blob_keys().await, 3);
+        let (start, end) = database.table_subspace(DataPrefix::Row, &table).range();
+        let rows = database
+            .storage
+            .scan(&start, &end)
+            .await
+            .expect("Unable to scan rows");
+        let stored = database
+            .decode_record(&table, &rows[0].1)
+            .expect("Unable to decode row");
+        assert!(matches!(stored.columns[5], Column::Int(_)));
+
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found.as_ref(), Some(&john));
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), Some(&["age"][..]))
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(Record::new(vec![Column::Int(20)])));
+        let records = database
+            .scan_table("Person", Some(&["resume"][..]))
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(
+            records,
+            vec![Record::new(vec![Column::Bytes(resume.clone())])]
+        );
+        assert_eq!(read_blob("John").await, Some(resume.clone()));
+        assert_eq!(read_blob("Jane").await, None);
+
+        // a streamed blob replaces the previous one
+        let parts = vec![Ok(vec![1; 70_000]), Ok(vec![2; 70_000]), Ok(vec![3])];
+        let written = database
+            .write_blob(
                 "Person",
-                &Columns(&vec![&Column::String("John".to_string())]),
+                &Columns(&vec![&name]),
+                "resume",
+                futures::stream::iter(parts),
             )
             .await
-            .expect("Unable to get record");
-        assert_eq!(found_record, Some(record));
+            .expect("Unable to write blob");
+        assert!(written);
+        let mut expected = vec![1; 70_000];
+        expected.extend(vec![2; 70_000]);
+        expected.push(3);
+        assert_eq!(read_blob("John").await, Some(expected));
+        assert_eq!(blob_keys().await, 3);
+        let jane = Column::String("Jane".to_string());
+        let written = database
+            .write_blob(
+                "Person",
+                &Columns(&vec![&jane]),
+                "resume",
+                futures::stream::iter(vec![Ok(vec![1])]),
+            )
+            .await
+            .expect("Unable to write blob");
+        assert!(!written);
+
+        // updates and deletes clear the blobs of the row
+        let mut updated = person_record("John", 21);
+        updated.columns.push(Column::Null);
+        database
+            .update("Person", &Columns(&vec![&name]), &updated)
+            .await
+            .expect("Unable to update record");
+        assert_eq!(blob_keys().await, 0);
+        assert_eq!(read_blob("John").await, None);
+        database
+            .update("Person", &Columns(&vec![&name]), &john)
+            .await
+            .expect("Unable to update record");
+        let deleted = database
+            .delete_by_pk("Person", &Columns(&vec![&name]))
+            .await
+            .expect("Unable to delete record");
+        assert!(deleted);
+        assert_eq!(blob_keys().await, 0);
+
+        // blob fields can't be indexed
+        let result = database
+            .add_index("Person", &Index::new("idx_resume", vec!["resume"]))
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidBlobField(_))));
+        let result = database
+            .read_blob("Person", &Columns(&vec![&name]), "photo")
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::InvalidBlobField(_))));
     }
 
     #[tokio::test]
-    async fn insert_multiple_records() {
+    async fn test_drop_and_rename_column() {
         let _guard = fdb_testcontainer::get_db_once().await;
         let storage = Storage::new(_guard.clone());
-        let database = Database::new(Subspace::all(), storage);
-        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
-        table.add_field(Field::new("name".to_string(), FieldType::String));
-        table.add_field(Field::new("age".to_string(), FieldType::Int));
-        table.add_field(Field::new("height".to_string(), FieldType::Float));
-        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
-        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        let database = Database::new(
+            Subspace::all().subspace(&"test_drop_and_rename_column"),
+            storage,
+        );
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
         database
-            .create_table(&table)
+            .create_table(&table, false)
             .await
             .expect("Unable to create table");
+        database
+            .insert("Person", &person_record("John", 20))
+            .await
+            .expect("Unable to insert record");
 
-        // populate database
-        for i in 0..10 {
-            let record = Record {
-                columns: vec![
-                    Column::String(format!("John {}", i)),
-                    Column::Int(i),
-                    Column::Float(i as f64),
-                    Column::Bool(i % 2 == 0),
-                    Column::Bytes(b"arbitrary data".to_vec()),
-                ],
-            };
+        // the stored row is read without the dropped column
+        database
+            .drop_column("Person", "height")
+            .await
+            .expect("Unable to drop column");
+        let mut john = person_record("John", 20);
+        john.columns.remove(2);
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(john.clone()));
+
+        // rows written after the drop and fields added after it line up with the old rows
+        let default = Column::String("unknown".to_string());
+        database
+            .add_column(
+                "Person",
+                Field::new("email".to_string(), FieldType::String),
+                default.clone(),
+            )
+            .await
+            .expect("Unable to add column");
+        john.columns.push(default);
+        let mut jane = person_record("Jane", 30);
+        jane.columns.remove(2);
+        jane.columns
+            .push(Column::String("jane@example.com".to_string()));
+        database
+            .insert("Person", &jane)
+            .await
+            .expect("Unable to insert record");
+        let records = database
+            .scan_table("Person", None)
+            .await
+            .expect("Unable to scan table")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![john.clone(), jane.clone()]);
+
+        assert!(matches!(
+            database.drop_column("Person", "name").await,
+            Err(SqlLayerError::ColumnInUse(_, _))
+        ));
+        assert!(matches!(
+            database.drop_column("Person", "age").await,
+            Err(SqlLayerError::ColumnInUse(_, _))
+        ));
+        assert!(matches!(
+            database.drop_column("Person", "height").await,
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+
+        // the primary key and the indexes follow the renamed fields
+        database
+            .rename_column("Person", "age", "years")
+            .await
+            .expect("Unable to rename column");
+        database
+            .rename_column("Person", "name", "full_name")
+            .await
+            .expect("Unable to rename column");
+        let table = database
+            .get_table("Person")
+            .await
+            .expect("Unable to get table")
+            .expect("Missing table");
+        assert_eq!(table.primary_key, vec!["full_name"]);
+        assert_eq!(table.get_index("idx_age").unwrap().fields(), &vec!["years"]);
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), None)
+            .await
+            .expect("Unable to get record");
+        assert_eq!(found, Some(john));
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_age",
+                vec![Column::Int(30)]..=vec![Column::Int(30)],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![jane]);
+        assert!(matches!(
+            database.rename_column("Person", "years", "email").await,
+            Err(SqlLayerError::FieldAlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_migrate() {
+        let _guard = fdb_testcontainer::get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        let subspace = Subspace::all().subspace(&"test_migrate");
+        let database = Database::new(subspace.clone(), storage.clone());
+        database
+            .create_table(&person_table(), false)
+            .await
+            .expect("Unable to create table");
+        for (name, age) in [("John", 20), ("Jane", 30)] {
             database
-                .insert("Person", &record)
+                .insert("Person", &person_record(name, age))
                 .await
                 .expect("Unable to insert record");
         }
+        assert_eq!(database.schema_version().await.unwrap(), 0);
 
-        // check record are inserted
-        for i in 0..10 {
-            let expected = Record {
-                columns: vec![
-                    Column::String(format!("John {}", i)),
-                    Column::Int(i),
-                    Column::Float(i as f64),
-                    Column::Bool(i % 2 == 0),
-                    Column::Bytes(b"arbitrary data".to_vec()),
-                ],
-            };
-            // check that the record is retrievable from pk
-            let found = database
-                .get_record_by_pk(
-                    "Person",
-                    &Columns(&vec![&Column::String(format!("John {}", i))]),
-                )
-                .await
-                .expect("Unable to get record");
-            assert_eq!(found, Some(expected));
-        }
+        let mut migrations = Migrations::new();
+        let add_email = MigrationStep::AddColumn {
+            table: "Person".to_string(),
+            field: Field::new("email".to_string(), FieldType::String),
+            default: Column::Null,
+        };
+        migrations
+            .register(Migration::new(1, "add email", vec![add_email]))
+            .expect("Unable to register migration");
+        let fill_email = MigrationStep::backfill("Person", |mut record: Record| {
+            if let Column::String(name) = &record.columns[0] {
+                record.columns[5] = Column::String(format!("{}@example.com", name.to_lowercase()));
+            }
+            Ok(record)
+        });
+        let index_email = MigrationStep::AddIndex {
+            table: "Person".to_string(),
+            index: Index::new_unique("idx_email", vec!["email"]),
+        };
+        migrations
+            .register(Migration::new(
+                2,
+                "fill and index emails",
+                vec![fill_email, index_email],
+            ))
+            .expect("Unable to register migration");
+
+        // concurrent instances apply every migration once
+        let other = Database::new(subspace, storage);
+        let (migrated, other_migrated) =
+            futures::join!(database.migrate(&migrations), other.migrate(&migrations));
+        assert_eq!(migrated.expect("Unable to migrate"), 2);
+        assert_eq!(other_migrated.expect("Unable to migrate"), 2);
+        assert_eq!(
+            database.applied_migrations().await.unwrap(),
+            vec![
+                (1, "add email".to_string()),
+                (2, "fill and index emails".to_string())
+            ]
+        );
+        let table = database.get_table("Person").await.unwrap().unwrap();
+        assert_eq!(table.schema_version, 2);
+        assert_eq!(
+            table.get_index("idx_email").map(|index| index.state()),
+            Some(IndexState::Ready)
+        );
+
+        let email = Column::String("jane@example.com".to_string());
+        let mut jane = person_record("Jane", 30);
+        jane.columns.push(email.clone());
+        let records = database
+            .scan_index_range(
+                "Person",
+                "idx_email",
+                vec![email.clone()]..=vec![email],
+                None,
+            )
+            .await
+            .expect("Unable to scan index")
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("Unable to read records");
+        assert_eq!(records, vec![jane]);
+
+        // a failing migration leaves the database at the previous version
+        let index_age = MigrationStep::AddIndex {
+            table: "Person".to_string(),
+            index: Index::new("idx_age", vec!["age"]),
+        };
+        let add_email = MigrationStep::AddColumn {
+            table: "Person".to_string(),
+            field: Field::new("email".to_string(), FieldType::String),
+            default: Column::Null,
+        };
+        migrations
+            .register(Migration::new(
+                3,
+                "add email again",
+                vec![index_age, add_email],
+            ))
+            .expect("Unable to register migration");
+        assert!(matches!(
+            database.migrate(&migrations).await,
+            Err(SqlLayerError::FieldAlreadyExists(_))
+        ));
+        assert_eq!(database.schema_version().await.unwrap(), 2);
+        let table = database.get_table("Person").await.unwrap().unwrap();
+        assert!(table.get_index("idx_age").is_none());
     }
 
     #[tokio::test]
-    async fn test_retrieve_row_by_index() {
+    async fn test_projection() {
         let _guard = fdb_testcontainer::get_db_once().await;
         let storage = Storage::new(_guard.clone());
-        let database = Database::new(Subspace::all(), storage);
-        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
-        table.add_field(Field::new("name".to_string(), FieldType::String));
-        table.add_field(Field::new("age".to_string(), FieldType::Int));
-        table.add_field(Field::new("height".to_string(), FieldType::Float));
-        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
-        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        let database = Database::new(Subspace::all().subspace(&"test_projection"), storage);
+        let mut table = person_table();
+        table.add_index(&Index::new("idx_age", vec!["age"]));
         database
-            .create_table(&table)
+            .create_table(&table, false)
             .await
             .expect("Unable to create table");
-
-        let index = &Index::new("idx_age", vec!["age"]);
         database
-            .add_index("Person", index)
+            .insert("Person", &person_record("John", 20))
             .await
-            .expect("Unable to add index");
+            .expect("Unable to insert record");
 
-        let record = Record {
-            columns: vec![
-                Column::String("John".to_string()),
-                Column::Int(20),
-                Column::Float(20.5),
-                Column::Bool(true),
-                Column::Bytes(b"arbitrary data".to_vec()),
-            ],
+        let expected = Record {
+            columns: vec![Column::Int(20), Column::String("John".to_string())],
         };
+        let projection: &[&str] = &["age", "name"];
 
-        database
-            .insert("Person", &record)
+        let name = Column::String("John".to_string());
+        let found = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), Some(projection))
             .await
-            .expect("Unable to insert record");
+            .expect("Unable to get record");
+        assert_eq!(found, Some(expected.clone()));
+
+        let records = database
+            .scan_table("Person", Some(projection))
+            .await
+            .expect("Unable to scan table")
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(&records[..], [Ok(record)] if record == &expected));
+
+        let records = database
+            .scan_index_range("Person", "idx_age", .., Some(projection))
+            .await
+            .expect("Unable to scan index")
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(&records[..], [Ok(record)] if record == &expected));
+
+        let result = database
+            .get_record_by_pk("Person", &Columns(&vec![&name]), Some(&["unknown"][..]))
+            .await;
+        assert!(matches!(result, Err(SqlLayerError::MissingColumn(_))));
     }
 }
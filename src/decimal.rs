@@ -0,0 +1,295 @@
+//! # Decimal Module
+//!
+//! Exact decimal numbers, for the values such as amounts of money that floats can't
+//! represent exactly. A [`Decimal`] is an `i128` mantissa scaled by a power of ten, so it
+//! holds up to 38 significant digits, up to [`MAX_SCALE`] of them after the decimal point.
+//!
+//! Decimals compare by value whatever their scale, `1.50` being equal to `1.5`. In keys,
+//! they are tuple-encoded as byte strings sorting as the decimals do: a sign byte, the
+//! exponent of the first significant digit, then the significant digits, the exponent and
+//! the digits being inverted for negative decimals. Stored rows hold the mantissa and the
+//! scale, and the rows encoded with a row schema the Avro `decimal` logical type, at the
+//! scale of their field, see [`Field::new_decimal`](crate::Field::new_decimal).
+
+use crate::errors::SqlLayerError;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The largest number of digits after the decimal point.
+pub const MAX_SCALE: u8 = 38;
+
+/// Sign bytes of the keys of decimals.
+const NEGATIVE: u8 = 0;
+const ZERO: u8 = 1;
+const POSITIVE: u8 = 2;
+
+/// Ends the digits of the keys of negative decimals, greater than any inverted digit, so
+/// that a negative decimal sorts after the ones extending its digits.
+const NEGATIVE_END: u8 = 10;
+
+/// An exact decimal number, see the module documentation.
+#[derive(Clone, Copy, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Creates the decimal `mantissa × 10^-scale`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidDecimal` if the scale is above [`MAX_SCALE`].
+    pub fn new(mantissa: i128, scale: u8) -> crate::errors::Result<Self> {
+        if scale > MAX_SCALE {
+            return Err(SqlLayerError::InvalidDecimal(format!(
+                "scale {scale} is above {MAX_SCALE}"
+            )));
+        }
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Returns the number of digits after the decimal point.
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Returns the same decimal with the given number of digits after the decimal point,
+    /// or `None` if it has more significant digits after it, or doesn't fit at that scale.
+    pub fn rescale(&self, scale: u8) -> Option<Decimal> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa
+                .checked_mul(10_i128.checked_pow((scale - self.scale).into())?)?
+        } else {
+            let divisor = 10_i128.pow((self.scale - scale).into());
+            if self.mantissa % divisor != 0 {
+                return None;
+            }
+            self.mantissa / divisor
+        };
+        Some(Decimal { mantissa, scale })
+    }
+
+    /// Returns the same decimal without trailing zeros after the decimal point.
+    pub fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    /// Returns the bytes of the decimal in the stored rows: the mantissa in big-endian,
+    /// then the scale.
+    pub(crate) fn to_stored(self) -> Vec<u8> {
+        let mut bytes = self.mantissa.to_be_bytes().to_vec();
+        bytes.push(self.scale);
+        bytes
+    }
+
+    /// Reads the bytes written by [`Decimal::to_stored`].
+    pub(crate) fn from_stored(bytes: &[u8]) -> Option<Decimal> {
+        let (mantissa, scale) = bytes.split_last_chunk::<1>()?;
+        let decimal = Decimal::from_be_bytes(mantissa, scale[0])?;
+        (mantissa.len() == 16).then_some(decimal)
+    }
+
+    /// Reads a mantissa in big-endian two's complement, of at most 16 bytes, as written
+    /// in the Avro `decimal` logical type.
+    pub(crate) fn from_be_bytes(mantissa: &[u8], scale: u8) -> Option<Decimal> {
+        if mantissa.len() > 16 || scale > MAX_SCALE {
+            return None;
+        }
+        let sign = match mantissa.first() {
+            Some(first) if first & 0x80 != 0 => 0xff,
+            _ => 0,
+        };
+        let mut bytes = [sign; 16];
+        bytes[16 - mantissa.len()..].copy_from_slice(mantissa);
+        Some(Decimal {
+            mantissa: i128::from_be_bytes(bytes),
+            scale,
+        })
+    }
+
+    /// Returns the bytes the decimal is tuple-encoded as in keys, see the module
+    /// documentation.
+    pub(crate) fn key(&self) -> Vec<u8> {
+        let Decimal { mantissa, scale } = self.normalize();
+        if mantissa == 0 {
+            return vec![ZERO];
+        }
+        let mut digits = mantissa.unsigned_abs().to_string().into_bytes();
+        // the decimal is 0.d1d2... × 10^exponent, which fits a byte for 39 digits at most
+        let exponent = (digits.len() as i16 - i16::from(scale) + 128) as u8;
+        while digits.last() == Some(&b'0') {
+            digits.pop();
+        }
+        let digits = digits.iter().map(|digit| digit - b'0');
+        let mut key = Vec::with_capacity(digits.len() + 3);
+        if mantissa > 0 {
+            key.extend([POSITIVE, exponent]);
+            key.extend(digits);
+        } else {
+            key.extend([NEGATIVE, !exponent]);
+            key.extend(digits.map(|digit| 9 - digit));
+            key.push(NEGATIVE_END);
+        }
+        key
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal {
+            mantissa: value.into(),
+            scale: 0,
+        }
+    }
+}
+
+/// Parses a decimal written with an optional sign and an optional decimal point, keeping
+/// its digits after the decimal point as its scale, e.g. `-12.50`.
+impl FromStr for Decimal {
+    type Err = SqlLayerError;
+
+    fn from_str(text: &str) -> crate::errors::Result<Self> {
+        let invalid = || SqlLayerError::InvalidDecimal(text.to_string());
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|byte| byte.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let scale = u8::try_from(fraction.len())
+            .ok()
+            .filter(|scale| *scale <= MAX_SCALE)
+            .ok_or_else(invalid)?;
+        let mut mantissa = format!("{integer}{fraction}")
+            .parse::<i128>()
+            .map_err(|_| invalid())?;
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(Decimal { mantissa, scale })
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = usize::from(self.scale);
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decimal::Decimal;
+    use crate::errors::SqlLayerError;
+    use foundationdb_tuple::pack;
+
+    fn decimal(text: &str) -> Decimal {
+        text.parse().expect("Invalid decimal")
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for text in ["12.50", "-0.05", "0", "7", "-123456789.123456789"] {
+            assert_eq!(decimal(text).to_string(), text);
+        }
+        assert_eq!(decimal("+.5").to_string(), "0.5");
+        let parsed = decimal("-12.50");
+        assert_eq!((parsed.mantissa(), parsed.scale()), (-1250, 2));
+        for invalid in ["", ".", "1.2.3", "1e5", "abc", "--1"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Decimal>(),
+                    Err(SqlLayerError::InvalidDecimal(_))
+                ),
+                "{invalid}"
+            );
+        }
+        assert!(Decimal::new(1, 39).is_err());
+    }
+
+    #[test]
+    fn test_rescale() {
+        assert_eq!(decimal("1.5").rescale(3).unwrap().mantissa(), 1500);
+        assert_eq!(decimal("1.500").rescale(1).unwrap().mantissa(), 15);
+        assert_eq!(decimal("1.55").rescale(1), None);
+        assert_eq!(Decimal::new(i128::MAX, 0).unwrap().rescale(1), None);
+        assert_eq!(decimal("-1.500").normalize().to_string(), "-1.5");
+    }
+
+    #[test]
+    fn test_order() {
+        let ordered = [
+            "-1000", "-40.5", "-40", "-4.05", "-4", "-0.5", "-0.05", "0", "0.05", "0.5", "4",
+            "4.05", "40", "40.5", "1000",
+        ]
+        .map(decimal);
+        for pair in ordered.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+            assert!(pack(&pair[0].key()) < pack(&pair[1].key()));
+        }
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert_eq!(decimal("1.50").key(), decimal("1.5").key());
+        assert_eq!(decimal("-0.00"), decimal("0"));
+    }
+
+    #[test]
+    fn test_stored_bytes() {
+        let value = decimal("-12.50");
+        let bytes = value.to_stored();
+        assert_eq!(bytes.len(), 17);
+        let stored = Decimal::from_stored(&bytes).unwrap();
+        assert_eq!((stored.mantissa(), stored.scale()), (-1250, 2));
+        assert_eq!(Decimal::from_stored(&bytes[1..]), None);
+
+        let read = Decimal::from_be_bytes(&[0xfb, 0x1e], 2).unwrap();
+        assert_eq!((read.mantissa(), read.scale()), (-1250, 2));
+    }
+}
@@ -0,0 +1,201 @@
+//! # Deserialize Module
+//!
+//! Maps records into user types implementing `serde::Deserialize`. A record is seen as a
+//! map from the names of the fields of its table to its columns, so that a struct reads
+//! the columns named after its fields, whatever their position in the table:
+//!
+//! - Columns are deserialized from their natural type: strings, 64-bit integers and
+//!   floats, booleans and bytes. Integers deserialize into narrower types as long as the
+//!   value fits.
+//! - A null column deserializes into `None`, and a field missing from the struct is
+//!   ignored.
+//! - Bytes deserialize into `Vec<u8>` as well as into byte buffers, strings into unit
+//!   enum variants, and arrays into sequences of their elements.
+
+use crate::errors::SqlLayerError;
+use crate::record::{Column, Record};
+use crate::table::Table;
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserializer};
+use std::iter::zip;
+
+/// Deserializes a record of a table, holding every field of the table in order.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::Deserialization` if the record doesn't match the type, e.g. a
+/// field of the type isn't a field of the table, or a column has another type.
+pub(crate) fn from_record<T: DeserializeOwned>(
+    table: &Table,
+    record: Record,
+) -> crate::errors::Result<T> {
+    let entries = zip(&table.fields, record.columns)
+        .map(|(field, column)| (field.name.as_str(), ColumnDeserializer(column)));
+    T::deserialize(MapDeserializer::new(entries))
+        .map_err(|err: Error| SqlLayerError::Deserialization(err.to_string()))
+}
+
+/// Deserializes a single column.
+struct ColumnDeserializer(Column);
+
+impl IntoDeserializer<'_, Error> for ColumnDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for ColumnDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Column::String(value) => visitor.visit_string(value),
+            Column::Int(value) => visitor.visit_i64(value),
+            Column::UInt64(value) => visitor.visit_u64(value),
+            Column::Float(value) => visitor.visit_f64(value),
+            Column::Bool(value) => visitor.visit_bool(value),
+            Column::Bytes(value) => visitor.visit_byte_buf(value),
+            Column::Date(value) => visitor.visit_i32(value),
+            Column::Time(value) | Column::Timestamp(value) => visitor.visit_i64(value),
+            Column::Uuid(value) => visitor.visit_bytes(&value),
+            Column::Decimal(value) => visitor.visit_string(value.to_string()),
+            Column::Array(values) => visitor.visit_seq(SeqDeserializer::new(
+                values.into_iter().map(ColumnDeserializer),
+            )),
+            Column::Null => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Column::Null => visitor.visit_none(),
+            column => visitor.visit_some(ColumnDeserializer(column)),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Column::Bytes(value) => visitor.visit_seq(SeqDeserializer::new(value.into_iter())),
+            column => ColumnDeserializer(column).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Column::String(value) => value
+                .into_deserializer()
+                .deserialize_enum(name, variants, visitor),
+            column => ColumnDeserializer(column).deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deserialize::from_record;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, Record};
+    use crate::table::{Field, FieldType, Table};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Status {
+        Single,
+        Married,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        age: i32,
+        name: String,
+        height: Option<f64>,
+        status: Status,
+        photo: Vec<u8>,
+        tags: Vec<String>,
+    }
+
+    fn table() -> Table {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("status".to_string(), FieldType::String));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        table.add_field(Field::new("is_active".to_string(), FieldType::Bool));
+        table.add_field(Field::new_array("tags".to_string(), FieldType::String));
+        table
+    }
+
+    #[test]
+    fn test_from_record() {
+        let record = Record::new(vec![
+            Column::String("John".to_string()),
+            Column::Int(20),
+            Column::Null,
+            Column::String("Married".to_string()),
+            Column::Bytes(vec![1, 2, 3]),
+            Column::Bool(true),
+            Column::Array(vec![Column::String("admin".to_string())]),
+        ]);
+        let person = from_record::<Person>(&table(), record).expect("Unable to deserialize");
+        assert_eq!(
+            person,
+            Person {
+                age: 20,
+                name: "John".to_string(),
+                height: None,
+                status: Status::Married,
+                photo: vec![1, 2, 3],
+                tags: vec!["admin".to_string()],
+            }
+        );
+
+        // an integer doesn't fit a narrower type
+        let record = Record::new(vec![
+            Column::String("John".to_string()),
+            Column::Int(i64::MAX),
+            Column::Float(1.8),
+            Column::String("Single".to_string()),
+            Column::Bytes(vec![]),
+            Column::Bool(true),
+            Column::Array(vec![]),
+        ]);
+        assert!(matches!(
+            from_record::<Person>(&table(), record),
+            Err(SqlLayerError::Deserialization(_))
+        ));
+
+        // a field of the struct isn't a field of the table
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Unknown {
+            email: String,
+        }
+        let record = Record::new(vec![Column::Null; 7]);
+        assert!(matches!(
+            from_record::<Unknown>(&table(), record),
+            Err(SqlLayerError::Deserialization(_))
+        ));
+    }
+}
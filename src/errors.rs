@@ -1,29 +1,132 @@
-use foundationdb::FdbBindingError;
+use foundationdb::{FdbBindingError, FdbError};
+
+/// FoundationDB error code of a transaction delayed by the throttling of one of its tags.
+const TAG_THROTTLED: i32 = 1213;
 
 pub type Result<T> = std::result::Result<T, SqlLayerError>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SqlLayerError {
     #[error("FoundationDB error : {0}")]
-    Fdb(#[from] foundationdb::FdbBindingError),
+    Fdb(foundationdb::FdbBindingError),
     #[error("FoundationDB error : {0}")]
-    FdbError(#[from] foundationdb::FdbError),
+    FdbError(foundationdb::FdbError),
     #[error("Apache Avro error : {0}")]
     Avro(#[from] apache_avro::Error),
-    #[error("Missing column in primary key: {0}")]
+    #[error("Missing column: {0}")]
     MissingColumn(String),
-    #[error("Mismatched column type in primary key: {0} vs {1}")]
+    #[error("Mismatched column type: expected {0}, found {1}")]
     MismatchedColumnType(String, String),
     #[error("Table not found: {0}")]
     TableNotFound(String),
     #[error("Table already exists: {0}")]
     TableAlreadyExists(String),
+    #[error("Field already exists: {0}")]
+    FieldAlreadyExists(String),
+    #[error("Column {0} is used by {1}")]
+    ColumnInUse(String, String),
     #[error("Index not found: {0}")]
     IndexNotFound(String),
+    #[error("Index is not ready: {0}")]
+    IndexNotReady(String),
+    #[error("Index already exists: {0}")]
+    IndexAlreadyExists(String),
+    #[error("Unique constraint violation on index {index}: {values}")]
+    UniqueConstraintViolation { index: String, values: String },
+    #[error("Duplicate primary key: {0}")]
+    DuplicatePrimaryKey(String),
+    #[error("Invalid identifier: {0}")]
+    InvalidIdentifier(String),
+    #[error("Invalid SQL text: {0}")]
+    InvalidSqlText(String),
+    #[error("Unknown fields written by a newer schema: {0} trailing bytes")]
+    UnknownFields(usize),
+    #[error("Integer overflow: {0}")]
+    Overflow(String),
+    #[error("Invalid continuation token")]
+    InvalidContinuation,
+    #[error("Invalid page limit, a page holds at least one record")]
+    InvalidPageLimit,
+    #[error("Invalid transaction tag: {0}")]
+    InvalidTransactionTag(String),
+    #[error("Transaction throttled by the cluster because of its tags")]
+    TagThrottled,
+    #[error("Database is read-only, the cluster is locked")]
+    ReadOnly,
+    #[error("Invalid record: {0}")]
+    Validation(crate::validation::ValidationReport),
+    #[error("Not supported by the FoundationDB client or cluster: {0}")]
+    Unsupported(String),
+    #[error("Invalid plan snapshot, expected a plan line after: {0}")]
+    InvalidPlanSnapshot(String),
+    #[error("Result too large, the query read {0}: read it page by page with a paginated scan")]
+    ResultTooLarge(crate::limits::ResultLimit),
+    #[error("Unable to deserialize record: {0}")]
+    Deserialization(String),
+    #[error("Check constraint violated: {0}")]
+    ConstraintViolation(String),
+    #[error("Foreign key constraint violated: {0}")]
+    ForeignKeyViolation(String),
+    #[error("Invalid migration: {0}")]
+    InvalidMigration(String),
+    #[error("Row of table {0} written with an unknown schema: {1:016x}")]
+    UnknownRowSchema(String, u64),
+    #[error("Row codec error: {0}")]
+    Codec(String),
+    #[error("Invalid blob field: {0}")]
+    InvalidBlobField(String),
+    #[error("Invalid array field: {0}")]
+    InvalidArrayField(String),
+    #[error("Invalid enum field: {0}")]
+    InvalidEnumField(String),
+    #[error("Invalid collation: {0}")]
+    InvalidCollation(String),
+    #[error("Invalid primary key: {0}")]
+    InvalidPrimaryKey(String),
+    #[error("Invalid foreign key: {0}")]
+    InvalidForeignKey(String),
+    #[error("Invalid constraint: {0}")]
+    InvalidConstraint(String),
+    #[error("Invalid serial field: {0}")]
+    InvalidSerialField(String),
+    #[error("Blob of field {0} replaced or deleted while streamed")]
+    BlobChanged(String),
+    #[error("Invalid decimal: {0}")]
+    InvalidDecimal(String),
+}
+
+impl From<FdbBindingError> for SqlLayerError {
+    /// Errors raised by the layer inside a transaction closure are boxed into a
+    /// `FdbBindingError::CustomError`, unwrap them so callers get the original error back.
+    fn from(value: FdbBindingError) -> Self {
+        match value {
+            FdbBindingError::CustomError(error) => match error.downcast::<SqlLayerError>() {
+                Ok(error) => *error,
+                Err(error) => SqlLayerError::Fdb(FdbBindingError::CustomError(error)),
+            },
+            FdbBindingError::NonRetryableFdbError(error) => error.into(),
+            error => SqlLayerError::Fdb(error),
+        }
+    }
+}
+
+impl From<FdbError> for SqlLayerError {
+    fn from(value: FdbError) -> Self {
+        if value.code() == TAG_THROTTLED {
+            return SqlLayerError::TagThrottled;
+        }
+        SqlLayerError::FdbError(value)
+    }
 }
 
 impl From<SqlLayerError> for FdbBindingError {
+    /// FoundationDB errors are handed back unboxed, so that a transaction closure failing
+    /// on a retryable error is retried.
     fn from(value: SqlLayerError) -> Self {
-        FdbBindingError::CustomError(Box::new(value))
+        match value {
+            SqlLayerError::Fdb(error) => error,
+            SqlLayerError::FdbError(error) => error.into(),
+            error => FdbBindingError::CustomError(Box::new(error)),
+        }
     }
 }
@@ -1,9 +1,29 @@
+use crate::plan::Direction;
 use serde::{Deserialize, Serialize};
 
+/// Build state of an index.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexState {
+    /// The index is maintained by writes but existing rows are still being indexed.
+    Backfilling,
+    /// Every row of the table is indexed.
+    #[default]
+    Ready,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Index {
     name: String,
     fields: Vec<String>,
+    #[serde(default)]
+    unique: bool,
+    #[serde(default)]
+    state: IndexState,
+    #[serde(default)]
+    multi_entry: bool,
+    /// Directions of the indexed fields, in order, the fields past the end being ascending.
+    #[serde(default)]
+    directions: Vec<Direction>,
 }
 
 impl Index {
@@ -11,13 +31,83 @@ impl Index {
         Self {
             name: name.into(),
             fields: fields.into_iter().map(|f| f.into()).collect(),
+            unique: false,
+            state: IndexState::Ready,
+            multi_entry: false,
+            directions: vec![],
+        }
+    }
+
+    /// Creates an index over an array field holding an entry per distinct element of the
+    /// arrays rather than one per row, so that the rows whose array contains a value, see
+    /// `Predicate::Contains`, are read through the index. A row with an empty array has
+    /// no entry, a row with a null array a null entry.
+    pub fn new_multi_entry<S1: Into<String>, S2: Into<String>>(name: S1, field: S2) -> Self {
+        Self {
+            multi_entry: true,
+            ..Self::new(name, vec![field])
         }
     }
 
+    /// Creates an index rejecting two rows with the same indexed values.
+    pub fn new_unique<S1: Into<String>, S2: Into<String>>(name: S1, fields: Vec<S2>) -> Self {
+        Self {
+            unique: true,
+            ..Self::new(name, fields)
+        }
+    }
+
+    /// Orders the entries of the index by the fields in the given directions, so that a
+    /// forward read of the index returns e.g. the latest records first. The values of a
+    /// descending field are encoded complemented in the keys, see `KeyColumns`.
+    pub fn with_directions(self, directions: Vec<Direction>) -> Self {
+        Self { directions, ..self }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
     pub fn fields(&self) -> &Vec<String> {
         &self.fields
     }
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+    pub fn is_multi_entry(&self) -> bool {
+        self.multi_entry
+    }
+    pub fn directions(&self) -> &[Direction] {
+        &self.directions
+    }
+    /// Direction of the indexed field at the given position.
+    pub fn direction(&self, position: usize) -> Direction {
+        self.directions.get(position).copied().unwrap_or_default()
+    }
+    pub fn state(&self) -> IndexState {
+        self.state
+    }
+    pub fn set_state(&mut self, state: IndexState) {
+        self.state = state;
+    }
+    /// Renames an indexed field, leaving the index unchanged if it doesn't cover it.
+    pub(crate) fn rename_field(&mut self, from: &str, to: &str) {
+        for field in &mut self.fields {
+            if field == from {
+                *field = to.to_string();
+            }
+        }
+    }
+}
+
+/// Health report of an index, as returned by `Database::list_indexes`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndexStatus {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub unique: bool,
+    pub state: IndexState,
+    /// Size of the index entries in bytes, as estimated by FoundationDB, or `None` if the
+    /// cluster can't estimate range sizes.
+    pub approximate_size: Option<i64>,
+    pub entry_count: u64,
 }
@@ -1,8 +1,41 @@
+mod aggregate;
+mod codec;
+mod collation;
+mod compression;
+mod conversions;
 mod database;
+mod decimal;
+mod deserialize;
 mod errors;
 mod index;
+mod limits;
+mod migrations;
+mod plan;
+mod plan_snapshot;
+mod predicate;
+mod query;
 mod record;
 pub mod row;
+mod row_schema;
+mod schema;
+mod sort;
+mod sql;
 mod storage;
 mod table;
 mod table_metadata;
+mod temporal;
+mod validation;
+
+// lets the code generated by the derive macros name this crate from within
+extern crate self as sql_layer;
+
+pub use codec::{RowCodec, RowEncoding};
+pub use collation::Collation;
+pub use compression::RowCompression;
+pub use decimal::Decimal;
+pub use errors::{Result, SqlLayerError};
+pub use record::{Column, FromColumn, Record, SqlRecord};
+pub use table::{Field, FieldType, Table};
+
+#[cfg(feature = "derive")]
+pub use sql_layer_derive::SqlRecord;
@@ -0,0 +1,232 @@
+//! # Limits Module
+//!
+//! Guards against queries materializing more than a service can afford. The queries
+//! abort with a [`SqlLayerError::ResultTooLarge`] error as soon as they cross one of the
+//! configured [`ResultLimits`], rather than buffering an unbounded table into memory or
+//! streaming it for as long as it takes:
+//! - The streaming scans, `Database::scan_table`, `scan_where`, `scan_sorted`,
+//!   `scan_distinct` and `join`, end their stream with the error, see [`limit_records`].
+//! - The records buffered in memory, by the sorts of `scan_sorted` or the right table of a
+//!   nested loop join, are bounded as they are collected.
+//! - The `SELECT` statements run by `Database::execute` are bounded by the records they
+//!   return. `Database::aggregate` and the `DELETE` statements, which return no record,
+//!   are bounded by their duration alone, see [`ResultLimits::duration_only`].
+//!
+//! Large results should be read page by page with the paginated scans instead, which
+//! bound the records held at once whatever the size of the table.
+
+use crate::errors::SqlLayerError;
+use crate::record::{Column, Record};
+use futures::{pin_mut, Stream, StreamExt};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// Bounds on the results of a query, every limit being disabled by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResultLimits {
+    /// Maximum number of records returned.
+    pub max_rows: Option<usize>,
+    /// Maximum size of the records returned, as estimated by [`record_size`].
+    pub max_bytes: Option<usize>,
+    /// Maximum time spent reading the records, checked as records are read.
+    pub max_duration: Option<Duration>,
+}
+
+impl ResultLimits {
+    /// Keeps the duration limit alone, for the queries reading records they don't return,
+    /// such as aggregates: the rows and bytes limits bound returned records only.
+    pub fn duration_only(self) -> Self {
+        Self {
+            max_duration: self.max_duration,
+            ..Self::default()
+        }
+    }
+}
+
+/// The limit crossed by a query, along with its configured value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResultLimit {
+    Rows(usize),
+    Bytes(usize),
+    Duration(Duration),
+}
+
+impl Display for ResultLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultLimit::Rows(rows) => write!(f, "more than {rows} rows"),
+            ResultLimit::Bytes(bytes) => write!(f, "more than {bytes} bytes"),
+            ResultLimit::Duration(duration) => write!(f, "for longer than {duration:?}"),
+        }
+    }
+}
+
+/// Tracks the records read by a query against its limits.
+pub struct ResultGuard {
+    limits: ResultLimits,
+    rows: usize,
+    bytes: usize,
+    started: Instant,
+}
+
+impl ResultGuard {
+    /// Starts tracking a query, its duration being measured from now.
+    pub fn new(limits: ResultLimits) -> Self {
+        Self {
+            limits,
+            rows: 0,
+            bytes: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Accounts for a record read by the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::ResultTooLarge` if the record makes the query cross one of
+    /// its limits.
+    pub fn check(&mut self, record: &Record) -> crate::errors::Result<()> {
+        self.rows += 1;
+        self.bytes += record_size(record);
+        if let Some(max_rows) = self.limits.max_rows {
+            if self.rows > max_rows {
+                return Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(max_rows)));
+            }
+        }
+        if let Some(max_bytes) = self.limits.max_bytes {
+            if self.bytes > max_bytes {
+                return Err(SqlLayerError::ResultTooLarge(ResultLimit::Bytes(max_bytes)));
+            }
+        }
+        if let Some(max_duration) = self.limits.max_duration {
+            if self.started.elapsed() > max_duration {
+                return Err(SqlLayerError::ResultTooLarge(ResultLimit::Duration(
+                    max_duration,
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bounds a stream of records by the limits: the stream ends with a
+/// `SqlLayerError::ResultTooLarge` error as soon as a record crosses one of them, see
+/// [`ResultGuard::check`]. The duration is measured from the call.
+pub(crate) fn limit_records<S>(
+    limits: ResultLimits,
+    records: S,
+) -> impl Stream<Item = crate::errors::Result<Record>>
+where
+    S: Stream<Item = crate::errors::Result<Record>>,
+{
+    let mut guard = ResultGuard::new(limits);
+    async_stream::try_stream! {
+        pin_mut!(records);
+        while let Some(record) = records.next().await {
+            let record = record?;
+            guard.check(&record)?;
+            yield record;
+        }
+    }
+}
+
+/// Estimates the memory held by a record: the size of its scalar columns, plus the length
+/// of its strings and bytes, and the size of the elements of its arrays.
+pub fn record_size(record: &Record) -> usize {
+    record.columns.iter().map(column_size).sum()
+}
+
+fn column_size(column: &Column) -> usize {
+    match column {
+        Column::String(value) => value.len(),
+        Column::Bytes(value) => value.len(),
+        Column::Int(_)
+        | Column::UInt64(_)
+        | Column::Float(_)
+        | Column::Time(_)
+        | Column::Timestamp(_) => 8,
+        Column::Date(_) => 4,
+        Column::Uuid(_) | Column::Decimal(_) => 16,
+        Column::Array(values) => values.iter().map(column_size).sum(),
+        Column::Bool(_) | Column::Null => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::limits::{limit_records, record_size, ResultGuard, ResultLimit, ResultLimits};
+    use crate::record::{Column, Record};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    fn record(name: &str) -> Record {
+        Record::new(vec![Column::String(name.to_string()), Column::Int(20)])
+    }
+
+    #[test]
+    fn test_result_guard() {
+        assert_eq!(record_size(&record("John")), 12);
+
+        let mut guard = ResultGuard::new(ResultLimits::default());
+        for _ in 0..1000 {
+            guard.check(&record("John")).unwrap();
+        }
+
+        let mut guard = ResultGuard::new(ResultLimits {
+            max_rows: Some(2),
+            ..Default::default()
+        });
+        guard.check(&record("John")).unwrap();
+        guard.check(&record("Jane")).unwrap();
+        assert!(matches!(
+            guard.check(&record("Bob")),
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+
+        let mut guard = ResultGuard::new(ResultLimits {
+            max_bytes: Some(20),
+            ..Default::default()
+        });
+        guard.check(&record("John")).unwrap();
+        assert!(matches!(
+            guard.check(&record("Jane")),
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Bytes(20)))
+        ));
+
+        let mut guard = ResultGuard::new(ResultLimits {
+            max_duration: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(
+            guard.check(&record("John")),
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Duration(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_limit_records() {
+        let limits = ResultLimits {
+            max_rows: Some(2),
+            ..Default::default()
+        };
+        let records =
+            futures::stream::iter(["John", "Jane", "Bob", "Alice"].map(|name| Ok(record(name))));
+        let limited = limit_records(limits, records).collect::<Vec<_>>().await;
+        assert_eq!(limited.len(), 3);
+        assert!(limited[..2].iter().all(|record| record.is_ok()));
+        assert!(matches!(
+            limited[2],
+            Err(SqlLayerError::ResultTooLarge(ResultLimit::Rows(2)))
+        ));
+
+        let records =
+            futures::stream::iter(["John", "Jane", "Bob", "Alice"].map(|name| Ok(record(name))));
+        let limited = limit_records(limits.duration_only(), records)
+            .collect::<Vec<_>>()
+            .await;
+        assert!(limited.len() == 4 && limited.iter().all(|record| record.is_ok()));
+    }
+}
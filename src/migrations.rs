@@ -0,0 +1,158 @@
+//! # Migrations Module
+//!
+//! Ordered schema changes, registered by an application and applied to a database by
+//! `Database::migrate`. Every migration has a version, greater than the versions of the
+//! migrations registered before it, and steps:
+//!
+//! - `AddColumn` adds a field to a table, the rows already stored reading the given default.
+//! - `AddIndex` adds an index to a table, built from the rows already stored.
+//! - `Backfill` rewrites every record of a table with a function, e.g. to fill a column
+//!   added by a previous step.
+//!
+//! The database records the version of the last migration applied, its schema version,
+//! along with the description of every migration applied. A migration is applied in a
+//! single transaction along with this record, so it is applied entirely or not at all, and
+//! exactly once however many instances of the application run the migrations at the same
+//! time.
+
+use crate::errors::SqlLayerError;
+use crate::index::Index;
+use crate::record::{Column, Record};
+use crate::table::Field;
+use std::sync::Arc;
+
+/// Rewrites a record of the table of a `Backfill` step.
+pub type Backfill = Arc<dyn Fn(Record) -> crate::errors::Result<Record> + Send + Sync>;
+
+/// A schema change of a migration.
+#[derive(Clone)]
+pub enum MigrationStep {
+    AddColumn {
+        table: String,
+        field: Field,
+        /// The value of the field in the rows already stored, see `Database::add_column`.
+        default: Column,
+    },
+    AddIndex {
+        table: String,
+        index: Index,
+    },
+    Backfill {
+        table: String,
+        update: Backfill,
+    },
+}
+
+impl MigrationStep {
+    /// Creates a step rewriting every record of a table with the given function. Records
+    /// left unchanged are not written.
+    pub fn backfill<S: Into<String>>(
+        table: S,
+        update: impl Fn(Record) -> crate::errors::Result<Record> + Send + Sync + 'static,
+    ) -> Self {
+        MigrationStep::Backfill {
+            table: table.into(),
+            update: Arc::new(update),
+        }
+    }
+}
+
+/// A versioned set of schema changes, applied together.
+#[derive(Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub description: String,
+    pub steps: Vec<MigrationStep>,
+}
+
+impl Migration {
+    pub fn new<S: Into<String>>(version: i64, description: S, steps: Vec<MigrationStep>) -> Self {
+        Self {
+            version,
+            description: description.into(),
+            steps,
+        }
+    }
+}
+
+/// The migrations of an application, in the order of their versions.
+#[derive(Clone, Default)]
+pub struct Migrations {
+    migrations: Vec<Migration>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the next migration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidMigration` if the version of the migration isn't
+    /// positive, or isn't greater than the version of the last migration registered.
+    pub fn register(&mut self, migration: Migration) -> crate::errors::Result<()> {
+        let last = self.migrations.last().map_or(0, |last| last.version);
+        if migration.version <= last {
+            return Err(SqlLayerError::InvalidMigration(format!(
+                "version {} must be greater than {last}",
+                migration.version
+            )));
+        }
+        self.migrations.push(migration);
+        Ok(())
+    }
+
+    /// Iterates over the migrations of a version greater than the given one, in order.
+    pub(crate) fn pending(&self, version: i64) -> impl Iterator<Item = &Migration> {
+        self.migrations
+            .iter()
+            .filter(move |migration| migration.version > version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::migrations::{Migration, MigrationStep, Migrations};
+    use crate::record::Column;
+    use crate::table::{Field, FieldType};
+
+    #[test]
+    fn test_register() {
+        let mut migrations = Migrations::new();
+        let add_email = MigrationStep::AddColumn {
+            table: "Person".to_string(),
+            field: Field::new("email".to_string(), FieldType::String),
+            default: Column::Null,
+        };
+        migrations
+            .register(Migration::new(1, "add email", vec![add_email]))
+            .expect("Unable to register migration");
+        migrations
+            .register(Migration::new(
+                3,
+                "backfill emails",
+                vec![MigrationStep::backfill("Person", Ok)],
+            ))
+            .expect("Unable to register migration");
+        for version in [0, 3] {
+            assert!(matches!(
+                migrations.register(Migration::new(version, "again", vec![])),
+                Err(SqlLayerError::InvalidMigration(_))
+            ));
+        }
+
+        let pending = |version| {
+            migrations
+                .pending(version)
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pending(0), vec![1, 3]);
+        assert_eq!(pending(1), vec![3]);
+        assert_eq!(pending(2), vec![3]);
+        assert_eq!(pending(3), Vec::<i64>::new());
+    }
+}
@@ -0,0 +1,958 @@
+//! # Plan Module
+//!
+//! The query planner chooses how the records matching a [`Predicate`] are read from a
+//! table. Only the top-level conjunction of the predicate is considered:
+//!
+//! - `PrimaryKeyLookup` when every primary key column is compared for equality, none
+//!   being collated.
+//! - `PrimaryKeyLookups` when every primary key column is compared for equality or to an
+//!   `IN` list: the keys are read with concurrent point lookups.
+//! - `IndexRangeScan` when a ready secondary index has its leading columns compared for
+//!   equality, optionally followed by a range on the next column. The index constraining
+//!   the most columns wins. A `LIKE 'prefix%'` on a string column is the range of the
+//!   strings starting with the prefix. The range is in the order of the index keys, its
+//!   bounds being swapped on a descending column. The column of a multi-entry index is
+//!   instead constrained by a `Contains`, whose value is the one looked up in the index.
+//! - `IndexLookups` when no index has a leading column compared for equality, but one has
+//!   it compared to an `IN` list: the entries of every value are read concurrently.
+//! - `IndexIntersection` when the best index and another one constraining other columns
+//!   both have every column compared for equality: the entries of such a range are
+//!   ordered by row id, so both ranges are intersected as they are scanned and only the
+//!   rows referenced by both are read.
+//! - `IndexUnion` when no index applies to the conjunction but one of its terms is a
+//!   disjunction whose every branch can be read through an index: the ranges are scanned
+//!   and the rows referenced by any of them are read once. Ranges of a single index are
+//!   merged in the order of the index.
+//! - `TableScan` otherwise.
+//!
+//! The values bounding the collated columns of an index are collated, as its keys are, see
+//! [`Collation`].
+//!
+//! Whatever the plan, the whole predicate is still evaluated on every record read, the
+//! plan only narrows down which rows are read.
+//!
+//! When records are requested in a given order, [`Plan::ordered`] tells whether the plan,
+//! or a traversal of the primary key or of an index covering the sort keys in their
+//! directions, reads them in that order. Otherwise they are sorted once read.
+//!
+//! Mutations are planned from the table definition alone, see [`WritePath`].
+
+use crate::collation::Collation;
+use crate::index::{Index, IndexState};
+use crate::predicate::Predicate;
+use crate::record::Column;
+use crate::table::Table;
+use serde::{Deserialize, Serialize};
+use std::ops::Bound;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Plan {
+    /// Every row of the table is read.
+    TableScan,
+    /// The primary key is fully known, at most one row is read.
+    PrimaryKeyLookup { pk: Vec<Column> },
+    /// Several primary keys are fully known, their rows are read concurrently.
+    PrimaryKeyLookups { pks: Vec<Vec<Column>> },
+    /// The rows referenced by a range of a secondary index are read.
+    IndexRangeScan(IndexRange),
+    /// The rows referenced by every range, each ordered by row id, are read.
+    IndexIntersection(Vec<IndexRange>),
+    /// The rows referenced by any of the ranges are read.
+    IndexUnion(Vec<IndexRange>),
+    /// The rows referenced by the entries of an index whose leading column is one of the
+    /// values are read, the entries of every value concurrently.
+    IndexLookups { index: String, values: Vec<Column> },
+    /// Every row of the table is read in primary key order.
+    PrimaryKeyScan,
+}
+
+/// How a mutation maintains what is derived from the rows of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePath {
+    /// The table has no secondary index, so nothing but the primary key constrains a row:
+    /// a row is overwritten without reading the version it replaces, only its primary key
+    /// entry is read for its row id.
+    Blind,
+    /// The previous version of a row is read to clear its index entries and enforce the
+    /// unique indexes.
+    Maintained,
+}
+
+impl WritePath {
+    /// Chooses the write path of the table.
+    pub fn new(table: &Table) -> Self {
+        if table.indexes.is_empty() {
+            WritePath::Blind
+        } else {
+            WritePath::Maintained
+        }
+    }
+}
+
+/// Direction of a sort key, or of a column of the primary key or of an index, whose keys
+/// are then read in that order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    /// Nulls first, then increasing values.
+    #[default]
+    Ascending,
+    /// Decreasing values, then nulls.
+    Descending,
+}
+
+/// A column records are sorted by, as in `ORDER BY field DESC`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub field: String,
+    pub direction: Direction,
+}
+
+impl SortKey {
+    pub fn asc<S: Into<String>>(field: S) -> Self {
+        Self {
+            field: field.into(),
+            direction: Direction::Ascending,
+        }
+    }
+
+    pub fn desc<S: Into<String>>(field: S) -> Self {
+        Self {
+            field: field.into(),
+            direction: Direction::Descending,
+        }
+    }
+}
+
+/// Bounds over the leading columns of a secondary index, in the order of its keys: on a
+/// descending column, the start bounds the largest values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRange {
+    pub index: String,
+    pub start: Bound<Vec<Column>>,
+    pub end: Bound<Vec<Column>>,
+}
+
+/// An index usable by a plan, along with how well it narrows the rows read.
+struct Candidate<'a> {
+    /// The number of columns compared for equality, then whether the next one is bounded.
+    score: (usize, bool),
+    /// The constrained columns.
+    fields: &'a [String],
+    /// Whether the index is unique and every column is compared for equality.
+    exact: bool,
+    /// Whether every column is compared for equality, the entries of the range then being
+    /// ordered by row id.
+    ordered: bool,
+    range: IndexRange,
+}
+
+impl Plan {
+    /// Chooses the access path to the records of the table matching the predicate.
+    ///
+    /// The predicate is expected to have been validated against the table.
+    pub fn new(table: &Table, predicate: &Predicate) -> Self {
+        let conjuncts = conjuncts(predicate);
+
+        // the primary key is binary, it can't look up the strings matching a collated one
+        let pk = table
+            .primary_key
+            .iter()
+            .map(|field| match equality(&conjuncts, field) {
+                _ if table.collation(field) != Collation::Binary => None,
+                Some(value) => Some(vec![value.clone()]),
+                None => in_values(&conjuncts, field),
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(pk) = pk {
+            if pk.iter().all(|values| values.len() == 1) {
+                let pk = pk.into_iter().flatten().collect();
+                return Plan::PrimaryKeyLookup { pk };
+            }
+            return Plan::PrimaryKeyLookups { pks: product(pk) };
+        }
+
+        let candidates = table
+            .indexes
+            .iter()
+            .filter_map(|index| candidate(table, &conjuncts, index))
+            .collect::<Vec<_>>();
+        let first = best(candidates.iter());
+        // point lookups of a few values narrow down the rows better than a range
+        let lookups = first
+            .is_none_or(|first| first.score.0 == 0)
+            .then(|| {
+                table
+                    .indexes
+                    .iter()
+                    .find_map(|index| index_lookups(table, &conjuncts, index))
+            })
+            .flatten();
+        if let Some(lookups) = lookups {
+            return lookups;
+        }
+        let Some(first) = first else {
+            return conjuncts
+                .iter()
+                .find_map(|conjunct| union(table, conjunct))
+                .unwrap_or(Plan::TableScan);
+        };
+        // a unique index matched exactly references at most one row, and only ranges
+        // ordered by row id are intersected
+        let second = best(candidates.iter().filter(|candidate| {
+            !first.exact
+                && first.ordered
+                && candidate.ordered
+                && !candidate
+                    .fields
+                    .iter()
+                    .any(|field| first.fields.contains(field))
+        }));
+        match second {
+            Some(second) => {
+                Plan::IndexIntersection(vec![first.range.clone(), second.range.clone()])
+            }
+            None => Plan::IndexRangeScan(first.range.clone()),
+        }
+    }
+
+    /// Adapts the plan so that records are read ordered by the sort keys.
+    ///
+    /// Keys are only read forward, so only sort keys being the leading columns of the
+    /// primary key or of a ready index, in the directions of these columns, can be provided
+    /// by a traversal, multi-entry indexes being ordered by the elements of the arrays
+    /// rather than by the arrays, and indexes by the collated strings of collated columns
+    /// rather than by the strings. A table scan is then turned into a traversal of the
+    /// primary key or of the index, while other plans are kept as long as they already read
+    /// records in that order.
+    ///
+    /// # Returns
+    ///
+    /// Returns the plan reading records in order, or `None` if the records read by the plan
+    /// must be sorted.
+    pub fn ordered(self, table: &Table, order_by: &[SortKey]) -> Option<Plan> {
+        let covers = |columns: &[String], direction: &dyn Fn(usize) -> Direction| {
+            order_by
+                .iter()
+                .enumerate()
+                .all(|(i, key)| columns.get(i) == Some(&key.field) && direction(i) == key.direction)
+        };
+        let pk_covers = || covers(&table.primary_key, &|i| table.primary_key_direction(i));
+        let collated = order_by
+            .iter()
+            .any(|key| table.collation(&key.field) != Collation::Binary);
+        let index_covers = |index: &Index| {
+            !index.is_multi_entry() && !collated && covers(index.fields(), &|i| index.direction(i))
+        };
+        if order_by.is_empty() || matches!(self, Plan::PrimaryKeyLookup { .. }) {
+            return Some(self);
+        }
+        let ordered = match &self {
+            Plan::PrimaryKeyScan => pk_covers(),
+            Plan::IndexRangeScan(range) => table.get_index(&range.index).is_some_and(index_covers),
+            Plan::IndexUnion(ranges) => common_index(ranges)
+                .and_then(|index| table.get_index(index))
+                .is_some_and(index_covers),
+            _ => false,
+        };
+        if ordered {
+            return Some(self);
+        }
+        match self {
+            Plan::TableScan if pk_covers() => Some(Plan::PrimaryKeyScan),
+            Plan::TableScan => table
+                .indexes
+                .iter()
+                .find(|index| index.state() == IndexState::Ready && index_covers(index))
+                .map(|index| {
+                    Plan::IndexRangeScan(IndexRange {
+                        index: index.name().to_string(),
+                        start: Bound::Unbounded,
+                        end: Bound::Unbounded,
+                    })
+                }),
+            _ => None,
+        }
+    }
+}
+
+/// The index of every range, if they all share one.
+pub fn common_index(ranges: &[IndexRange]) -> Option<&str> {
+    let (first, others) = ranges.split_first()?;
+    others
+        .iter()
+        .all(|range| range.index == first.index)
+        .then_some(first.index.as_str())
+}
+
+/// The candidate with the best score, the first one declared on ties.
+fn best<'a, 'b>(candidates: impl Iterator<Item = &'b Candidate<'a>>) -> Option<&'b Candidate<'a>> {
+    candidates.fold(None, |best, candidate| match best {
+        Some(best) if best.score >= candidate.score => Some(best),
+        _ => Some(candidate),
+    })
+}
+
+/// Bounds the leading columns of a ready index with the conjuncts, if any applies.
+fn candidate<'a>(
+    table: &Table,
+    conjuncts: &[&Predicate],
+    index: &'a Index,
+) -> Option<Candidate<'a>> {
+    if index.state() != IndexState::Ready {
+        return None;
+    }
+    let prefix = index
+        .fields()
+        .iter()
+        .map_while(|field| {
+            let value = if index.is_multi_entry() {
+                contained(conjuncts, field)
+            } else {
+                equality(conjuncts, field)
+            }?;
+            Some(table.collation(field).column(value).into_owned())
+        })
+        .collect::<Vec<_>>();
+    let (lower, upper) = match index.fields().get(prefix.len()) {
+        Some(field) if !index.is_multi_entry() => {
+            let collation = table.collation(field);
+            let (lower, upper) = (
+                lower_bound(conjuncts, field, collation),
+                upper_bound(conjuncts, field, collation),
+            );
+            match index.direction(prefix.len()) {
+                Direction::Ascending => (lower, upper),
+                Direction::Descending => (upper, lower),
+            }
+        }
+        _ => (None, None),
+    };
+    let bounded = lower.is_some() || upper.is_some();
+    if prefix.is_empty() && !bounded {
+        return None;
+    }
+    let constrained = prefix.len() + usize::from(bounded);
+    Some(Candidate {
+        score: (prefix.len(), bounded),
+        fields: &index.fields()[..constrained],
+        exact: index.is_unique() && prefix.len() == index.fields().len(),
+        ordered: prefix.len() == index.fields().len(),
+        range: IndexRange {
+            index: index.name().to_string(),
+            start: bound(&prefix, lower),
+            end: bound(&prefix, upper),
+        },
+    })
+}
+
+/// Looks up the values of an `IN` list on the leading column of a ready index, if any.
+fn index_lookups(table: &Table, conjuncts: &[&Predicate], index: &Index) -> Option<Plan> {
+    if index.state() != IndexState::Ready || index.is_multi_entry() {
+        return None;
+    }
+    let field = index.fields().first()?;
+    let collation = table.collation(field);
+    // distinct values may collate to the same key
+    let mut values = Vec::<Column>::new();
+    for value in in_values(conjuncts, field)? {
+        let value = collation.column(&value).into_owned();
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    Some(Plan::IndexLookups {
+        index: index.name().to_string(),
+        values,
+    })
+}
+
+/// Builds every combination of one value per column, in order.
+fn product(columns: Vec<Vec<Column>>) -> Vec<Vec<Column>> {
+    columns.into_iter().fold(vec![vec![]], |keys, values| {
+        keys.iter()
+            .flat_map(|key| {
+                values.iter().map(move |value| {
+                    let mut key = key.clone();
+                    key.push(value.clone());
+                    key
+                })
+            })
+            .collect()
+    })
+}
+
+/// Reads each branch of a disjunction through its best index, if they all have one.
+fn union(table: &Table, predicate: &Predicate) -> Option<Plan> {
+    let disjuncts = disjuncts(predicate);
+    if disjuncts.len() < 2 {
+        return None;
+    }
+    disjuncts
+        .iter()
+        .map(|disjunct| {
+            let conjuncts = conjuncts(disjunct);
+            let candidates = table
+                .indexes
+                .iter()
+                .filter_map(|index| candidate(table, &conjuncts, index))
+                .collect::<Vec<_>>();
+            best(candidates.iter()).map(|candidate| candidate.range.clone())
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(Plan::IndexUnion)
+}
+
+/// Flattens the top-level disjunction of the predicate.
+fn disjuncts(predicate: &Predicate) -> Vec<&Predicate> {
+    match predicate {
+        Predicate::Or(left, right) => {
+            let mut disjuncts = disjuncts(left);
+            disjuncts.extend(disjuncts(right));
+            disjuncts
+        }
+        predicate => vec![predicate],
+    }
+}
+
+/// Flattens the top-level conjunction of the predicate.
+fn conjuncts(predicate: &Predicate) -> Vec<&Predicate> {
+    match predicate {
+        Predicate::And(left, right) => {
+            let mut conjuncts = conjuncts(left);
+            conjuncts.extend(conjuncts(right));
+            conjuncts
+        }
+        predicate => vec![predicate],
+    }
+}
+
+/// The value a field is compared for equality with, nulls never match so are ignored.
+fn equality<'a>(conjuncts: &[&'a Predicate], field: &str) -> Option<&'a Column> {
+    conjuncts.iter().find_map(|predicate| match predicate {
+        Predicate::Eq(name, value) if name == field && *value != Column::Null => Some(value),
+        _ => None,
+    })
+}
+
+/// The value an array field is required to contain, nulls never match so are ignored.
+fn contained<'a>(conjuncts: &[&'a Predicate], field: &str) -> Option<&'a Column> {
+    conjuncts.iter().find_map(|predicate| match predicate {
+        Predicate::Contains(name, value) if name == field && *value != Column::Null => Some(value),
+        _ => None,
+    })
+}
+
+/// The distinct values of the `IN` list a field is compared to, nulls never match so are
+/// ignored.
+fn in_values(conjuncts: &[&Predicate], field: &str) -> Option<Vec<Column>> {
+    conjuncts.iter().find_map(|predicate| match predicate {
+        Predicate::In(name, values) if name == field => {
+            let mut distinct = Vec::<Column>::new();
+            for value in values {
+                if *value != Column::Null && !distinct.contains(value) {
+                    distinct.push(value.clone());
+                }
+            }
+            Some(distinct)
+        }
+        _ => None,
+    })
+}
+
+fn lower_bound(
+    conjuncts: &[&Predicate],
+    field: &str,
+    collation: Collation,
+) -> Option<Bound<Column>> {
+    let collate = |value: &Column| collation.column(value).into_owned();
+    conjuncts.iter().find_map(|predicate| match predicate {
+        Predicate::Gt(name, value) if name == field => Some(Bound::Excluded(collate(value))),
+        Predicate::Between(name, low, _) if name == field => Some(Bound::Included(collate(low))),
+        Predicate::LikePrefix(name, prefix) if name == field && !prefix.is_empty() => Some(
+            Bound::Included(Column::String(collation.apply(prefix).into_owned())),
+        ),
+        _ => None,
+    })
+}
+
+/// The upper bound of a field, the end of a prefix being computed from the collated prefix
+/// and not collated again.
+fn upper_bound(
+    conjuncts: &[&Predicate],
+    field: &str,
+    collation: Collation,
+) -> Option<Bound<Column>> {
+    let collate = |value: &Column| collation.column(value).into_owned();
+    conjuncts.iter().find_map(|predicate| match predicate {
+        Predicate::Lt(name, value) if name == field => Some(Bound::Excluded(collate(value))),
+        Predicate::Between(name, _, high) if name == field => Some(Bound::Included(collate(high))),
+        Predicate::LikePrefix(name, prefix) if name == field => {
+            prefix_end(&collation.apply(prefix)).map(|end| Bound::Excluded(Column::String(end)))
+        }
+        _ => None,
+    })
+}
+
+/// The smallest string greater than every string starting with the prefix.
+///
+/// Strings sort by code point, as their UTF-8 tuple encoding does, so the last character
+/// that can be incremented is. There is none for an empty prefix or one made of
+/// `char::MAX` only, every string above the prefix then starts with it.
+fn prefix_end(prefix: &str) -> Option<String> {
+    let mut chars = prefix.chars().collect::<Vec<_>>();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            '\u{d7ff}' => Some('\u{e000}'),
+            last => char::from_u32(last as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// Appends a bound on the next column to the equality prefix.
+///
+/// Without a bound, the prefix itself is included: every key starting with it matches.
+fn bound(prefix: &[Column], column: Option<Bound<Column>>) -> Bound<Vec<Column>> {
+    let with = |column: Column| {
+        let mut columns = prefix.to_vec();
+        columns.push(column);
+        columns
+    };
+    match column {
+        Some(Bound::Included(column)) => Bound::Included(with(column)),
+        Some(Bound::Excluded(column)) => Bound::Excluded(with(column)),
+        _ if prefix.is_empty() => Bound::Unbounded,
+        _ => Bound::Included(prefix.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collation::Collation;
+    use crate::index::{Index, IndexState};
+    use crate::plan::{prefix_end, Direction, IndexRange, Plan, SortKey, WritePath};
+    use crate::predicate::Predicate;
+    use crate::record::Column;
+    use crate::table::{Field, FieldType, Table};
+    use std::ops::Bound;
+
+    fn table() -> Table {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("city".to_string(), FieldType::String));
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        table.add_index(&Index::new("idx_city_age", vec!["city", "age"]));
+        table
+    }
+
+    #[test]
+    fn test_primary_key_lookup() {
+        let predicate = Predicate::eq("name", Column::String("John".to_string()))
+            .and(Predicate::gt("age", Column::Int(18)));
+        assert_eq!(
+            Plan::new(&table(), &predicate),
+            Plan::PrimaryKeyLookup {
+                pk: vec![Column::String("John".to_string())]
+            }
+        );
+    }
+
+    #[test]
+    fn test_index_range_scan() {
+        let table = table();
+
+        let predicate = Predicate::gt("age", Column::Int(18));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_age".to_string(),
+                start: Bound::Excluded(vec![Column::Int(18)]),
+                end: Bound::Unbounded,
+            })
+        );
+
+        // the composite index constrains more columns
+        let predicate = Predicate::between("age", Column::Int(18), Column::Int(30))
+            .and(Predicate::eq("city", Column::String("Paris".to_string())));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_city_age".to_string(),
+                start: Bound::Included(vec![Column::String("Paris".to_string()), Column::Int(18)]),
+                end: Bound::Included(vec![Column::String("Paris".to_string()), Column::Int(30)]),
+            })
+        );
+
+        let predicate = Predicate::eq("city", Column::String("Paris".to_string()));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_city_age".to_string(),
+                start: Bound::Included(vec![Column::String("Paris".to_string())]),
+                end: Bound::Included(vec![Column::String("Paris".to_string())]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_multi_entry_index() {
+        let mut table = table();
+        table.add_field(Field::new_array("tags".to_string(), FieldType::String));
+        table.add_index(&Index::new_multi_entry("idx_tags", "tags"));
+        let admin = Column::String("admin".to_string());
+
+        let predicate = Predicate::contains("tags", admin.clone());
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_tags".to_string(),
+                start: Bound::Included(vec![admin.clone()]),
+                end: Bound::Included(vec![admin.clone()]),
+            })
+        );
+
+        // the entries are elements, not arrays
+        let predicate = Predicate::eq("tags", Column::Array(vec![admin]));
+        assert_eq!(Plan::new(&table, &predicate), Plan::TableScan);
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("tags")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_index_intersection() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("city".to_string(), FieldType::String));
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        table.add_index(&Index::new("idx_city", vec!["city"]));
+
+        let predicate = Predicate::eq("age", Column::Int(18))
+            .and(Predicate::eq("city", Column::String("Paris".to_string())));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexIntersection(vec![
+                IndexRange {
+                    index: "idx_age".to_string(),
+                    start: Bound::Included(vec![Column::Int(18)]),
+                    end: Bound::Included(vec![Column::Int(18)]),
+                },
+                IndexRange {
+                    index: "idx_city".to_string(),
+                    start: Bound::Included(vec![Column::String("Paris".to_string())]),
+                    end: Bound::Included(vec![Column::String("Paris".to_string())]),
+                },
+            ])
+        );
+
+        // a range isn't ordered by row id, it isn't intersected
+        let predicate = Predicate::gt("age", Column::Int(18))
+            .and(Predicate::eq("city", Column::String("Paris".to_string())));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_city".to_string(),
+                start: Bound::Included(vec![Column::String("Paris".to_string())]),
+                end: Bound::Included(vec![Column::String("Paris".to_string())]),
+            })
+        );
+
+        // a unique index matched exactly is enough on its own
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("email".to_string(), FieldType::String));
+        table.add_index(&Index::new("idx_age", vec!["age"]));
+        table.add_index(&Index::new_unique("idx_email", vec!["email"]));
+        let predicate = Predicate::gt("age", Column::Int(18)).and(Predicate::eq(
+            "email",
+            Column::String("john@doe.com".to_string()),
+        ));
+        assert!(matches!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange { index, .. }) if index == "idx_email"
+        ));
+    }
+
+    #[test]
+    fn test_table_scan() {
+        let mut table = table();
+
+        // disjunctions with an unindexed branch and unindexed columns aren't planned
+        let predicate = Predicate::gt("age", Column::Int(18)).or(Predicate::is_null("city"));
+        assert_eq!(Plan::new(&table, &predicate), Plan::TableScan);
+        let predicate = Predicate::like_prefix("name", "J");
+        assert_eq!(Plan::new(&table, &predicate), Plan::TableScan);
+
+        // indexes being backfilled are skipped
+        table
+            .get_index_mut("idx_age")
+            .unwrap()
+            .set_state(IndexState::Backfilling);
+        let predicate = Predicate::gt("age", Column::Int(18));
+        assert_eq!(Plan::new(&table, &predicate), Plan::TableScan);
+    }
+
+    #[test]
+    fn test_ordered() {
+        let table = table();
+        let by_age = [SortKey::asc("age")];
+
+        // a table scan traverses the primary key or an index covering the sort keys
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("name")]),
+            Some(Plan::PrimaryKeyScan)
+        );
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &by_age),
+            Some(Plan::IndexRangeScan(IndexRange {
+                index: "idx_age".to_string(),
+                start: Bound::Unbounded,
+                end: Bound::Unbounded,
+            }))
+        );
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("city"), SortKey::asc("age")]),
+            Some(Plan::IndexRangeScan(IndexRange {
+                index: "idx_city_age".to_string(),
+                start: Bound::Unbounded,
+                end: Bound::Unbounded,
+            }))
+        );
+
+        // a range of the covering index is already ordered
+        let plan = Plan::new(&table, &Predicate::gt("age", Column::Int(18)));
+        assert_eq!(plan.clone().ordered(&table, &by_age), Some(plan));
+
+        // the ranges of a single index are merged in its order, unlike those of several
+        let plan = Plan::new(
+            &table,
+            &Predicate::lt("age", Column::Int(18)).or(Predicate::gt("age", Column::Int(60))),
+        );
+        assert!(matches!(plan, Plan::IndexUnion(_)));
+        assert_eq!(plan.clone().ordered(&table, &by_age), Some(plan));
+        let plan = Plan::new(
+            &table,
+            &Predicate::gt("age", Column::Int(60))
+                .or(Predicate::eq("city", Column::String("Paris".to_string()))),
+        );
+        assert!(matches!(plan, Plan::IndexUnion(_)));
+        assert_eq!(plan.ordered(&table, &by_age), None);
+
+        // keys in another direction than their index, uncovered keys and intersections
+        // are sorted
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::desc("age")]),
+            None
+        );
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("age"), SortKey::asc("name")]),
+            None
+        );
+        let plan = Plan::new(
+            &table,
+            &Predicate::eq("city", Column::String("Paris".to_string())),
+        );
+        assert_eq!(plan.ordered(&table, &by_age), None);
+    }
+
+    #[test]
+    fn test_descending_keys() {
+        let mut table = Table::new("Event".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Int));
+        table.add_field(Field::new("kind".to_string(), FieldType::String));
+        table.add_field(Field::new("at".to_string(), FieldType::Timestamp));
+        table.set_primary_key_directions(vec![Direction::Descending]);
+        table.add_index(
+            &Index::new("idx_kind_at", vec!["kind", "at"])
+                .with_directions(vec![Direction::Ascending, Direction::Descending]),
+        );
+
+        // the bounds of the descending column are swapped
+        let kind = Column::String("login".to_string());
+        let predicate = Predicate::eq("kind", kind.clone())
+            .and(Predicate::gt("at", Column::Timestamp(10)))
+            .and(Predicate::lt("at", Column::Timestamp(20)));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_kind_at".to_string(),
+                start: Bound::Excluded(vec![kind.clone(), Column::Timestamp(20)]),
+                end: Bound::Excluded(vec![kind, Column::Timestamp(10)]),
+            })
+        );
+
+        // only keys in the directions of their columns are read in order
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::desc("id")]),
+            Some(Plan::PrimaryKeyScan)
+        );
+        assert_eq!(Plan::TableScan.ordered(&table, &[SortKey::asc("id")]), None);
+        assert!(matches!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("kind"), SortKey::desc("at")]),
+            Some(Plan::IndexRangeScan(IndexRange { index, .. })) if index == "idx_kind_at"
+        ));
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("kind"), SortKey::asc("at")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_index_union() {
+        let table = table();
+        let predicate = Predicate::gt("age", Column::Int(60))
+            .or(Predicate::eq("city", Column::String("Paris".to_string())))
+            .and(Predicate::like_prefix("name", "J"));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexUnion(vec![
+                IndexRange {
+                    index: "idx_age".to_string(),
+                    start: Bound::Excluded(vec![Column::Int(60)]),
+                    end: Bound::Unbounded,
+                },
+                IndexRange {
+                    index: "idx_city_age".to_string(),
+                    start: Bound::Included(vec![Column::String("Paris".to_string())]),
+                    end: Bound::Included(vec![Column::String("Paris".to_string())]),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_write_path() {
+        assert_eq!(WritePath::new(&table()), WritePath::Maintained);
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        assert_eq!(WritePath::new(&table), WritePath::Blind);
+    }
+
+    #[test]
+    fn test_like_prefix() {
+        let mut table = table();
+        table.add_index(&Index::new("idx_name", vec!["name"]));
+
+        let predicate = Predicate::like_prefix("name", "Jo");
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_name".to_string(),
+                start: Bound::Included(vec![Column::String("Jo".to_string())]),
+                end: Bound::Excluded(vec![Column::String("Jp".to_string())]),
+            })
+        );
+
+        // the leading column of a composite index
+        let predicate = Predicate::like_prefix("city", "Pa");
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_city_age".to_string(),
+                start: Bound::Included(vec![Column::String("Pa".to_string())]),
+                end: Bound::Excluded(vec![Column::String("Pb".to_string())]),
+            })
+        );
+
+        // an empty prefix matches every string
+        let predicate = Predicate::like_prefix("name", "");
+        assert_eq!(Plan::new(&table, &predicate), Plan::TableScan);
+
+        assert_eq!(prefix_end("ab"), Some("ac".to_string()));
+        assert_eq!(prefix_end("a\u{d7ff}"), Some("a\u{e000}".to_string()));
+        assert_eq!(prefix_end("a\u{10ffff}"), Some("b".to_string()));
+        assert_eq!(prefix_end("\u{10ffff}"), None);
+    }
+
+    #[test]
+    fn test_in_lists() {
+        let table = table();
+        let john = || Column::String("John".to_string());
+        let jane = || Column::String("Jane".to_string());
+
+        let predicate = Predicate::in_list("name", vec![john(), Column::Null, jane(), john()]);
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::PrimaryKeyLookups {
+                pks: vec![vec![john()], vec![jane()]]
+            }
+        );
+        let predicate = Predicate::in_list("name", vec![john()]);
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::PrimaryKeyLookup { pk: vec![john()] }
+        );
+
+        // beats a range, not an equality
+        let predicate = Predicate::in_list("age", vec![Column::Int(18), Column::Int(20)])
+            .and(Predicate::gt("age", Column::Int(10)));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexLookups {
+                index: "idx_age".to_string(),
+                values: vec![Column::Int(18), Column::Int(20)],
+            }
+        );
+        let predicate = Predicate::in_list("age", vec![Column::Int(18), Column::Int(20)])
+            .and(Predicate::eq("city", Column::String("Paris".to_string())));
+        assert!(matches!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(_)
+        ));
+    }
+
+    #[test]
+    fn test_collations() {
+        let mut table = table();
+        table.fields[0].collation = Collation::CaseInsensitive;
+        table.fields[2].collation = Collation::CaseInsensitive;
+        table.add_index(&Index::new("idx_name", vec!["name"]));
+        let string = |value: &str| Column::String(value.to_string());
+
+        // the primary key is binary, a collated index is read with collated bounds
+        let predicate = Predicate::eq("name", string("John"));
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_name".to_string(),
+                start: Bound::Included(vec![string("john")]),
+                end: Bound::Included(vec![string("john")]),
+            })
+        );
+        let predicate = Predicate::like_prefix("city", "PA");
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexRangeScan(IndexRange {
+                index: "idx_city_age".to_string(),
+                start: Bound::Included(vec![string("pa")]),
+                end: Bound::Excluded(vec![string("pb")]),
+            })
+        );
+        let predicate = Predicate::in_list("city", vec![string("Paris"), string("PARIS")]);
+        assert_eq!(
+            Plan::new(&table, &predicate),
+            Plan::IndexLookups {
+                index: "idx_city_age".to_string(),
+                values: vec![string("paris")],
+            }
+        );
+
+        // collated keys don't sort as the strings do
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("city"), SortKey::asc("age")]),
+            None
+        );
+        assert_eq!(
+            Plan::TableScan.ordered(&table, &[SortKey::asc("name")]),
+            Some(Plan::PrimaryKeyScan)
+        );
+    }
+}
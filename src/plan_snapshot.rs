@@ -0,0 +1,211 @@
+//! # Plan Snapshot Module
+//!
+//! Pins the plans chosen for a corpus of queries, so that a change of the planner can't
+//! silently change how critical queries read their tables.
+//!
+//! A [`PlanHarness`] holds a fixture catalog: the definitions of the tables, along with
+//! their indexes and the state of every index. Plans only depend on the table definitions,
+//! so no database is needed. The queries are `SELECT` statements, planned as
+//! `Database::explain` plans them.
+//!
+//! A snapshot is plain text listing every query on its own line, followed by its plan on
+//! an indented line, entries being separated by blank lines:
+//!
+//! ```text
+//! SELECT * FROM Person WHERE name = 'John'
+//!     PrimaryKeyLookup { pk: [String("John")] }
+//! ```
+//!
+//! [`PlanHarness::snapshot`] renders the snapshot of a corpus, to be stored next to the
+//! tests. [`PlanHarness::diff`] replans the queries of a stored snapshot and returns those
+//! whose plan changed.
+
+use crate::errors::SqlLayerError;
+use crate::plan::Plan;
+use crate::sql::{parse, Command};
+use crate::table::Table;
+
+/// Indentation of the plan lines of a snapshot.
+const PLAN_INDENT: &str = "    ";
+
+/// A fixture catalog queries are planned against.
+pub struct PlanHarness {
+    tables: Vec<Table>,
+}
+
+/// A query whose plan differs from its snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanChange {
+    pub query: String,
+    /// The plan recorded in the snapshot.
+    pub expected: String,
+    /// The plan chosen now.
+    pub actual: String,
+}
+
+impl PlanHarness {
+    pub fn new(tables: Vec<Table>) -> Self {
+        Self { tables }
+    }
+
+    /// Plans a `SELECT` statement against the catalog.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The text isn't a single `SELECT` statement.
+    /// - The table isn't part of the catalog.
+    /// - The `WHERE` clause doesn't match the fields of the table.
+    pub fn explain(&self, query: &str) -> crate::errors::Result<Plan> {
+        let mut commands = parse(query)?;
+        let (
+            Some(Command::Select {
+                table, predicate, ..
+            }),
+            None,
+        ) = (commands.pop(), commands.pop())
+        else {
+            return Err(SqlLayerError::InvalidSqlText(format!(
+                "expected a single SELECT statement: {query}"
+            )));
+        };
+        let table = self
+            .tables
+            .iter()
+            .find(|candidate| candidate.name == table)
+            .ok_or(SqlLayerError::TableNotFound(table))?;
+        match predicate {
+            Some(predicate) => {
+                predicate.validate(table)?;
+                Ok(Plan::new(table, &predicate))
+            }
+            None => Ok(Plan::TableScan),
+        }
+    }
+
+    /// Renders the snapshot of the plans of a corpus of queries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query can't be planned, see [`PlanHarness::explain`].
+    pub fn snapshot(&self, queries: &[&str]) -> crate::errors::Result<String> {
+        let entries = queries
+            .iter()
+            .map(|query| {
+                let plan = self.explain(query)?;
+                Ok(format!("{}\n{PLAN_INDENT}{plan:?}\n", query.trim()))
+            })
+            .collect::<crate::errors::Result<Vec<_>>>()?;
+        Ok(entries.join("\n"))
+    }
+
+    /// Replans the queries of a snapshot and returns those whose plan changed, in the
+    /// order of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The snapshot is malformed: a query isn't followed by a plan line, or a plan line
+    ///   isn't preceded by a query.
+    /// - A query can't be planned, see [`PlanHarness::explain`].
+    pub fn diff(&self, snapshot: &str) -> crate::errors::Result<Vec<PlanChange>> {
+        let mut changes = vec![];
+        let mut lines = snapshot.lines().filter(|line| !line.trim().is_empty());
+        while let Some(query) = lines.next() {
+            let expected = lines
+                .next()
+                .filter(|line| line.starts_with(PLAN_INDENT) && !query.starts_with(PLAN_INDENT))
+                .ok_or(SqlLayerError::InvalidPlanSnapshot(query.to_string()))?
+                .trim();
+            let actual = format!("{:?}", self.explain(query)?);
+            if actual != expected {
+                changes.push(PlanChange {
+                    query: query.to_string(),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::index::{Index, IndexState};
+    use crate::plan_snapshot::{PlanChange, PlanHarness};
+    use crate::table::{Field, FieldType, Table};
+
+    const QUERIES: [&str; 5] = [
+        "SELECT * FROM Person",
+        "SELECT * FROM Person WHERE name = 'John'",
+        "SELECT * FROM Person WHERE age = 20 AND height > 1.5",
+        "SELECT * FROM Person WHERE city LIKE 'Par%'",
+        "SELECT * FROM Person WHERE height > 1.5",
+    ];
+
+    const SNAPSHOT: &str = r#"SELECT * FROM Person
+    TableScan
+
+SELECT * FROM Person WHERE name = 'John'
+    PrimaryKeyLookup { pk: [String("John")] }
+
+SELECT * FROM Person WHERE age = 20 AND height > 1.5
+    IndexRangeScan(IndexRange { index: "idx_age_height", start: Excluded([Int(20), Float(1.5)]), end: Included([Int(20)]) })
+
+SELECT * FROM Person WHERE city LIKE 'Par%'
+    IndexRangeScan(IndexRange { index: "idx_city", start: Included([String("Par")]), end: Excluded([String("Pas")]) })
+
+SELECT * FROM Person WHERE height > 1.5
+    TableScan
+"#;
+
+    fn catalog() -> Vec<Table> {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("city".to_string(), FieldType::String));
+        table.add_index(&Index::new("idx_age_height", vec!["age", "height"]));
+        table.add_index(&Index::new("idx_city", vec!["city"]));
+        vec![table]
+    }
+
+    #[test]
+    fn test_plan_snapshot() {
+        let harness = PlanHarness::new(catalog());
+        let snapshot = harness.snapshot(&QUERIES).expect("Unable to plan queries");
+        assert_eq!(snapshot, SNAPSHOT);
+        assert_eq!(harness.diff(SNAPSHOT).unwrap(), vec![]);
+
+        // an index no longer ready changes the plans going through it
+        let mut catalog = catalog();
+        catalog[0]
+            .get_index_mut("idx_city")
+            .unwrap()
+            .set_state(IndexState::Backfilling);
+        let harness = PlanHarness::new(catalog);
+        assert_eq!(
+            harness.diff(SNAPSHOT).unwrap(),
+            vec![PlanChange {
+                query: QUERIES[3].to_string(),
+                expected: r#"IndexRangeScan(IndexRange { index: "idx_city", start: Included([String("Par")]), end: Excluded([String("Pas")]) })"#.to_string(),
+                actual: "TableScan".to_string(),
+            }]
+        );
+
+        assert!(matches!(
+            harness.diff("SELECT * FROM Person\n"),
+            Err(SqlLayerError::InvalidPlanSnapshot(_))
+        ));
+        assert!(matches!(
+            harness.explain("SELECT * FROM Unknown"),
+            Err(SqlLayerError::TableNotFound(_))
+        ));
+        assert!(matches!(
+            harness.explain("DELETE FROM Person"),
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+    }
+}
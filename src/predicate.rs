@@ -0,0 +1,489 @@
+//! # Predicate Module
+//!
+//! A `Predicate` is a filter over the records of a table, such as the `WHERE` clause of a
+//! query. Columns are referenced by field name: a predicate is first validated against the
+//! table it applies to, then evaluated against each record.
+//!
+//! Comparisons follow SQL semantics regarding nulls: comparing a null column to anything
+//! never matches, `IsNull` must be used to select null columns. Strings compare in the
+//! collation of their field, see [`Collation`].
+
+use crate::collation::Collation;
+use crate::errors::SqlLayerError;
+use crate::record::{Column, Record};
+use crate::table::{FieldType, Table};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// The column is equal to the value.
+    Eq(String, Column),
+    /// The column is strictly lower than the value.
+    Lt(String, Column),
+    /// The column is strictly greater than the value.
+    Gt(String, Column),
+    /// The column lies between the two values, both included.
+    Between(String, Column, Column),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    IsNull(String),
+    /// The string column starts with the prefix, as `LIKE 'prefix%'` does.
+    LikePrefix(String, String),
+    /// The column is equal to one of the values, as `IN (...)` does.
+    In(String, Vec<Column>),
+    /// The array column has an element equal to the value.
+    Contains(String, Column),
+}
+
+impl Predicate {
+    pub fn eq<S: Into<String>>(field: S, value: Column) -> Self {
+        Predicate::Eq(field.into(), value)
+    }
+
+    pub fn lt<S: Into<String>>(field: S, value: Column) -> Self {
+        Predicate::Lt(field.into(), value)
+    }
+
+    pub fn gt<S: Into<String>>(field: S, value: Column) -> Self {
+        Predicate::Gt(field.into(), value)
+    }
+
+    pub fn between<S: Into<String>>(field: S, low: Column, high: Column) -> Self {
+        Predicate::Between(field.into(), low, high)
+    }
+
+    pub fn is_null<S: Into<String>>(field: S) -> Self {
+        Predicate::IsNull(field.into())
+    }
+
+    pub fn like_prefix<S1: Into<String>, S2: Into<String>>(field: S1, prefix: S2) -> Self {
+        Predicate::LikePrefix(field.into(), prefix.into())
+    }
+
+    pub fn in_list<S: Into<String>>(field: S, values: Vec<Column>) -> Self {
+        Predicate::In(field.into(), values)
+    }
+
+    pub fn contains<S: Into<String>>(field: S, value: Column) -> Self {
+        Predicate::Contains(field.into(), value)
+    }
+
+    pub fn and(self, other: Predicate) -> Self {
+        Predicate::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Predicate) -> Self {
+        Predicate::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Checks that every referenced column exists in the table and that the compared
+    /// values match the type of their column.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - A referenced column is not a field of the table.
+    /// - A value does not match the type of its column, is NaN, or a prefix is applied to
+    ///   a non-string column.
+    /// - `Contains` is applied to a non-array column, or its value doesn't match the item
+    ///   type of the column.
+    pub fn validate(&self, table: &Table) -> crate::errors::Result<()> {
+        match self {
+            Predicate::Eq(field, value)
+            | Predicate::Lt(field, value)
+            | Predicate::Gt(field, value) => check_value(table, field, value),
+            Predicate::Between(field, low, high) => {
+                check_value(table, field, low)?;
+                check_value(table, field, high)
+            }
+            Predicate::And(left, right) | Predicate::Or(left, right) => {
+                left.validate(table)?;
+                right.validate(table)
+            }
+            Predicate::IsNull(field) => field_type(table, field).map(|_| ()),
+            Predicate::LikePrefix(field, prefix) => {
+                check_value(table, field, &Column::String(prefix.clone()))
+            }
+            Predicate::In(field, values) => values
+                .iter()
+                .try_for_each(|value| check_value(table, field, value)),
+            Predicate::Contains(field, value) => check_element(table, field, value),
+        }
+    }
+
+    /// Evaluates the predicate against a record of the table.
+    ///
+    /// The predicate is expected to have been validated against the table, a column that
+    /// can't be found or compared simply doesn't match.
+    pub fn evaluate(&self, table: &Table, record: &Record) -> bool {
+        let column = |field: &str| {
+            table
+                .get_field_pos(field)
+                .and_then(|i| record.columns.get(i))
+                .unwrap_or(&Column::Null)
+        };
+        match self {
+            Predicate::Eq(field, value) => {
+                compare(table.collation(field), column(field), value) == Some(Ordering::Equal)
+            }
+            Predicate::Lt(field, value) => {
+                compare(table.collation(field), column(field), value) == Some(Ordering::Less)
+            }
+            Predicate::Gt(field, value) => {
+                compare(table.collation(field), column(field), value) == Some(Ordering::Greater)
+            }
+            Predicate::Between(field, low, high) => {
+                let column = column(field);
+                matches!(
+                    compare(table.collation(field), column, low),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ) && matches!(
+                    compare(table.collation(field), column, high),
+                    Some(Ordering::Less | Ordering::Equal)
+                )
+            }
+            Predicate::And(left, right) => {
+                left.evaluate(table, record) && right.evaluate(table, record)
+            }
+            Predicate::Or(left, right) => {
+                left.evaluate(table, record) || right.evaluate(table, record)
+            }
+            Predicate::IsNull(field) => matches!(column(field), Column::Null),
+            Predicate::LikePrefix(field, prefix) => match column(field) {
+                Column::String(value) => starts_with(table.collation(field), value, prefix),
+                _ => false,
+            },
+            Predicate::In(field, values) => {
+                let column = column(field);
+                values.iter().any(|value| {
+                    compare(table.collation(field), column, value) == Some(Ordering::Equal)
+                })
+            }
+            Predicate::Contains(field, value) => match column(field) {
+                Column::Array(elements) => elements.iter().any(|element| {
+                    compare(table.collation(field), element, value) == Some(Ordering::Equal)
+                }),
+                _ => false,
+            },
+        }
+    }
+
+    /// Evaluates the predicate as a `CHECK` constraint: the record passes unless the
+    /// predicate is false, a comparison involving a null being unknown rather than false.
+    pub fn check(&self, table: &Table, record: &Record) -> bool {
+        self.truth(table, record) != Some(false)
+    }
+
+    /// Evaluates the predicate with the three-valued logic of SQL, `None` being unknown.
+    fn truth(&self, table: &Table, record: &Record) -> Option<bool> {
+        let column = |field: &str| {
+            table
+                .get_field_pos(field)
+                .and_then(|i| record.columns.get(i))
+                .unwrap_or(&Column::Null)
+        };
+        match self {
+            Predicate::Eq(field, value) => {
+                Some(compare(table.collation(field), column(field), value)? == Ordering::Equal)
+            }
+            Predicate::Lt(field, value) => {
+                Some(compare(table.collation(field), column(field), value)? == Ordering::Less)
+            }
+            Predicate::Gt(field, value) => {
+                Some(compare(table.collation(field), column(field), value)? == Ordering::Greater)
+            }
+            Predicate::Between(field, low, high) => {
+                let column = column(field);
+                let above = compare(table.collation(field), column, low)? != Ordering::Less;
+                let below = compare(table.collation(field), column, high)? != Ordering::Greater;
+                Some(above && below)
+            }
+            Predicate::And(left, right) => {
+                match (left.truth(table, record), right.truth(table, record)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Predicate::Or(left, right) => {
+                match (left.truth(table, record), right.truth(table, record)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Predicate::IsNull(field) => Some(matches!(column(field), Column::Null)),
+            Predicate::LikePrefix(field, prefix) => match column(field) {
+                Column::Null => None,
+                Column::String(value) => Some(starts_with(table.collation(field), value, prefix)),
+                _ => Some(false),
+            },
+            Predicate::In(field, values) => {
+                let column = column(field);
+                let matches = values
+                    .iter()
+                    .map(|value| compare(table.collation(field), column, value));
+                let mut truth = Some(false);
+                for matched in matches {
+                    match matched {
+                        Some(Ordering::Equal) => return Some(true),
+                        None => truth = None,
+                        Some(_) => {}
+                    }
+                }
+                truth
+            }
+            Predicate::Contains(field, value) => match column(field) {
+                Column::Null => None,
+                Column::Array(elements) => {
+                    let mut truth = Some(false);
+                    for element in elements {
+                        match compare(table.collation(field), element, value) {
+                            Some(Ordering::Equal) => return Some(true),
+                            None => truth = None,
+                            Some(_) => {}
+                        }
+                    }
+                    truth
+                }
+                _ => Some(false),
+            },
+        }
+    }
+}
+
+/// Compares two non-null columns in the collation of their field, nulls never compare.
+fn compare(collation: Collation, column: &Column, value: &Column) -> Option<Ordering> {
+    if matches!(column, Column::Null) || matches!(value, Column::Null) {
+        return None;
+    }
+    collation
+        .column(column)
+        .partial_cmp(&collation.column(value))
+}
+
+/// Whether a string starts with the prefix, both collated.
+fn starts_with(collation: Collation, value: &str, prefix: &str) -> bool {
+    collation
+        .apply(value)
+        .starts_with(collation.apply(prefix).as_ref())
+}
+
+fn field_type(table: &Table, field: &str) -> crate::errors::Result<FieldType> {
+    table
+        .get_field_pos(field)
+        .map(|i| table.fields[i].r#type)
+        .ok_or(SqlLayerError::MissingColumn(field.to_string()))
+}
+
+/// Checks that the field is an array whose elements can be compared with the value.
+fn check_element(table: &Table, field: &str, value: &Column) -> crate::errors::Result<()> {
+    let position = table
+        .get_field_pos(field)
+        .ok_or(SqlLayerError::MissingColumn(field.to_string()))?;
+    let field = &table.fields[position];
+    let (expected, found) = match field.item_type {
+        Some(item_type) if field.r#type == FieldType::Array => {
+            if value.is_of_type(item_type) {
+                return check_not_nan(value);
+            }
+            (format!("Array of {item_type:?}"), format!("{value:?}"))
+        }
+        _ => ("Array".to_string(), format!("{:?}", field.r#type)),
+    };
+    Err(SqlLayerError::MismatchedColumnType(expected, found))
+}
+
+fn check_value(table: &Table, field: &str, value: &Column) -> crate::errors::Result<()> {
+    let expected = field_type(table, field)?;
+    if !value.is_of_type(expected) {
+        return Err(SqlLayerError::MismatchedColumnType(
+            format!("{expected:?}"),
+            format!("{value:?}"),
+        ));
+    }
+    check_not_nan(value)
+}
+
+/// Checks that a value isn't NaN, which records can't hold: no column compares with it,
+/// while a range of index keys bounded by it would hold every float.
+fn check_not_nan(value: &Column) -> crate::errors::Result<()> {
+    match value {
+        Column::Float(float) if float.is_nan() => Err(SqlLayerError::MismatchedColumnType(
+            "Float other than NaN".to_string(),
+            format!("{value:?}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collation::Collation;
+    use crate::errors::SqlLayerError;
+    use crate::predicate::Predicate;
+    use crate::record::{Column, Record};
+    use crate::table::{Field, FieldType, Table};
+
+    fn table() -> Table {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table
+    }
+
+    fn record(name: &str, age: Option<i64>) -> Record {
+        Record {
+            columns: vec![
+                Column::String(name.to_string()),
+                age.map(Column::Int).unwrap_or(Column::Null),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let table = table();
+        let john = record("John", Some(20));
+        let jane = record("Jane", None);
+
+        assert!(Predicate::eq("age", Column::Int(20)).evaluate(&table, &john));
+        assert!(!Predicate::eq("age", Column::Int(21)).evaluate(&table, &john));
+        assert!(Predicate::lt("age", Column::Int(21)).evaluate(&table, &john));
+        assert!(Predicate::gt("age", Column::Int(19)).evaluate(&table, &john));
+        assert!(Predicate::between("age", Column::Int(20), Column::Int(30)).evaluate(&table, &john));
+        assert!(
+            !Predicate::between("age", Column::Int(21), Column::Int(30)).evaluate(&table, &john)
+        );
+        assert!(Predicate::like_prefix("name", "Jo").evaluate(&table, &john));
+        assert!(!Predicate::like_prefix("name", "Jo").evaluate(&table, &jane));
+
+        // nulls only match IsNull
+        assert!(Predicate::is_null("age").evaluate(&table, &jane));
+        assert!(!Predicate::is_null("age").evaluate(&table, &john));
+        assert!(!Predicate::lt("age", Column::Int(100)).evaluate(&table, &jane));
+
+        let predicate = Predicate::eq("name", Column::String("Jane".to_string()))
+            .or(Predicate::gt("age", Column::Int(18)).and(Predicate::like_prefix("name", "J")));
+        assert!(predicate.evaluate(&table, &john));
+        assert!(predicate.evaluate(&table, &jane));
+        assert!(!predicate.evaluate(&table, &record("Bob", Some(40))));
+
+        let predicate = Predicate::in_list("age", vec![Column::Int(19), Column::Int(20)]);
+        assert!(predicate.evaluate(&table, &john));
+        assert!(!predicate.evaluate(&table, &jane));
+        assert!(!Predicate::in_list("age", vec![Column::Null]).evaluate(&table, &jane));
+    }
+
+    #[test]
+    fn test_collated_evaluate() {
+        let mut table = table();
+        table.fields[0].collation = Collation::CaseInsensitive;
+        let john = record("John", Some(20));
+        let string = |value: &str| Column::String(value.to_string());
+
+        assert!(Predicate::eq("name", string("JOHN")).evaluate(&table, &john));
+        assert!(Predicate::like_prefix("name", "jo").evaluate(&table, &john));
+        assert!(Predicate::in_list("name", vec![string("john")]).evaluate(&table, &john));
+        // "John" sorts before "jane" in binary, after it case-insensitively
+        assert!(Predicate::gt("name", string("jane")).evaluate(&table, &john));
+        assert!(Predicate::eq("name", string("JOHN")).check(&table, &john));
+
+        table.fields[0].collation = Collation::Binary;
+        assert!(!Predicate::eq("name", string("JOHN")).evaluate(&table, &john));
+        assert!(!Predicate::gt("name", string("jane")).evaluate(&table, &john));
+    }
+
+    #[test]
+    fn test_check() {
+        let table = table();
+        let john = record("John", Some(20));
+        let jane = record("Jane", None);
+
+        let adult = Predicate::gt("age", Column::Int(17));
+        assert!(adult.check(&table, &john));
+        assert!(!adult.check(&table, &record("Bob", Some(12))));
+        // a comparison with null is unknown, which passes
+        assert!(adult.check(&table, &jane));
+
+        // unknown AND false is false, unknown OR true is true
+        let predicate = adult.clone().and(Predicate::like_prefix("name", "B"));
+        assert!(!predicate.check(&table, &jane));
+        let predicate = adult.or(Predicate::like_prefix("name", "J"));
+        assert!(predicate.check(&table, &jane));
+        let predicate = Predicate::in_list("age", vec![Column::Int(20), Column::Int(30)]);
+        assert!(predicate.check(&table, &john));
+        assert!(predicate.check(&table, &jane));
+        assert!(!predicate.check(&table, &record("Bob", Some(40))));
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut table = table();
+        table.add_field(Field::new_array("tags".to_string(), FieldType::String));
+        let tags = |tags: &[&str]| {
+            let mut record = record("John", Some(20));
+            let tags = tags.iter().map(|tag| Column::String(tag.to_string()));
+            record.columns.push(Column::Array(tags.collect()));
+            record
+        };
+        let admin = Predicate::contains("tags", Column::String("admin".to_string()));
+        admin.validate(&table).expect("Invalid predicate");
+        assert!(admin.evaluate(&table, &tags(&["user", "admin"])));
+        assert!(!admin.evaluate(&table, &tags(&["user"])));
+        assert!(!admin.evaluate(&table, &tags(&[])));
+
+        // a null array is unknown, as is an array holding a null
+        let mut null = record("John", Some(20));
+        null.columns.push(Column::Null);
+        assert!(!admin.evaluate(&table, &null));
+        assert!(admin.check(&table, &null));
+        let mut with_null = tags(&["user"]);
+        with_null.columns[2] = Column::Array(vec![Column::Null]);
+        assert!(admin.check(&table, &with_null));
+        assert!(!admin.check(&table, &tags(&["user"])));
+
+        assert!(matches!(
+            Predicate::contains("tags", Column::Int(1)).validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            Predicate::contains("name", Column::String("J".to_string())).validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let table = table();
+        assert!(Predicate::eq("age", Column::Int(20))
+            .validate(&table)
+            .is_ok());
+        assert!(matches!(
+            Predicate::eq("unknown", Column::Int(20)).validate(&table),
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+        assert!(matches!(
+            Predicate::eq("age", Column::String("20".to_string())).validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            Predicate::like_prefix("age", "2").validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            Predicate::in_list("age", vec![Column::Int(2), Column::Bool(true)]).validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+
+        let mut table = table;
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        assert!(Predicate::lt("height", Column::Float(-0.0))
+            .validate(&table)
+            .is_ok());
+        assert!(matches!(
+            Predicate::lt("height", Column::Float(f64::NAN)).validate(&table),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+}
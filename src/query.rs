@@ -0,0 +1,195 @@
+//! # Query Module
+//!
+//! Queries spanning several tables, executed by `Database::join`.
+//!
+//! A join reads the left table once and, for every left record, looks up the right records
+//! sharing its join column. The [`JoinStrategy`] picks how the right side is looked up from
+//! the right table definition: through its primary key or a ready index when the join
+//! column leads one, or by comparing against the whole right table otherwise.
+//!
+//! As in SQL, joins are inner joins and null join columns never match.
+
+use crate::errors::SqlLayerError;
+use crate::index::IndexState;
+use crate::record::{Column, Record};
+use crate::table::Table;
+
+/// An inner equi-join of two tables on one column of each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub left: String,
+    pub left_field: String,
+    pub right: String,
+    pub right_field: String,
+}
+
+impl Join {
+    /// Joins the records of `left` to the records of `right` whose `right_field` equals
+    /// their `left_field`.
+    pub fn new(left: &str, left_field: &str, right: &str, right_field: &str) -> Self {
+        Self {
+            left: left.to_string(),
+            left_field: left_field.to_string(),
+            right: right.to_string(),
+            right_field: right_field.to_string(),
+        }
+    }
+}
+
+/// How the right records matching a left record are found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinStrategy {
+    /// The join column is the whole primary key of the right table: one point lookup per
+    /// left record.
+    PrimaryKeyLookup,
+    /// The join column leads a ready index of the right table: one index range scan per
+    /// left record.
+    IndexLookup(String),
+    /// The right table is read once in memory and compared to every left record.
+    NestedLoop,
+}
+
+impl JoinStrategy {
+    /// Picks the cheapest way to look up the right records joined on `right_field`.
+    pub(crate) fn new(right: &Table, right_field: &str) -> Self {
+        if right.primary_key.len() == 1 && right.primary_key[0] == right_field {
+            return JoinStrategy::PrimaryKeyLookup;
+        }
+        right
+            .indexes
+            .iter()
+            .find(|index| {
+                index.state() == IndexState::Ready
+                    && index
+                        .fields()
+                        .first()
+                        .is_some_and(|field| field == right_field)
+            })
+            .map(|index| JoinStrategy::IndexLookup(index.name().to_string()))
+            .unwrap_or(JoinStrategy::NestedLoop)
+    }
+}
+
+/// Resolves the join columns against both tables, returning their positions.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - A join column is not a field of its table.
+/// - The join columns have different types.
+pub(crate) fn join_positions(
+    join: &Join,
+    left: &Table,
+    right: &Table,
+) -> crate::errors::Result<(usize, usize)> {
+    let left_position = left
+        .get_field_pos(&join.left_field)
+        .ok_or(SqlLayerError::MissingColumn(join.left_field.clone()))?;
+    let right_position = right
+        .get_field_pos(&join.right_field)
+        .ok_or(SqlLayerError::MissingColumn(join.right_field.clone()))?;
+    let left_type = left.fields[left_position].r#type;
+    let right_type = right.fields[right_position].r#type;
+    if left_type != right_type {
+        return Err(SqlLayerError::MismatchedColumnType(
+            format!("{left_type:?}"),
+            format!("{right_type:?}"),
+        ));
+    }
+    Ok((left_position, right_position))
+}
+
+/// Builds the joined record: the columns of the left record followed by the columns of
+/// the right record.
+pub(crate) fn combine(left: &Record, right: &Record) -> Record {
+    let columns = left
+        .columns
+        .iter()
+        .chain(right.columns.iter())
+        .cloned()
+        .collect();
+    Record { columns }
+}
+
+/// Whether two join columns match, nulls never do.
+pub(crate) fn joins(left: &Column, right: &Column) -> bool {
+    !matches!(left, Column::Null) && left == right
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::{Index, IndexState};
+    use crate::query::{combine, join_positions, joins, Join, JoinStrategy};
+    use crate::record::{Column, Record};
+    use crate::table::{Field, FieldType, Table};
+
+    fn orders_table() -> Table {
+        let mut table = Table::new("orders".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Int));
+        table.add_field(Field::new("customer_id".to_string(), FieldType::Int));
+        table.add_field(Field::new("label".to_string(), FieldType::String));
+        table
+    }
+
+    #[test]
+    fn test_join_strategy() {
+        let mut table = orders_table();
+        assert_eq!(
+            JoinStrategy::new(&table, "id"),
+            JoinStrategy::PrimaryKeyLookup
+        );
+        assert_eq!(
+            JoinStrategy::new(&table, "customer_id"),
+            JoinStrategy::NestedLoop
+        );
+
+        let mut index = Index::new("idx_customer", vec!["customer_id", "label"]);
+        index.set_state(IndexState::Backfilling);
+        table.add_index(&index);
+        assert_eq!(
+            JoinStrategy::new(&table, "customer_id"),
+            JoinStrategy::NestedLoop
+        );
+        table
+            .get_index_mut("idx_customer")
+            .unwrap()
+            .set_state(IndexState::Ready);
+        assert_eq!(
+            JoinStrategy::new(&table, "customer_id"),
+            JoinStrategy::IndexLookup("idx_customer".to_string())
+        );
+        assert_eq!(JoinStrategy::new(&table, "label"), JoinStrategy::NestedLoop);
+    }
+
+    #[test]
+    fn test_join_positions() {
+        let table = orders_table();
+        let join = Join::new("orders", "customer_id", "orders", "id");
+        assert_eq!(join_positions(&join, &table, &table).unwrap(), (1, 0));
+        let join = Join::new("orders", "label", "orders", "id");
+        assert!(join_positions(&join, &table, &table).is_err());
+        let join = Join::new("orders", "unknown", "orders", "id");
+        assert!(join_positions(&join, &table, &table).is_err());
+    }
+
+    #[test]
+    fn test_combine() {
+        let left = Record {
+            columns: vec![Column::Int(1), Column::Int(2)],
+        };
+        let right = Record {
+            columns: vec![Column::String("a".to_string())],
+        };
+        assert_eq!(
+            combine(&left, &right).columns,
+            vec![
+                Column::Int(1),
+                Column::Int(2),
+                Column::String("a".to_string())
+            ]
+        );
+        assert!(joins(&Column::Int(1), &Column::Int(1)));
+        assert!(!joins(&Column::Int(1), &Column::Int(2)));
+        assert!(!joins(&Column::Null, &Column::Null));
+    }
+}
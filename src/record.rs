@@ -1,6 +1,21 @@
+use crate::decimal::Decimal;
+use crate::errors::SqlLayerError;
+use crate::plan::Direction;
 use crate::row::Row;
+use crate::table::{Field, FieldType, Table};
+use crate::validation::{ValidationReport, ViolationKind};
 use foundationdb_tuple::{TupleDepth, TuplePack, VersionstampOffset};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::io::Write;
+use std::iter::{repeat, zip};
+
+/// Application metadata attached to a row, such as trace ids or the source system.
+///
+/// Metadata isn't part of the table schema: any key can be set without altering the table.
+/// It is stored as a tuple next to the row, in a single value that must stay under the
+/// 100 kB FoundationDB limit.
+pub type RowMetadata = BTreeMap<String, String>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Record {
@@ -10,13 +25,689 @@ pub struct Record {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Column {
     String(String),
+    /// An integer of an `Int`, `Int32` or `Int16` field.
     Int(i64),
+    /// An unsigned integer, tuple-encoded as an integer so that it sorts along with the
+    /// `Int` columns.
+    UInt64(u64),
+    /// A float. Records holding NaN are rejected when written, and negative zero has the
+    /// key of zero.
     Float(f64),
     Bool(bool),
     Bytes(Vec<u8>),
+    /// Days since the Unix epoch, see the `temporal` module.
+    Date(i32),
+    /// Microseconds since midnight.
+    Time(i64),
+    /// Microseconds since the Unix epoch, in UTC.
+    Timestamp(i64),
+    /// The 16 bytes of a UUID, tuple-encoded as such so that UUIDs sort as their bytes do.
+    Uuid([u8; 16]),
+    /// An exact decimal, see [`Decimal`].
+    Decimal(Decimal),
+    /// A list of columns of the item type of its field, tuple-encoded as a nested tuple so
+    /// that arrays sort element by element.
+    Array(Vec<Column>),
     Null,
 }
 
+impl Record {
+    /// Builds a record from its columns, in the order of the fields of its table.
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self { columns }
+    }
+
+    /// Starts building a record of the table, column by column, see [`RecordBuilder`].
+    pub fn builder(table: &Table) -> RecordBuilder<'_> {
+        RecordBuilder {
+            table,
+            columns: vec![None; table.fields.len()],
+        }
+    }
+
+    /// Builds a record from columns given by field name, in any order.
+    ///
+    /// The columns are laid out in the order of the fields of the table, as positional
+    /// records are. Fields left without a column take their default, if they have one.
+    /// Column types aren't checked here, they are when the record is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table the record belongs to.
+    /// * `columns` - The columns, each along with the name of its field.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error reporting every column that:
+    /// - Doesn't match a field of the table.
+    /// - Is given more than once.
+    /// - Is missing, one per field left without a column nor a default.
+    pub fn from_named(
+        table: &Table,
+        columns: Vec<(&str, Column)>,
+    ) -> crate::errors::Result<Record> {
+        let mut report = ValidationReport::default();
+        let mut ordered = vec![None; table.fields.len()];
+        for (name, column) in columns {
+            match table.get_field_pos(name) {
+                None => report.add(Some(name), ViolationKind::UnknownColumn),
+                Some(position) if ordered[position].is_some() => {
+                    report.add(Some(name), ViolationKind::DuplicateColumn)
+                }
+                Some(position) => ordered[position] = Some(column),
+            }
+        }
+        let mut record = Record { columns: vec![] };
+        for (field, column) in table.fields.iter().zip(ordered) {
+            match column.or_else(|| field.default.clone()) {
+                Some(column) => record.columns.push(column),
+                None => report.add(Some(&field.name), ViolationKind::MissingColumn),
+            }
+        }
+        report.into_result()?;
+        Ok(record)
+    }
+
+    /// Reads the column of a field, converted into a Rust value.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table the record belongs to.
+    /// * `name` - The name of the field to read.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some(value))` holding the value of the column, or `Ok(None)` if the
+    /// column is null.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The field doesn't exist in the table.
+    /// - The column can't be read as a `T`.
+    pub fn get<T: FromColumn>(
+        &self,
+        table: &Table,
+        name: &str,
+    ) -> crate::errors::Result<Option<T>> {
+        let position = table
+            .get_field_pos(name)
+            .ok_or(SqlLayerError::MissingColumn(name.to_string()))?;
+        match self.columns.get(position) {
+            None | Some(Column::Null) => Ok(None),
+            Some(column) => T::from_column(column).map(Some).ok_or_else(|| {
+                SqlLayerError::MismatchedColumnType(
+                    std::any::type_name::<T>().to_string(),
+                    format!("{column:?}"),
+                )
+            }),
+        }
+    }
+
+    /// Reads the column at a position as a Rust value.
+    ///
+    /// Unlike [`Record::get`], nulls are only read into `Option`s, which lets struct fields
+    /// tell nullable columns apart, see [`SqlRecord`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column can't be read as a `T`, positions past the end of the
+    /// record being null.
+    pub fn column_as<T: FromColumn>(&self, position: usize) -> crate::errors::Result<T> {
+        crate::conversions::convert(self.columns.get(position).unwrap_or(&Column::Null))
+    }
+
+    /// Reads a row stored for a table as a record of its current fields: the columns of the
+    /// dropped fields are skipped, and a row stored before fields were added is completed
+    /// with the default of the added fields, or null.
+    ///
+    /// Stored rows hold temporal columns as the integers they are encoded as, enums as the
+    /// positions of their variants, and UUIDs, decimals and arrays as bytes, they are typed
+    /// after their fields.
+    pub(crate) fn from_stored_row(row: Row, table: &Table) -> Record {
+        let mut record = Record::from(row);
+        for &slot in table.dropped_columns.iter().rev() {
+            if (slot as usize) < record.columns.len() {
+                record.columns.remove(slot as usize);
+            }
+        }
+        for (column, field) in record.columns.iter_mut().zip(&table.fields) {
+            *column = std::mem::replace(column, Column::Null).with_field(field);
+        }
+        if let Some(added) = table.fields.get(record.columns.len()..) {
+            record.columns.extend(
+                added
+                    .iter()
+                    .map(|field| field.default.clone().unwrap_or(Column::Null)),
+            );
+        }
+        record
+    }
+
+    /// Writes the record as the row stored for a table, holding a null column in the place
+    /// of each dropped field, and the position of their variant for the enum fields.
+    pub(crate) fn to_stored_row(&self, table: &Table) -> Row {
+        let mut row = Row::from(self);
+        for ((stored, column), field) in zip(&mut row.columns, &self.columns).zip(&table.fields) {
+            let ordinal = match column {
+                Column::String(variant) if field.r#type == FieldType::Enum => {
+                    field.variant_ordinal(variant)
+                }
+                _ => None,
+            };
+            if let Some(ordinal) = ordinal {
+                *stored = Some(crate::row::Column::new_int(ordinal as i64));
+            }
+        }
+        for &slot in &table.dropped_columns {
+            let slot = (slot as usize).min(row.columns.len());
+            row.columns.insert(slot, None);
+        }
+        row
+    }
+
+    /// Keeps only the columns at the given positions, in the order of `positions`.
+    ///
+    /// Positions past the end of the record yield `Column::Null`.
+    pub fn project(&self, positions: &[usize]) -> Record {
+        let columns = positions
+            .iter()
+            .map(|&i| self.columns.get(i).cloned().unwrap_or(Column::Null))
+            .collect();
+        Record { columns }
+    }
+}
+
+/// Builds a record by setting its columns by field name, in any order, sparing callers
+/// from knowing the position of every field in wide tables. Values are converted into
+/// columns with `Into<Column>`, so plain strings, integers, floats, booleans and bytes can
+/// be set, and `None` sets a null.
+#[derive(Debug)]
+pub struct RecordBuilder<'a> {
+    table: &'a Table,
+    columns: Vec<Option<Column>>,
+}
+
+impl RecordBuilder<'_> {
+    /// Sets the column of a field, replacing the value previously set if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The field doesn't exist in the table.
+    /// - The value doesn't match the type of the field, nor converts into it, see
+    ///   [`Column::coerce`].
+    pub fn set(mut self, name: &str, value: impl Into<Column>) -> crate::errors::Result<Self> {
+        let position = self
+            .table
+            .get_field_pos(name)
+            .ok_or(SqlLayerError::MissingColumn(name.to_string()))?;
+        let column = value.into();
+        let field_type = self.table.fields[position].r#type;
+        let Some(column) = column.coerce(field_type).map(Cow::into_owned) else {
+            return Err(SqlLayerError::MismatchedColumnType(
+                format!("{field_type:?}"),
+                format!("{column:?}"),
+            ));
+        };
+        self.columns[position] = Some(column);
+        Ok(self)
+    }
+
+    /// Builds the record, with its columns in the order of the fields of the table.
+    ///
+    /// Fields left unset take their default, if they have one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error naming every field left unset without a default, nulls
+    /// must be set explicitly.
+    pub fn build(self) -> crate::errors::Result<Record> {
+        let mut report = ValidationReport::default();
+        let mut columns = vec![];
+        for (field, column) in self.table.fields.iter().zip(self.columns) {
+            match column.or_else(|| field.default.clone()) {
+                Some(column) => columns.push(column),
+                None => report.add(Some(&field.name), ViolationKind::MissingColumn),
+            }
+        }
+        report.into_result()?;
+        Ok(Record { columns })
+    }
+}
+
+/// Rust structs stored as the rows of a table.
+///
+/// Implementations are generated by `#[derive(SqlRecord)]`, behind the `derive` feature:
+/// every struct field is a column, in declaration order, whose type is given by its
+/// [`FromColumn`] implementation.
+pub trait SqlRecord: Sized {
+    /// The definition of the table the structs are stored in, without secondary indexes.
+    fn table() -> Table;
+
+    fn into_record(self) -> Record;
+
+    /// Reads a struct back from a record of its table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column can't be read into its struct field, see
+    /// [`Record::column_as`].
+    fn from_record(record: Record) -> crate::errors::Result<Self>;
+}
+
+/// Rust types a column can be read as, see [`Record::get`].
+pub trait FromColumn: Sized {
+    /// The type of the fields holding such values.
+    const FIELD_TYPE: FieldType;
+
+    /// Whether the fields holding such values accept `Column::Null`.
+    const NULLABLE: bool = false;
+
+    /// Converts a column, returning `None` if it holds another type.
+    fn from_column(column: &Column) -> Option<Self>;
+}
+
+/// Nullable columns, read as `None` when null.
+impl<T: FromColumn> FromColumn for Option<T> {
+    const FIELD_TYPE: FieldType = T::FIELD_TYPE;
+    const NULLABLE: bool = true;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Null => Some(None),
+            column => T::from_column(column).map(Some),
+        }
+    }
+}
+
+impl FromColumn for String {
+    const FIELD_TYPE: FieldType = FieldType::String;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for i64 {
+    const FIELD_TYPE: FieldType = FieldType::Int;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for i32 {
+    const FIELD_TYPE: FieldType = FieldType::Int32;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Int(value) => i32::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for i16 {
+    const FIELD_TYPE: FieldType = FieldType::Int16;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Int(value) => i16::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for u64 {
+    const FIELD_TYPE: FieldType = FieldType::UInt64;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::UInt64(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for f64 {
+    const FIELD_TYPE: FieldType = FieldType::Float;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for bool {
+    const FIELD_TYPE: FieldType = FieldType::Bool;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumn for Vec<u8> {
+    const FIELD_TYPE: FieldType = FieldType::Bytes;
+
+    fn from_column(column: &Column) -> Option<Self> {
+        match column {
+            Column::Bytes(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Column {
+    /// Whether the column can be stored in a field of the given type, nulls fit any field.
+    ///
+    /// The integer columns are of the type of every integer field, whatever their range,
+    /// see [`Column::coerce`] for the columns actually stored.
+    pub fn is_of_type(&self, field_type: FieldType) -> bool {
+        matches!(
+            (field_type, self),
+            (FieldType::String, Column::String(_))
+                | (
+                    FieldType::Int | FieldType::Int32 | FieldType::Int16 | FieldType::UInt64,
+                    Column::Int(_) | Column::UInt64(_)
+                )
+                | (FieldType::Float, Column::Float(_))
+                | (FieldType::Bool, Column::Bool(_))
+                | (FieldType::Bytes, Column::Bytes(_))
+                | (FieldType::Date, Column::Date(_))
+                | (FieldType::Time, Column::Time(_))
+                | (FieldType::Timestamp, Column::Timestamp(_))
+                | (FieldType::Uuid, Column::Uuid(_))
+                | (FieldType::Decimal, Column::Decimal(_))
+                | (FieldType::Array, Column::Array(_))
+                | (FieldType::Enum, Column::String(_))
+                | (_, Column::Null)
+        )
+    }
+
+    /// Converts the column into the column stored in a field of the given type, along the
+    /// coercion matrix of the numeric columns, which only holds lossless conversions:
+    ///
+    /// | Column    | Converts into                                                     |
+    /// |-----------|-------------------------------------------------------------------|
+    /// | `Int`     | `UInt64` if not negative, `Float` if exact, `Decimal`             |
+    /// | `UInt64`  | `Int` up to `i64::MAX`, `Float` if exact, `Decimal`               |
+    /// | `Float`   | the integer types if integral and in range, `Decimal`             |
+    /// | `Decimal` | the integer types and `Float` if integral and in range            |
+    ///
+    /// `Int32` and `Int16` fields hold the `Int` columns in their range, and a float is
+    /// exact up to 2^53. Nulls fit any field.
+    ///
+    /// # Returns
+    ///
+    /// Returns the column itself if it is stored as it is, the converted column, or `None`
+    /// if the column doesn't convert into the type.
+    pub fn coerce(&self, field_type: FieldType) -> Option<Cow<'_, Column>> {
+        let integer = match self {
+            Column::Int(value) => Some(i128::from(*value)),
+            Column::UInt64(value) => Some(i128::from(*value)),
+            // infinite and NaN floats have no fractional part of zero
+            Column::Float(value) if value.fract() == 0.0 && value.abs() < 2_f64.powi(64) => {
+                Some(*value as i128)
+            }
+            Column::Decimal(value) => value.rescale(0).map(|integer| integer.mantissa()),
+            _ => None,
+        };
+        let converted = match (field_type, self) {
+            (_, Column::Null) => return Some(Cow::Borrowed(self)),
+            (FieldType::Int, _) => integer
+                .and_then(|value| i64::try_from(value).ok())
+                .map(Column::Int),
+            (FieldType::Int32, _) => integer
+                .and_then(|value| i32::try_from(value).ok())
+                .map(|value| Column::Int(value.into())),
+            (FieldType::Int16, _) => integer
+                .and_then(|value| i16::try_from(value).ok())
+                .map(|value| Column::Int(value.into())),
+            (FieldType::UInt64, _) => integer
+                .and_then(|value| u64::try_from(value).ok())
+                .map(Column::UInt64),
+            (FieldType::Float, Column::Float(_)) | (FieldType::Decimal, Column::Decimal(_)) => {
+                return Some(Cow::Borrowed(self));
+            }
+            (FieldType::Float, _) => integer
+                .filter(|value| value.unsigned_abs() <= 1 << 53)
+                .map(|value| Column::Float(value as f64)),
+            // floats are displayed with the shortest digits reading back as the same float
+            (FieldType::Decimal, Column::Float(value)) => {
+                value.to_string().parse().ok().map(Column::Decimal)
+            }
+            (FieldType::Decimal, _) => integer
+                .and_then(|value| Decimal::new(value, 0).ok())
+                .map(Column::Decimal),
+            (field_type, column) => {
+                return column.is_of_type(field_type).then_some(Cow::Borrowed(self));
+            }
+        };
+        converted.map(|converted| {
+            if converted == *self {
+                Cow::Borrowed(self)
+            } else {
+                Cow::Owned(converted)
+            }
+        })
+    }
+
+    /// Compares columns of any type, in the order their tuple encodings sort in keys.
+    ///
+    /// Nulls come first, then bytes, strings, arrays, integers, floats, booleans and UUIDs.
+    /// Unsigned integers, dates, times and timestamps sort along with the integers they
+    /// are encoded as, and decimals along with the bytes they are encoded as. Arrays
+    /// compare element by element, a prefix first. Floats are compared with
+    /// [`f64::total_cmp`] once made canonical, as their tuple encoding does: negative zero
+    /// is equal to zero, and every NaN sorts after the positive infinity.
+    pub fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Column::String(a), Column::String(b)) => a.cmp(b),
+            (Column::Float(a), Column::Float(b)) => {
+                canonical_float(*a).total_cmp(&canonical_float(*b))
+            }
+            (Column::Bool(a), Column::Bool(b)) => a.cmp(b),
+            (Column::Uuid(a), Column::Uuid(b)) => a.cmp(b),
+            (Column::Array(a), Column::Array(b)) => zip(a, b)
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (a, b) => match (a.packed_int(), b.packed_int()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => match (a.packed_bytes(), b.packed_bytes()) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    _ => a.type_rank().cmp(&b.type_rank()),
+                },
+            },
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Column::Null => 0,
+            Column::Bytes(_) | Column::Decimal(_) => 1,
+            Column::String(_) => 2,
+            Column::Array(_) => 3,
+            Column::Int(_)
+            | Column::UInt64(_)
+            | Column::Date(_)
+            | Column::Time(_)
+            | Column::Timestamp(_) => 4,
+            Column::Float(_) => 5,
+            Column::Bool(_) => 6,
+            Column::Uuid(_) => 7,
+        }
+    }
+
+    /// Returns the integer an integer or temporal column is tuple-encoded as.
+    fn packed_int(&self) -> Option<i128> {
+        match self {
+            Column::Int(value) | Column::Time(value) | Column::Timestamp(value) => {
+                Some((*value).into())
+            }
+            Column::UInt64(value) => Some((*value).into()),
+            Column::Date(value) => Some((*value).into()),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes a bytes or decimal column is tuple-encoded as.
+    fn packed_bytes(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            Column::Bytes(value) => Some(value.into()),
+            Column::Decimal(value) => Some(value.key().into()),
+            _ => None,
+        }
+    }
+
+    /// Types a column read from a stored row after its field, see
+    /// [`Column::with_field_type`], the elements of an array after the item type of the
+    /// field, and the position of an enum variant as its text.
+    pub(crate) fn with_field(self, field: &Field) -> Column {
+        match (self, field.r#type, field.item_type) {
+            (Column::Int(ordinal), FieldType::Enum, _) => {
+                match usize::try_from(ordinal)
+                    .ok()
+                    .and_then(|i| field.variants.get(i))
+                {
+                    Some(variant) => Column::String(variant.clone()),
+                    None => Column::Int(ordinal),
+                }
+            }
+            (Column::Bytes(value), FieldType::Array, item_type) => {
+                match array_from_bytes(&value, item_type) {
+                    Some(values) => Column::Array(values),
+                    None => Column::Bytes(value),
+                }
+            }
+            (column, field_type, _) => column.with_field_type(field_type),
+        }
+    }
+
+    /// Types a column read from a stored row after the type of its field: stored rows hold
+    /// temporal columns as the integers they are encoded as, and UUIDs and decimals as
+    /// bytes.
+    pub(crate) fn with_field_type(self, field_type: FieldType) -> Column {
+        match (self, field_type) {
+            (Column::Int(value), FieldType::Date) => {
+                i32::try_from(value).map_or(Column::Int(value), Column::Date)
+            }
+            (Column::Int(value), FieldType::Time) => Column::Time(value),
+            (Column::Int(value), FieldType::UInt64) => Column::UInt64(value as u64),
+            (Column::Int(value), FieldType::Timestamp) => Column::Timestamp(value),
+            (Column::Bytes(value), FieldType::Uuid) => match <[u8; 16]>::try_from(&value[..]) {
+                Ok(uuid) => Column::Uuid(uuid),
+                Err(_) => Column::Bytes(value),
+            },
+            (Column::Bytes(value), FieldType::Decimal) => match Decimal::from_stored(&value) {
+                Some(decimal) => Column::Decimal(decimal),
+                None => Column::Bytes(value),
+            },
+            (column, _) => column,
+        }
+    }
+}
+
+/// Columns of the same type compare by value, as do integers and unsigned integers,
+/// columns of other types don't compare. Nulls are equal to each other, consistently with
+/// `PartialEq`.
+impl PartialOrd for Column {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Column::String(a), Column::String(b)) => a.partial_cmp(b),
+            (Column::Int(a), Column::Int(b)) => a.partial_cmp(b),
+            (Column::UInt64(a), Column::UInt64(b)) => a.partial_cmp(b),
+            (Column::Int(a), Column::UInt64(b)) => i128::from(*a).partial_cmp(&i128::from(*b)),
+            (Column::UInt64(a), Column::Int(b)) => i128::from(*a).partial_cmp(&i128::from(*b)),
+            (Column::Float(a), Column::Float(b)) => a.partial_cmp(b),
+            (Column::Bool(a), Column::Bool(b)) => a.partial_cmp(b),
+            (Column::Bytes(a), Column::Bytes(b)) => a.partial_cmp(b),
+            (Column::Date(a), Column::Date(b)) => a.partial_cmp(b),
+            (Column::Time(a), Column::Time(b)) => a.partial_cmp(b),
+            (Column::Timestamp(a), Column::Timestamp(b)) => a.partial_cmp(b),
+            (Column::Uuid(a), Column::Uuid(b)) => a.partial_cmp(b),
+            (Column::Decimal(a), Column::Decimal(b)) => a.partial_cmp(b),
+            (Column::Array(a), Column::Array(b)) => a.partial_cmp(b),
+            (Column::Null, Column::Null) => Some(std::cmp::Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Column {
+    fn from(value: &str) -> Self {
+        Column::String(value.to_string())
+    }
+}
+
+impl From<String> for Column {
+    fn from(value: String) -> Self {
+        Column::String(value)
+    }
+}
+
+impl From<i64> for Column {
+    fn from(value: i64) -> Self {
+        Column::Int(value)
+    }
+}
+
+/// Lets integer literals, which default to `i32`, be used as columns.
+impl From<i32> for Column {
+    fn from(value: i32) -> Self {
+        Column::Int(value.into())
+    }
+}
+
+impl From<i16> for Column {
+    fn from(value: i16) -> Self {
+        Column::Int(value.into())
+    }
+}
+
+impl From<u64> for Column {
+    fn from(value: u64) -> Self {
+        Column::UInt64(value)
+    }
+}
+
+impl From<f64> for Column {
+    fn from(value: f64) -> Self {
+        Column::Float(value)
+    }
+}
+
+impl From<bool> for Column {
+    fn from(value: bool) -> Self {
+        Column::Bool(value)
+    }
+}
+
+impl From<Vec<u8>> for Column {
+    fn from(value: Vec<u8>) -> Self {
+        Column::Bytes(value)
+    }
+}
+
+/// `None` is a null column.
+impl<T: Into<Column>> From<Option<T>> for Column {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Column::Null, Into::into)
+    }
+}
+
 impl From<Row> for Record {
     fn from(value: Row) -> Self {
         let columns = value
@@ -31,6 +722,34 @@ impl From<Row> for Record {
     }
 }
 
+impl From<&crate::row::Column> for Column {
+    fn from(value: &crate::row::Column) -> Self {
+        match value {
+            crate::row::Column {
+                column_string: Some(column),
+                ..
+            } => Column::String(column.0.clone()),
+            crate::row::Column {
+                column_int: Some(column),
+                ..
+            } => Column::Int(column.0),
+            crate::row::Column {
+                column_float: Some(column),
+                ..
+            } => Column::Float(column.0),
+            crate::row::Column {
+                column_bool: Some(column),
+                ..
+            } => Column::Bool(column.0),
+            crate::row::Column {
+                column_bytes: Some(column),
+                ..
+            } => Column::Bytes(column.0.clone()),
+            _ => Column::Null,
+        }
+    }
+}
+
 impl From<crate::row::Column> for Column {
     fn from(value: crate::row::Column) -> Self {
         if let Some(column) = value.column_string {
@@ -65,6 +784,33 @@ impl<'a> Columns<'a> {
     }
 }
 
+/// Type code of the UUIDs in the tuple encoding, followed by their 16 raw bytes.
+const UUID_CODE: u8 = 0x30;
+
+/// The positive quiet NaN, the only NaN written in keys.
+const CANONICAL_NAN: f64 = f64::from_bits(0x7ff8_0000_0000_0000);
+
+/// Returns the float a float column is tuple-encoded as, so that the floats equal to each
+/// other share a key: negative zero is encoded as zero, and every NaN as the positive
+/// quiet NaN, sorting after the positive infinity.
+///
+/// Records can't hold NaN, it is only expected in the rows written before it was
+/// rejected. The keys of negative zeros written before they were made canonical only
+/// match again once their index is rebuilt.
+fn canonical_float(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else if value.is_nan() {
+        CANONICAL_NAN
+    } else {
+        value
+    }
+}
+
+/// Type codes starting and ending a nested tuple in the tuple encoding.
+const NESTED_CODE: u8 = 0x05;
+const NESTED_END: u8 = 0x00;
+
 impl TuplePack for Column {
     fn pack<W: Write>(
         &self,
@@ -74,9 +820,31 @@ impl TuplePack for Column {
         match self {
             Column::String(value) => value.pack(w, tuple_depth),
             Column::Int(value) => value.pack(w, tuple_depth),
-            Column::Float(value) => value.pack(w, tuple_depth),
+            Column::UInt64(value) => value.pack(w, tuple_depth),
+            Column::Float(value) => canonical_float(*value).pack(w, tuple_depth),
             Column::Bool(value) => value.pack(w, tuple_depth),
             Column::Bytes(value) => value.pack(w, tuple_depth),
+            Column::Date(value) => value.pack(w, tuple_depth),
+            Column::Time(value) | Column::Timestamp(value) => value.pack(w, tuple_depth),
+            Column::Uuid(value) => {
+                w.write_all(&[UUID_CODE])?;
+                w.write_all(value)?;
+                Ok(VersionstampOffset::None {
+                    size: 1 + value.len() as u32,
+                })
+            }
+            Column::Decimal(value) => value.key().pack(w, tuple_depth),
+            Column::Array(values) => {
+                let mut bytes = vec![NESTED_CODE];
+                for value in values {
+                    value.pack(&mut bytes, tuple_depth.increment())?;
+                }
+                bytes.push(NESTED_END);
+                w.write_all(&bytes)?;
+                Ok(VersionstampOffset::None {
+                    size: bytes.len() as u32,
+                })
+            }
             Column::Null => ().pack(w, tuple_depth),
         }
     }
@@ -92,6 +860,67 @@ impl TuplePack for Columns<'_> {
     }
 }
 
+/// The columns of a primary key or index entry, each encoded in the direction of its key
+/// column, see [`Direction`], the columns past the end of the directions being ascending.
+///
+/// An ascending column is tuple-encoded as any column. A descending column is encoded as
+/// the byte string of its complemented tuple encoding, so that larger values sort first
+/// and nulls last, the keys staying valid tuples.
+pub(crate) struct KeyColumns<'a> {
+    columns: &'a [&'a Column],
+    directions: &'a [Direction],
+}
+
+impl<'a> KeyColumns<'a> {
+    pub(crate) fn new(columns: &'a [&'a Column], directions: &'a [Direction]) -> Self {
+        Self {
+            columns,
+            directions,
+        }
+    }
+}
+
+/// Returns the bytes a column of a descending key column is encoded as: its tuple encoding
+/// with every byte complemented, followed by `0xff`.
+///
+/// Complementing reverses the order of two encodings differing at a byte, but not of an
+/// encoding and a longer one it starts, e.g. a string and the same string followed by a
+/// nul character, whose escape sequence goes on where the shorter one ends with `0x00`.
+/// That escape sequence continues with `0xff`, complemented to `0x00` below the final
+/// `0xff` of the shorter encoding, which therefore sorts after the longer one.
+fn descending_key(column: &Column, tuple_depth: TupleDepth) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    column.pack(&mut bytes, tuple_depth)?;
+    for byte in &mut bytes {
+        *byte = !*byte;
+    }
+    bytes.push(0xff);
+    Ok(bytes)
+}
+
+impl TuplePack for KeyColumns<'_> {
+    /// Packs the columns as the elements of the key, which is never nested in a tuple.
+    fn pack<W: Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> std::io::Result<VersionstampOffset> {
+        let mut bytes = vec![];
+        let depth = tuple_depth.increment();
+        let directions = self.directions.iter().chain(repeat(&Direction::Ascending));
+        for (column, direction) in zip(self.columns, directions) {
+            match direction {
+                Direction::Ascending => column.pack(&mut bytes, depth)?,
+                Direction::Descending => descending_key(column, depth)?.pack(&mut bytes, depth)?,
+            };
+        }
+        w.write_all(&bytes)?;
+        Ok(VersionstampOffset::None {
+            size: bytes.len() as u32,
+        })
+    }
+}
+
 impl From<&Record> for Row {
     fn from(value: &Record) -> Self {
         let mut row = Row::new();
@@ -112,8 +941,16 @@ impl From<&Column> for crate::row::Column {
             Column::String(value) => crate::row::Column::new_string(value.to_string()),
             Column::Int(value) => crate::row::Column::new_int(*value),
             Column::Float(value) => crate::row::Column::new_float(*value),
+            // typed back after their fields, see `Record::from_stored_row`
+            Column::UInt64(value) => crate::row::Column::new_int(*value as i64),
             Column::Bool(value) => crate::row::Column::new_bool(*value),
             Column::Bytes(value) => crate::row::Column::new_bytes(value.clone()),
+            // typed back after their fields, see `Record::from_stored_row`
+            Column::Date(value) => crate::row::Column::new_int((*value).into()),
+            Column::Time(value) | Column::Timestamp(value) => crate::row::Column::new_int(*value),
+            Column::Uuid(value) => crate::row::Column::new_bytes(value.to_vec()),
+            Column::Decimal(value) => crate::row::Column::new_bytes(value.to_stored()),
+            Column::Array(values) => crate::row::Column::new_bytes(array_bytes(values)),
             Column::Null => {
                 unreachable!("Null column is not allowed in a record")
             }
@@ -121,10 +958,42 @@ impl From<&Column> for crate::row::Column {
     }
 }
 
+/// Encodes the elements of an array as a row of the generic schema, stored rows holding
+/// arrays as these bytes.
+pub(crate) fn array_bytes(values: &[Column]) -> Vec<u8> {
+    let mut row = Row::new();
+    for value in values {
+        match value {
+            Column::Null => row.add_null_column(),
+            value => row.add_column(value.into()),
+        }
+    }
+    row.to_bytes()
+        .expect("Rows of columns always match the generic schema")
+}
+
+/// Decodes the elements of an array written by [`array_bytes`], typed after the item type
+/// of their field.
+fn array_from_bytes(bytes: &[u8], item_type: Option<FieldType>) -> Option<Vec<Column>> {
+    let row = Row::from_bytes(bytes).ok()?;
+    let values = Record::from(row).columns.into_iter();
+    Some(match item_type {
+        Some(item_type) => values
+            .map(|value| value.with_field_type(item_type))
+            .collect(),
+        None => values.collect(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::record::{Column, Record};
+    use crate::errors::SqlLayerError;
+    use crate::plan::Direction;
+    use crate::record::{Column, KeyColumns, Record};
     use crate::row::Row;
+    use crate::table::{Field, FieldType, Table};
+    use crate::validation::{Violation, ViolationKind};
+    use foundationdb_tuple::pack;
 
     #[test]
     fn test_convert_row_to_record() {
@@ -150,4 +1019,564 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_project_record() {
+        let record = Record {
+            columns: vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(20.5),
+            ],
+        };
+        assert_eq!(
+            record.project(&[2, 0]),
+            Record {
+                columns: vec![Column::Float(20.5), Column::String("John".to_string())]
+            }
+        );
+    }
+
+    #[test]
+    fn test_total_cmp() {
+        let mut columns = vec![
+            Column::Bool(false),
+            Column::Float(-1.5),
+            Column::Int(3),
+            Column::Int(-2),
+            Column::String("b".to_string()),
+            Column::String("a".to_string()),
+            Column::Bytes(vec![1]),
+            Column::Null,
+        ];
+        columns.sort_by(Column::total_cmp);
+        assert_eq!(
+            columns,
+            vec![
+                Column::Null,
+                Column::Bytes(vec![1]),
+                Column::String("a".to_string()),
+                Column::String("b".to_string()),
+                Column::Int(-2),
+                Column::Int(3),
+                Column::Float(-1.5),
+                Column::Bool(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_temporal_columns() {
+        // temporal columns sort chronologically, as the integers they are encoded as
+        let dates = [Column::Date(-1), Column::Date(0), Column::Date(19_782)];
+        for pair in dates.windows(2) {
+            assert!(pair[0].total_cmp(&pair[1]).is_lt());
+            assert!(pack(&pair[0]) < pack(&pair[1]));
+        }
+        assert_eq!(pack(&Column::Timestamp(-5)), pack(&-5i64));
+        assert!(Column::Timestamp(5).total_cmp(&Column::Int(3)).is_gt());
+
+        // stored rows hold integers, typed after their fields
+        let mut table = Table::new("Event".to_string(), vec!["day".to_string()]);
+        table.add_field(Field::new("day".to_string(), FieldType::Date));
+        table.add_field(Field::new("at".to_string(), FieldType::Time));
+        let mut created = Field::new("created".to_string(), FieldType::Timestamp);
+        created.set_default(Column::Timestamp(0)).unwrap();
+        table.add_field(created);
+        let record = Record::new(vec![Column::Date(-1), Column::Time(1), Column::Null]);
+        let row = record.to_stored_row(&table);
+        assert_eq!(row.columns[0], Some(crate::row::Column::new_int(-1)));
+        let mut row = Row::from(&record);
+        row.columns.pop();
+        assert_eq!(
+            Record::from_stored_row(row, &table),
+            Record::new(vec![
+                Column::Date(-1),
+                Column::Time(1),
+                Column::Timestamp(0)
+            ])
+        );
+        let bytes = table.to_bytes().expect("Unable to serialize table");
+        let table = Table::from_bytes(&bytes).expect("Unable to deserialize table");
+        assert_eq!(table.fields[2].default, Some(Column::Timestamp(0)));
+    }
+
+    #[test]
+    fn test_uuid_columns() {
+        let low = Column::Uuid([0; 16]);
+        let high = Column::Uuid([0xff; 16]);
+        let mut packed = vec![0x30];
+        packed.extend([0xff; 16]);
+        assert_eq!(pack(&high), packed);
+        assert!(pack(&low) < pack(&high));
+        assert!(low.total_cmp(&high).is_lt());
+        assert!(high.total_cmp(&Column::Bool(true)).is_gt());
+
+        let mut table = Table::new("Person".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Uuid));
+        let row = Row::from(&Record::new(vec![high.clone()]));
+        assert_eq!(
+            row.columns[0],
+            Some(crate::row::Column::new_bytes(vec![0xff; 16]))
+        );
+        assert_eq!(
+            Record::from_stored_row(row, &table),
+            Record::new(vec![high])
+        );
+    }
+
+    #[test]
+    fn test_decimal_columns() {
+        let decimal = |text: &str| Column::Decimal(text.parse().expect("Invalid decimal"));
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert!(decimal("-4.05").total_cmp(&decimal("4")).is_lt());
+        assert!(pack(&decimal("-4.05")) < pack(&decimal("4")));
+        assert!(pack(&(decimal("4"), 1)) < pack(&(decimal("4.05"), 0)));
+        assert!(decimal("4").is_of_type(FieldType::Decimal));
+
+        let mut table = Table::new("Payment".to_string(), vec!["amount".to_string()]);
+        table.add_field(Field::new_decimal("amount".to_string(), 2));
+        let record = Record::new(vec![decimal("-12.50")]);
+        let row = Row::from(&record);
+        assert_eq!(Record::from_stored_row(row, &table), record);
+    }
+
+    #[test]
+    fn test_array_columns() {
+        let array =
+            |values: &[i64]| Column::Array(values.iter().copied().map(Column::Int).collect());
+        let ordered = [
+            Column::String("a".to_string()),
+            array(&[]),
+            array(&[1]),
+            array(&[1, 2]),
+            array(&[2]),
+            Column::Int(0),
+        ];
+        for pair in ordered.windows(2) {
+            assert!(pair[0].total_cmp(&pair[1]).is_lt(), "{pair:?}");
+            assert!(pack(&pair[0]) < pack(&pair[1]), "{pair:?}");
+        }
+        let with_null = Column::Array(vec![Column::Null]);
+        assert!(array(&[]).total_cmp(&with_null).is_lt());
+        assert!(with_null.total_cmp(&array(&[1])).is_lt());
+        assert!(pack(&array(&[])) < pack(&with_null));
+        assert!(pack(&with_null) < pack(&array(&[1])));
+
+        let mut table = Table::new("Event".to_string(), vec!["days".to_string()]);
+        table.add_field(Field::new_array("days".to_string(), FieldType::Date));
+        let record = Record::new(vec![Column::Array(vec![
+            Column::Date(19_782),
+            Column::Null,
+        ])]);
+        let row = Row::from(&record);
+        assert!(row.columns[0].as_ref().unwrap().column_bytes.is_some());
+        assert_eq!(Record::from_stored_row(row, &table), record);
+    }
+
+    #[test]
+    fn test_integer_columns() {
+        assert!(Column::Int(-1).total_cmp(&Column::UInt64(0)).is_lt());
+        assert!(Column::UInt64(u64::MAX)
+            .total_cmp(&Column::Float(0.0))
+            .is_lt());
+        assert_eq!(pack(&Column::UInt64(5)), pack(&Column::Int(5)));
+        assert!(pack(&Column::Int(i64::MAX)) < pack(&Column::UInt64(u64::MAX)));
+        assert_eq!(
+            Column::Int(5).partial_cmp(&Column::UInt64(5)),
+            Some(std::cmp::Ordering::Equal)
+        );
+
+        let mut table = Table::new("Counter".to_string(), vec!["hits".to_string()]);
+        table.add_field(Field::new("hits".to_string(), FieldType::UInt64));
+        let record = Record::new(vec![Column::UInt64(u64::MAX)]);
+        let row = record.to_stored_row(&table);
+        assert_eq!(Record::from_stored_row(row, &table), record);
+    }
+
+    #[test]
+    fn test_float_keys() {
+        let ordered = [
+            f64::NEG_INFINITY,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.5,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .map(Column::Float);
+        for pair in ordered.windows(2) {
+            assert!(pair[0].total_cmp(&pair[1]).is_lt(), "{pair:?}");
+            assert!(pack(&pair[0]) < pack(&pair[1]), "{pair:?}");
+        }
+
+        // equal floats share a key
+        assert_eq!(pack(&Column::Float(-0.0)), pack(&Column::Float(0.0)));
+        assert!(Column::Float(-0.0).total_cmp(&Column::Float(0.0)).is_eq());
+        let negative_nan = Column::Float(-f64::NAN);
+        assert_eq!(pack(&negative_nan), pack(&Column::Float(f64::NAN)));
+        assert!(negative_nan
+            .total_cmp(&Column::Float(f64::INFINITY))
+            .is_gt());
+    }
+
+    #[test]
+    fn test_descending_keys() {
+        let key = |columns: &[&Column], directions: &[Direction]| {
+            pack(&KeyColumns::new(columns, directions))
+        };
+        let descending = |column: &Column| key(&[column], &[Direction::Descending]);
+
+        // every encoding starting another one, as a string and a string it prefixes, is
+        // reversed as well
+        let ordered = [
+            Column::Null,
+            Column::Bytes(vec![]),
+            Column::Bytes(vec![0]),
+            Column::String("a".to_string()),
+            Column::String("a\0".to_string()),
+            Column::String("ab".to_string()),
+            Column::Array(vec![]),
+            Column::Array(vec![Column::Null]),
+            Column::Int(-1),
+            Column::Int(0),
+            Column::Int(1 << 40),
+            Column::Float(0.5),
+            Column::Bool(true),
+        ];
+        for pair in ordered.windows(2) {
+            assert!(descending(&pair[0]) > descending(&pair[1]), "{pair:?}");
+        }
+        let [low, high] = ["4.5", "4.55"].map(|text| Column::Decimal(text.parse().unwrap()));
+        assert!(descending(&low) > descending(&high));
+
+        // the columns past the directions are ascending
+        let (one, two) = (Column::Int(1), Column::Int(2));
+        assert_eq!(key(&[&one, &two], &[]), pack(&(1, 2)));
+        let descending_first = [Direction::Descending];
+        assert!(key(&[&two, &one], &descending_first) < key(&[&one, &one], &descending_first));
+        assert!(key(&[&one, &one], &descending_first) < key(&[&one, &two], &descending_first));
+    }
+
+    #[test]
+    fn test_coerce() {
+        let coerce = |column: Column, field_type| {
+            column.coerce(field_type).map(std::borrow::Cow::into_owned)
+        };
+        let decimal = |text: &str| Column::Decimal(text.parse().unwrap());
+        assert_eq!(
+            coerce(Column::Int(3), FieldType::Float),
+            Some(Column::Float(3.0))
+        );
+        assert_eq!(coerce(Column::Int(1 << 54), FieldType::Float), None);
+        assert_eq!(
+            coerce(Column::Int(3), FieldType::Decimal),
+            Some(decimal("3"))
+        );
+        assert_eq!(
+            coerce(Column::Int(3), FieldType::UInt64),
+            Some(Column::UInt64(3))
+        );
+        assert_eq!(coerce(Column::Int(-3), FieldType::UInt64), None);
+        assert_eq!(
+            coerce(Column::UInt64(3), FieldType::Int),
+            Some(Column::Int(3))
+        );
+        assert_eq!(coerce(Column::UInt64(u64::MAX), FieldType::Int), None);
+        assert_eq!(
+            coerce(Column::Float(3.0), FieldType::Int16),
+            Some(Column::Int(3))
+        );
+        assert_eq!(coerce(Column::Float(3.5), FieldType::Int), None);
+        assert_eq!(coerce(Column::Float(f64::NAN), FieldType::Int), None);
+        assert_eq!(
+            coerce(Column::Float(-4.05), FieldType::Decimal),
+            Some(decimal("-4.05"))
+        );
+        assert_eq!(
+            coerce(decimal("3.00"), FieldType::Int32),
+            Some(Column::Int(3))
+        );
+        assert_eq!(coerce(decimal("3.5"), FieldType::Int32), None);
+        assert_eq!(coerce(Column::Int(40_000), FieldType::Int16), None);
+        assert_eq!(
+            coerce(Column::Int(40_000), FieldType::Int32),
+            Some(Column::Int(40_000))
+        );
+        assert_eq!(coerce(Column::Int(1 << 40), FieldType::Int32), None);
+        assert_eq!(
+            coerce(Column::String("3".to_string()), FieldType::Int),
+            None
+        );
+        assert_eq!(coerce(Column::Null, FieldType::UInt64), Some(Column::Null));
+
+        // columns of the type of the field are stored as they are
+        let column = Column::Int(3);
+        assert!(matches!(
+            column.coerce(FieldType::Int),
+            Some(std::borrow::Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_enum_columns() {
+        let mut table = Table::new("Order".to_string(), vec!["id".to_string()]);
+        table.add_field(Field::new("id".to_string(), FieldType::Int));
+        table.add_field(Field::new("note".to_string(), FieldType::String));
+        table.add_field(Field::new_enum(
+            "status".to_string(),
+            vec!["pending".to_string(), "shipped".to_string()],
+        ));
+        table.drop_field("note").expect("Unable to drop field");
+        assert!(Column::String("shipped".to_string()).is_of_type(FieldType::Enum));
+
+        // stored rows hold the position of the variant
+        let record = Record::new(vec![Column::Int(1), Column::String("shipped".to_string())]);
+        let row = record.to_stored_row(&table);
+        assert_eq!(
+            row.columns[2]
+                .as_ref()
+                .unwrap()
+                .column_int
+                .as_ref()
+                .unwrap()
+                .0,
+            1
+        );
+        assert_eq!(Record::from_stored_row(row, &table), record);
+
+        let record = Record::new(vec![Column::Int(2), Column::Null]);
+        let row = record.to_stored_row(&table);
+        assert_eq!(Record::from_stored_row(row, &table), record);
+    }
+
+    #[test]
+    fn test_from_named() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+
+        let record = Record::from_named(
+            &table,
+            vec![
+                ("height", Column::Float(1.8)),
+                ("name", Column::String("John".to_string())),
+                ("age", Column::Int(20)),
+            ],
+        )
+        .expect("Unable to build record");
+        assert_eq!(
+            record.columns,
+            vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(1.8)
+            ]
+        );
+
+        let result = Record::from_named(
+            &table,
+            vec![
+                ("name", Column::String("John".to_string())),
+                ("weight", Column::Float(80.0)),
+                ("name", Column::String("Jane".to_string())),
+            ],
+        );
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        let violation = |field: &str, kind| Violation {
+            field: Some(field.to_string()),
+            kind,
+        };
+        assert_eq!(
+            report.violations,
+            vec![
+                violation("weight", ViolationKind::UnknownColumn),
+                violation("name", ViolationKind::DuplicateColumn),
+                violation("age", ViolationKind::MissingColumn),
+                violation("height", ViolationKind::MissingColumn),
+            ]
+        );
+
+        // omitted fields take their default
+        table.fields[1].set_default(Column::Int(18)).unwrap();
+        let record = Record::from_named(
+            &table,
+            vec![
+                ("name", Column::String("John".to_string())),
+                ("height", Column::Float(1.8)),
+            ],
+        )
+        .expect("Unable to build record");
+        assert_eq!(record.columns[1], Column::Int(18));
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+
+        let record = Record::builder(&table)
+            .set("age", 20)
+            .and_then(|builder| builder.set("name", "John"))
+            .and_then(|builder| builder.set("photo", None::<Vec<u8>>))
+            .and_then(|builder| builder.set("height", 1.8))
+            .and_then(|builder| builder.build())
+            .expect("Unable to build record");
+        assert_eq!(
+            record.columns,
+            vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(1.8),
+                Column::Null
+            ]
+        );
+
+        let result = Record::builder(&table).set("weight", 80.0);
+        assert!(matches!(result, Err(SqlLayerError::MissingColumn(_))));
+        let result = Record::builder(&table).set("age", "twenty");
+        assert!(matches!(
+            result,
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+
+        let result = Record::builder(&table)
+            .set("name", "John")
+            .and_then(|builder| builder.build());
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        let fields = report
+            .violations
+            .iter()
+            .map(|violation| violation.field.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![Some("age"), Some("height"), Some("photo")]);
+
+        table.fields[3].set_default(Column::Bytes(vec![])).unwrap();
+        let result = Record::builder(&table)
+            .set("name", "John")
+            .and_then(|builder| builder.build());
+        let Err(SqlLayerError::Validation(report)) = &result else {
+            panic!("Expected a validation error, got {result:?}");
+        };
+        assert_eq!(report.violations.len(), 2);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        let record = Record {
+            columns: vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Float(1.8),
+                Column::Bool(true),
+                Column::Null,
+            ],
+        };
+
+        assert_eq!(
+            record.get::<String>(&table, "name").unwrap(),
+            Some("John".to_string())
+        );
+        assert_eq!(record.get::<i64>(&table, "age").unwrap(), Some(20));
+        assert_eq!(record.get::<f64>(&table, "height").unwrap(), Some(1.8));
+        assert_eq!(
+            record.get::<bool>(&table, "is_married").unwrap(),
+            Some(true)
+        );
+        assert_eq!(record.get::<Vec<u8>>(&table, "photo").unwrap(), None);
+
+        assert!(matches!(
+            record.get::<i64>(&table, "weight"),
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+        assert!(matches!(
+            record.get::<i64>(&table, "name"),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_column_as() {
+        let record = Record::new(vec![Column::Int(20), Column::Null]);
+        assert_eq!(record.column_as::<i64>(0).unwrap(), 20);
+        assert_eq!(record.column_as::<Option<i64>>(0).unwrap(), Some(20));
+        assert_eq!(record.column_as::<Option<String>>(1).unwrap(), None);
+        assert_eq!(record.column_as::<Option<bool>>(2).unwrap(), None);
+        assert!(matches!(
+            record.column_as::<String>(1),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        assert!(matches!(
+            record.column_as::<String>(0),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_sql_record() {
+        use crate::SqlRecord;
+
+        #[derive(Clone, Debug, PartialEq, SqlRecord)]
+        #[sql_record(table = "Person")]
+        struct Person {
+            #[sql_record(primary_key)]
+            name: String,
+            age: i64,
+            height: Option<f64>,
+            photo: Vec<u8>,
+        }
+
+        let table = Person::table();
+        assert_eq!(table.name, "Person");
+        assert_eq!(table.primary_key, vec!["name".to_string()]);
+        let fields = table
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.r#type, field.nullable))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("name", FieldType::String, false),
+                ("age", FieldType::Int, false),
+                ("height", FieldType::Float, true),
+                ("photo", FieldType::Bytes, false)
+            ]
+        );
+
+        let person = Person {
+            name: "John".to_string(),
+            age: 20,
+            height: None,
+            photo: b"arbitrary data".to_vec(),
+        };
+        let record = person.clone().into_record();
+        assert_eq!(
+            record.columns,
+            vec![
+                Column::String("John".to_string()),
+                Column::Int(20),
+                Column::Null,
+                Column::Bytes(b"arbitrary data".to_vec())
+            ]
+        );
+        assert_eq!(Person::from_record(record).unwrap(), person);
+    }
 }
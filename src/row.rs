@@ -1,3 +1,4 @@
+use crate::schema::SchemaMode;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, Bytes};
 
@@ -84,11 +85,12 @@ impl Row {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> crate::errors::Result<Self> {
-        let schema = apache_avro::schema::Schema::parse_str(SCHEMA)?;
-        let mut data = bytes;
-        let value = apache_avro::from_avro_datum(&schema, &mut data, None)?;
-        let row = apache_avro::from_value::<Row>(&value)?;
-        Ok(row)
+        Self::from_bytes_with_mode(bytes, SchemaMode::Compat)
+    }
+
+    /// Deserializes bytes written by this or a newer version of the crate.
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Self> {
+        crate::schema::decode_datum(SCHEMA, bytes, mode)
     }
 }
 
@@ -0,0 +1,515 @@
+//! # Row Schema Module
+//!
+//! Rows were first stored with the generic schema of [`Row`], a union of every column type
+//! for each column, which repeats the bookkeeping of every type in every row and doesn't
+//! tell which field a column belongs to. A [`RowSchema`] is instead generated from the
+//! definition of a table: an Avro record with a field per column of the stored rows, of
+//! the type of the table field, and only nullable when the table field is.
+//!
+//! Rows are encoded in the Avro single object encoding: the `C3 01` marker, the 64-bit
+//! Rabin fingerprint of the schema, little-endian, then the datum. The schema is stored
+//! along with the table metadata under its fingerprint, so that rows written with a
+//! previous definition of the table remain readable. A row of the generic schema never
+//! starts with the marker, which would be a negative count of columns.
+//!
+//! The fields of the record are named after the table fields. The names Avro doesn't
+//! accept, or starting with an underscore, are replaced with `_` and the position of the
+//! column, the name of the table field being kept in the documentation of the Avro field.
+//! The columns of dropped fields are `_` and their position as well, and always null.
+//! The columns of blob fields are the ids of their blobs, stored out of the rows.
+//! Dates, times and timestamps are written as the Avro `date`, `time-micros` and
+//! `timestamp-micros` logical types, UUIDs as their 16 bytes, decimals as the Avro
+//! `decimal` logical type, at the scale of their field, arrays as the bytes of their
+//! elements encoded with the generic schema, and enums as the positions of their variants.
+//! Integers of every width are Avro `long`s, the unsigned ones cast to signed ones.
+
+use crate::codec::RowCodec;
+use crate::decimal::{Decimal, MAX_SCALE};
+use crate::errors::SqlLayerError;
+use crate::record::{array_bytes, Column};
+use crate::row::Row;
+use crate::schema::SchemaMode;
+use crate::table::{Field, FieldType, Table};
+use apache_avro::rabin::Rabin;
+use apache_avro::schema::Schema;
+use apache_avro::types::Value;
+use std::iter::zip;
+
+/// Marker of the Avro single object encoding.
+const MARKER: [u8; 2] = [0xc3, 0x01];
+
+/// Length of the header of an encoded row: the marker and the fingerprint of its schema.
+const HEADER_LENGTH: usize = MARKER.len() + 8;
+
+/// The Avro schema of the rows of a table, see the module documentation.
+#[derive(Debug)]
+pub(crate) struct RowSchema {
+    json: String,
+    schema: Schema,
+    fingerprint: u64,
+    /// Name of the Avro field of every column, whether the column is nullable, and the
+    /// logical type it is written as, if any.
+    columns: Vec<(String, bool, Option<LogicalType>)>,
+}
+
+/// The Avro logical types of the columns, see the module documentation.
+#[derive(Clone, Copy, Debug)]
+enum LogicalType {
+    Date,
+    TimeMicros,
+    TimestampMicros,
+    /// A decimal of the given scale.
+    Decimal(u8),
+}
+
+impl RowSchema {
+    /// Generates the schema of the rows of a table, from its current definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Avro` if the generated schema isn't valid.
+    pub(crate) fn new(table: &Table) -> crate::errors::Result<Self> {
+        let mut columns = Vec::with_capacity(table.fields.len() + table.dropped_columns.len());
+        for position in 0..table.fields.len() + table.dropped_columns.len() {
+            let column = if table.dropped_columns.contains(&(position as i64)) {
+                format!(r#"{{"name":"_{position}","type":"null"}}"#)
+            } else {
+                let field = &table.fields[position - dropped_before(table, position)];
+                // the rows hold the id of the blob of a blob field
+                let r#type = if field.blob {
+                    r#""long""#.to_string()
+                } else {
+                    avro_type(field)
+                };
+                let nullable = field.nullable
+                    || table.allow_null_primary_key && table.primary_key.contains(&field.name);
+                let r#type = if nullable {
+                    format!(r#"["null",{type}]"#)
+                } else {
+                    r#type
+                };
+                if is_avro_name(&field.name) {
+                    format!(r#"{{"name":"{}","type":{type}}}"#, field.name)
+                } else {
+                    format!(
+                        r#"{{"name":"_{position}","doc":{},"type":{type}}}"#,
+                        json_string(&field.name)
+                    )
+                }
+            };
+            columns.push(column);
+        }
+        Self::parse(&format!(
+            r#"{{"type":"record","name":"Row","fields":[{}]}}"#,
+            columns.join(",")
+        ))
+    }
+
+    /// Parses a schema stored by [`RowSchema::json`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Avro` if the schema isn't valid, or
+    /// `SqlLayerError::Deserialization` if it isn't the schema of a row.
+    pub(crate) fn parse(json: &str) -> crate::errors::Result<Self> {
+        let schema = Schema::parse_str(json)?;
+        let Schema::Record(record) = &schema else {
+            return Err(SqlLayerError::Deserialization(format!(
+                "row schema isn't a record: {json}"
+            )));
+        };
+        let columns = record
+            .fields
+            .iter()
+            .map(|field| {
+                let nullable = matches!(field.schema, Schema::Union(_));
+                (field.name.clone(), nullable, logical_type(&field.schema))
+            })
+            .collect();
+        let fingerprint = schema.fingerprint::<Rabin>().bytes;
+        let fingerprint = u64::from_le_bytes(fingerprint.try_into().map_err(|_| {
+            SqlLayerError::Deserialization("Rabin fingerprints are 8 bytes long".to_string())
+        })?);
+        Ok(Self {
+            json: json.to_string(),
+            schema,
+            fingerprint,
+            columns,
+        })
+    }
+
+    /// Returns the schema in JSON, as stored.
+    pub(crate) fn json(&self) -> &str {
+        &self.json
+    }
+
+    /// Returns the 64-bit Rabin fingerprint of the schema, identifying it in the encoded
+    /// rows.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Returns the fingerprint of the schema an encoded row was written with, or `None` for
+    /// a row of the generic schema.
+    pub(crate) fn fingerprint_of(bytes: &[u8]) -> Option<u64> {
+        if bytes.len() < HEADER_LENGTH || bytes[..MARKER.len()] != MARKER {
+            return None;
+        }
+        let fingerprint = bytes[MARKER.len()..HEADER_LENGTH].try_into().ok()?;
+        Some(u64::from_le_bytes(fingerprint))
+    }
+}
+
+impl RowCodec for RowSchema {
+    /// Encodes a stored row, see `Record::to_stored_row`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::Avro` if the row doesn't match the schema, e.g. it has a
+    /// null column in a field that isn't nullable, or `SqlLayerError::InvalidDecimal` if
+    /// a decimal doesn't fit the scale of its field.
+    fn encode(&self, row: &Row) -> crate::errors::Result<Vec<u8>> {
+        // stored rows hold a column for every field and every dropped field
+        debug_assert_eq!(self.columns.len(), row.columns.len());
+        let fields = zip(&self.columns, &row.columns)
+            .map(|((name, nullable, logical), column)| {
+                let value = match column.as_ref().map(Column::from) {
+                    Some(column) => avro_value(column, *logical)?,
+                    None => Value::Null,
+                };
+                let value = if *nullable {
+                    Value::Union((value != Value::Null) as u32, Box::new(value))
+                } else {
+                    value
+                };
+                Ok((name.clone(), value))
+            })
+            .collect::<crate::errors::Result<Vec<_>>>()?;
+        let mut bytes = MARKER.to_vec();
+        bytes.extend_from_slice(&self.fingerprint.to_le_bytes());
+        bytes.extend(apache_avro::to_avro_datum(
+            &self.schema,
+            Value::Record(fields),
+        )?);
+        Ok(bytes)
+    }
+
+    /// Decodes a row encoded with this schema, see [`RowSchema::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The row wasn't encoded with this schema.
+    /// - The row holds unknown trailing bytes in `SchemaMode::Strict`.
+    fn decode(&self, bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Row> {
+        if Self::fingerprint_of(bytes) != Some(self.fingerprint) {
+            return Err(SqlLayerError::Deserialization(format!(
+                "row not encoded with schema {:016x}",
+                self.fingerprint
+            )));
+        }
+        let mut data = &bytes[HEADER_LENGTH..];
+        let value = apache_avro::from_avro_datum(&self.schema, &mut data, None)?;
+        if mode == SchemaMode::Strict && !data.is_empty() {
+            return Err(SqlLayerError::UnknownFields(data.len()));
+        }
+        let Value::Record(fields) = value else {
+            return Err(SqlLayerError::Deserialization(format!(
+                "row isn't a record: {value:?}"
+            )));
+        };
+        let mut row = Row::new();
+        for ((_, value), (_, _, logical)) in zip(fields, &self.columns) {
+            match column(value, *logical)? {
+                Column::Null => row.add_null_column(),
+                column => row.add_column((&column).into()),
+            }
+        }
+        Ok(row)
+    }
+}
+
+/// Counts the dropped columns stored before the given position.
+fn dropped_before(table: &Table, position: usize) -> usize {
+    table
+        .dropped_columns
+        .iter()
+        .filter(|dropped| **dropped < position as i64)
+        .count()
+}
+
+/// Writes a column of a stored row as the Avro value of its type, stored rows holding
+/// temporal columns as integers and decimals as bytes, see `Record::from_stored_row`.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidDecimal` if a decimal doesn't fit the scale of its
+/// field.
+fn avro_value(column: Column, logical: Option<LogicalType>) -> crate::errors::Result<Value> {
+    Ok(match column {
+        Column::String(value) => Value::String(value),
+        Column::Int(value) => temporal_value(logical, value),
+        Column::UInt64(value) => Value::Long(value as i64),
+        Column::Float(value) => Value::Double(value),
+        Column::Bool(value) => Value::Boolean(value),
+        Column::Bytes(value) => match (logical, Decimal::from_stored(&value)) {
+            (Some(LogicalType::Decimal(scale)), Some(decimal)) => decimal_value(decimal, scale)?,
+            _ => Value::Bytes(value),
+        },
+        Column::Date(value) => Value::Date(value),
+        Column::Time(value) => Value::TimeMicros(value),
+        Column::Timestamp(value) => Value::TimestampMicros(value),
+        Column::Uuid(value) => Value::Bytes(value.to_vec()),
+        Column::Array(values) => Value::Bytes(array_bytes(&values)),
+        Column::Decimal(value) => match logical {
+            Some(LogicalType::Decimal(scale)) => decimal_value(value, scale)?,
+            _ => Value::Bytes(value.to_stored()),
+        },
+        Column::Null => Value::Null,
+    })
+}
+
+/// Writes a decimal at the scale of its field, as the big-endian bytes of its mantissa.
+fn decimal_value(decimal: Decimal, scale: u8) -> crate::errors::Result<Value> {
+    let rescaled = decimal.rescale(scale).ok_or_else(|| {
+        SqlLayerError::InvalidDecimal(format!("{decimal} doesn't fit the scale {scale}"))
+    })?;
+    let mantissa = rescaled.mantissa().to_be_bytes().to_vec();
+    Ok(Value::Decimal(apache_avro::Decimal::from(mantissa)))
+}
+
+fn column(value: Value, logical: Option<LogicalType>) -> crate::errors::Result<Column> {
+    match value {
+        Value::Union(_, value) => column(*value, logical),
+        Value::Null => Ok(Column::Null),
+        Value::String(value) => Ok(Column::String(value)),
+        Value::Long(value) => Ok(Column::Int(value)),
+        Value::Double(value) => Ok(Column::Float(value)),
+        Value::Boolean(value) => Ok(Column::Bool(value)),
+        Value::Bytes(value) => Ok(Column::Bytes(value)),
+        Value::Date(value) => Ok(Column::Date(value)),
+        Value::TimeMicros(value) => Ok(Column::Time(value)),
+        Value::TimestampMicros(value) => Ok(Column::Timestamp(value)),
+        Value::Decimal(value) => {
+            let scale = match logical {
+                Some(LogicalType::Decimal(scale)) => scale,
+                _ => 0,
+            };
+            Vec::<u8>::try_from(value)
+                .ok()
+                .and_then(|mantissa| Decimal::from_be_bytes(&mantissa, scale))
+                .map(Column::Decimal)
+                .ok_or_else(|| {
+                    SqlLayerError::Deserialization("invalid decimal row column".to_string())
+                })
+        }
+        value => Err(SqlLayerError::Deserialization(format!(
+            "unexpected row column: {value:?}"
+        ))),
+    }
+}
+
+/// Returns the Avro type of a field, in JSON.
+fn avro_type(field: &Field) -> String {
+    match field.r#type {
+        FieldType::String => r#""string""#.to_string(),
+        // the rows hold the position of the variant of an enum field, and unsigned integers
+        // cast to signed ones
+        FieldType::Int
+        | FieldType::Int32
+        | FieldType::Int16
+        | FieldType::UInt64
+        | FieldType::Enum => r#""long""#.to_string(),
+        FieldType::Float => r#""double""#.to_string(),
+        FieldType::Bool => r#""boolean""#.to_string(),
+        FieldType::Bytes | FieldType::Uuid | FieldType::Array => r#""bytes""#.to_string(),
+        FieldType::Date => r#"{"type":"int","logicalType":"date"}"#.to_string(),
+        FieldType::Time => r#"{"type":"long","logicalType":"time-micros"}"#.to_string(),
+        FieldType::Timestamp => r#"{"type":"long","logicalType":"timestamp-micros"}"#.to_string(),
+        FieldType::Decimal => format!(
+            r#"{{"type":"bytes","logicalType":"decimal","precision":{MAX_SCALE},"scale":{}}}"#,
+            field.scale
+        ),
+    }
+}
+
+/// Returns the logical type of a column, if any.
+fn logical_type(schema: &Schema) -> Option<LogicalType> {
+    match schema {
+        Schema::Union(union) => union.variants().iter().find_map(logical_type),
+        Schema::Date => Some(LogicalType::Date),
+        Schema::TimeMicros => Some(LogicalType::TimeMicros),
+        Schema::TimestampMicros => Some(LogicalType::TimestampMicros),
+        Schema::Decimal(decimal) => Some(LogicalType::Decimal(decimal.scale as u8)),
+        _ => None,
+    }
+}
+
+/// Writes an integer of a stored row as the logical type of its column, stored rows
+/// holding temporal columns as integers, see `Record::from_stored_row`.
+fn temporal_value(logical: Option<LogicalType>, value: i64) -> Value {
+    match logical {
+        // a date out of range doesn't match the schema, failing the encoding
+        Some(LogicalType::Date) => i32::try_from(value).map_or(Value::Long(value), Value::Date),
+        Some(LogicalType::TimeMicros) => Value::TimeMicros(value),
+        Some(LogicalType::TimestampMicros) => Value::TimestampMicros(value),
+        _ => Value::Long(value),
+    }
+}
+
+/// Whether a field name can name an Avro field as is, see the module documentation.
+fn is_avro_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && chars.all(|next| next.is_ascii_alphanumeric() || next == '_')
+}
+
+/// Quotes a string as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            control if (control as u32) < 0x20 => {
+                json.push_str(&format!("\\u{:04x}", control as u32))
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::RowCodec;
+    use crate::errors::SqlLayerError;
+    use crate::record::{Column, Record};
+    use crate::row::Row;
+    use crate::row_schema::RowSchema;
+    use crate::schema::SchemaMode;
+    use crate::table::{Field, FieldType, Table};
+
+    #[test]
+    fn test_row_schema() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new_not_null("name".to_string(), FieldType::String));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("nick name".to_string(), FieldType::String));
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        table.drop_field("photo").expect("Unable to drop field");
+        let schema = RowSchema::new(&table).expect("Unable to generate schema");
+        assert_eq!(
+            schema.json(),
+            r#"{"type":"record","name":"Row","fields":[{"name":"name","type":"string"},"#
+                .to_string()
+                + r#"{"name":"age","type":["null","long"]},"#
+                + r#"{"name":"_2","doc":"nick name","type":["null","string"]},"#
+                + r#"{"name":"_3","type":"null"}]}"#
+        );
+
+        let record = Record::new(vec![
+            Column::String("John".to_string()),
+            Column::Int(20),
+            Column::Null,
+        ]);
+        let row = record.to_stored_row(&table);
+        let bytes = schema.encode(&row).expect("Unable to encode row");
+        assert_eq!(
+            RowSchema::fingerprint_of(&bytes),
+            Some(schema.fingerprint())
+        );
+        // the row is smaller than with the generic schema
+        assert!(bytes.len() < row.to_bytes().expect("Unable to encode row").len());
+        assert_eq!(RowSchema::fingerprint_of(&row.to_bytes().unwrap()), None);
+
+        let parsed = RowSchema::parse(schema.json()).expect("Unable to parse schema");
+        assert_eq!(parsed.fingerprint(), schema.fingerprint());
+        let decoded = parsed
+            .decode(&bytes, SchemaMode::Strict)
+            .expect("Unable to decode row");
+        assert_eq!(decoded, row);
+
+        let mut trailing = bytes.clone();
+        trailing.push(1);
+        assert!(matches!(
+            parsed.decode(&trailing, SchemaMode::Strict),
+            Err(SqlLayerError::UnknownFields(1))
+        ));
+
+        // a null in a field that isn't nullable doesn't match the schema
+        let mut row = Row::new();
+        for _ in 0..4 {
+            row.add_null_column();
+        }
+        assert!(schema.encode(&row).is_err());
+    }
+
+    #[test]
+    fn test_temporal_columns() {
+        let mut table = Table::new("Event".to_string(), vec!["day".to_string()]);
+        table.add_field(Field::new_not_null("day".to_string(), FieldType::Date));
+        table.add_field(Field::new("at".to_string(), FieldType::Time));
+        table.add_field(Field::new("created".to_string(), FieldType::Timestamp));
+        let schema = RowSchema::new(&table).expect("Unable to generate schema");
+        assert_eq!(
+            schema.json(),
+            r#"{"type":"record","name":"Row","fields":["#.to_string()
+                + r#"{"name":"day","type":{"type":"int","logicalType":"date"}},"#
+                + r#"{"name":"at","type":["null",{"type":"long","logicalType":"time-micros"}]},"#
+                + r#"{"name":"created","type":["null","#
+                + r#"{"type":"long","logicalType":"timestamp-micros"}]}]}"#
+        );
+
+        let record = Record::new(vec![
+            Column::Date(19_782),
+            Column::Time(45_015_500_000),
+            Column::Timestamp(-1_000_000),
+        ]);
+        let bytes = schema
+            .encode(&record.to_stored_row(&table))
+            .expect("Unable to encode row");
+        let row = schema
+            .decode(&bytes, SchemaMode::Strict)
+            .expect("Unable to decode row");
+        assert_eq!(Record::from_stored_row(row, &table), record);
+    }
+
+    #[test]
+    fn test_decimal_columns() {
+        let mut table = Table::new("Payment".to_string(), vec!["amount".to_string()]);
+        table.add_field(Field::new_decimal("amount".to_string(), 2));
+        let schema = RowSchema::new(&table).expect("Unable to generate schema");
+        assert_eq!(
+            schema.json(),
+            r#"{"type":"record","name":"Row","fields":[{"name":"amount","type":["null","#
+                .to_string()
+                + r#"{"type":"bytes","logicalType":"decimal","precision":38,"scale":2}]}]}"#
+        );
+
+        // decimals are stored at the scale of their field
+        let record = Record::new(vec![Column::Decimal("-12.5".parse().unwrap())]);
+        let bytes = schema
+            .encode(&record.to_stored_row(&table))
+            .expect("Unable to encode row");
+        let row = schema
+            .decode(&bytes, SchemaMode::Strict)
+            .expect("Unable to decode row");
+        let decoded = Record::from_stored_row(row, &table);
+        assert_eq!(decoded, record);
+        let Column::Decimal(decimal) = &decoded.columns[0] else {
+            panic!("Not a decimal column");
+        };
+        assert_eq!(decimal.scale(), 2);
+
+        let record = Record::new(vec![Column::Decimal("0.125".parse().unwrap())]);
+        assert!(matches!(
+            schema.encode(&record.to_stored_row(&table)),
+            Err(SqlLayerError::InvalidDecimal(_))
+        ));
+    }
+}
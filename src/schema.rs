@@ -0,0 +1,73 @@
+//! # Schema Module
+//!
+//! Rows and table definitions are stored as Avro datums, without their writer schema.
+//! Newer versions of the crate append their new fields at the end of the records, so a
+//! datum written by a newer version decodes with the current schema and leaves the bytes
+//! of the unknown fields unread.
+//!
+//! The [`SchemaMode`] tells what to do with those bytes: ignore them, so that older
+//! deployments keep working during a rolling upgrade, or reject the datum.
+
+use crate::errors::SqlLayerError;
+use serde::de::DeserializeOwned;
+
+/// Handling of the fields written by newer versions of the crate.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SchemaMode {
+    /// Unknown trailing fields are ignored.
+    #[default]
+    Compat,
+    /// Unknown trailing fields fail the decoding.
+    Strict,
+}
+
+/// Decodes an Avro datum written with `schema` or any newer version of it.
+///
+/// Only fields appended at the end of the top-level record can be detected: a field added
+/// to a nested record shifts the following fields and fails the decoding in both modes.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The datum doesn't match the schema.
+/// - The datum holds unknown trailing fields in `SchemaMode::Strict`.
+pub(crate) fn decode_datum<T: DeserializeOwned>(
+    schema: &str,
+    bytes: &[u8],
+    mode: SchemaMode,
+) -> crate::errors::Result<T> {
+    let schema = apache_avro::schema::Schema::parse_str(schema)?;
+    let mut data = bytes;
+    let value = apache_avro::from_avro_datum(&schema, &mut data, None)?;
+    if mode == SchemaMode::Strict && !data.is_empty() {
+        return Err(SqlLayerError::UnknownFields(data.len()));
+    }
+    Ok(apache_avro::from_value::<T>(&value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::schema::SchemaMode;
+    use crate::table_metadata::TableMetadata;
+
+    #[test]
+    fn test_unknown_trailing_fields() {
+        let metadata = TableMetadata::new("Person".to_string());
+        let mut bytes = metadata.to_bytes().expect("Unable to serialize metadata");
+        // a boolean field appended by a newer version
+        bytes.push(1);
+
+        let decoded = TableMetadata::from_bytes_with_mode(&bytes, SchemaMode::Compat)
+            .expect("Unable to deserialize metadata");
+        assert_eq!(decoded, metadata);
+
+        let result = TableMetadata::from_bytes_with_mode(&bytes, SchemaMode::Strict);
+        assert!(matches!(result, Err(SqlLayerError::UnknownFields(1))));
+
+        bytes.pop();
+        let decoded = TableMetadata::from_bytes_with_mode(&bytes, SchemaMode::Strict)
+            .expect("Unable to deserialize metadata");
+        assert_eq!(decoded, metadata);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::plan::Direction;
+use crate::record::{Column, Record};
+use futures::{Stream, StreamExt};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Compares records along sort keys, given as field positions with their direction.
+pub(crate) fn compare_records(
+    a: &Record,
+    b: &Record,
+    sort_keys: &[(usize, Direction)],
+) -> Ordering {
+    sort_keys
+        .iter()
+        .map(|&(position, direction)| {
+            let ordering = match (a.columns.get(position), b.columns.get(position)) {
+                (Some(a), Some(b)) => a.total_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            };
+            match direction {
+                Direction::Ascending => ordering,
+                Direction::Descending => ordering.reverse(),
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Merges streams of records, each ordered along the sort keys, into one stream in that
+/// order, such as the ranges of an index read by a union plan.
+///
+/// Each stream is read one record ahead of the merged stream, so the records are never held
+/// at once, and each merged record costs a comparison per stream. Records with equal sort
+/// keys are yielded in the order of their streams.
+///
+/// A record read from several streams, as told by its `identity` columns, is yielded once.
+/// Its copies share its sort keys, so only the identities of the records sharing the sort
+/// keys of the last record yielded are held.
+pub(crate) fn merge_ordered<'a, S>(
+    streams: Vec<S>,
+    sort_keys: &'a [(usize, Direction)],
+    identity: &'a [usize],
+) -> impl Stream<Item = crate::errors::Result<Record>> + 'a
+where
+    S: Stream<Item = crate::errors::Result<Record>> + 'a,
+{
+    async_stream::try_stream! {
+        let mut streams = streams.into_iter().map(Box::pin).collect::<Vec<_>>();
+        let mut heads = Vec::with_capacity(streams.len());
+        for stream in &mut streams {
+            heads.push(stream.next().await.transpose()?);
+        }
+        let positions = sort_keys.iter().map(|&(i, _)| i).collect::<Vec<_>>();
+        let mut last_keys: Option<Vec<Column>> = None;
+        let mut yielded: Vec<Vec<Column>> = vec![];
+        loop {
+            let next = heads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, head)| Some((i, head.as_ref()?)))
+                .min_by(|(_, a), (_, b)| compare_records(a, b, sort_keys))
+                .map(|(i, _)| i);
+            let Some(next) = next else {
+                break;
+            };
+            let Some(record) = heads[next].take() else {
+                break;
+            };
+            heads[next] = streams[next].next().await.transpose()?;
+
+            let keys = record.project(&positions).columns;
+            let same_keys = last_keys.as_ref().is_some_and(|last_keys| {
+                std::iter::zip(last_keys, &keys).all(|(a, b)| a.total_cmp(b).is_eq())
+            });
+            if !same_keys {
+                yielded.clear();
+                last_keys = Some(keys);
+            }
+            let key = record.project(identity).columns;
+            if yielded.contains(&key) {
+                continue;
+            }
+            yielded.push(key);
+            yield record;
+        }
+    }
+}
+
+/// Intersects streams of row ids, each in increasing order, such as the equal-value ranges
+/// of the indexes read by an intersection plan, into the stream of the ids found in all of
+/// them, in increasing order.
+///
+/// Each stream is read one id ahead of the intersection, and the stream lagging behind the
+/// largest id read is advanced until it catches up, so no id is held beyond the current
+/// ones. An empty list of streams intersects to nothing.
+pub(crate) fn intersect_ordered<'a, S>(
+    streams: Vec<S>,
+) -> impl Stream<Item = crate::errors::Result<i64>> + 'a
+where
+    S: Stream<Item = crate::errors::Result<i64>> + 'a,
+{
+    async_stream::try_stream! {
+        let mut streams = streams.into_iter().map(Box::pin).collect::<Vec<_>>();
+        let mut heads = Vec::with_capacity(streams.len());
+        for stream in &mut streams {
+            let Some(head) = stream.next().await.transpose()? else {
+                return;
+            };
+            heads.push(head);
+        }
+        while let Some(&target) = heads.iter().max() {
+            let mut matched = true;
+            for (head, stream) in std::iter::zip(&mut heads, &mut streams) {
+                while *head < target {
+                    let Some(next) = stream.next().await.transpose()? else {
+                        return;
+                    };
+                    *head = next;
+                }
+                matched &= *head == target;
+            }
+            if !matched {
+                continue;
+            }
+            yield target;
+            for (head, stream) in std::iter::zip(&mut heads, &mut streams) {
+                let Some(next) = stream.next().await.transpose()? else {
+                    return;
+                };
+                *head = next;
+            }
+        }
+    }
+}
+
+/// Keeps the first `limit` records of a stream along sort keys, without holding the others.
+///
+/// The records are kept in a max-heap whose top is the last record kept, so each pushed
+/// record costs `O(log limit)` and memory stays bounded by `limit`. Records with equal
+/// sort keys keep their push order, as a stable sort would.
+pub(crate) struct TopK<'a> {
+    heap: BinaryHeap<Ranked<'a>>,
+    limit: usize,
+    sort_keys: &'a [(usize, Direction)],
+    pushed: u64,
+}
+
+impl<'a> TopK<'a> {
+    pub(crate) fn new(limit: usize, sort_keys: &'a [(usize, Direction)]) -> Self {
+        TopK {
+            heap: BinaryHeap::with_capacity(limit.saturating_add(1).min(1024)),
+            limit,
+            sort_keys,
+            pushed: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: Record) {
+        let ranked = Ranked {
+            record,
+            sequence: self.pushed,
+            sort_keys: self.sort_keys,
+        };
+        self.pushed += 1;
+        if self.heap.len() < self.limit {
+            self.heap.push(ranked);
+            return;
+        }
+        match self.heap.peek_mut() {
+            Some(mut last) if ranked < *last => *last = ranked,
+            _ => {}
+        }
+    }
+
+    /// Returns the kept records in order.
+    pub(crate) fn into_sorted_vec(self) -> Vec<Record> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.record)
+            .collect()
+    }
+}
+
+/// A record ordered by sort keys, then by the order it was pushed in.
+struct Ranked<'a> {
+    record: Record,
+    sequence: u64,
+    sort_keys: &'a [(usize, Direction)],
+}
+
+impl Ord for Ranked<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_records(&self.record, &other.record, self.sort_keys)
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+impl PartialOrd for Ranked<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Ranked<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::SqlLayerError;
+    use crate::plan::Direction;
+    use crate::record::{Column, Record};
+    use crate::sort::{compare_records, intersect_ordered, merge_ordered, TopK};
+    use futures::TryStreamExt;
+
+    #[test]
+    fn test_top_k() {
+        let records = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3]
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Record {
+                columns: vec![Column::Int(value), Column::Int(i as i64)],
+            })
+            .collect::<Vec<_>>();
+
+        for sort_keys in [
+            vec![(0, Direction::Ascending)],
+            vec![(0, Direction::Descending)],
+        ] {
+            let mut sorted = records.clone();
+            sorted.sort_by(|a, b| compare_records(a, b, &sort_keys));
+            for limit in [0, 1, 3, 10, 20] {
+                let mut top = TopK::new(limit, &sort_keys);
+                for record in records.iter().cloned() {
+                    top.push(record);
+                }
+                let expected = sorted.iter().take(limit).cloned().collect::<Vec<_>>();
+                assert_eq!(top.into_sorted_vec(), expected);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_ordered() {
+        // records hold a sort key and an identity
+        let record = |key: i64, id: i64| Record {
+            columns: vec![Column::Int(key), Column::Int(id)],
+        };
+        let stream = |records: Vec<Record>| futures::stream::iter(records.into_iter().map(Ok));
+        let streams = vec![
+            stream(vec![record(1, 1), record(3, 3), record(3, 4), record(7, 7)]),
+            stream(vec![]),
+            stream(vec![record(2, 2), record(3, 4), record(3, 5), record(8, 8)]),
+            stream(vec![record(3, 3), record(7, 7), record(9, 9)]),
+        ];
+        let sort_keys = [(0, Direction::Ascending)];
+        let merged = merge_ordered(streams, &sort_keys, &[1])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            merged,
+            vec![
+                record(1, 1),
+                record(2, 2),
+                record(3, 3),
+                record(3, 4),
+                record(3, 5),
+                record(7, 7),
+                record(8, 8),
+                record(9, 9),
+            ]
+        );
+
+        // an error of a stream ends the merged stream
+        let streams = vec![
+            futures::stream::iter(vec![Ok(record(1, 1))]),
+            futures::stream::iter(vec![Err(SqlLayerError::InvalidContinuation)]),
+        ];
+        let merged = merge_ordered(streams, &sort_keys, &[1])
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(matches!(merged, Err(SqlLayerError::InvalidContinuation)));
+    }
+
+    #[tokio::test]
+    async fn test_intersect_ordered() {
+        let stream = |ids: Vec<i64>| futures::stream::iter(ids.into_iter().map(Ok));
+        let streams = vec![
+            stream(vec![1, 3, 4, 7, 9, 12]),
+            stream(vec![2, 3, 7, 8, 9]),
+            stream(vec![3, 5, 7, 9, 10]),
+        ];
+        let intersected = intersect_ordered(streams)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(intersected, vec![3, 7, 9]);
+
+        // an empty stream, or no stream at all, intersects to nothing
+        let streams = vec![stream(vec![1, 2]), stream(vec![])];
+        let intersected = intersect_ordered(streams)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(intersected.is_empty());
+        let intersected = intersect_ordered(Vec::<futures::stream::Empty<_>>::new())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(intersected.is_empty());
+
+        // an error of a stream ends the intersection
+        let streams = vec![
+            futures::stream::iter(vec![Ok(1), Ok(2)]),
+            futures::stream::iter(vec![Ok(1), Err(SqlLayerError::InvalidContinuation)]),
+        ];
+        let intersected = intersect_ordered(streams).try_collect::<Vec<_>>().await;
+        assert!(matches!(
+            intersected,
+            Err(SqlLayerError::InvalidContinuation)
+        ));
+    }
+}
@@ -0,0 +1,1242 @@
+//! # SQL Module
+//!
+//! The SQL front-end of the layer, and helpers to safely move text between SQL statements
+//! and the layer.
+//!
+//! SQL text is parsed with `sqlparser` and translated into [`Command`]s driving the typed
+//! API, see `Database::execute`. The supported subset is:
+//!
+//! - `CREATE TABLE` with `TEXT`, `INT`, `FLOAT`, `BOOL`, `BYTEA`, `DATE`, `TIME`,
+//!   `TIMESTAMP`, `UUID`, `DECIMAL(precision, scale)` and `SERIAL` columns (and their
+//!   usual synonyms), optionally `NOT NULL` or with a `DEFAULT` literal, a primary key,
+//!   whose columns are `NOT NULL`, `CHECK` constraints written as `WHERE` clauses and
+//!   foreign keys referencing the primary key of a table, `ON DELETE RESTRICT`, `CASCADE`
+//!   or `SET NULL`. With `IF NOT EXISTS`, an existing table of the same name is kept as
+//!   is. A text column may be collated, e.g.
+//!   `email TEXT COLLATE case_insensitive`, see [`Collation`]. A `SERIAL` column numbers
+//!   the rows inserted without it, see [`Field::new_serial`].
+//! - `INSERT INTO ... VALUES`, with or without a column list.
+//! - `SELECT` of columns or `*` from a single table, with an optional `WHERE` clause.
+//! - `DELETE FROM` a single table, with an optional `WHERE` clause.
+//!
+//! `WHERE` clauses are translated into [`Predicate`]s, so they combine comparisons of a
+//! column with a literal, `BETWEEN`, `IS NULL` and `LIKE 'prefix%'` with `AND` and `OR`.
+//!
+//! Dates, times, timestamps and UUIDs are written as typed literals, e.g.
+//! `DATE '2024-01-15'`, `TIME '12:30:00'`, `TIMESTAMP '2024-01-15 12:30:00'` in UTC or
+//! `UUID '67e55044-10b1-426f-9247-bb680e5fe0c8'`. Numbers are converted to the decimals
+//! of the `DECIMAL` columns, `DECIMAL '12.50'` keeping the exact digits of the longer
+//! ones.
+//!
+//! Table, field and index names end up tuple-encoded inside FoundationDB keys, so they are
+//! validated before reaching the storage: an identifier must be non-empty and must not
+//! contain a NUL byte. SQL text handed over as raw bytes must be valid UTF-8.
+
+use crate::collation::Collation;
+use crate::decimal::MAX_SCALE;
+use crate::errors::SqlLayerError;
+use crate::predicate::Predicate;
+use crate::record::{Column, Record};
+use crate::table::{Check, Field, FieldType, ForeignKey, OnDelete, Table};
+use crate::temporal;
+use sqlparser::ast::{
+    BinaryOperator, ColumnOption, CreateTable, DataType, Delete, Distinct, Expr, FromTable, Insert,
+    ObjectName, Query, ReferentialAction, SelectItem, SetExpr, Statement, TableConstraint,
+    TableFactor, TableWithJoins, UnaryOperator, Value,
+};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::borrow::Cow;
+use std::fmt::Display;
+
+/// A SQL statement translated into the typed API.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    CreateTable {
+        table: Table,
+        /// Whether an existing table of the same name is kept rather than reported.
+        if_not_exists: bool,
+    },
+    Insert {
+        table: String,
+        /// The columns the values are given for, or `None` for every column in order.
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Column>>,
+    },
+    Select {
+        table: String,
+        /// The selected columns, or `None` for `*`.
+        projection: Option<Vec<String>>,
+        predicate: Option<Predicate>,
+        /// Whether duplicate rows are removed, as `SELECT DISTINCT` does.
+        distinct: bool,
+    },
+    Delete {
+        table: String,
+        predicate: Option<Predicate>,
+    },
+}
+
+impl Command {
+    /// The SQL operation of the statement, such as `SELECT`.
+    pub fn operation(&self) -> &'static str {
+        match self {
+            Command::CreateTable { .. } => "CREATE TABLE",
+            Command::Insert { .. } => "INSERT",
+            Command::Select { .. } => "SELECT",
+            Command::Delete { .. } => "DELETE",
+        }
+    }
+
+    /// The name of the table the statement applies to.
+    pub fn table(&self) -> &str {
+        match self {
+            Command::CreateTable { table, .. } => &table.name,
+            Command::Insert { table, .. }
+            | Command::Select { table, .. }
+            | Command::Delete { table, .. } => table,
+        }
+    }
+}
+
+/// Outcome of a statement run by `Database::execute`.
+#[derive(Debug, PartialEq)]
+pub enum QueryResult {
+    Created,
+    Inserted(u64),
+    Rows(Vec<Record>),
+    Deleted(u64),
+}
+
+/// Parses SQL text into commands, one per statement.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidSqlText` if the text can't be parsed or uses a statement,
+/// an expression or a type outside of the supported subset.
+pub fn parse(sql: &str) -> crate::errors::Result<Vec<Command>> {
+    let statements = Parser::parse_sql(&GenericDialect {}, sql)
+        .map_err(|err| SqlLayerError::InvalidSqlText(err.to_string()))?;
+    statements.iter().map(command).collect()
+}
+
+/// Parses a boolean SQL expression, such as the body of a `WHERE` clause, into a predicate.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidSqlText` if the text can't be parsed or falls outside of
+/// the expressions supported in `WHERE` clauses.
+pub(crate) fn parse_predicate(expression: &str) -> crate::errors::Result<Predicate> {
+    let expr = Parser::new(&GenericDialect {})
+        .try_with_sql(expression)
+        .and_then(|mut parser| parser.parse_expr())
+        .map_err(|err| SqlLayerError::InvalidSqlText(err.to_string()))?;
+    predicate(&expr)
+}
+
+/// Builds the record inserted by a row of an `INSERT`, ordered as the fields of the table.
+///
+/// Columns missing from the column list take the default of their field, or are null if it
+/// has none, and integer literals given for float fields are converted.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The column list and the row don't have the same length.
+/// - A listed column is not a field of the table.
+pub(crate) fn insert_record(
+    table: &Table,
+    columns: Option<&[String]>,
+    values: Vec<Column>,
+) -> crate::errors::Result<Record> {
+    let values = match columns {
+        None => values,
+        Some(columns) => {
+            if columns.len() != values.len() {
+                return Err(SqlLayerError::InvalidSqlText(format!(
+                    "{} columns but {} values",
+                    columns.len(),
+                    values.len()
+                )));
+            }
+            let mut ordered = table
+                .fields
+                .iter()
+                .map(|field| field.default.clone().unwrap_or(Column::Null))
+                .collect::<Vec<_>>();
+            for (name, value) in columns.iter().zip(values) {
+                let position = table
+                    .get_field_pos(name)
+                    .ok_or(SqlLayerError::MissingColumn(name.clone()))?;
+                ordered[position] = value;
+            }
+            ordered
+        }
+    };
+    let columns = values
+        .into_iter()
+        .zip(
+            table
+                .fields
+                .iter()
+                .map(|field| Some(field.r#type))
+                .chain(std::iter::repeat(None)),
+        )
+        .map(|(value, field_type)| match field_type {
+            Some(field_type) => coerce(value, field_type),
+            None => value,
+        })
+        .collect();
+    Ok(Record { columns })
+}
+
+/// Converts a number literal to the numeric type of its field, see `Column::coerce`. The
+/// columns that can't be converted are kept as they are, to be reported by the validation
+/// of the record.
+fn coerce(value: Column, field_type: FieldType) -> Column {
+    match value.coerce(field_type) {
+        Some(Cow::Owned(coerced)) => coerced,
+        _ => value,
+    }
+}
+
+fn unsupported(what: &str, node: &impl Display) -> SqlLayerError {
+    SqlLayerError::InvalidSqlText(format!("unsupported {what}: {node}"))
+}
+
+fn command(statement: &Statement) -> crate::errors::Result<Command> {
+    match statement {
+        Statement::CreateTable(create_table) => create_table_command(create_table),
+        Statement::Insert(insert) => insert_command(insert),
+        Statement::Query(query) => select_command(query),
+        Statement::Delete(delete) => delete_command(delete),
+        statement => Err(unsupported("statement", statement)),
+    }
+}
+
+fn create_table_command(create_table: &CreateTable) -> crate::errors::Result<Command> {
+    let name = object_name(&create_table.name)?;
+    let mut fields = vec![];
+    let mut primary_key = vec![];
+    let mut checks = vec![];
+    let mut foreign_keys = vec![];
+    for column in &create_table.columns {
+        let mut field = Field::new(column.name.value.clone(), field_type(&column.data_type)?);
+        match field.r#type {
+            FieldType::Decimal => field.scale = decimal_scale(&column.data_type)?,
+            FieldType::Enum => field.variants = enum_variants(&column.data_type),
+            _ => {}
+        }
+        if let Some(name) = &column.collation {
+            field.collation = collation(name)?;
+        }
+        if is_serial(&column.data_type) {
+            field.serial = true;
+            field.nullable = false;
+        }
+        for option in &column.options {
+            match &option.option {
+                ColumnOption::NotNull => field.nullable = false,
+                ColumnOption::Default(expr) => {
+                    field.set_default(coerce(literal(expr)?, field.r#type))?;
+                }
+                ColumnOption::Unique {
+                    is_primary: true, ..
+                } => primary_key.push(column.name.value.clone()),
+                ColumnOption::Check(expr) => {
+                    let name = option.name.as_ref().map_or_else(
+                        || format!("{name}_{}_check", column.name.value),
+                        |name| name.value.clone(),
+                    );
+                    checks.push(Check::new(name, expr.to_string()));
+                }
+                ColumnOption::ForeignKey {
+                    foreign_table,
+                    on_delete,
+                    on_update,
+                    ..
+                } => {
+                    let name = option.name.as_ref().map_or_else(
+                        || format!("{name}_{}_fkey", column.name.value),
+                        |name| name.value.clone(),
+                    );
+                    foreign_keys.push(ForeignKey::new(
+                        name,
+                        vec![column.name.value.clone()],
+                        object_name(foreign_table)?,
+                        referential_action(on_delete, on_update)?,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        fields.push(field);
+    }
+    for constraint in &create_table.constraints {
+        match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => {
+                primary_key.extend(columns.iter().map(|column| column.value.clone()))
+            }
+            TableConstraint::Check {
+                name: constraint_name,
+                expr,
+            } => {
+                let constraint_name = constraint_name.as_ref().map_or_else(
+                    || format!("{name}_check{}", checks.len() + 1),
+                    |name| name.value.clone(),
+                );
+                checks.push(Check::new(constraint_name, expr.to_string()));
+            }
+            TableConstraint::ForeignKey {
+                name: constraint_name,
+                columns,
+                foreign_table,
+                on_delete,
+                on_update,
+                ..
+            } => {
+                let fields = columns
+                    .iter()
+                    .map(|column| column.value.clone())
+                    .collect::<Vec<_>>();
+                let constraint_name = constraint_name.as_ref().map_or_else(
+                    || format!("{name}_{}_fkey", fields.join("_")),
+                    |name| name.value.clone(),
+                );
+                foreign_keys.push(ForeignKey::new(
+                    constraint_name,
+                    fields,
+                    object_name(foreign_table)?,
+                    referential_action(on_delete, on_update)?,
+                ));
+            }
+            constraint => return Err(unsupported("constraint", constraint)),
+        }
+    }
+    if primary_key.is_empty() {
+        return Err(SqlLayerError::InvalidSqlText(format!(
+            "missing primary key on table {name}"
+        )));
+    }
+
+    // as in SQL, the primary key columns are NOT NULL
+    for field in &mut fields {
+        if primary_key.contains(&field.name) {
+            field.nullable = false;
+        }
+    }
+    let mut table = Table::new(name, primary_key);
+    for field in fields {
+        table.add_field(field);
+    }
+    for check in checks {
+        table.add_check(check)?;
+    }
+    for foreign_key in foreign_keys {
+        table.add_foreign_key(foreign_key)?;
+    }
+    Ok(Command::CreateTable {
+        table,
+        if_not_exists: create_table.if_not_exists,
+    })
+}
+
+/// Maps the `ON DELETE` action of a foreign key, `NO ACTION` standing for `RESTRICT`.
+///
+/// Referenced primary keys can't be changed, so `ON UPDATE` can only restrict as well.
+fn referential_action(
+    on_delete: &Option<ReferentialAction>,
+    on_update: &Option<ReferentialAction>,
+) -> crate::errors::Result<OnDelete> {
+    if let Some(action) = on_update {
+        if !matches!(
+            action,
+            ReferentialAction::Restrict | ReferentialAction::NoAction
+        ) {
+            return Err(unsupported("ON UPDATE action", action));
+        }
+    }
+    match on_delete {
+        None | Some(ReferentialAction::Restrict | ReferentialAction::NoAction) => {
+            Ok(OnDelete::Restrict)
+        }
+        Some(ReferentialAction::Cascade) => Ok(OnDelete::Cascade),
+        Some(ReferentialAction::SetNull) => Ok(OnDelete::SetNull),
+        Some(action) => Err(unsupported("ON DELETE action", action)),
+    }
+}
+
+/// Maps a SQL type to a field type by its spelling, which doesn't depend on the dialect.
+fn field_type(data_type: &DataType) -> crate::errors::Result<FieldType> {
+    let name = data_type.to_string().to_uppercase();
+    // a display width comes before the sign, as in `BIGINT(20) UNSIGNED`
+    let unsigned = name.ends_with(" UNSIGNED");
+    let name = name.split('(').next().unwrap_or_default().trim();
+    match name {
+        "TEXT" | "STRING" | "VARCHAR" | "CHAR" | "CHARACTER VARYING" => Ok(FieldType::String),
+        "BIGINT" if unsigned => Ok(FieldType::UInt64),
+        "BIGINT UNSIGNED" | "UBIGINT" | "UINT64" => Ok(FieldType::UInt64),
+        "INT" | "INTEGER" | "BIGINT" | "INT8" | "INT64" => Ok(FieldType::Int),
+        "INT4" | "INT32" => Ok(FieldType::Int32),
+        "SMALLINT" | "INT2" | "INT16" => Ok(FieldType::Int16),
+        "SERIAL" | "BIGSERIAL" | "SERIAL8" => Ok(FieldType::Int),
+        "SERIAL4" => Ok(FieldType::Int32),
+        "SMALLSERIAL" | "SERIAL2" => Ok(FieldType::Int16),
+        "FLOAT" | "REAL" | "DOUBLE" | "DOUBLE PRECISION" | "FLOAT8" | "FLOAT64" => {
+            Ok(FieldType::Float)
+        }
+        "BOOL" | "BOOLEAN" => Ok(FieldType::Bool),
+        "BYTEA" | "BLOB" | "BINARY" | "VARBINARY" | "BYTES" => Ok(FieldType::Bytes),
+        "DATE" => Ok(FieldType::Date),
+        "TIME" => Ok(FieldType::Time),
+        "TIMESTAMP" | "DATETIME" => Ok(FieldType::Timestamp),
+        "UUID" => Ok(FieldType::Uuid),
+        "DECIMAL" | "NUMERIC" | "DEC" => Ok(FieldType::Decimal),
+        "ENUM" => Ok(FieldType::Enum),
+        _ => Err(unsupported("data type", data_type)),
+    }
+}
+
+/// Whether a SQL type numbers the records, as `SERIAL` does, see [`Field::new_serial`].
+fn is_serial(data_type: &DataType) -> bool {
+    data_type.to_string().to_uppercase().contains("SERIAL")
+}
+
+/// Reads the scale of a `DECIMAL(precision, scale)` type, 0 when it is omitted.
+fn decimal_scale(data_type: &DataType) -> crate::errors::Result<u8> {
+    let name = data_type.to_string();
+    let Some((_, arguments)) = name.split_once('(') else {
+        return Ok(0);
+    };
+    let arguments = arguments.trim_end_matches(')');
+    match arguments.split_once(',') {
+        None => Ok(0),
+        Some((_, scale)) => scale
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|scale| *scale <= MAX_SCALE)
+            .ok_or_else(|| unsupported("decimal scale", data_type)),
+    }
+}
+
+/// Reads the variants of an `ENUM('a', 'b')` type, the quoted strings between its
+/// parentheses.
+fn enum_variants(data_type: &DataType) -> Vec<String> {
+    let name = data_type.to_string();
+    let Some((_, arguments)) = name.split_once('(') else {
+        return Vec::new();
+    };
+    let mut variants = Vec::new();
+    let mut variant = None;
+    let mut chars = arguments.chars().peekable();
+    while let Some(char) = chars.next() {
+        let Some(text) = variant.as_mut() else {
+            if char == '\'' {
+                variant = Some(String::new());
+            }
+            continue;
+        };
+        if char != '\'' {
+            text.push(char);
+        } else if chars.peek() == Some(&'\'') {
+            // quotes are doubled inside a quoted string
+            text.push(char);
+            chars.next();
+        } else {
+            variants.extend(variant.take());
+        }
+    }
+    variants
+}
+
+fn insert_command(insert: &Insert) -> crate::errors::Result<Command> {
+    let table = object_name(&insert.table_name)?;
+    let columns = (!insert.columns.is_empty()).then(|| {
+        insert
+            .columns
+            .iter()
+            .map(|column| column.value.clone())
+            .collect()
+    });
+    let Some(source) = &insert.source else {
+        return Err(SqlLayerError::InvalidSqlText(
+            "INSERT without VALUES".to_string(),
+        ));
+    };
+    let SetExpr::Values(values) = source.body.as_ref() else {
+        return Err(unsupported("INSERT source", &source.body));
+    };
+    let rows = values
+        .rows
+        .iter()
+        .map(|row| row.iter().map(literal).collect())
+        .collect::<crate::errors::Result<Vec<_>>>()?;
+    Ok(Command::Insert {
+        table,
+        columns,
+        rows,
+    })
+}
+
+fn select_command(query: &Query) -> crate::errors::Result<Command> {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return Err(unsupported("query", query));
+    };
+    if query.order_by.is_some()
+        || query.limit.is_some()
+        || query.offset.is_some()
+        || matches!(select.distinct, Some(Distinct::On(_)))
+        || select.having.is_some()
+    {
+        return Err(unsupported("query", query));
+    }
+    let table = single_table(&select.from)?;
+    let projection = match select.projection.as_slice() {
+        [SelectItem::Wildcard(_)] => None,
+        items => Some(
+            items
+                .iter()
+                .map(|item| match item {
+                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Ok(ident.value.clone()),
+                    item => Err(unsupported("projection", item)),
+                })
+                .collect::<crate::errors::Result<Vec<_>>>()?,
+        ),
+    };
+    let predicate = select.selection.as_ref().map(predicate).transpose()?;
+    Ok(Command::Select {
+        table,
+        projection,
+        predicate,
+        distinct: select.distinct.is_some(),
+    })
+}
+
+fn delete_command(delete: &Delete) -> crate::errors::Result<Command> {
+    let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) = &delete.from;
+    let table = single_table(from)?;
+    let predicate = delete.selection.as_ref().map(predicate).transpose()?;
+    Ok(Command::Delete { table, predicate })
+}
+
+fn single_table(from: &[TableWithJoins]) -> crate::errors::Result<String> {
+    match from {
+        [TableWithJoins {
+            relation: TableFactor::Table { name, .. },
+            joins,
+        }] if joins.is_empty() => object_name(name),
+        _ => Err(SqlLayerError::InvalidSqlText(
+            "a single table is expected".to_string(),
+        )),
+    }
+}
+
+fn object_name(name: &ObjectName) -> crate::errors::Result<String> {
+    match name.0.as_slice() {
+        [ident] => Ok(ident.value.clone()),
+        _ => Err(unsupported("qualified name", name)),
+    }
+}
+
+fn collation(name: &ObjectName) -> crate::errors::Result<Collation> {
+    let name = object_name(name)?;
+    Collation::from_name(&name).ok_or(SqlLayerError::InvalidCollation(name))
+}
+
+fn predicate(expr: &Expr) -> crate::errors::Result<Predicate> {
+    match expr {
+        Expr::Nested(expr) => predicate(expr),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => Ok(predicate(left)?.and(predicate(right)?)),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Or,
+            right,
+        } => Ok(predicate(left)?.or(predicate(right)?)),
+        Expr::BinaryOp { left, op, right } => comparison(left, op, right),
+        Expr::IsNull(expr) => Ok(Predicate::is_null(identifier(expr)?)),
+        Expr::Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        } => Ok(Predicate::between(
+            identifier(expr)?,
+            literal(low)?,
+            literal(high)?,
+        )),
+        Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } => Ok(Predicate::in_list(
+            identifier(expr)?,
+            list.iter()
+                .map(literal)
+                .collect::<crate::errors::Result<_>>()?,
+        )),
+        Expr::Like {
+            negated: false,
+            expr,
+            pattern,
+            escape_char: None,
+            ..
+        } => {
+            let Column::String(pattern) = literal(pattern)? else {
+                return Err(unsupported("LIKE pattern", pattern));
+            };
+            // only `prefix%` patterns without other wildcards can be evaluated
+            match pattern.strip_suffix('%') {
+                Some(prefix) if !prefix.contains(['%', '_']) => {
+                    Ok(Predicate::like_prefix(identifier(expr)?, prefix))
+                }
+                _ => Err(unsupported("LIKE pattern", &pattern)),
+            }
+        }
+        expr => Err(unsupported("expression", expr)),
+    }
+}
+
+/// Translates the comparison of a column with a literal, `20 < age` becoming `age > 20`.
+fn comparison(left: &Expr, op: &BinaryOperator, right: &Expr) -> crate::errors::Result<Predicate> {
+    let (field, op, value) = match (left, right) {
+        (Expr::Identifier(field), value) => (field, op.clone(), value),
+        (value, Expr::Identifier(field)) => {
+            let op = match op {
+                BinaryOperator::Lt => BinaryOperator::Gt,
+                BinaryOperator::Gt => BinaryOperator::Lt,
+                BinaryOperator::LtEq => BinaryOperator::GtEq,
+                BinaryOperator::GtEq => BinaryOperator::LtEq,
+                op => op.clone(),
+            };
+            (field, op, value)
+        }
+        _ => return Err(unsupported("comparison", op)),
+    };
+    let field = field.value.clone();
+    let value = literal(value)?;
+    match op {
+        BinaryOperator::Eq => Ok(Predicate::eq(field, value)),
+        BinaryOperator::Lt => Ok(Predicate::lt(field, value)),
+        BinaryOperator::Gt => Ok(Predicate::gt(field, value)),
+        BinaryOperator::LtEq => {
+            Ok(Predicate::lt(field.clone(), value.clone()).or(Predicate::eq(field, value)))
+        }
+        BinaryOperator::GtEq => {
+            Ok(Predicate::gt(field.clone(), value.clone()).or(Predicate::eq(field, value)))
+        }
+        op => Err(unsupported("operator", &op)),
+    }
+}
+
+fn identifier(expr: &Expr) -> crate::errors::Result<String> {
+    match expr {
+        Expr::Identifier(ident) => Ok(ident.value.clone()),
+        expr => Err(unsupported("column reference", expr)),
+    }
+}
+
+fn literal(expr: &Expr) -> crate::errors::Result<Column> {
+    match expr {
+        Expr::Value(value) => value_column(value),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr: operand,
+        } => match literal(operand)? {
+            Column::Int(value) => Ok(Column::Int(-value)),
+            Column::Float(value) => Ok(Column::Float(-value)),
+            _ => Err(unsupported("literal", expr)),
+        },
+        Expr::TypedString { data_type, value } => {
+            typed_literal(data_type, value).ok_or_else(|| unsupported("literal", expr))
+        }
+        expr => Err(unsupported("literal", expr)),
+    }
+}
+
+/// Reads a typed literal of a temporal type, see the `temporal` module for their text, a
+/// UUID or a decimal.
+fn typed_literal(data_type: &DataType, value: &str) -> Option<Column> {
+    match field_type(data_type).ok()? {
+        FieldType::Date => temporal::parse_date(value).map(Column::Date),
+        FieldType::Time => temporal::parse_time(value).map(Column::Time),
+        FieldType::Timestamp => temporal::parse_timestamp(value).map(Column::Timestamp),
+        FieldType::Uuid => parse_uuid(value).map(Column::Uuid),
+        FieldType::Decimal => value.parse().ok().map(Column::Decimal),
+        _ => None,
+    }
+}
+
+/// Parses a UUID of 32 hexadecimal digits, hyphenated or not.
+fn parse_uuid(value: &str) -> Option<[u8; 16]> {
+    let hyphenated =
+        value.len() == 36 && [8, 13, 18, 23].iter().all(|&i| value.as_bytes()[i] == b'-');
+    let hex = if hyphenated {
+        value.replace('-', "")
+    } else {
+        value.to_string()
+    };
+    if hex.len() != 32 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut uuid = [0; 16];
+    for (byte, pair) in uuid.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(uuid)
+}
+
+fn value_column(value: &Value) -> crate::errors::Result<Column> {
+    match value {
+        Value::Number(number, _) => number
+            .parse::<i64>()
+            .map(Column::Int)
+            .or_else(|_| number.parse::<f64>().map(Column::Float))
+            .map_err(|_| unsupported("number", value)),
+        Value::SingleQuotedString(value) => Ok(Column::String(value.clone())),
+        Value::Boolean(value) => Ok(Column::Bool(*value)),
+        Value::Null => Ok(Column::Null),
+        Value::HexStringLiteral(hex) => hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .filter(|pair| pair.len() == 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Column::Bytes)
+            .ok_or_else(|| unsupported("hexadecimal literal", value)),
+        value => Err(unsupported("literal", value)),
+    }
+}
+
+/// Checks that `identifier` can safely be used as a table, field or index name.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidIdentifier` if the identifier is empty or contains a NUL byte.
+pub fn check_identifier(identifier: &str) -> crate::errors::Result<()> {
+    if identifier.is_empty() || identifier.contains('\0') {
+        return Err(SqlLayerError::InvalidIdentifier(
+            identifier.escape_debug().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Quotes an identifier so it can be embedded in a SQL statement.
+///
+/// The identifier is wrapped in double quotes and embedded double quotes are doubled,
+/// `my "table"` becoming `"my ""table"""`.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidIdentifier` if the identifier is not valid, see [`check_identifier`].
+pub fn quote_identifier(identifier: &str) -> crate::errors::Result<String> {
+    check_identifier(identifier)?;
+    Ok(format!("\"{}\"", identifier.replace('"', "\"\"")))
+}
+
+/// Quotes a string literal so it can be embedded in a SQL statement.
+///
+/// The value is wrapped in single quotes and embedded single quotes are doubled,
+/// `it's` becoming `'it''s'`.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidSqlText` if the value contains a NUL byte.
+pub fn quote_literal(value: &str) -> crate::errors::Result<String> {
+    if value.contains('\0') {
+        return Err(SqlLayerError::InvalidSqlText(
+            "NUL byte in string literal".to_string(),
+        ));
+    }
+    Ok(format!("'{}'", value.replace('\'', "''")))
+}
+
+/// Decodes SQL text received as raw bytes.
+///
+/// # Errors
+///
+/// Returns `SqlLayerError::InvalidSqlText` if the bytes are not valid UTF-8 or contain a NUL byte.
+pub fn decode_sql_text(bytes: &[u8]) -> crate::errors::Result<&str> {
+    let text =
+        std::str::from_utf8(bytes).map_err(|err| SqlLayerError::InvalidSqlText(err.to_string()))?;
+    if let Some(position) = text.find('\0') {
+        return Err(SqlLayerError::InvalidSqlText(format!(
+            "NUL byte at position {position}"
+        )));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collation::Collation;
+    use crate::errors::SqlLayerError;
+    use crate::predicate::Predicate;
+    use crate::record::{Column, Record};
+    use crate::sql::{
+        check_identifier, decode_sql_text, insert_record, parse, quote_identifier, quote_literal,
+        Command,
+    };
+    use crate::table::{Check, Field, FieldType, ForeignKey, OnDelete, Table};
+
+    #[test]
+    fn test_check_identifier() {
+        assert!(check_identifier("Person").is_ok());
+        assert!(matches!(
+            check_identifier(""),
+            Err(SqlLayerError::InvalidIdentifier(_))
+        ));
+        assert!(matches!(
+            check_identifier("Per\0son"),
+            Err(SqlLayerError::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(
+            quote_identifier("my \"table\"").unwrap(),
+            "\"my \"\"table\"\"\""
+        );
+        assert_eq!(quote_literal("it's").unwrap(), "'it''s'");
+        assert!(quote_literal("a\0b").is_err());
+    }
+
+    #[test]
+    fn test_decode_sql_text() {
+        assert_eq!(
+            decode_sql_text(b"SELECT * FROM Person").unwrap(),
+            "SELECT * FROM Person"
+        );
+        assert!(matches!(
+            decode_sql_text(b"SELECT \xff"),
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+        assert!(matches!(
+            decode_sql_text(b"SELECT \0"),
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        let commands = parse(
+            "CREATE TABLE Person \
+             (name TEXT PRIMARY KEY, age INT NOT NULL, height DOUBLE DEFAULT 2, photo BYTEA)",
+        )
+        .unwrap();
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new_not_null("name".to_string(), FieldType::String));
+        table.add_field(Field::new_not_null("age".to_string(), FieldType::Int));
+        let mut height = Field::new("height".to_string(), FieldType::Float);
+        height.set_default(Column::Float(2.0)).unwrap();
+        table.add_field(height);
+        table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        assert_eq!(
+            commands,
+            vec![Command::CreateTable {
+                table,
+                if_not_exists: false
+            }]
+        );
+
+        let commands = parse("CREATE TABLE IF NOT EXISTS Person (name TEXT PRIMARY KEY)").unwrap();
+        assert!(matches!(
+            &commands[0],
+            Command::CreateTable {
+                if_not_exists: true,
+                ..
+            }
+        ));
+
+        let commands =
+            parse("CREATE TABLE Orders (customer INT, id INT, PRIMARY KEY (customer, id))")
+                .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(table.primary_key, vec!["customer", "id"]);
+        assert!(table.fields.iter().all(|field| !field.nullable));
+
+        let commands = parse(
+            "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT CHECK (age >= 0), \
+             CHECK (name <> ''))",
+        );
+        assert!(matches!(commands, Err(SqlLayerError::InvalidSqlText(_))));
+        let commands = parse(
+            "CREATE TABLE Person (name TEXT PRIMARY KEY, age INT CHECK (age >= 0), \
+             CHECK (age < 150))",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(
+            table.checks,
+            vec![
+                Check::new("Person_age_check", "age >= 0"),
+                Check::new("Person_check2", "age < 150")
+            ]
+        );
+
+        let commands = parse(
+            "CREATE TABLE Orders (id INT PRIMARY KEY, customer TEXT REFERENCES Person (name), \
+             CONSTRAINT orders_parent FOREIGN KEY (id) REFERENCES Orders (id) ON DELETE CASCADE)",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(
+            table.foreign_keys,
+            vec![
+                ForeignKey::new(
+                    "Orders_customer_fkey",
+                    vec!["customer".to_string()],
+                    "Person",
+                    OnDelete::Restrict
+                ),
+                ForeignKey::new(
+                    "orders_parent",
+                    vec!["id".to_string()],
+                    "Orders",
+                    OnDelete::Cascade
+                )
+            ]
+        );
+        assert!(matches!(
+            parse(
+                "CREATE TABLE Orders (id INT PRIMARY KEY, \
+                 customer TEXT REFERENCES Person (name) ON UPDATE CASCADE)"
+            ),
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+
+        assert!(matches!(
+            parse("CREATE TABLE Person (name TEXT)"),
+            Err(SqlLayerError::InvalidSqlText(_))
+        ));
+        assert!(matches!(
+            parse("CREATE TABLE Person (name TEXT PRIMARY KEY, age INT DEFAULT 'twenty')"),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_parse_insert() {
+        let commands =
+            parse("INSERT INTO Person (age, name) VALUES (20, 'John'), (-3, 'Jane')").unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::Insert {
+                table: "Person".to_string(),
+                columns: Some(vec!["age".to_string(), "name".to_string()]),
+                rows: vec![
+                    vec![Column::Int(20), Column::String("John".to_string())],
+                    vec![Column::Int(-3), Column::String("Jane".to_string())],
+                ],
+            }]
+        );
+
+        let commands = parse(
+            "INSERT INTO Event VALUES \
+             (DATE '2024-02-29', TIME '12:30:15.5', TIMESTAMP '1970-01-01 00:00:01')",
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::Insert {
+                table: "Event".to_string(),
+                columns: None,
+                rows: vec![vec![
+                    Column::Date(19_782),
+                    Column::Time(45_015_500_000),
+                    Column::Timestamp(1_000_000),
+                ]],
+            }]
+        );
+        assert!(parse("INSERT INTO Event VALUES (DATE '2023-02-29')").is_err());
+
+        let commands =
+            parse("INSERT INTO Event VALUES (UUID '67e55044-10b1-426f-9247-bb680e5fe0c8')")
+                .unwrap();
+        let uuid = 0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8_u128.to_be_bytes();
+        assert_eq!(
+            commands,
+            vec![Command::Insert {
+                table: "Event".to_string(),
+                columns: None,
+                rows: vec![vec![Column::Uuid(uuid)]],
+            }]
+        );
+        assert!(parse("INSERT INTO Event VALUES (UUID '67e55044-10b1')").is_err());
+    }
+
+    #[test]
+    fn test_parse_select_and_delete() {
+        let commands = parse(
+            "SELECT name FROM Person WHERE age >= 18 AND (name LIKE 'J%' OR height IS NULL); \
+             DELETE FROM Person WHERE 30 < age",
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                Command::Select {
+                    table: "Person".to_string(),
+                    projection: Some(vec!["name".to_string()]),
+                    predicate: Some(
+                        Predicate::gt("age", Column::Int(18))
+                            .or(Predicate::eq("age", Column::Int(18)))
+                            .and(
+                                Predicate::like_prefix("name", "J")
+                                    .or(Predicate::is_null("height"))
+                            )
+                    ),
+                    distinct: false,
+                },
+                Command::Delete {
+                    table: "Person".to_string(),
+                    predicate: Some(Predicate::gt("age", Column::Int(30))),
+                },
+            ]
+        );
+
+        for sql in [
+            "UPDATE Person SET age = 20",
+            "SELECT * FROM Person JOIN Orders ON name = customer",
+            "SELECT * FROM Person WHERE name LIKE '%n'",
+            "SELECT * FROM Person ORDER BY name",
+            "SELECT DISTINCT ON (age) name FROM Person",
+        ] {
+            assert!(matches!(parse(sql), Err(SqlLayerError::InvalidSqlText(_))));
+        }
+    }
+
+    #[test]
+    fn test_parse_in_list() {
+        let commands = parse("SELECT * FROM Person WHERE age IN (18, 20) OR name NOT IN ('John')");
+        assert!(matches!(commands, Err(SqlLayerError::InvalidSqlText(_))));
+        let commands = parse("SELECT * FROM Person WHERE age IN (18, -20)").unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::Select {
+                table: "Person".to_string(),
+                projection: None,
+                predicate: Some(Predicate::in_list(
+                    "age",
+                    vec![Column::Int(18), Column::Int(-20)]
+                )),
+                distinct: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_select_distinct() {
+        let commands = parse("SELECT DISTINCT age FROM Person").unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::Select {
+                table: "Person".to_string(),
+                projection: Some(vec!["age".to_string()]),
+                predicate: None,
+                distinct: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_insert_record() {
+        let mut table = Table::new("Person".to_string(), vec!["name".to_string()]);
+        table.add_field(Field::new("name".to_string(), FieldType::String));
+        table.add_field(Field::new("height".to_string(), FieldType::Float));
+        table.add_field(Field::new("age".to_string(), FieldType::Int));
+        table.add_field(Field::new("country".to_string(), FieldType::String));
+        table.fields[3]
+            .set_default(Column::String("France".to_string()))
+            .unwrap();
+
+        let columns = ["height".to_string(), "name".to_string()];
+        let record = insert_record(
+            &table,
+            Some(&columns),
+            vec![Column::Int(2), Column::String("John".to_string())],
+        )
+        .unwrap();
+        assert_eq!(
+            record,
+            Record {
+                columns: vec![
+                    Column::String("John".to_string()),
+                    Column::Float(2.0),
+                    Column::Null,
+                    Column::String("France".to_string())
+                ]
+            }
+        );
+
+        let columns = ["unknown".to_string()];
+        assert!(matches!(
+            insert_record(&table, Some(&columns), vec![Column::Int(2)]),
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_decimal_columns() {
+        let commands = parse(
+            "CREATE TABLE Payment (id INT PRIMARY KEY, amount DECIMAL(10, 2) DEFAULT 0.1, \
+             rate NUMERIC)",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(table.fields[1].r#type, FieldType::Decimal);
+        assert_eq!(table.fields[1].scale, 2);
+        assert_eq!(
+            table.fields[1].default,
+            Some(Column::Decimal("0.1".parse().unwrap()))
+        );
+        assert_eq!(
+            (table.fields[2].r#type, table.fields[2].scale),
+            (FieldType::Decimal, 0)
+        );
+        assert!(
+            parse("CREATE TABLE Payment (id INT PRIMARY KEY, amount DECIMAL(50, 40))").is_err()
+        );
+
+        let commands =
+            parse("INSERT INTO Payment VALUES (1, DECIMAL '12345678901234567890.25', 3)").unwrap();
+        let Command::Insert { rows, .. } = &commands[0] else {
+            panic!("Expected an INSERT command");
+        };
+        let record = insert_record(table, None, rows[0].clone()).unwrap();
+        assert_eq!(
+            record.columns,
+            vec![
+                Column::Int(1),
+                Column::Decimal("12345678901234567890.25".parse().unwrap()),
+                Column::Decimal("3".parse().unwrap()),
+            ]
+        );
+        let record =
+            insert_record(table, None, vec![Column::Int(1), Column::Float(-4.05)]).unwrap();
+        assert_eq!(record.columns[1], Column::Decimal("-4.05".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_integer_columns() {
+        let commands = parse(
+            "CREATE TABLE Sensor (id BIGINT UNSIGNED PRIMARY KEY, level SMALLINT, \
+             reading INT4, ratio REAL DEFAULT 1)",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        let types = table
+            .fields
+            .iter()
+            .map(|field| field.r#type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                FieldType::UInt64,
+                FieldType::Int16,
+                FieldType::Int32,
+                FieldType::Float
+            ]
+        );
+        assert_eq!(table.fields[3].default, Some(Column::Float(1.0)));
+
+        let record = insert_record(
+            table,
+            None,
+            vec![
+                Column::Int(7),
+                Column::Int(2),
+                Column::Int(3),
+                Column::Int(4),
+            ],
+        )
+        .unwrap();
+        assert_eq!(record.columns[0], Column::UInt64(7));
+        assert_eq!(record.columns[3], Column::Float(4.0));
+    }
+
+    #[test]
+    fn test_enum_columns() {
+        let commands = parse(
+            "CREATE TABLE Orders (id INT PRIMARY KEY, \
+             status ENUM('pending', 'shipped', 'won''t ship') DEFAULT 'pending')",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(table.fields[1].r#type, FieldType::Enum);
+        assert_eq!(
+            table.fields[1].variants,
+            vec!["pending", "shipped", "won't ship"]
+        );
+        assert_eq!(
+            table.fields[1].default,
+            Some(Column::String("pending".to_string()))
+        );
+        assert!(matches!(
+            parse("CREATE TABLE Orders (id INT PRIMARY KEY, status ENUM('a') DEFAULT 'b')"),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_collated_columns() {
+        let commands = parse(
+            "CREATE TABLE Person (id INT PRIMARY KEY, email TEXT COLLATE case_insensitive, \
+             name TEXT COLLATE \"Unicode\", city TEXT)",
+        )
+        .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(table.fields[1].collation, Collation::CaseInsensitive);
+        assert_eq!(table.fields[2].collation, Collation::Unicode);
+        assert_eq!(table.fields[3].collation, Collation::Binary);
+        assert!(matches!(
+            parse("CREATE TABLE Person (id INT PRIMARY KEY, email TEXT COLLATE nocase)"),
+            Err(SqlLayerError::InvalidCollation(_))
+        ));
+    }
+
+    #[test]
+    fn test_serial_columns() {
+        let commands =
+            parse("CREATE TABLE Orders (id SERIAL PRIMARY KEY, line SMALLSERIAL, label TEXT)")
+                .unwrap();
+        let Command::CreateTable { table, .. } = &commands[0] else {
+            panic!("Expected a CREATE TABLE command");
+        };
+        assert_eq!(table.fields[0], Field::new_serial("id".to_string()));
+        assert_eq!(table.fields[1].r#type, FieldType::Int16);
+        assert!(table.fields[1].serial && !table.fields[1].nullable);
+        assert!(!table.fields[2].serial);
+
+        // an omitted serial column is left null, for the insertion to number it
+        let columns = ["label".to_string()];
+        let record =
+            insert_record(table, Some(&columns), vec![Column::String("a".to_string())]).unwrap();
+        assert_eq!(record.columns[0], Column::Null);
+    }
+}
@@ -9,7 +9,14 @@
 //! - `set`: Store a key-value pair in the database.
 //! - `get`: Retrieve the value associated with a specific key.
 //! - `delete`: Remove a key-value pair from the database.
+//! - `clear_range`: Remove every key-value pair of a range at once.
 //! - `flip_atomic_bool`: Perform an atomic operation to modify a boolean-like value at a given key.
+//! - `estimated_size` / `count`: Measure a range of keys.
+//! - `first` / `last`: Retrieve the endpoints of a range of keys.
+//! - `run`: Run a transaction carrying the transaction tags of the storage.
+//! - `run_with_profile`: Run a transaction with the options of a [`CommitProfile`].
+//! - `capabilities`: The FoundationDB features available, see [`Capabilities`].
+//! - `throttle_level`: How much bulk and background transactions are currently slowed down.
 //!
 //! ## Notes
 //!
@@ -22,23 +29,301 @@
 //! The module also includes unit tests to verify the correctness of its functionality. The tests
 //! rely on the `fdb_testcontainer` crate, which sets up a test instance of FoundationDB.
 
+use crate::errors::SqlLayerError;
 use foundationdb::future::FdbValue;
-use foundationdb::{Database, FdbBindingError, RangeOption};
+use foundationdb::options::TransactionOption;
+use foundationdb::{
+    Database, FdbBindingError, FdbResult, MaybeCommitted, RangeOption, RetryableTransaction,
+    Transaction,
+};
 use futures::Stream;
 use futures_util::stream::StreamExt;
 use futures_util::TryStreamExt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 
 const MAX_SCAN_SIZE: usize = 20;
 
+/// Maximum number of tags FoundationDB accepts on a transaction.
+const MAX_TRANSACTION_TAGS: usize = 5;
+
+/// Maximum length in bytes of a transaction tag.
+pub(crate) const MAX_TRANSACTION_TAG_LENGTH: usize = 16;
+
+/// Latency of bulk and background transactions above which the cluster is deemed
+/// saturated, see [`Storage::set_throttle_latency`].
+const DEFAULT_THROTTLE_LATENCY: Duration = Duration::from_millis(200);
+
+/// Highest throttle level, see [`Storage::throttle_level`].
+const MAX_THROTTLE_LEVEL: u32 = 20;
+
+/// Delay added before a bulk or background transaction per throttle level.
+const THROTTLE_STEP: Duration = Duration::from_millis(10);
+
+/// API version the crate is built for, set by the `fdb-7_3` feature of the bindings.
+const API_VERSION: i32 = 730;
+
+/// FoundationDB features the storage can rely on.
+///
+/// They are derived from the API version negotiated with the client library: the lowest of
+/// [`API_VERSION`] and the highest version the library supports. Features a cluster runs
+/// with disabled, or older servers reached through the multi-version client, are opted
+/// out of with [`Storage::set_capabilities`]. Operations depending on a missing feature
+/// fall back to a slower path, or fail with [`SqlLayerError::Unsupported`] when there is
+/// none.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Capabilities {
+    pub api_version: i32,
+    /// Entries resolved into the rows they reference in a single request (7.1).
+    pub mapped_ranges: bool,
+    /// Clusters partitioned into tenants (7.1).
+    pub tenants: bool,
+    /// Range sizes estimated from the sampling of the storage servers (6.3).
+    pub estimated_range_size: bool,
+}
+
+impl Capabilities {
+    /// The features available at an API version.
+    pub fn for_api_version(api_version: i32) -> Self {
+        Self {
+            api_version,
+            mapped_ranges: api_version >= 710,
+            tenants: api_version >= 710,
+            estimated_range_size: api_version >= 630,
+        }
+    }
+
+    /// Negotiates the API version with the loaded client library.
+    pub fn detect() -> Self {
+        Self::for_api_version(foundationdb::api::get_max_api_version().min(API_VERSION))
+    }
+}
+
+/// How a transaction competes with the others, selected per operation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CommitProfile {
+    /// User-facing operations, run with the default options.
+    #[default]
+    Interactive,
+    /// Large writes issued in many transactions, such as imports. Read versions are
+    /// obtained without confirming with the cluster that they are the latest
+    /// (`CausalReadRisky`), which spares a round trip per transaction: in the rare event of
+    /// a fault and a misbehaving clock, a transaction reading stale data fails to commit
+    /// with a conflict and is retried.
+    Bulk,
+    /// Maintenance jobs, such as index backfills and checksums. Transactions run at batch
+    /// priority (`PriorityBatch`): the cluster throttles them first when saturated, so they
+    /// stop competing with user-facing traffic.
+    Background,
+}
+
 #[derive(Clone)]
 pub struct Storage {
     pub database: Arc<Database>,
+    /// Tags attached to every transaction, for cluster-side throttling.
+    tags: Vec<String>,
+    /// Whether transactions may read a locked database, shared by the clones.
+    read_lock_aware: Arc<AtomicBool>,
+    capabilities: Capabilities,
+    /// Latency above which bulk and background transactions are slowed down, if any.
+    throttle_latency: Option<Duration>,
+    /// Current throttle level, shared by the clones.
+    throttle_level: Arc<AtomicU32>,
 }
 
 impl Storage {
     pub fn new(database: Arc<Database>) -> Self {
-        Self { database }
+        Self {
+            database,
+            tags: vec![],
+            read_lock_aware: Arc::new(AtomicBool::new(false)),
+            capabilities: Capabilities::detect(),
+            throttle_latency: Some(DEFAULT_THROTTLE_LATENCY),
+            throttle_level: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Returns the FoundationDB features the storage relies on.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Overrides the detected FoundationDB features, typically to opt out of a feature the
+    /// cluster doesn't support.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Sets the latency of bulk and background transactions above which they are slowed
+    /// down, or `None` to never slow them down. Defaults to 200ms.
+    ///
+    /// Those transactions are run by jobs writing or reading many batches, such as imports
+    /// and index backfills. Their latency rises as the cluster saturates: its storage
+    /// queues fill up and commits take longer. Every transaction slower than the latency
+    /// raises the throttle level by one, every faster one lowers it by one, and each level
+    /// delays the next bulk or background transaction by 10ms, up to 200ms. Interactive
+    /// transactions are never delayed.
+    pub fn set_throttle_latency(&mut self, latency: Option<Duration>) {
+        self.throttle_latency = latency;
+    }
+
+    /// Returns the current throttle level, from 0 when bulk and background transactions
+    /// run at full speed up to 20, see [`Storage::set_throttle_latency`].
+    ///
+    /// The level is also recorded on the `fdb.transaction` span of every transaction.
+    pub fn throttle_level(&self) -> u32 {
+        self.throttle_level.load(Ordering::Relaxed)
+    }
+
+    /// Raises or lowers the throttle level by one, within its bounds.
+    fn adjust_throttle_level(&self, saturated: bool) {
+        let _ = self
+            .throttle_level
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |level| {
+                Some(if saturated {
+                    (level + 1).min(MAX_THROTTLE_LEVEL)
+                } else {
+                    level.saturating_sub(1)
+                })
+            });
+    }
+
+    /// Sets the tags attached to every transaction run by the storage.
+    ///
+    /// Tags let operators throttle a workload from the cluster, and the cluster throttles
+    /// busy tags on its own. A throttled transaction is delayed and retried, it only fails
+    /// with [`SqlLayerError::TagThrottled`] once the retry limit or the timeout configured on
+    /// the database is reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tag is empty or longer than 16 bytes, or if there are more than
+    /// 4 tags: FoundationDB accepts 5 tags per transaction and one is kept for the table tag.
+    pub fn set_transaction_tags(&mut self, tags: Vec<String>) -> crate::errors::Result<()> {
+        if tags.len() >= MAX_TRANSACTION_TAGS {
+            return Err(SqlLayerError::InvalidTransactionTag(format!(
+                "at most {} tags are allowed",
+                MAX_TRANSACTION_TAGS - 1
+            )));
+        }
+        if let Some(tag) = tags
+            .iter()
+            .find(|tag| tag.is_empty() || tag.len() > MAX_TRANSACTION_TAG_LENGTH)
+        {
+            return Err(SqlLayerError::InvalidTransactionTag(tag.clone()));
+        }
+        self.tags = tags;
+        Ok(())
+    }
+
+    /// Sets whether transactions may read a locked database.
+    ///
+    /// Transactions that aren't lock aware fail on a locked database, even when they only
+    /// read. Reading a locked database doesn't allow writing it: commits still fail.
+    pub(crate) fn set_read_lock_aware(&self, read_lock_aware: bool) {
+        self.read_lock_aware
+            .store(read_lock_aware, Ordering::Relaxed);
+    }
+
+    /// Applies the options of the storage to a transaction: its tags, along with an
+    /// optional extra tag, whether it may read a locked database, and the options of the
+    /// commit profile.
+    pub(crate) fn prepare_transaction(
+        &self,
+        trx: &Transaction,
+        tag: Option<&str>,
+        profile: CommitProfile,
+    ) -> FdbResult<()> {
+        for tag in self.tags.iter().map(String::as_str).chain(tag) {
+            trx.set_option(TransactionOption::AutoThrottleTag(tag.to_string()))?;
+        }
+        if self.read_lock_aware.load(Ordering::Relaxed) {
+            trx.set_option(TransactionOption::ReadLockAware)?;
+        }
+        match profile {
+            CommitProfile::Interactive => {}
+            CommitProfile::Bulk => trx.set_option(TransactionOption::CausalReadRisky)?,
+            CommitProfile::Background => trx.set_option(TransactionOption::PriorityBatch)?,
+        }
+        Ok(())
+    }
+
+    /// Runs a transaction with [`Database::run`], retrying it on retryable errors.
+    ///
+    /// The transaction carries the tags of the storage, along with `tag` if given, see
+    /// [`Storage::prepare_transaction`]. It runs with the [`CommitProfile::Interactive`]
+    /// profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the closure, or the error of the transaction if it can't be
+    /// retried.
+    pub async fn run<F, Fut, T>(&self, tag: Option<&str>, closure: F) -> crate::errors::Result<T>
+    where
+        F: Fn(RetryableTransaction, MaybeCommitted) -> Fut,
+        Fut: Future<Output = Result<T, FdbBindingError>>,
+    {
+        self.run_with_profile(CommitProfile::Interactive, tag, closure)
+            .await
+    }
+
+    /// Runs a transaction as [`Storage::run`] does, with the options of a commit profile.
+    ///
+    /// The transaction is traced by a `fdb.transaction` span, recording its tag, its
+    /// profile, the number of times it has been attempted and the throttle level.
+    ///
+    /// Bulk and background transactions are delayed while the cluster is saturated, see
+    /// [`Storage::set_throttle_latency`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the closure, or the error of the transaction if it can't be
+    /// retried.
+    pub async fn run_with_profile<F, Fut, T>(
+        &self,
+        profile: CommitProfile,
+        tag: Option<&str>,
+        closure: F,
+    ) -> crate::errors::Result<T>
+    where
+        F: Fn(RetryableTransaction, MaybeCommitted) -> Fut,
+        Fut: Future<Output = Result<T, FdbBindingError>>,
+    {
+        let closure = &closure;
+        let attempts = &AtomicU32::new(0);
+        let throttle_latency = self
+            .throttle_latency
+            .filter(|_| profile != CommitProfile::Interactive);
+        let throttle_level = self.throttle_level();
+        let span = tracing::info_span!(
+            "fdb.transaction",
+            db.system = "foundationdb",
+            tag,
+            profile = ?profile,
+            attempts = tracing::field::Empty,
+            throttle_level,
+        );
+        if throttle_latency.is_some() && throttle_level > 0 {
+            tokio::time::sleep(THROTTLE_STEP * throttle_level).await;
+        }
+        let started = Instant::now();
+        let value = self
+            .database
+            .run(|trx, maybe_committed| async move {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                self.prepare_transaction(&trx, tag, profile)?;
+                closure(trx, maybe_committed).await
+            })
+            .instrument(span.clone())
+            .await;
+        span.record("attempts", attempts.load(Ordering::Relaxed));
+        if let Some(latency) = throttle_latency {
+            self.adjust_throttle_level(started.elapsed() > latency);
+        }
+        Ok(value?)
     }
 
     /// Sets a key-value pair in the FoundationDB database.
@@ -58,12 +343,11 @@ impl Storage {
     /// This method will return an error if the transaction to set the key-value
     /// pair in FoundationDB cannot be completed.
     pub async fn set(&self, key: &[u8], value: &[u8]) -> crate::errors::Result<()> {
-        self.database
-            .run(|trx, _| async move {
-                trx.set(key, value);
-                Ok(())
-            })
-            .await?;
+        self.run(None, |trx, _| async move {
+            trx.set(key, value);
+            Ok(())
+        })
+        .await?;
         Ok(())
     }
 
@@ -86,8 +370,7 @@ impl Storage {
     /// from the FoundationDB database cannot be completed.
     pub async fn get(&self, key: &[u8]) -> crate::errors::Result<Option<Vec<u8>>> {
         let value = self
-            .database
-            .run(|trx, _| async move { Ok(trx.get(key, true).await?) })
+            .run(None, |trx, _| async move { Ok(trx.get(key, true).await?) })
             .await?;
         let value = value.map(|v| v.to_vec());
         Ok(value)
@@ -109,15 +392,36 @@ impl Storage {
     /// This method will return an error if the transaction to delete the key-value
     /// pair from the FoundationDB database cannot be completed.
     pub async fn delete(&self, key: &[u8]) -> crate::errors::Result<()> {
-        self.database
-            .run(|trx, _| async move {
-                trx.clear(key);
-                Ok(())
-            })
-            .await?;
+        self.run(None, |trx, _| async move {
+            trx.clear(key);
+            Ok(())
+        })
+        .await?;
         Ok(())
     }
 
+    /// Deletes every key-value pair stored in a range from the FoundationDB database.
+    ///
+    /// The range is cleared with a single range clear, whatever the number of keys it
+    /// holds, rather than by reading and deleting its keys one by one.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A byte slice representing the starting key of the range (inclusive).
+    /// * `end`: A byte slice representing the ending key of the range (exclusive).
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the transaction to clear the range cannot be
+    /// completed.
+    pub async fn clear_range(&self, start: &[u8], end: &[u8]) -> crate::errors::Result<()> {
+        self.run(None, |trx, _| async move {
+            trx.clear_range(start, end);
+            Ok(())
+        })
+        .await
+    }
+
     /// Scans a range of key-value pairs in the FoundationDB database.
     ///
     /// # Parameters
@@ -141,8 +445,7 @@ impl Storage {
         end: &[u8],
     ) -> crate::errors::Result<Vec<(Vec<u8>, Vec<u8>)>> {
         let kvs = self
-            .database
-            .run(|trx, _| async move {
+            .run(None, |trx, _| async move {
                 let range = RangeOption::from((start, end));
                 let stream = trx.get_ranges_keyvalues(range, false);
                 collect_stream(stream).await
@@ -152,6 +455,126 @@ impl Storage {
         Ok(kvs)
     }
 
+    /// Estimates the size in bytes of the key-value pairs stored in a range.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A byte slice representing the starting key of the range (inclusive).
+    /// * `end`: A byte slice representing the ending key of the range (exclusive).
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the estimation computed by FoundationDB from its
+    /// sampling of the storage servers. Small ranges may be reported as empty.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if:
+    /// - Range size estimates aren't supported, see [`Capabilities`].
+    /// - The transaction to estimate the range size cannot be completed.
+    pub async fn estimated_size(&self, start: &[u8], end: &[u8]) -> crate::errors::Result<i64> {
+        if !self.capabilities.estimated_range_size {
+            return Err(SqlLayerError::Unsupported(
+                "get_estimated_range_size".to_string(),
+            ));
+        }
+        let size = self
+            .run(None, |trx, _| async move {
+                Ok(trx.get_estimated_range_size_bytes(start, end).await?)
+            })
+            .await?;
+        Ok(size)
+    }
+
+    /// Counts the key-value pairs stored in a range.
+    ///
+    /// The range is walked with [`Storage::full_scan`], so counting is linear in the
+    /// number of keys but never holds more than one scan batch in memory.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A byte slice representing the starting key of the range (inclusive).
+    /// * `end`: A byte slice representing the ending key of the range (exclusive).
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if one of the scan transactions cannot be completed.
+    pub async fn count(&self, start: &[u8], end: &[u8]) -> crate::errors::Result<u64> {
+        self.full_scan(start, end)
+            .await
+            .try_fold(0, |count, _| async move { Ok(count + 1) })
+            .await
+    }
+
+    /// Retrieves the first key-value pair stored in a range.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A byte slice representing the starting key of the range (inclusive).
+    /// * `end`: A byte slice representing the ending key of the range (exclusive).
+    ///
+    /// # Returns
+    ///
+    /// Returns the key-value pair with the smallest key of the range, or `None` if the range
+    /// is empty.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the transaction reading the range cannot be
+    /// completed.
+    pub async fn first(
+        &self,
+        start: &[u8],
+        end: &[u8],
+    ) -> crate::errors::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.endpoint(start, end, false).await
+    }
+
+    /// Retrieves the last key-value pair stored in a range, reading the range backward.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A byte slice representing the starting key of the range (inclusive).
+    /// * `end`: A byte slice representing the ending key of the range (exclusive).
+    ///
+    /// # Returns
+    ///
+    /// Returns the key-value pair with the greatest key of the range, or `None` if the range
+    /// is empty.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the transaction reading the range cannot be
+    /// completed.
+    pub async fn last(
+        &self,
+        start: &[u8],
+        end: &[u8],
+    ) -> crate::errors::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.endpoint(start, end, true).await
+    }
+
+    async fn endpoint(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        reverse: bool,
+    ) -> crate::errors::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let kv = self
+            .run(None, |trx, _| async move {
+                let mut range = RangeOption::from((start, end));
+                range.limit = Some(1);
+                range.reverse = reverse;
+                let kvs = trx.get_range(&range, 1, false).await?;
+                Ok(kvs
+                    .iter()
+                    .next()
+                    .map(|kv| (kv.key().to_vec(), kv.value().to_vec())))
+            })
+            .await?;
+        Ok(kv)
+    }
+
     /// Performs a full key-value scan over a specified range in the FoundationDB database.
     ///
     /// The scan uses a streaming approach to retrieve large amounts of data without loading
@@ -283,6 +706,34 @@ mod tests {
         assert_eq!(result.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_clear_range() {
+        let _guard = get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+
+        for i in 0..10 {
+            let key = pack(&("clear", &i));
+            storage
+                .set(&key, format!("value{}", i).as_bytes())
+                .await
+                .expect("Unable to set key");
+        }
+
+        let start = pack(&("clear", &2));
+        let end = pack(&("clear", &8));
+        storage
+            .clear_range(&start, &end)
+            .await
+            .expect("Unable to clear range");
+        let result = storage
+            .scan(&pack(&("clear", &0)), &pack(&("clear", &10)))
+            .await
+            .expect("Unable to scan");
+        let keys = result.into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        let expected = [0, 1, 8, 9].map(|i| pack(&("clear", &i)));
+        assert_eq!(keys, expected);
+    }
+
     #[tokio::test]
     async fn test_bigger_than_max_scan() {
         let _guard = get_db_once().await;
@@ -326,4 +777,155 @@ mod tests {
             .await;
         assert_eq!(result.len(), 100);
     }
+
+    #[tokio::test]
+    async fn test_count() {
+        let _guard = get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+
+        for i in 0..MAX_SCAN_SIZE * 2 + 1 {
+            let key = pack(&("count", &i));
+            storage
+                .set(&key, format!("value{}", i).as_bytes())
+                .await
+                .expect("Unable to set key");
+        }
+
+        let start = pack(&("count", &0));
+        let end = pack(&("count", &1000));
+        let count = storage.count(&start, &end).await.expect("Unable to count");
+        assert_eq!(count, (MAX_SCAN_SIZE * 2 + 1) as u64);
+    }
+
+    #[tokio::test]
+    async fn test_first_and_last() {
+        let _guard = get_db_once().await;
+        let storage = Storage::new(_guard.clone());
+        for i in 0..5 {
+            let key = pack(&("endpoint", &i));
+            storage
+                .set(&key, format!("value{}", i).as_bytes())
+                .await
+                .expect("Unable to set key");
+        }
+
+        let start = pack(&("endpoint", &1));
+        let end = pack(&("endpoint", &4));
+        let first = storage.first(&start, &end).await.expect("Unable to read");
+        assert_eq!(first, Some((pack(&("endpoint", &1)), b"value1".to_vec())));
+        let last = storage.last(&start, &end).await.expect("Unable to read");
+        assert_eq!(last, Some((pack(&("endpoint", &3)), b"value3".to_vec())));
+
+        let empty = pack(&("endpoint", &10));
+        let first = storage.first(&empty, &empty).await.expect("Unable to read");
+        assert_eq!(first, None);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_tags() {
+        let _guard = get_db_once().await;
+        let mut storage = Storage::new(_guard.clone());
+        let result = storage.set_transaction_tags(vec!["a_tag_longer_than_16_bytes".to_string()]);
+        assert!(matches!(
+            result,
+            Err(crate::errors::SqlLayerError::InvalidTransactionTag(_))
+        ));
+        let result = storage.set_transaction_tags(vec!["tag".to_string(); 5]);
+        assert!(matches!(
+            result,
+            Err(crate::errors::SqlLayerError::InvalidTransactionTag(_))
+        ));
+
+        storage
+            .set_transaction_tags(vec!["batch".to_string(), "reporting".to_string()])
+            .expect("Unable to set tags");
+        let key = pack(&("tagged", 1));
+        storage
+            .set(&key, b"value")
+            .await
+            .expect("Unable to set key");
+        let key = key.as_slice();
+        let value = storage
+            .run(Some("extra"), |trx, _| async move {
+                Ok(trx.get(key, false).await?)
+            })
+            .await
+            .expect("Unable to get key");
+        assert_eq!(value.as_deref(), Some(&b"value"[..]));
+
+        for profile in [
+            CommitProfile::Interactive,
+            CommitProfile::Bulk,
+            CommitProfile::Background,
+        ] {
+            let value = storage
+                .run_with_profile(profile, None, |trx, _| async move {
+                    Ok(trx.get(key, false).await?)
+                })
+                .await
+                .expect("Unable to get key");
+            assert_eq!(value.as_deref(), Some(&b"value"[..]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        let capabilities = Capabilities::for_api_version(API_VERSION);
+        assert!(capabilities.mapped_ranges && capabilities.tenants);
+        assert!(capabilities.estimated_range_size);
+        let capabilities = Capabilities::for_api_version(630);
+        assert!(!capabilities.mapped_ranges && !capabilities.tenants);
+        assert!(capabilities.estimated_range_size);
+        assert!(!Capabilities::for_api_version(620).estimated_range_size);
+
+        let _guard = get_db_once().await;
+        let mut storage = Storage::new(_guard.clone());
+        assert!(storage.capabilities().api_version <= API_VERSION);
+        let (start, end) = (pack(&("capabilities", 0)), pack(&("capabilities", 1)));
+        storage
+            .estimated_size(&start, &end)
+            .await
+            .expect("Unable to estimate size");
+
+        storage.set_capabilities(Capabilities::for_api_version(620));
+        let result = storage.estimated_size(&start, &end).await;
+        assert!(matches!(
+            result,
+            Err(crate::errors::SqlLayerError::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_throttle_level() {
+        let _guard = get_db_once().await;
+        let mut storage = Storage::new(_guard.clone());
+        let run = |storage: &Storage, profile| {
+            let storage = storage.clone();
+            async move {
+                storage
+                    .run_with_profile(profile, None, |trx, _| async move {
+                        trx.set(b"throttle", b"value");
+                        Ok(())
+                    })
+                    .await
+                    .expect("Unable to run transaction");
+            }
+        };
+
+        // every transaction is too slow for a zero latency
+        storage.set_throttle_latency(Some(Duration::ZERO));
+        run(&storage, CommitProfile::Bulk).await;
+        run(&storage, CommitProfile::Background).await;
+        assert_eq!(storage.throttle_level(), 2);
+        run(&storage, CommitProfile::Interactive).await;
+        assert_eq!(storage.throttle_level(), 2);
+
+        storage.set_throttle_latency(Some(Duration::from_secs(60)));
+        run(&storage, CommitProfile::Bulk).await;
+        assert_eq!(storage.throttle_level(), 1);
+
+        storage.set_throttle_latency(None);
+        run(&storage, CommitProfile::Bulk).await;
+        assert_eq!(storage.throttle_level(), 1);
+    }
 }
@@ -1,14 +1,69 @@
+use crate::codec::RowEncoding;
+use crate::collation::Collation;
+use crate::compression::{RowCompression, DEFAULT_COMPRESSION_THRESHOLD};
+use crate::errors::SqlLayerError;
 pub(crate) use crate::index::Index;
+use crate::plan::Direction;
+use crate::predicate::Predicate;
+use crate::record::Column;
+use crate::schema::SchemaMode;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 const SCHEMA: &str = include_str!("assets/schemas/table.json");
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Table {
     pub name: String,
     pub fields: Vec<Field>,
     pub primary_key: Vec<String>,
     pub indexes: Vec<Index>,
+    /// Whether `Column::Null` is accepted in primary key columns.
+    ///
+    /// Nulls are rejected by default. When allowed, a null packs as the tuple
+    /// nil value, so it is always encoded, and therefore matched, the same way.
+    #[serde(default)]
+    pub allow_null_primary_key: bool,
+    /// Constraints every record written to the table must pass.
+    #[serde(default)]
+    pub checks: Vec<Check>,
+    /// References from the records of the table to the rows of other tables.
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+    /// Positions, in the stored rows, of the columns of the dropped fields, in ascending
+    /// order. The stored rows keep a column in each of these places, which reads ignore.
+    #[serde(default)]
+    pub dropped_columns: Vec<i64>,
+    /// Id allocated to the table when it is created, keying its rows, primary key and
+    /// index entries. `None` for a table not created yet, or created before ids were
+    /// allocated, whose data is keyed by its name.
+    #[serde(default)]
+    pub id: Option<i64>,
+    /// Version of the definition of the table, 0 when created and bumped by every change
+    /// to it.
+    #[serde(default)]
+    pub schema_version: i64,
+    /// Codec of the stored rows, chosen when the table is created.
+    #[serde(default)]
+    pub row_encoding: RowEncoding,
+    /// Compression of the stored rows of at least `compression_threshold` bytes.
+    #[serde(default)]
+    pub compression: RowCompression,
+    #[serde(default = "default_compression_threshold")]
+    pub compression_threshold: i64,
+    /// Directions of the primary key columns, in order, the columns past the end being
+    /// ascending, see [`Table::set_primary_key_directions`].
+    #[serde(default)]
+    pub primary_key_directions: Vec<Direction>,
+    /// Names of the tables holding a foreign key to this one, itself included when it
+    /// references itself, recorded as they are created so that deleting a row doesn't
+    /// look the referencing tables up among all the tables.
+    #[serde(default)]
+    pub referencing_tables: Vec<String>,
+}
+
+fn default_compression_threshold() -> i64 {
+    DEFAULT_COMPRESSION_THRESHOLD
 }
 
 impl Table {
@@ -18,17 +73,238 @@ impl Table {
             fields: vec![],
             primary_key,
             indexes: vec![],
+            allow_null_primary_key: false,
+            checks: vec![],
+            foreign_keys: vec![],
+            dropped_columns: vec![],
+            id: None,
+            schema_version: 0,
+            row_encoding: RowEncoding::Avro,
+            compression: RowCompression::None,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            primary_key_directions: vec![],
+            referencing_tables: vec![],
         }
     }
 
+    pub fn set_allow_null_primary_key(&mut self, allow: bool) {
+        self.allow_null_primary_key = allow;
+    }
+
+    /// Orders the rows by the primary key columns in the given directions, so that a
+    /// forward scan of the primary key returns e.g. the latest rows first. It only applies
+    /// to a table not created yet, the keys of the rows being encoded after them.
+    pub fn set_primary_key_directions(&mut self, directions: Vec<Direction>) {
+        self.primary_key_directions = directions;
+    }
+
+    /// Direction of the primary key column at the given position.
+    pub fn primary_key_direction(&self, position: usize) -> Direction {
+        self.primary_key_directions
+            .get(position)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the codec of the stored rows, see [`RowEncoding`]. It only applies to a table
+    /// not created yet, the rows of a table keeping the codec it was created with.
+    pub fn set_row_encoding(&mut self, encoding: RowEncoding) {
+        self.row_encoding = encoding;
+    }
+
+    /// Compresses the stored rows of at least `threshold` bytes, see [`RowCompression`].
+    pub fn set_compression(&mut self, compression: RowCompression, threshold: i64) {
+        self.compression = compression;
+        self.compression_threshold = threshold;
+    }
+
     pub fn add_field(&mut self, field: Field) {
         self.fields.push(field);
     }
 
+    /// Adds an index to the table, see [`Table::check_indexes`] for the indexes a table
+    /// may hold.
     pub fn add_index(&mut self, index: &Index) {
         self.indexes.push(index.clone());
     }
 
+    /// Adds a check constraint to the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidConstraint` if the table already has a constraint of
+    /// the same name, or `SqlLayerError::InvalidSqlText` if the expression isn't supported,
+    /// see [`Check::predicate`].
+    pub fn add_check(&mut self, check: Check) -> crate::errors::Result<()> {
+        if self.checks.iter().any(|other| other.name == check.name) {
+            return Err(SqlLayerError::InvalidConstraint(format!(
+                "duplicate constraint {}",
+                check.name
+            )));
+        }
+        check.predicate()?;
+        self.checks.push(check);
+        Ok(())
+    }
+
+    /// Adds a foreign key to the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidForeignKey` if the table already has a constraint of
+    /// the same name, or if the foreign key has no field or a field the table doesn't have.
+    pub fn add_foreign_key(&mut self, foreign_key: ForeignKey) -> crate::errors::Result<()> {
+        if self
+            .foreign_keys
+            .iter()
+            .any(|other| other.name == foreign_key.name)
+            || self
+                .checks
+                .iter()
+                .any(|other| other.name == foreign_key.name)
+        {
+            return Err(SqlLayerError::InvalidForeignKey(format!(
+                "duplicate constraint {}",
+                foreign_key.name
+            )));
+        }
+        if foreign_key.fields.is_empty() {
+            return Err(SqlLayerError::InvalidForeignKey(format!(
+                "foreign key {} has no field",
+                foreign_key.name
+            )));
+        }
+        if let Some(field) = foreign_key
+            .fields
+            .iter()
+            .find(|field| self.get_field_pos(field).is_none())
+        {
+            return Err(SqlLayerError::InvalidForeignKey(format!(
+                "foreign key {} references unknown field {field}",
+                foreign_key.name
+            )));
+        }
+        self.foreign_keys.push(foreign_key);
+        Ok(())
+    }
+
+    /// Removes a field from the table. The column of the field stays in the stored rows and
+    /// is ignored from then on, see [`Table::dropped_columns`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The table has no field of this name.
+    /// - The field is part of the primary key, or is used by an index, a check or a foreign
+    ///   key of the table.
+    pub fn drop_field(&mut self, field_name: &str) -> crate::errors::Result<()> {
+        let position = self
+            .get_field_pos(field_name)
+            .ok_or_else(|| SqlLayerError::MissingColumn(field_name.to_string()))?;
+        let in_use = |user: String| SqlLayerError::ColumnInUse(field_name.to_string(), user);
+        if self.primary_key.iter().any(|name| name == field_name) {
+            return Err(in_use("the primary key".to_string()));
+        }
+        if let Some(index) = self
+            .indexes
+            .iter()
+            .find(|index| index.fields().iter().any(|name| name == field_name))
+        {
+            return Err(in_use(format!("index {}", index.name())));
+        }
+        if let Some(foreign_key) = self
+            .foreign_keys
+            .iter()
+            .find(|foreign_key| foreign_key.fields.iter().any(|name| name == field_name))
+        {
+            return Err(in_use(format!("foreign key {}", foreign_key.name)));
+        }
+
+        let mut table = self.clone();
+        table.fields.remove(position);
+        table.validate_checks(field_name)?;
+        let slot = self.stored_position(position) as i64;
+        let at = table
+            .dropped_columns
+            .partition_point(|&dropped| dropped < slot);
+        table.dropped_columns.insert(at, slot);
+        *self = table;
+        Ok(())
+    }
+
+    /// Renames a field of the table, along with its references from the primary key, the
+    /// indexes and the foreign keys of the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The new name is not a valid identifier, or is the name of another field.
+    /// - The table has no field of the old name.
+    /// - The field is used by a check of the table, whose expression would no longer match.
+    pub fn rename_field(&mut self, from: &str, to: &str) -> crate::errors::Result<()> {
+        crate::sql::check_identifier(to)?;
+        let position = self
+            .get_field_pos(from)
+            .ok_or_else(|| SqlLayerError::MissingColumn(from.to_string()))?;
+        if self.get_field_pos(to).is_some() {
+            return Err(SqlLayerError::FieldAlreadyExists(to.to_string()));
+        }
+
+        let mut table = self.clone();
+        table.fields[position].name = to.to_string();
+        table.validate_checks(from)?;
+        for name in table.primary_key.iter_mut().chain(
+            table
+                .foreign_keys
+                .iter_mut()
+                .flat_map(|foreign_key| foreign_key.fields.iter_mut()),
+        ) {
+            if name == from {
+                *name = to.to_string();
+            }
+        }
+        for index in &mut table.indexes {
+            index.rename_field(from, to);
+        }
+        *self = table;
+        Ok(())
+    }
+
+    /// Checks that the expressions of the checks still match the fields of the table once
+    /// the given field was dropped or renamed.
+    fn validate_checks(&self, field_name: &str) -> crate::errors::Result<()> {
+        for check in &self.checks {
+            if check.predicate()?.validate(self).is_err() {
+                return Err(SqlLayerError::ColumnInUse(
+                    field_name.to_string(),
+                    format!("check {}", check.name),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Position, in the stored rows, of the column of the field at the given position.
+    pub(crate) fn stored_position(&self, position: usize) -> usize {
+        let mut stored = position;
+        for &slot in &self.dropped_columns {
+            if slot as usize <= stored {
+                stored += 1;
+            }
+        }
+        stored
+    }
+
+    /// Iterates over the foreign keys referencing the given table.
+    pub fn foreign_keys_to<'a>(
+        &'a self,
+        parent: &'a str,
+    ) -> impl Iterator<Item = &'a ForeignKey> + 'a {
+        self.foreign_keys
+            .iter()
+            .filter(move |foreign_key| foreign_key.parent == parent)
+    }
+
     pub fn to_bytes(&self) -> crate::errors::Result<Vec<u8>> {
         let schema = apache_avro::schema::Schema::parse_str(SCHEMA)?;
         let value = apache_avro::to_value(self)?;
@@ -37,27 +313,560 @@ impl Table {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> crate::errors::Result<Self> {
-        let schema = apache_avro::schema::Schema::parse_str(SCHEMA)?;
-        let mut data = bytes;
-        let value = apache_avro::from_avro_datum(&schema, &mut data, None)?;
-        let table = apache_avro::from_value::<Self>(&value)?;
+        Self::from_bytes_with_mode(bytes, SchemaMode::Compat)
+    }
+
+    /// Deserializes bytes written by this or a newer version of the crate.
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Self> {
+        let mut table: Table = crate::schema::decode_datum(SCHEMA, bytes, mode)?;
+        for field in &mut table.fields {
+            let default = field.default.take();
+            field.default = default.map(|default| default.with_field(field));
+        }
         Ok(table)
     }
 
     pub fn get_field_pos(&self, field_name: &str) -> Option<usize> {
         self.fields.iter().position(|f| f.name == field_name)
     }
+
+    pub fn get_index(&self, index_name: &str) -> Option<&Index> {
+        self.indexes.iter().find(|index| index.name() == index_name)
+    }
+
+    pub fn get_index_mut(&mut self, index_name: &str) -> Option<&mut Index> {
+        self.indexes
+            .iter_mut()
+            .find(|index| index.name() == index_name)
+    }
+
+    /// Checks that the blob fields of the table, see [`Field::new_blob`], are `Bytes`
+    /// fields out of the primary key, the indexes and the foreign keys, which are built
+    /// from the columns stored in the rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidBlobField` naming the first blob field that isn't.
+    pub fn check_blob_fields(&self) -> crate::errors::Result<()> {
+        for field in self.fields.iter().filter(|field| field.blob) {
+            let invalid = |reason: String| {
+                SqlLayerError::InvalidBlobField(format!("{} {reason}", field.name))
+            };
+            if field.r#type != FieldType::Bytes {
+                return Err(invalid(format!("is of type {:?}", field.r#type)));
+            }
+            if self.primary_key.contains(&field.name) {
+                return Err(invalid("is part of the primary key".to_string()));
+            }
+            if let Some(index) = self
+                .indexes
+                .iter()
+                .find(|index| index.fields().contains(&field.name))
+            {
+                return Err(invalid(format!("is used by index {}", index.name())));
+            }
+            if let Some(foreign_key) = self
+                .foreign_keys
+                .iter()
+                .find(|foreign_key| foreign_key.fields.contains(&field.name))
+            {
+                return Err(invalid(format!(
+                    "is used by foreign key {}",
+                    foreign_key.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the array fields of the table, see [`Field::new_array`], have an item
+    /// type other than `Array`, and that the multi-entry indexes, see
+    /// [`Index::new_multi_entry`], cover a single array field.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidArrayField` naming the first field or index that
+    /// isn't valid.
+    pub fn check_array_fields(&self) -> crate::errors::Result<()> {
+        for field in &self.fields {
+            let array = field.r#type == FieldType::Array;
+            match field.item_type {
+                Some(FieldType::Array) if array => {
+                    return Err(SqlLayerError::InvalidArrayField(format!(
+                        "{} holds nested arrays",
+                        field.name
+                    )));
+                }
+                None if array => {
+                    return Err(SqlLayerError::InvalidArrayField(format!(
+                        "{} has no item type",
+                        field.name
+                    )));
+                }
+                _ => {}
+            }
+        }
+        for index in self.indexes.iter().filter(|index| index.is_multi_entry()) {
+            let is_array = |name: &String| {
+                self.get_field_pos(name)
+                    .is_some_and(|i| self.fields[i].r#type == FieldType::Array)
+            };
+            if !matches!(index.fields().as_slice(), [field] if is_array(field)) {
+                return Err(SqlLayerError::InvalidArrayField(format!(
+                    "multi-entry index {} doesn't cover a single array field",
+                    index.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the enum fields of the table, see [`Field::new_enum`], have distinct
+    /// variants, at least one, and that no array field holds enums, whose elements would be
+    /// stored as strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidEnumField` naming the first field that isn't valid.
+    pub fn check_enum_fields(&self) -> crate::errors::Result<()> {
+        for field in &self.fields {
+            let invalid =
+                |reason: &str| SqlLayerError::InvalidEnumField(format!("{} {reason}", field.name));
+            if field.r#type == FieldType::Array && field.item_type == Some(FieldType::Enum) {
+                return Err(invalid("holds enums"));
+            }
+            if field.r#type != FieldType::Enum {
+                continue;
+            }
+            if field.variants.is_empty() {
+                return Err(invalid("has no variants"));
+            }
+            if field
+                .variants
+                .iter()
+                .enumerate()
+                .any(|(i, variant)| field.variant_ordinal(variant) != Some(i))
+            {
+                return Err(invalid("has duplicate variants"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the fields of the table with a collation other than `Binary`, see
+    /// [`Field::collation`], hold strings or arrays of strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidCollation` naming the first field that doesn't.
+    pub fn check_collations(&self) -> crate::errors::Result<()> {
+        for field in &self.fields {
+            let strings = match field.r#type {
+                FieldType::String => true,
+                FieldType::Array => field.item_type == Some(FieldType::String),
+                _ => false,
+            };
+            if field.collation != Collation::Binary && !strings {
+                return Err(SqlLayerError::InvalidCollation(format!(
+                    "{} of type {:?} can't be collated {:?}",
+                    field.name, field.r#type, field.collation
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the serial fields of the table, see [`Field::new_serial`], are integer
+    /// fields without a default, which would leave their counter unused.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidSerialField` naming the first field that isn't valid.
+    pub fn check_serial_fields(&self) -> crate::errors::Result<()> {
+        for field in self.fields.iter().filter(|field| field.serial) {
+            let invalid = |reason: String| {
+                SqlLayerError::InvalidSerialField(format!("{} {reason}", field.name))
+            };
+            if !matches!(
+                field.r#type,
+                FieldType::Int | FieldType::Int32 | FieldType::Int16 | FieldType::UInt64
+            ) {
+                return Err(invalid(format!("is of type {:?}", field.r#type)));
+            }
+            if field.default.is_some() {
+                return Err(invalid("has a default".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the primary key names at least one field, that every name refers to a
+    /// distinct field of the table whose values can make a key, i.e. that isn't an array,
+    /// and that it has no more directions than columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidPrimaryKey` naming the first column that isn't valid.
+    pub fn check_primary_key(&self) -> crate::errors::Result<()> {
+        let invalid =
+            |reason: String| SqlLayerError::InvalidPrimaryKey(format!("{} {reason}", self.name));
+        if self.primary_key.is_empty() {
+            return Err(invalid("has no primary key column".to_string()));
+        }
+        for (i, name) in self.primary_key.iter().enumerate() {
+            let Some(position) = self.get_field_pos(name) else {
+                return Err(invalid(format!("has no field {name}")));
+            };
+            if self.primary_key[..i].contains(name) {
+                return Err(invalid(format!("repeats {name} in its primary key")));
+            }
+            let field = &self.fields[position];
+            if field.r#type == FieldType::Array {
+                return Err(invalid(format!("can't key its rows by array field {name}")));
+            }
+        }
+        if self.primary_key_directions.len() > self.primary_key.len() {
+            return Err(invalid(format!(
+                "has {} primary key directions for {} columns",
+                self.primary_key_directions.len(),
+                self.primary_key.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that the indexes of the table have distinct names and only index fields of
+    /// the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::IndexAlreadyExists` naming the first repeated index, or
+    /// `SqlLayerError::MissingColumn` naming the first indexed field the table doesn't have.
+    pub fn check_indexes(&self) -> crate::errors::Result<()> {
+        for (i, index) in self.indexes.iter().enumerate() {
+            if self.indexes[..i]
+                .iter()
+                .any(|other| other.name() == index.name())
+            {
+                return Err(SqlLayerError::IndexAlreadyExists(index.name().to_string()));
+            }
+            if let Some(field) = index
+                .fields()
+                .iter()
+                .find(|field| self.get_field_pos(field).is_none())
+            {
+                return Err(SqlLayerError::MissingColumn(field.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the collation of a field, `Binary` for a field the table doesn't have.
+    pub(crate) fn collation(&self, field_name: &str) -> Collation {
+        self.get_field_pos(field_name)
+            .map_or(Collation::Binary, |i| self.fields[i].collation)
+    }
+
+    /// Checks that the table, field and index names are valid identifiers.
+    pub fn check_identifiers(&self) -> crate::errors::Result<()> {
+        crate::sql::check_identifier(&self.name)?;
+        for field in &self.fields {
+            crate::sql::check_identifier(&field.name)?;
+        }
+        for index in &self.indexes {
+            crate::sql::check_identifier(index.name())?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// The schema of a table, as returned by `Database::describe`: its definition without the
+/// internals of its storage, such as its id or the columns of its dropped fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDescription {
+    pub name: String,
+    /// The fields, in the order of the columns of the records.
+    pub fields: Vec<Field>,
+    pub primary_key: Vec<String>,
+    pub primary_key_directions: Vec<Direction>,
+    pub indexes: Vec<Index>,
+    pub checks: Vec<Check>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+impl From<Table> for TableDescription {
+    fn from(table: Table) -> Self {
+        Self {
+            name: table.name,
+            fields: table.fields,
+            primary_key: table.primary_key,
+            primary_key_directions: table.primary_key_directions,
+            indexes: table.indexes,
+            checks: table.checks,
+            foreign_keys: table.foreign_keys,
+        }
+    }
+}
+
+/// A named `CHECK` constraint, e.g. `age >= 0`.
+///
+/// The expression is written as a `WHERE` clause is, and applies to a single record. As in
+/// SQL, a record passes unless the expression is false: a comparison with a null column
+/// is unknown rather than false, so nullable fields don't need to be tested for nulls.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub expression: String,
+}
+
+impl Check {
+    pub fn new<S1: Into<String>, S2: Into<String>>(name: S1, expression: S2) -> Self {
+        Self {
+            name: name.into(),
+            expression: expression.into(),
+        }
+    }
+
+    /// Parses the expression of the constraint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::InvalidSqlText` if the expression can't be parsed or falls
+    /// outside of the expressions supported in `WHERE` clauses.
+    pub fn predicate(&self) -> crate::errors::Result<Predicate> {
+        crate::sql::parse_predicate(&self.expression)
+    }
+}
+
+/// A named foreign key: the fields of a record referencing the primary key of a row of the
+/// parent table, in the order of the primary key fields.
+///
+/// A record whose foreign key fields are all set must reference an existing parent row,
+/// a record with a null foreign key field references nothing. Deleting a parent row still
+/// referenced is handled according to [`OnDelete`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKey {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub parent: String,
+    pub on_delete: OnDelete,
+}
+
+impl ForeignKey {
+    pub fn new<S1: Into<String>, S2: Into<String>>(
+        name: S1,
+        fields: Vec<String>,
+        parent: S2,
+        on_delete: OnDelete,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            fields,
+            parent: parent.into(),
+            on_delete,
+        }
+    }
+}
+
+/// What deleting a parent row does to the records referencing it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum OnDelete {
+    /// The deletion fails.
+    #[default]
+    Restrict,
+    /// The referencing records are deleted as well.
+    Cascade,
+    /// The foreign key fields of the referencing records are set to null.
+    SetNull,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Field {
     pub name: String,
     pub r#type: FieldType,
+    /// Whether `Column::Null` is accepted in the field, as opposed to a `NOT NULL` field.
+    ///
+    /// Primary key fields reject nulls regardless, unless the table allows null primary
+    /// keys.
+    #[serde(default = "nullable_by_default")]
+    pub nullable: bool,
+    /// The column filled in when a record built by field name omits the field.
+    ///
+    /// A null default is the same as no default, and isn't stored.
+    #[serde(default, with = "default_column")]
+    pub default: Option<Column>,
+    /// Whether the field is stored out of the rows, see [`Field::new_blob`].
+    #[serde(default)]
+    pub blob: bool,
+    /// The number of digits after the decimal point of a `Decimal` field, see
+    /// [`Field::new_decimal`].
+    #[serde(default)]
+    pub scale: u8,
+    /// The type of the elements of an `Array` field, see [`Field::new_array`].
+    #[serde(default)]
+    pub item_type: Option<FieldType>,
+    /// The values accepted in an `Enum` field, see [`Field::new_enum`].
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// How the strings of a `String` field, or of an array of strings, are compared and
+    /// indexed, see [`Collation`].
+    #[serde(default)]
+    pub collation: Collation,
+    /// Whether the field is numbered from a counter, see [`Field::new_serial`].
+    #[serde(default)]
+    pub serial: bool,
+}
+
+fn nullable_by_default() -> bool {
+    true
+}
+
+/// Stores default columns as the columns of a row are stored, temporal defaults being typed
+/// after their field once the table is read, see `Table::from_bytes_with_mode`.
+mod default_column {
+    use crate::record::Column;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        default: &Option<Column>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        default
+            .as_ref()
+            .filter(|default| **default != Column::Null)
+            .map(crate::row::Column::from)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Column>, D::Error> {
+        let default = Option::<crate::row::Column>::deserialize(deserializer)?;
+        Ok(default.map(Column::from))
+    }
 }
 
 impl Field {
     pub fn new(name: String, r#type: FieldType) -> Self {
-        Self { name, r#type }
+        Self {
+            name,
+            r#type,
+            nullable: true,
+            default: None,
+            blob: false,
+            scale: 0,
+            item_type: None,
+            variants: Vec::new(),
+            collation: Collation::Binary,
+            serial: false,
+        }
+    }
+
+    /// Creates a nullable `Array` field, holding lists of columns of the item type. The
+    /// elements may be null, and the decimal elements have the scale of the field.
+    ///
+    /// Arrays can't be nested, the item type can't be `Array`.
+    pub fn new_array(name: String, item_type: FieldType) -> Self {
+        Self {
+            item_type: Some(item_type),
+            ..Self::new(name, FieldType::Array)
+        }
+    }
+
+    /// Creates a nullable `Enum` field, accepting the string columns holding one of the
+    /// variants. The stored rows hold the position of the variant rather than its text, so
+    /// variants can only be appended to an existing field, never removed or reordered.
+    /// Enum columns compare and sort as strings, in keys as well.
+    pub fn new_enum(name: String, variants: Vec<String>) -> Self {
+        Self {
+            variants,
+            ..Self::new(name, FieldType::Enum)
+        }
+    }
+
+    /// Returns the position of a variant of an `Enum` field, as stored in the rows.
+    pub(crate) fn variant_ordinal(&self, variant: &str) -> Option<usize> {
+        self.variants.iter().position(|known| known == variant)
+    }
+
+    /// Creates a nullable `Decimal` field of the given scale. The field accepts the
+    /// decimals with at most `scale` digits after the decimal point, and the rows encoded
+    /// with a row schema store them at that scale, as the Avro `decimal` logical type.
+    pub fn new_decimal(name: String, scale: u8) -> Self {
+        Self {
+            scale,
+            ..Self::new(name, FieldType::Decimal)
+        }
+    }
+
+    /// Creates a nullable `Bytes` field stored out of the rows, in the blob subspace of the
+    /// table, split into chunks. The rows only hold a reference to their blob, so reading
+    /// the other columns doesn't read it, see `Database::read_blob` to stream a blob.
+    ///
+    /// Blob fields can't be part of the primary key, an index or a foreign key.
+    pub fn new_blob(name: String) -> Self {
+        Self {
+            blob: true,
+            ..Self::new(name, FieldType::Bytes)
+        }
+    }
+
+    /// Creates a `NOT NULL` `Int` field numbering the records: a record inserted with a
+    /// null column in the field gets the next value of a counter of the field, starting at
+    /// 1, see `Database::insert`. Values are unique and increase with the insertions of an
+    /// instance, but aren't dense. A value given explicitly is stored as is and doesn't
+    /// move the counter.
+    ///
+    /// The type of a serial field may be changed to another integer type, but the field
+    /// can't have a default.
+    pub fn new_serial(name: String) -> Self {
+        Self {
+            serial: true,
+            ..Self::new_not_null(name, FieldType::Int)
+        }
+    }
+
+    /// Creates a field rejecting `Column::Null`.
+    pub fn new_not_null(name: String, r#type: FieldType) -> Self {
+        Self {
+            nullable: false,
+            ..Self::new(name, r#type)
+        }
+    }
+
+    /// Sets the column filled in when a record built by field name omits the field.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlLayerError::MismatchedColumnType` if the column doesn't match the type
+    /// of the field nor converts into it, see `Column::coerce`, or isn't one of the
+    /// variants of an `Enum` field.
+    pub fn set_default(&mut self, default: Column) -> crate::errors::Result<()> {
+        let Some(default) = default.coerce(self.r#type).map(Cow::into_owned) else {
+            return Err(SqlLayerError::MismatchedColumnType(
+                format!("{:?}", self.r#type),
+                format!("{default:?}"),
+            ));
+        };
+        if !self.is_variant(&default) {
+            return Err(SqlLayerError::MismatchedColumnType(
+                format!("{:?} of {:?}", self.r#type, self.variants),
+                format!("{default:?}"),
+            ));
+        }
+        self.default = Some(default);
+        Ok(())
+    }
+
+    /// Whether a column of the type of the field holds one of its variants, when it is an
+    /// `Enum` field.
+    pub(crate) fn is_variant(&self, column: &Column) -> bool {
+        match column {
+            Column::String(variant) if self.r#type == FieldType::Enum => {
+                self.variant_ordinal(variant).is_some()
+            }
+            _ => true,
+        }
     }
 }
 
@@ -68,11 +877,29 @@ pub enum FieldType {
     Float,
     Bool,
     Bytes,
+    Date,
+    Time,
+    Timestamp,
+    Uuid,
+    Decimal,
+    Array,
+    Enum,
+    /// Integers of 32 bits, held in `Int` columns.
+    Int32,
+    /// Integers of 16 bits, held in `Int` columns.
+    Int16,
+    UInt64,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::table::{Field, FieldType, Table, SCHEMA};
+    use crate::codec::RowEncoding;
+    use crate::collation::Collation;
+    use crate::compression::RowCompression;
+    use crate::errors::SqlLayerError;
+    use crate::plan::Direction;
+    use crate::record::Column;
+    use crate::table::{Check, Field, FieldType, ForeignKey, Index, OnDelete, Table, SCHEMA};
     use apache_avro::to_value;
 
     #[test]
@@ -89,6 +916,183 @@ mod tests {
         table.add_field(Field::new("height".to_string(), FieldType::Float));
         table.add_field(Field::new("is_married".to_string(), FieldType::Bool));
         table.add_field(Field::new("photo".to_string(), FieldType::Bytes));
+        table.add_field(Field::new_not_null("email".to_string(), FieldType::String));
+        table.add_field(Field::new_blob("resume".to_string()));
+        table.add_field(Field::new_decimal("salary".to_string(), 2));
+        table.add_field(Field::new_array("tags".to_string(), FieldType::String));
+        let mut status = Field::new_enum(
+            "status".to_string(),
+            vec!["single".to_string(), "married".to_string()],
+        );
+        status
+            .set_default(Column::String("single".to_string()))
+            .expect("Invalid default");
+        assert!(matches!(
+            status.set_default(Column::String("widowed".to_string())),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        table.add_field(status);
+        let mut field = Field::new("country".to_string(), FieldType::String);
+        field
+            .set_default(Column::String("France".to_string()))
+            .expect("Invalid default");
+        assert!(matches!(
+            field.set_default(Column::Int(33)),
+            Err(SqlLayerError::MismatchedColumnType(_, _))
+        ));
+        table.add_field(field);
+        table
+            .add_foreign_key(ForeignKey::new(
+                "person_country_fk",
+                vec!["country".to_string()],
+                "Country",
+                OnDelete::SetNull,
+            ))
+            .expect("Invalid foreign key");
+        assert!(matches!(
+            table.add_foreign_key(ForeignKey::new(
+                "person_city_fk",
+                vec!["city".to_string()],
+                "City",
+                OnDelete::Restrict,
+            )),
+            Err(SqlLayerError::InvalidForeignKey(_))
+        ));
+        table
+            .add_check(Check::new("person_age_check", "age >= 0"))
+            .expect("Invalid check");
+        assert!(matches!(
+            table.add_check(Check::new("person_age_check", "age < 150")),
+            Err(SqlLayerError::InvalidConstraint(_))
+        ));
+
+        // the dropped columns keep their place in the stored rows
+        table.drop_field("height").expect("Unable to drop field");
+        table.drop_field("age").expect("Unable to drop field");
+        assert_eq!(table.dropped_columns, vec![2, 3]);
+        assert_eq!(table.stored_position(2), 4);
+        assert!(matches!(
+            table.drop_field("firstname"),
+            Err(SqlLayerError::ColumnInUse(_, _))
+        ));
+        assert!(matches!(
+            table.drop_field("country"),
+            Err(SqlLayerError::ColumnInUse(_, _))
+        ));
+        table
+            .rename_field("country", "nation")
+            .expect("Unable to rename field");
+        table
+            .rename_field("lastname", "surname")
+            .expect("Unable to rename field");
+        assert_eq!(table.primary_key, vec!["firstname", "surname"]);
+        assert_eq!(table.foreign_keys[0].fields, vec!["nation"]);
+        assert!(matches!(
+            table.rename_field("surname", "email"),
+            Err(SqlLayerError::FieldAlreadyExists(_))
+        ));
+        table.check_blob_fields().expect("Invalid blob field");
+        let mut indexed = table.clone();
+        indexed.add_index(&Index::new("idx_resume", vec!["resume"]));
+        assert!(matches!(
+            indexed.check_blob_fields(),
+            Err(SqlLayerError::InvalidBlobField(_))
+        ));
+        table.check_array_fields().expect("Invalid array field");
+        let mut nested = table.clone();
+        nested.add_field(Field::new_array("matrix".to_string(), FieldType::Array));
+        assert!(matches!(
+            nested.check_array_fields(),
+            Err(SqlLayerError::InvalidArrayField(_))
+        ));
+        let mut indexed = table.clone();
+        indexed.add_index(&Index::new_multi_entry("idx_email", "email"));
+        assert!(matches!(
+            indexed.check_array_fields(),
+            Err(SqlLayerError::InvalidArrayField(_))
+        ));
+        table.add_index(&Index::new_multi_entry("idx_tags", "tags"));
+        table.check_array_fields().expect("Invalid array field");
+        table.check_enum_fields().expect("Invalid enum field");
+        for field in [
+            Field::new_enum("gender".to_string(), vec![]),
+            Field::new_enum("gender".to_string(), vec!["x".to_string(), "x".to_string()]),
+            Field::new_array("genders".to_string(), FieldType::Enum),
+        ] {
+            let mut invalid = table.clone();
+            invalid.add_field(field);
+            assert!(matches!(
+                invalid.check_enum_fields(),
+                Err(SqlLayerError::InvalidEnumField(_))
+            ));
+        }
+        let email = table.get_field_pos("email").unwrap();
+        table.fields[email].collation = Collation::CaseInsensitive;
+        let tags = table.get_field_pos("tags").unwrap();
+        table.fields[tags].collation = Collation::Unicode;
+        table.check_collations().expect("Invalid collation");
+        let mut invalid = table.clone();
+        let mut field = Field::new("rank".to_string(), FieldType::Int);
+        field.collation = Collation::CaseInsensitive;
+        invalid.add_field(field);
+        assert!(matches!(
+            invalid.check_collations(),
+            Err(SqlLayerError::InvalidCollation(_))
+        ));
+        table.add_field(Field::new_serial("number".to_string()));
+        table.check_serial_fields().expect("Invalid serial field");
+        for field in [
+            Field {
+                r#type: FieldType::String,
+                ..Field::new_serial("code".to_string())
+            },
+            Field {
+                default: Some(Column::Int(1)),
+                ..Field::new_serial("code".to_string())
+            },
+        ] {
+            let mut invalid = table.clone();
+            invalid.add_field(field);
+            assert!(matches!(
+                invalid.check_serial_fields(),
+                Err(SqlLayerError::InvalidSerialField(_))
+            ));
+        }
+        table.check_primary_key().expect("Invalid primary key");
+        for primary_key in [
+            vec![],
+            vec!["firstname".to_string(), "lastame".to_string()],
+            vec!["firstname".to_string(), "firstname".to_string()],
+            vec!["tags".to_string()],
+        ] {
+            let mut invalid = table.clone();
+            invalid.primary_key = primary_key;
+            assert!(matches!(
+                invalid.check_primary_key(),
+                Err(SqlLayerError::InvalidPrimaryKey(_))
+            ));
+        }
+        let mut invalid = table.clone();
+        invalid.set_primary_key_directions(vec![Direction::Descending; 3]);
+        assert!(matches!(
+            invalid.check_primary_key(),
+            Err(SqlLayerError::InvalidPrimaryKey(_))
+        ));
+        table.check_indexes().expect("Invalid index");
+        let mut invalid = table.clone();
+        invalid.add_index(&Index::new("idx_tags", vec!["email"]));
+        assert!(matches!(
+            invalid.check_indexes(),
+            Err(SqlLayerError::IndexAlreadyExists(_))
+        ));
+        let mut invalid = table.clone();
+        invalid.add_index(&Index::new("idx_emial", vec!["emial"]));
+        assert!(matches!(
+            invalid.check_indexes(),
+            Err(SqlLayerError::MissingColumn(_))
+        ));
+        table.set_row_encoding(RowEncoding::Bincode);
+        table.set_compression(RowCompression::Zstd, 512);
 
         let value = to_value(&table).expect("Failed to convert table to avro value");
 
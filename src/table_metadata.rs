@@ -1,3 +1,4 @@
+use crate::schema::SchemaMode;
 use serde::{Deserialize, Serialize};
 
 const SCHEMA: &str = include_str!("assets/schemas/table_metadata.json");
@@ -5,6 +6,8 @@ const SCHEMA: &str = include_str!("assets/schemas/table_metadata.json");
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct TableMetadata {
     pub name: String,
+    /// Row id counter of the tables created before row ids were reserved by blocks, only
+    /// read to start their counter.
     pub max_row_id: u64,
 }
 
@@ -24,11 +27,12 @@ impl TableMetadata {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> crate::errors::Result<Self> {
-        let schema = apache_avro::schema::Schema::parse_str(SCHEMA)?;
-        let mut data = bytes;
-        let value = apache_avro::from_avro_datum(&schema, &mut data, None)?;
-        let table_metadata = apache_avro::from_value::<TableMetadata>(&value)?;
-        Ok(table_metadata)
+        Self::from_bytes_with_mode(bytes, SchemaMode::Compat)
+    }
+
+    /// Deserializes bytes written by this or a newer version of the crate.
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: SchemaMode) -> crate::errors::Result<Self> {
+        crate::schema::decode_datum(SCHEMA, bytes, mode)
     }
 
     pub fn increment_max_row_id(&mut self) {
@@ -40,9 +44,35 @@ impl TableMetadata {
     }
 }
 
+/// Payload statistics of a table, as returned by `Database::stats`.
+///
+/// The counters are maintained with atomic operations by every write, so they are
+/// available without scanning the table. Rows written before the statistics existed are
+/// not accounted for.
+///
+/// The sizes are estimates for the tables without secondary indexes: their upserts don't
+/// read the row they replace, which is accounted for as a row of the size of its
+/// replacement.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TableStats {
+    pub row_count: u64,
+    /// Sum of the encoded sizes of the rows, in bytes.
+    pub total_bytes: u64,
+    /// Encoded size of the largest row ever written, in bytes. It is a high-water mark,
+    /// deleting or shrinking that row doesn't lower it.
+    pub largest_row: u64,
+}
+
+impl TableStats {
+    /// Returns the average encoded size of a row, in bytes, or `None` if the table is empty.
+    pub fn average_row_size(&self) -> Option<f64> {
+        (self.row_count > 0).then(|| self.total_bytes as f64 / self.row_count as f64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::table_metadata::TableMetadata;
+    use crate::table_metadata::{TableMetadata, TableStats};
 
     #[test]
     fn test_table_metadata() {
@@ -53,4 +83,15 @@ mod tests {
         assert_eq!(metadata.name, "Person");
         assert_eq!(metadata.max_row_id, 1);
     }
+
+    #[test]
+    fn test_average_row_size() {
+        assert_eq!(TableStats::default().average_row_size(), None);
+        let stats = TableStats {
+            row_count: 4,
+            total_bytes: 90,
+            largest_row: 40,
+        };
+        assert_eq!(stats.average_row_size(), Some(22.5));
+    }
 }
@@ -0,0 +1,133 @@
+//! # Temporal Module
+//!
+//! The values of the `Date`, `Time` and `Timestamp` columns, and the parsing of their
+//! ISO 8601 text, as written in SQL typed literals such as `DATE '2024-01-15'`:
+//!
+//! - A date is the number of days since the Unix epoch: `YYYY-MM-DD`.
+//! - A time of day is the number of microseconds since midnight: `HH:MM:SS[.ffffff]`.
+//! - A timestamp is the number of microseconds since the Unix epoch, in UTC:
+//!   `YYYY-MM-DD HH:MM:SS[.ffffff]`, optionally with a `T` separator and a `Z` suffix.
+//!
+//! The three are tuple-encoded as the integers they hold, so they sort chronologically in
+//! keys, and stored as the Avro `date`, `time-micros` and `timestamp-micros` logical types
+//! in the rows encoded with a row schema.
+
+/// Microseconds in a day.
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Parses a date, returning the number of days since the Unix epoch.
+///
+/// Returns `None` if the text isn't a valid `YYYY-MM-DD` date.
+pub(crate) fn parse_date(text: &str) -> Option<i32> {
+    let (year, rest) = text.split_once('-')?;
+    let (month, day) = rest.split_once('-')?;
+    let year = digits(year, 4)?;
+    let month = digits(month, 2)? as u32;
+    let day = digits(day, 2)? as u32;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    i32::try_from(days_from_civil(year, month, day)).ok()
+}
+
+/// Parses a time of day, returning the number of microseconds since midnight.
+///
+/// Returns `None` if the text isn't a valid `HH:MM:SS` time, with up to six fractional
+/// digits.
+pub(crate) fn parse_time(text: &str) -> Option<i64> {
+    let (time, fraction) = match text.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (text, None),
+    };
+    let mut parts = time.split(':');
+    let hour = digits(parts.next()?, 2)?;
+    let minute = digits(parts.next()?, 2)?;
+    let second = digits(parts.next()?, 2)?;
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let micros = match fraction {
+        None => 0,
+        Some(fraction) if (1..=6).contains(&fraction.len()) => {
+            digits(fraction, fraction.len())? * 10_i64.pow(6 - fraction.len() as u32)
+        }
+        Some(_) => return None,
+    };
+    Some(((hour * 60 + minute) * 60 + second) * 1_000_000 + micros)
+}
+
+/// Parses a timestamp in UTC, returning the number of microseconds since the Unix epoch.
+///
+/// Returns `None` if the text isn't a date and a time separated by a space or a `T`,
+/// optionally followed by `Z`.
+pub(crate) fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = text.split_once([' ', 'T'])?;
+    Some(i64::from(parse_date(date)?) * MICROS_PER_DAY + parse_time(time)?)
+}
+
+/// Parses a number of exactly `length` decimal digits.
+fn digits(text: &str, length: usize) -> Option<i64> {
+    if text.len() != length || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days since the Unix epoch of a date of the proleptic Gregorian
+/// calendar, counting in eras of 400 years starting on March 1st.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::temporal::{parse_date, parse_time, parse_timestamp};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11_017));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2024-02-29"), Some(19_782));
+        for invalid in [
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "today",
+        ] {
+            assert_eq!(parse_date(invalid), None, "{invalid}");
+        }
+
+        assert_eq!(parse_time("00:00:00"), Some(0));
+        assert_eq!(parse_time("12:30:15.5"), Some(45_015_500_000));
+        assert_eq!(parse_time("23:59:59.999999"), Some(86_399_999_999));
+        for invalid in ["24:00:00", "12:30", "12:30:15.1234567", "12:60:00"] {
+            assert_eq!(parse_time(invalid), None, "{invalid}");
+        }
+
+        assert_eq!(parse_timestamp("1970-01-01 00:00:01"), Some(1_000_000));
+        assert_eq!(
+            parse_timestamp("2023-11-14T22:13:20.123456Z"),
+            Some(1_700_000_000_123_456)
+        );
+        assert_eq!(parse_timestamp("1969-12-31 23:59:59"), Some(-1_000_000));
+        assert_eq!(parse_timestamp("2023-11-14"), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! # Validation Module
+//!
+//! Records are validated against their table before being written. Rather than stopping at
+//! the first problem, every violation found in a record is collected into a
+//! [`ValidationReport`], so that callers can report all of them at once.
+
+use crate::errors::SqlLayerError;
+use std::fmt::{Display, Formatter};
+
+/// The reason a record is rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    ColumnCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The record has no column for the field.
+    MissingColumn,
+    /// The record has a column past the last field, at the given position.
+    ExtraColumn {
+        position: usize,
+    },
+    /// A named column doesn't match any field.
+    UnknownColumn,
+    /// A named column is given more than once.
+    DuplicateColumn,
+    MismatchedColumnType {
+        expected: String,
+        found: String,
+    },
+    NullPrimaryKey,
+    /// A `NOT NULL` field holds `Column::Null`.
+    NullColumn,
+}
+
+/// A single problem found in a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The field the violation applies to, `None` when it concerns the whole record.
+    pub field: Option<String>,
+    pub kind: ViolationKind,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(field) = &self.field {
+            write!(f, "{field}: ")?;
+        }
+        match &self.kind {
+            ViolationKind::ColumnCountMismatch { expected, found } => {
+                write!(f, "expected {expected} columns, found {found}")
+            }
+            ViolationKind::MissingColumn => write!(f, "missing column"),
+            ViolationKind::ExtraColumn { position } => {
+                write!(f, "unexpected column at position {position}")
+            }
+            ViolationKind::UnknownColumn => write!(f, "unknown column"),
+            ViolationKind::DuplicateColumn => write!(f, "column given more than once"),
+            ViolationKind::MismatchedColumnType { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ViolationKind::NullPrimaryKey => write!(f, "null value in primary key"),
+            ViolationKind::NullColumn => write!(f, "null value in NOT NULL column"),
+        }
+    }
+}
+
+/// Every violation found while validating a record.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn add(&mut self, field: Option<&str>, kind: ViolationKind) {
+        self.violations.push(Violation {
+            field: field.map(|field| field.to_string()),
+            kind,
+        });
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Turns the report into an error if any violation has been found.
+    pub fn into_result(self) -> crate::errors::Result<()> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(SqlLayerError::Validation(self))
+        }
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validation::{ValidationReport, ViolationKind};
+
+    #[test]
+    fn test_validation_report() {
+        let mut report = ValidationReport::default();
+        assert!(report.is_valid());
+
+        report.add(
+            None,
+            ViolationKind::ColumnCountMismatch {
+                expected: 5,
+                found: 4,
+            },
+        );
+        report.add(
+            Some("age"),
+            ViolationKind::MismatchedColumnType {
+                expected: "Int".to_string(),
+                found: "String(\"twenty\")".to_string(),
+            },
+        );
+        report.add(Some("name"), ViolationKind::NullPrimaryKey);
+        report.add(Some("photo"), ViolationKind::MissingColumn);
+        report.add(None, ViolationKind::ExtraColumn { position: 5 });
+        report.add(Some("email"), ViolationKind::NullColumn);
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.to_string(),
+            "expected 5 columns, found 4; age: expected Int, found String(\"twenty\"); name: null value in primary key; photo: missing column; unexpected column at position 5; email: null value in NOT NULL column"
+        );
+        assert!(report.into_result().is_err());
+    }
+}